@@ -0,0 +1,97 @@
+// Iteration of the components of 100k entities, with the components spread over several
+// archetypes
+//
+// cargo bench --bench ecs
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use cobia::ecs::{Entity, Without, World};
+//
+//
+struct Position(f32, f32, f32);
+
+struct Velocity(f32, f32, f32);
+
+struct Health(u32);
+
+struct Frozen;
+//
+//
+const ENTITIES: usize = 100_000;
+
+const RUNS: u32 = 100;
+//
+//
+/// the average time of a run of the function, after a warm up run
+fn measure<F:FnMut()>(name:&str, mut f:F) {
+
+    f();
+
+    let start = Instant::now();
+
+    for _ in 0..RUNS {
+        f();
+    }
+
+    let elapsed = start.elapsed() / RUNS;
+
+    println!("{:<32} {:>10.3?} {:>8.2} ns/entity", name, elapsed, per_entity(elapsed));
+
+}
+//
+fn per_entity(elapsed:Duration) -> f64 { elapsed.as_nanos() as f64 / ENTITIES as f64 }
+//
+//
+fn main() {
+
+    let mut world = World::new();
+
+    for i in 0..ENTITIES {
+
+        let e = world.spawn((Position(i as f32, 0.0, 0.0), Velocity(1.0, 1.0, 1.0)));
+
+        // four archetypes: (P, V), (P, V, H), (P, V, F) and (P, V, H, F)
+        if i % 2 == 0 {
+            world.insert(e, Health(100)).unwrap();
+        }
+        if i % 3 == 0 {
+            world.insert(e, Frozen).unwrap();
+        }
+
+    }
+
+    println!("{} entities in {} archetypes", world.len(), world.archetypes().iter().filter(|a| !a.is_empty()).count());
+
+    measure("iter Position", || {
+        black_box(world.iter::<Position>().map(|(_, p)| p.0 + p.2).sum::<f32>());
+    });
+
+    measure("iter Velocity", || {
+        black_box(world.iter::<Velocity>().map(|(_, v)| v.0 + v.1 + v.2).sum::<f32>());
+    });
+
+    measure("iter_mut Position", || {
+        world.iter_mut::<Position>().for_each(|(_, p)| p.1 += 1.0);
+    });
+
+    // the columns of an archetype are read together, in the order of the rows
+    measure("query &mut Position, &Velocity", || {
+        for (p, v) in world.query::<(&mut Position, &Velocity), ()>().unwrap().iter() {
+            p.0 += v.0;
+            p.1 += v.1;
+            p.2 += v.2;
+        }
+    });
+
+    measure("query Entity, &Health, !Frozen", || {
+        let mut query = world.query::<(Entity, &Health), Without<Frozen>>().unwrap();
+        black_box(query.iter().map(|(e, h)| e.index() + h.0).fold(0u32, u32::wrapping_add));
+    });
+
+    measure("get Health by entity", || {
+        let entities:Vec<_> = world.entities().collect();
+        black_box(entities.iter().filter_map(|e| world.get::<Health>(*e)).map(|h| h.0).sum::<u32>());
+    });
+
+}
@@ -0,0 +1,110 @@
+// Given to the loaders, record the other files an asset is made of so it can be reloaded when
+// one of them change
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::error_handler::EAsset;
+use crate::core::file::get_file_content;
+
+use error_stack::{Result, ResultExt};
+//
+//
+/// What a loader know about the asset it decode
+pub struct LoadContext<'a> {
+
+    path: &'a str,
+    dependencies: Vec<String>
+
+}
+//
+impl<'a> LoadContext<'a> {
+    //
+    pub(crate) fn new(path:&'a str) -> Self { Self { path, dependencies: Vec::new() } }
+    //
+    /// path of the file of the asset
+    pub fn path(&self) -> &str { self.path }
+    //
+    /// Path of a file relative to the directory of the asset, absolute paths are kept as is
+    ///
+    /// # Parameters
+    ///
+    /// * 'relative' - a path found in the asset, like the texture of a material
+    ///
+    pub fn resolve(&self, relative:&str) -> String {
+
+        let relative = relative.replace('\\', "/");
+
+        match Path::new(self.path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() && Path::new(&relative).is_relative() =>
+                format!("{}/{}",dir.display(),relative),
+            _ => relative
+        }
+
+    }
+    //
+    /// Read a file the asset is made of, like an include of a shader. The asset is reloaded
+    /// when this file change
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the file, see resolve for the paths relative to the asset
+    ///
+    pub fn read(&mut self, path:&str) -> Result<Vec<u8>,EAsset> {
+
+        self.add_dependency(path);
+
+        get_file_content(path)
+            .change_context(EAsset::Load)
+            .attach_printable_lazy(|| format!("Can't read the dependency {} of {}",path,self.path))
+
+    }
+    //
+    /// Record a file the asset use without reading it, the asset is reloaded when it change
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the file, see resolve for the paths relative to the asset
+    ///
+    pub fn add_dependency(&mut self, path:&str) {
+
+        if !self.dependencies.iter().any(|d| d == path) {
+            self.dependencies.push(path.to_string());
+        }
+
+    }
+    //
+    /// every file recorded so far
+    pub fn dependencies(&self) -> &[String] { &self.dependencies }
+    //
+    pub(crate) fn into_dependencies(self) -> Vec<String> { self.dependencies }
+    //
+}
+//
+//
+/// Absolute form of a path used to match the paths given by the file watcher with the ones of
+/// the assets. A file that does not exist is resolved with its parent directory
+///
+/// # Parameters
+///
+/// * 'path' - the path to resolve
+///
+pub(crate) fn watch_key(path:&Path) -> PathBuf {
+
+    if let Ok(p) = fs::canonicalize(path) {
+        return p;
+    }
+
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new(".")
+    };
+
+    match (fs::canonicalize(parent), path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf()
+    }
+
+}
+//
+//
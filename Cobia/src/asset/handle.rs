@@ -0,0 +1,155 @@
+// Typed handles given by the asset server. A strong handle keep its asset alive, a weak one only
+// point to it
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::{Arc, Weak};
+use std::sync::mpsc::Sender;
+//
+//
+/// Unique id of an asset in an asset server, never reused
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct AssetId(pub(crate) u64);
+//
+//
+/// Shared by all the strong handles of an asset, tell the server when the last one is dropped
+pub(crate) struct HandleInner {
+
+    id: AssetId,
+    drops: Sender<AssetId>
+
+}
+//
+impl HandleInner {
+
+    pub(crate) fn new(id:AssetId, drops:Sender<AssetId>) -> Arc<Self> { Arc::new(Self { id, drops }) }
+
+}
+//
+impl Drop for HandleInner {
+
+    fn drop(&mut self) {
+
+        // the server may already be gone, nothing left to unload then
+        let _ = self.drops.send(self.id);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Strong handle
+//
+/// Keep an asset loaded, the asset is unloaded once every strong handle to it has been dropped
+pub struct Handle<T> {
+
+    inner: Arc<HandleInner>,
+    marker: PhantomData<fn() -> T>
+
+}
+//
+impl<T> Handle<T> {
+    //
+    pub(crate) fn new(inner:Arc<HandleInner>) -> Self { Self { inner, marker: PhantomData } }
+    //
+    pub(crate) fn inner(&self) -> &Arc<HandleInner> { &self.inner }
+    //
+    pub fn id(&self) -> AssetId { self.inner.id }
+    //
+    /// a weak handle to the same asset
+    pub fn downgrade(&self) -> WeakHandle<T> {
+
+        WeakHandle { id: self.inner.id, inner: Arc::downgrade(&self.inner), marker: PhantomData }
+
+    }
+    //
+    /// number of strong handles to the asset
+    pub fn strong_count(&self) -> usize { Arc::strong_count(&self.inner) }
+    //
+}
+//
+impl<T> Clone for Handle<T> {
+
+    fn clone(&self) -> Self { Self::new(self.inner.clone()) }
+
+}
+//
+impl<T> PartialEq for Handle<T> {
+
+    fn eq(&self, other:&Self) -> bool { self.inner.id == other.inner.id }
+
+}
+//
+impl<T> Eq for Handle<T> {}
+//
+impl<T> Hash for Handle<T> {
+
+    fn hash<H:Hasher>(&self, state:&mut H) { self.inner.id.hash(state) }
+
+}
+//
+impl<T> fmt::Debug for Handle<T> {
+
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+
+        write!(f, "Handle<{}>({})", std::any::type_name::<T>(), self.inner.id.0)
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Weak handle
+//
+/// Point to an asset without keeping it loaded
+pub struct WeakHandle<T> {
+
+    id: AssetId,
+    inner: Weak<HandleInner>,
+    marker: PhantomData<fn() -> T>
+
+}
+//
+impl<T> WeakHandle<T> {
+    //
+    pub fn id(&self) -> AssetId { self.id }
+    //
+    /// a strong handle, None if the asset has been unloaded
+    pub fn upgrade(&self) -> Option<Handle<T>> { self.inner.upgrade().map(Handle::new) }
+    //
+}
+//
+impl<T> Clone for WeakHandle<T> {
+
+    fn clone(&self) -> Self { Self { id: self.id, inner: self.inner.clone(), marker: PhantomData } }
+
+}
+//
+impl<T> PartialEq for WeakHandle<T> {
+
+    fn eq(&self, other:&Self) -> bool { self.id == other.id }
+
+}
+//
+impl<T> Eq for WeakHandle<T> {}
+//
+impl<T> Hash for WeakHandle<T> {
+
+    fn hash<H:Hasher>(&self, state:&mut H) { self.id.hash(state) }
+
+}
+//
+impl<T> fmt::Debug for WeakHandle<T> {
+
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+
+        write!(f, "WeakHandle<{}>({})", std::any::type_name::<T>(), self.id.0)
+
+    }
+
+}
+//
+//
@@ -0,0 +1,255 @@
+// Loaders of the asset types defined by the engine
+
+use std::path::Path;
+
+use super::{AssetLoader, LoadContext};
+use crate::core::error_handler::{EAsset, EScene, ESprite};
+use crate::font::{Font, decode_font};
+use crate::image::{
+    AnimatedImage,
+    AtlasTable,
+    Image,
+    TextureData,
+    decode_animated_image,
+    decode_image,
+    decode_texture
+};
+use crate::mesh::{MaterialLibrary, Mesh, decode_mtl, decode_obj};
+use crate::scene::{ImageSource, Scene, decode_gltf};
+use crate::sprite::{SpriteSheet, decode_aseprite};
+
+use error_stack::{Result, ResultExt};
+//
+//
+/// lowercase extension of a path, empty if there is none
+pub(super) fn extension(path:&str) -> String {
+
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+
+}
+//
+//
+/// Load still images, only the first frame of the animated ones
+pub struct ImageLoader;
+//
+impl AssetLoader for ImageLoader {
+
+    type Asset = Image;
+
+    fn extensions(&self) -> &[&str] {
+        &["png", "apng", "jpg", "jpeg", "gif", "bmp", "dib", "tga", "qoi", "hdr", "pic"]
+    }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Image,EAsset> {
+
+        decode_image(&bytes, &extension(ctx.path())).change_context(EAsset::Decode)
+
+    }
+
+}
+//
+//
+/// Load every frame of APNG and GIF images
+pub struct AnimatedImageLoader;
+//
+impl AssetLoader for AnimatedImageLoader {
+
+    type Asset = AnimatedImage;
+
+    fn extensions(&self) -> &[&str] { &["png", "apng", "gif"] }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<AnimatedImage,EAsset> {
+
+        decode_animated_image(&bytes, &extension(ctx.path())).change_context(EAsset::Decode)
+
+    }
+
+}
+//
+//
+/// Load KTX2 and DDS textures, the images that are not cooked yet become sRGB textures with
+/// their mip chain
+pub struct TextureLoader;
+//
+impl AssetLoader for TextureLoader {
+
+    type Asset = TextureData;
+
+    fn extensions(&self) -> &[&str] { &["ktx2", "dds", "png", "jpg", "jpeg", "gif", "bmp", "tga", "qoi", "hdr"] }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<TextureData,EAsset> {
+
+        let ext = extension(ctx.path());
+
+        if ext == "ktx2" || ext == "dds" {
+            return decode_texture(&bytes).change_context(EAsset::Decode);
+        }
+
+        let image = decode_image(&bytes, &ext).change_context(EAsset::Decode)?;
+
+        // same as the cooker, only the radiance images are linear
+        Ok(TextureData::from_image(&image, ext != "hdr"))
+
+    }
+
+}
+//
+//
+/// Load the tables written by the atlas builder
+pub struct AtlasTableLoader;
+//
+impl AssetLoader for AtlasTableLoader {
+
+    type Asset = AtlasTable;
+
+    fn extensions(&self) -> &[&str] { &["atlas"] }
+
+    fn load(&self, bytes:Vec<u8>, _ctx:&mut LoadContext) -> Result<AtlasTable,EAsset> {
+
+        let text = std::str::from_utf8(&bytes).map_err(|e| EAsset::Decode.attach_printable_default(e))?;
+
+        AtlasTable::parse(text).change_context(EAsset::Decode)
+
+    }
+
+}
+//
+//
+/// Load the meshes in the engine binary format and the OBJ files that are not cooked yet
+pub struct MeshLoader;
+//
+impl AssetLoader for MeshLoader {
+
+    type Asset = Mesh;
+
+    fn extensions(&self) -> &[&str] { &["cmsh", "obj"] }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Mesh,EAsset> {
+
+        match extension(ctx.path()).as_str() {
+            "obj" => decode_obj(&bytes).change_context(EAsset::Decode),
+            _ => Mesh::decode(&bytes).change_context(EAsset::Decode)
+        }
+
+    }
+
+}
+//
+//
+/// Load the MTL material libraries of the OBJ meshes. The texture paths of a loaded library are
+/// the asset paths of the images
+pub struct MaterialLibraryLoader;
+//
+impl AssetLoader for MaterialLibraryLoader {
+
+    type Asset = MaterialLibrary;
+
+    fn extensions(&self) -> &[&str] { &["mtl"] }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<MaterialLibrary,EAsset> {
+
+        let mut library = decode_mtl(&bytes).change_context(EAsset::Decode)?;
+
+        for material in library.iter_mut() {
+
+            let maps = [&mut material.diffuse_map, &mut material.specular_map, &mut material.normal_map];
+
+            for path in maps.into_iter().flatten() {
+                *path = ctx.resolve(path);
+            }
+
+        }
+
+        Ok(library)
+
+    }
+
+}
+//
+//
+/// Load glTF and GLB scenes, their external buffers are dependencies of the scene. The image
+/// files of a loaded scene are given by their asset path
+pub struct SceneLoader;
+//
+impl AssetLoader for SceneLoader {
+
+    type Asset = Scene;
+
+    fn extensions(&self) -> &[&str] { &["gltf", "glb"] }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Scene,EAsset> {
+
+        let read = |uri:&str| {
+            let path = ctx.resolve(uri);
+            ctx.read(&path).change_context(EScene::Load)
+        };
+
+        let mut scene = decode_gltf(&bytes, read).change_context(EAsset::Decode)?;
+
+        for image in scene.images.iter_mut() {
+
+            if let ImageSource::File(path) = image {
+                *path = ctx.resolve(path);
+            }
+
+        }
+
+        Ok(scene)
+
+    }
+
+}
+//
+//
+/// Load TrueType and OpenType fonts
+pub struct FontLoader;
+//
+impl AssetLoader for FontLoader {
+
+    type Asset = Font;
+
+    fn extensions(&self) -> &[&str] { &["ttf", "otf"] }
+
+    fn load(&self, bytes:Vec<u8>, _ctx:&mut LoadContext) -> Result<Font,EAsset> {
+
+        decode_font(bytes).change_context(EAsset::Decode)
+
+    }
+
+}
+//
+//
+/// Load the sprite sheets, the json files are read as Aseprite exports. The path of the image of
+/// a loaded sheet is the asset path of the image
+pub struct SpriteSheetLoader;
+//
+impl AssetLoader for SpriteSheetLoader {
+
+    type Asset = SpriteSheet;
+
+    fn extensions(&self) -> &[&str] { &["sprite", "json"] }
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<SpriteSheet,EAsset> {
+
+        let mut sheet = match extension(ctx.path()).as_str() {
+            "json" => decode_aseprite(&bytes),
+            _ => std::str::from_utf8(&bytes)
+                .map_err(|e| ESprite::Parse.attach_printable_default(e))
+                .and_then(SpriteSheet::parse)
+        }
+        .change_context(EAsset::Decode)?;
+
+        let image = ctx.resolve(sheet.image());
+        sheet.set_image(&image);
+
+        Ok(sheet)
+
+    }
+
+}
+//
+//
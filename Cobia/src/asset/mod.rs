@@ -0,0 +1,902 @@
+// Asset server: load assets by path, share them through typed handles and unload them once they
+// are not used anymore
+//
+// A load request read the file on the engine worker pool, the loader registered for its extension
+// and asset type decode it when the server is updated by the main thread. Requesting a path that
+// is already loaded or loading give back a handle to the same asset.
+//
+// The files read by a loader are recorded as dependencies of the asset. When the file of an asset
+// or one of its dependencies change, the asset is reloaded with everything that depend on it and
+// an AssetEvent is emitted for each of them.
+//
+// With the manifest written by the cooker, a request for a source asset read its cooked file
+// instead when a loader of the asset type handle it, the source does not need to be shipped.
+
+mod handle;
+mod loaders;
+mod context;
+
+pub use handle::{AssetId, Handle, WeakHandle};
+pub use loaders::{
+    AnimatedImageLoader,
+    AtlasTableLoader,
+    FontLoader,
+    ImageLoader,
+    MaterialLibraryLoader,
+    MeshLoader,
+    SceneLoader,
+    SpriteSheetLoader,
+    TextureLoader
+};
+pub use context::LoadContext;
+pub use crate::event::types::asset::AssetEvent;
+
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use handle::HandleInner;
+use context::watch_key;
+use crate::cook::Manifest;
+use crate::core::error_handler::EAsset;
+use crate::core::file::load_async;
+use crate::core::loader::LoadHandle;
+
+use error_stack::{Report, Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::tmp_path;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    struct Text(String);
+
+    struct TextLoader;
+
+    impl AssetLoader for TextLoader {
+
+        type Asset = Text;
+
+        fn extensions(&self) -> &[&str] { &["txt"] }
+
+        fn load(&self, bytes:Vec<u8>, _ctx:&mut LoadContext) -> Result<Text,EAsset> {
+            String::from_utf8(bytes).map(Text).map_err(|e| EAsset::Decode.attach_printable_default(e))
+        }
+
+    }
+
+    // text where the lines '#include <file>' are replaced by the file
+    struct ShaderLoader;
+
+    impl AssetLoader for ShaderLoader {
+
+        type Asset = Text;
+
+        fn extensions(&self) -> &[&str] { &["glsl"] }
+
+        fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Text,EAsset> {
+
+            let source = String::from_utf8(bytes).map_err(|e| EAsset::Decode.attach_printable_default(e))?;
+            let mut lines = Vec::new();
+
+            for line in source.lines() {
+
+                match line.strip_prefix("#include ") {
+                    Some(include) => {
+                        let path = ctx.resolve(include);
+                        let bytes = ctx.read(&path)?;
+                        let text = AssetLoader::load(self, bytes, &mut LoadContext::new(&path))?;
+                        lines.push(text.0);
+                    },
+                    None => lines.push(line.to_string())
+                }
+
+            }
+
+            Ok(Text(lines.join("\n")))
+
+        }
+
+    }
+
+    fn tmp_file(name:&str, content:&[u8]) -> String {
+
+        let path = tmp_path("asset", name);
+        fs::write(&path, content).unwrap();
+        path
+
+    }
+
+    // update the server until the asset is not loading anymore
+    fn wait(server:&mut AssetServer, id:AssetId) -> AssetState {
+
+        let start = Instant::now();
+
+        while server.state(id) == AssetState::Loading && start.elapsed() < Duration::from_secs(5) {
+            server.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        server.state(id)
+
+    }
+
+    fn text_server() -> AssetServer {
+
+        let mut server = AssetServer::new();
+        server.add_loader(TextLoader);
+        server.add_loader(ShaderLoader);
+        server
+
+    }
+
+    // update the server until every reload is done
+    fn wait_reloads(server:&mut AssetServer) {
+
+        let start = Instant::now();
+
+        while server.pending() > 0 && start.elapsed() < Duration::from_secs(5) {
+            server.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+    }
+
+    #[test]
+    fn load_and_deduplicate() {
+
+        let mut server = text_server();
+        let path = tmp_file("dedup.txt", b"hello");
+
+        let first = server.load::<Text>(&path).unwrap();
+        let second = server.load::<Text>(&path).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(server.len(), 1);
+
+        assert_eq!(wait(&mut server, first.id()), AssetState::Loaded);
+        assert_eq!(server.get(&second).unwrap().0, "hello");
+        assert_eq!(server.path(first.id()), Some(path.as_str()));
+        assert_eq!(server.take_events(), vec![AssetEvent::Loaded(first.id())]);
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn unload_last_strong_handle() {
+
+        let mut server = text_server();
+        let path = tmp_file("unload.txt", b"bye");
+
+        let handle = server.load::<Text>(&path).unwrap();
+        let copy = handle.clone();
+        let weak = handle.downgrade();
+        let id = handle.id();
+
+        wait(&mut server, id);
+
+        drop(handle);
+        server.update();
+        assert!(weak.upgrade().is_some());
+        assert_eq!(server.state(id), AssetState::Loaded);
+
+        drop(copy);
+        assert!(weak.upgrade().is_none());
+
+        server.update();
+        assert_eq!(server.state(id), AssetState::Unloaded);
+        assert!(server.is_empty());
+        assert_eq!(server.take_events().last(), Some(&AssetEvent::Unloaded(id)));
+
+        // loaded again from the disk
+        let again = server.load::<Text>(&path).unwrap();
+        assert_ne!(again.id(), id);
+        assert_eq!(wait(&mut server, again.id()), AssetState::Loaded);
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn failed_loads() {
+
+        let mut server = text_server();
+
+        // no loader for the extension or the type
+        assert!(server.load::<Text>("missing.bin").is_err());
+        assert!(server.load::<String>("missing.txt").is_err());
+
+        let missing = server.load::<Text>("/this/file/does/not/exist.txt").unwrap();
+        assert_eq!(wait(&mut server, missing.id()), AssetState::Failed);
+        assert!(server.get(&missing).is_none());
+        assert!(server.error(missing.id()).is_some());
+
+        let path = tmp_file("invalid.txt", &[0xff, 0xfe]);
+        let invalid = server.load::<Text>(&path).unwrap();
+        assert_eq!(wait(&mut server, invalid.id()), AssetState::Failed);
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn cascading_reload() {
+
+        let mut server = text_server();
+
+        let dir = PathBuf::from(tmp_path("asset", "reload"));
+        fs::create_dir_all(&dir).unwrap();
+
+        let common = dir.join("common.glsl");
+        let light = dir.join("light.glsl");
+        let main = dir.join("main.glsl");
+
+        fs::write(&common, "float gamma = 2.2;").unwrap();
+        fs::write(&light, "#include common.glsl\nvec3 light();").unwrap();
+        fs::write(&main, "#include light.glsl\nvoid main();").unwrap();
+
+        let main_shader = server.load::<Text>(main.to_str().unwrap()).unwrap();
+        let light_shader = server.load::<Text>(light.to_str().unwrap()).unwrap();
+
+        wait(&mut server, main_shader.id());
+        wait(&mut server, light_shader.id());
+        server.take_events();
+
+        assert_eq!(server.get(&main_shader).unwrap().0, "float gamma = 2.2;\nvec3 light();\nvoid main();");
+
+        // main include light that include common, both are reloaded
+        fs::write(&common, "float gamma = 1.0;").unwrap();
+        assert_eq!(server.file_changed(&common), 2);
+
+        wait_reloads(&mut server);
+
+        let mut events = server.take_events();
+        events.sort_by_key(|e| e.id());
+        assert_eq!(events, vec![AssetEvent::Reloaded(main_shader.id()), AssetEvent::Reloaded(light_shader.id())]);
+        assert_eq!(server.get(&main_shader).unwrap().0, "float gamma = 1.0;\nvec3 light();\nvoid main();");
+
+        // only main depend on its own file
+        assert_eq!(server.file_changed(&main), 1);
+        assert_eq!(server.file_changed(&dir.join("unrelated.glsl")), 0);
+        wait_reloads(&mut server);
+        server.take_events();
+
+        // a reload that fail keep the previous version
+        fs::write(&light, [0xff, 0xfe]).unwrap();
+        server.file_changed(&light);
+        wait_reloads(&mut server);
+
+        assert!(server.take_events().contains(&AssetEvent::Failed(light_shader.id())));
+        assert_eq!(server.state(light_shader.id()), AssetState::Loaded);
+        assert_eq!(server.get(&light_shader).unwrap().0, "float gamma = 1.0;\nvec3 light();");
+
+        fs::remove_dir_all(dir).unwrap();
+
+    }
+
+    #[test]
+    fn load_cooked_asset() {
+
+        let mut server = text_server();
+
+        let dir = PathBuf::from(tmp_path("asset", "cooked"));
+        fs::create_dir_all(dir.join("text")).unwrap();
+        fs::write(dir.join("text/greeting.txt"), "cooked").unwrap();
+
+        // only the cooked file is there, the source has no loader
+        let mut manifest = Manifest::new();
+        manifest.insert("text/greeting.src", crate::cook::ManifestEntry {
+            output: "text/greeting.txt".to_string(),
+            importer: "text".to_string(),
+            version: 1,
+            source_hash: 0
+        });
+
+        let fp = dir.join("cook.manifest");
+        manifest.save(fp.to_str().unwrap()).unwrap();
+        server.load_manifest(fp.to_str().unwrap()).unwrap();
+
+        let source = format!("{}/text/greeting.src",dir.display());
+        let handle = server.load::<Text>(&source).unwrap();
+
+        assert_eq!(wait(&mut server, handle.id()), AssetState::Loaded);
+        assert_eq!(server.get(&handle).unwrap().0, "cooked");
+        assert_eq!(server.path(handle.id()), Some(source.as_str()));
+
+        // a change of the cooked file reload the asset
+        assert_eq!(server.file_changed(&dir.join("text/greeting.txt")), 1);
+        assert!(server.load::<Text>(&format!("{}/text/other.src",dir.display())).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+
+    }
+
+    #[test]
+    fn add_in_memory() {
+
+        let mut server = AssetServer::new();
+
+        let handle = server.add(Text("memory".to_string()));
+        assert_eq!(server.state(handle.id()), AssetState::Loaded);
+
+        server.get_mut(&handle).unwrap().0.push('!');
+        assert_eq!(server.get(&handle).unwrap().0, "memory!");
+        assert_eq!(server.path(handle.id()), None);
+
+    }
+
+    #[test]
+    fn resolve_texture_paths() {
+
+        let mtl = b"newmtl wood\nmap_Kd wood.png\nmap_Bump ../shared/bump.png\n".to_vec();
+        let library = AssetLoader::load(&MaterialLibraryLoader, mtl, &mut LoadContext::new("models/crate.mtl")).unwrap();
+
+        let wood = library.get("wood").unwrap();
+        assert_eq!(wood.diffuse_map.as_deref(), Some("models/wood.png"));
+        assert_eq!(wood.normal_map.as_deref(), Some("models/../shared/bump.png"));
+
+        let path = crate::test_helper::get_relative_path("tests/fixtures/scene/scene.gltf");
+        let dir = Path::new(&path).parent().unwrap().display().to_string();
+
+        let scene = AssetLoader::load(&SceneLoader, fs::read(&path).unwrap(), &mut LoadContext::new(&path)).unwrap();
+        assert_eq!(scene.images, vec![crate::scene::ImageSource::File(format!("{}/checker board.png",dir))]);
+
+    }
+
+    #[test]
+    fn server_as_resource() {
+
+        use crate::ecs::{Scheduler, System, SystemContext, World};
+
+        let mut server = AssetServer::new();
+        let handle = server.add(Text("shared".to_string()));
+
+        let mut world = World::new();
+        world.insert_resource(server);
+
+        let read = Arc::new(Mutex::new(String::new()));
+        let log = read.clone();
+
+        let mut scheduler = Scheduler::with_threads(1);
+
+        scheduler.add_system(System::new("read", move |ctx:&mut SystemContext| {
+            let server = ctx.resource::<AssetServer>()?;
+            log.lock().unwrap().push_str(&server.get(&handle).unwrap().0);
+            Ok(())
+        }).reads_resource::<AssetServer>()).unwrap();
+
+        scheduler.run(&mut world).unwrap();
+
+        assert_eq!(*read.lock().unwrap(), "shared");
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loader
+//
+/// Anything that can be stored by the asset server
+pub trait Asset: Any + Send + Sync {}
+//
+impl<T:Any + Send + Sync> Asset for T {}
+//
+//
+/// Turn the content of a file into an asset
+pub trait AssetLoader: Send + Sync + 'static {
+    //
+    type Asset: Asset;
+    //
+    /// the file extensions handled by the loader, in lowercase and without the dot
+    fn extensions(&self) -> &[&str];
+    //
+    /// Decode an asset
+    ///
+    /// # Parameters
+    ///
+    /// * 'bytes' - the content of the file
+    /// * 'ctx' - the path of the asset, record the other files read to make the asset
+    ///
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Self::Asset,EAsset>;
+    //
+}
+//
+//
+/// An asset loader without its asset type, so they can all be stored together
+trait ErasedLoader: Send + Sync {
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Box<dyn Any + Send + Sync>,EAsset>;
+
+}
+//
+impl<L:AssetLoader> ErasedLoader for L {
+
+    fn load(&self, bytes:Vec<u8>, ctx:&mut LoadContext) -> Result<Box<dyn Any + Send + Sync>,EAsset> {
+
+        AssetLoader::load(self, bytes, ctx).map(|asset| Box::new(asset) as Box<dyn Any + Send + Sync>)
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Server
+//
+/// Where an asset is at
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AssetState {
+    Loading,
+    Loaded,
+    Failed,
+    // every strong handle has been dropped or the id was never given by this server
+    Unloaded
+}
+//
+//
+struct AssetEntry {
+
+    path:       Option<String>,
+    // the file read, the cooked one when the asset is redirected by the manifest
+    file:       Option<String>,
+    type_id:    TypeId,
+    state:      AssetState,
+    asset:      Option<Box<dyn Any + Send + Sync>>,
+    error:      Option<Report<EAsset>>,
+    loader:     Option<Arc<dyn ErasedLoader>>,
+    // the file being read for a load or a reload
+    request:    Option<LoadHandle>,
+    // the absolute path of the file of the asset and of the files read by its loader
+    watch:      Option<PathBuf>,
+    dependencies: Vec<PathBuf>,
+    handle:     Weak<HandleInner>
+
+}
+//
+//
+/// Own every asset and give handles to them
+pub struct AssetServer {
+
+    next_id:    u64,
+    assets:     HashMap<AssetId, AssetEntry>,
+    paths:      HashMap<(String, TypeId), AssetId>,
+    loaders:    HashMap<(String, TypeId), Arc<dyn ErasedLoader>>,
+    // the receiver is behind a mutex so the server can be a resource shared by the systems
+    drops:      (Sender<AssetId>, Mutex<Receiver<AssetId>>),
+    events:     Vec<AssetEvent>,
+    // the cooked assets and the directory their paths are relative to
+    manifest:   Option<(Manifest, String)>
+
+}
+//
+impl AssetServer {
+    //
+    /// a server without any loader
+    pub fn new() -> Self {
+
+        let (sender, receiver) = channel();
+
+        Self {
+            next_id:    1,
+            assets:     HashMap::new(),
+            paths:      HashMap::new(),
+            loaders:    HashMap::new(),
+            drops:      (sender, Mutex::new(receiver)),
+            events:     Vec::new(),
+            manifest:   None
+        }
+
+    }
+    //
+    /// a server with the loaders of the engine asset types (images, textures, atlas tables,
+    /// meshes, materials and scenes)
+    pub fn with_default_loaders() -> Self {
+
+        let mut server = Self::new();
+
+        server.add_loader(ImageLoader);
+        server.add_loader(AnimatedImageLoader);
+        server.add_loader(TextureLoader);
+        server.add_loader(AtlasTableLoader);
+        server.add_loader(MeshLoader);
+        server.add_loader(MaterialLibraryLoader);
+        server.add_loader(SceneLoader);
+        server.add_loader(FontLoader);
+        server.add_loader(SpriteSheetLoader);
+
+        server
+
+    }
+    //
+    /// Register a loader for all its extensions, it replace the loader of the same asset type
+    /// that was registered for one of them
+    ///
+    /// # Parameters
+    ///
+    /// * 'loader' - the new loader
+    ///
+    pub fn add_loader<L:AssetLoader>(&mut self, loader:L) {
+
+        let extensions:Vec<String> = loader.extensions().iter().map(|e| e.to_lowercase()).collect();
+        let loader:Arc<dyn ErasedLoader> = Arc::new(loader);
+
+        for ext in extensions {
+            self.loaders.insert((ext, TypeId::of::<L::Asset>()), loader.clone());
+        }
+
+    }
+    //
+    /// Use the cooked assets of a manifest for the next loads
+    ///
+    /// # Parameters
+    ///
+    /// * 'manifest' - the manifest written by the cooker
+    /// * 'root' - the directory of the manifest, the source and cooked paths are relative to it
+    ///
+    pub fn set_manifest(&mut self, manifest:Manifest, root:&str) {
+
+        self.manifest = Some((manifest, root.replace('\\', "/").trim_end_matches('/').to_string()));
+
+    }
+    //
+    /// Load the manifest written by the cooker and use its cooked assets for the next loads,
+    /// the paths of the manifest are relative to its directory
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the manifest
+    ///
+    pub fn load_manifest(&mut self, fp:&str) -> Result<(),EAsset> {
+
+        let manifest = Manifest::load(fp).change_context(EAsset::Load)?;
+
+        let root = match Path::new(fp).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+            _ => ".".to_string()
+        };
+
+        self.set_manifest(manifest, &root);
+
+        Ok(())
+
+    }
+    //
+    /// the cooked file of a source asset, if it is in the manifest and can be loaded as T
+    fn cooked<T:Asset>(&self, path:&str) -> Option<String> {
+
+        let (manifest, root) = self.manifest.as_ref()?;
+
+        let source = match root.as_str() {
+            "." => path.trim_start_matches("./"),
+            root => path.strip_prefix(root)?.strip_prefix('/')?
+        };
+
+        let output = &manifest.get(source)?.output;
+
+        match self.loaders.contains_key(&(loaders::extension(output), TypeId::of::<T>())) {
+            true => Some(format!("{}/{}",root,output)),
+            false => None
+        }
+
+    }
+    //
+    /// Request an asset, the file is read in the background and decoded during an update. The
+    /// handle of the asset is given back directly if it is already loaded or loading
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the file of the asset, the cooked file is read instead if the source
+    ///   is in the manifest
+    ///
+    pub fn load<T:Asset>(&mut self, path:&str) -> Result<Handle<T>,EAsset> {
+
+        let path = path.replace('\\', "/");
+        let key = (path.clone(), TypeId::of::<T>());
+
+        let existing = self.paths
+            .get(&key)
+            .and_then(|id| self.assets.get(id))
+            .and_then(|entry| entry.handle.upgrade());
+
+        if let Some(inner) = existing {
+            return Ok(Handle::new(inner));
+        }
+
+        let file = self.cooked::<T>(&path).unwrap_or_else(|| path.clone());
+
+        let loader = self.loaders.get(&(loaders::extension(&file), TypeId::of::<T>())).cloned().ok_or_else(||
+            EAsset::Loader
+                .as_report()
+                .attach_printable(format!("no loader of {} for {}",std::any::type_name::<T>(),path))
+        )?;
+
+        let (handle, id) = self.insert::<T>(Some(path.clone()), AssetState::Loading, None);
+
+        if let Some(entry) = self.assets.get_mut(&id) {
+            entry.request = Some(load_async(&file));
+            entry.loader = Some(loader);
+            entry.watch = Some(watch_key(Path::new(&file)));
+            entry.file = Some(file);
+        }
+
+        self.paths.insert(key, id);
+
+        Ok(handle)
+
+    }
+    //
+    /// Give an asset already in memory to the server
+    ///
+    /// # Parameters
+    ///
+    /// * 'asset' - the new asset
+    ///
+    pub fn add<T:Asset>(&mut self, asset:T) -> Handle<T> {
+
+        self.insert::<T>(None, AssetState::Loaded, Some(Box::new(asset))).0
+
+    }
+    //
+    fn insert<T:Asset>(
+        &mut self,
+        path:Option<String>,
+        state:AssetState,
+        asset:Option<Box<dyn Any + Send + Sync>>) -> (Handle<T>, AssetId) {
+
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+
+        let inner = HandleInner::new(id, self.drops.0.clone());
+
+        self.assets.insert(id, AssetEntry {
+                        path,
+            file:       None,
+            type_id:    TypeId::of::<T>(),
+                        state,
+                        asset,
+            error:      None,
+            loader:     None,
+            request:    None,
+            watch:      None,
+            dependencies: Vec::new(),
+            handle:     Arc::downgrade(&inner)
+        });
+
+        (Handle::new(inner), id)
+
+    }
+    //
+    /// the asset if it is loaded
+    pub fn get<T:Asset>(&self, handle:&Handle<T>) -> Option<&T> {
+
+        self.assets.get(&handle.id())?.asset.as_ref()?.downcast_ref()
+
+    }
+    //
+    /// the asset if it is loaded
+    pub fn get_mut<T:Asset>(&mut self, handle:&Handle<T>) -> Option<&mut T> {
+
+        self.assets.get_mut(&handle.id())?.asset.as_mut()?.downcast_mut()
+
+    }
+    //
+    pub fn state(&self, id:AssetId) -> AssetState {
+
+        self.assets.get(&id).map(|entry| entry.state).unwrap_or(AssetState::Unloaded)
+
+    }
+    //
+    /// why the asset failed to load
+    pub fn error(&self, id:AssetId) -> Option<&Report<EAsset>> {
+
+        self.assets.get(&id)?.error.as_ref()
+
+    }
+    //
+    /// path the asset has been requested with, None for the assets added from memory
+    pub fn path(&self, id:AssetId) -> Option<&str> {
+
+        self.assets.get(&id)?.path.as_deref()
+
+    }
+    //
+    /// number of assets loading, loaded or failed
+    pub fn len(&self) -> usize { self.assets.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.assets.is_empty() }
+    //
+    /// number of loads and reloads not finished yet
+    pub fn pending(&self) -> usize {
+
+        self.assets.values().filter(|entry| entry.request.is_some()).count()
+
+    }
+    //
+    /// the events emitted since the last call, in the order they happened
+    pub fn take_events(&mut self) -> Vec<AssetEvent> { std::mem::take(&mut self.events) }
+    //
+    /// Reload the assets made from a file that changed on the disk, and the assets that depend
+    /// on them. Return how many assets are reloaded, the new versions replace the old ones
+    /// during the next updates
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - the file that changed
+    ///
+    pub fn file_changed(&mut self, path:&Path) -> usize {
+
+        let mut changed = vec![watch_key(path)];
+        let mut visited = HashSet::new();
+        let mut reload = HashSet::new();
+
+        // an asset that is reloaded change for the assets that depend on it too
+        while let Some(file) = changed.pop() {
+
+            if !visited.insert(file.clone()) {
+                continue;
+            }
+
+            for (id, entry) in self.assets.iter() {
+
+                let concerned = entry.loader.is_some()
+                    && (entry.watch.as_ref() == Some(&file) || entry.dependencies.contains(&file));
+
+                if concerned && reload.insert(*id) {
+                    changed.extend(entry.watch.clone());
+                }
+
+            }
+
+        }
+
+        for id in reload.iter() {
+
+            if let Some(entry) = self.assets.get_mut(id) {
+
+                if let Some(previous) = entry.request.take() {
+                    previous.cancel();
+                }
+
+                entry.request = entry.file.as_deref().map(load_async);
+
+            }
+
+        }
+
+        reload.len()
+
+    }
+    //
+    /// Decode the files that have been read and unload the assets without strong handles. Must
+    /// be called by the main thread, the engine does it once per frame
+    pub fn update(&mut self) {
+
+        let finished:Vec<AssetId> = self.assets
+            .iter()
+            .filter(|(_, entry)| entry.request.as_ref().is_some_and(|r| r.is_finished()))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in finished {
+
+            let entry = match self.assets.get_mut(&id) {
+                Some(e) => e,
+                None => continue
+            };
+
+            let (request, loader) = match (entry.request.take(), entry.loader.clone()) {
+                (Some(r), Some(l)) => (r, l),
+                _ => continue
+            };
+
+            let path = entry.file.clone().unwrap_or_default();
+            let mut ctx = LoadContext::new(&path);
+
+            let result = match request.take() {
+                Some(Ok(bytes)) => loader.load(bytes, &mut ctx),
+                Some(Err(e)) => Err(e.change_context(EAsset::Load)),
+                None => Err(EAsset::Load.as_report().attach_printable("the read of the file has been cancelled"))
+            };
+
+            // the dependencies read before a failure are kept, fixing them trigger a reload
+            let dependencies:Vec<PathBuf> = ctx
+                .into_dependencies()
+                .iter()
+                .map(|d| watch_key(Path::new(d)))
+                .collect();
+
+            if !dependencies.is_empty() || result.is_ok() {
+                entry.dependencies = dependencies;
+            }
+
+            match result.attach_printable_lazy(|| format!("Can't load asset {}",path)) {
+
+                Ok(asset) => {
+
+                    let event = match entry.asset.is_some() {
+                        true => AssetEvent::Reloaded(id),
+                        false => AssetEvent::Loaded(id)
+                    };
+
+                    entry.asset = Some(asset);
+                    entry.error = None;
+                    entry.state = AssetState::Loaded;
+                    self.events.push(event);
+
+                },
+
+                // a failed reload keep the previous version of the asset
+                Err(e) => {
+
+                    if entry.asset.is_none() {
+                        entry.state = AssetState::Failed;
+                    }
+
+                    entry.error = Some(e);
+                    self.events.push(AssetEvent::Failed(id));
+
+                }
+
+            }
+
+        }
+
+        let dropped:Vec<AssetId> = match self.drops.1.get_mut() {
+            Ok(receiver) => receiver.try_iter().collect(),
+            Err(poisoned) => poisoned.into_inner().try_iter().collect()
+        };
+
+        for id in dropped {
+            self.unload(id);
+        }
+
+    }
+    //
+    fn unload(&mut self, id:AssetId) {
+
+        let entry = match self.assets.get(&id) {
+            Some(e) if e.handle.strong_count() == 0 => e,
+            _ => return
+        };
+
+        if let Some(request) = entry.request.as_ref() {
+            request.cancel();
+        }
+
+        if let Some(path) = entry.path.clone() {
+
+            let key = (path, entry.type_id);
+
+            // the path may already point to a newer load of the same file
+            if self.paths.get(&key) == Some(&id) {
+                self.paths.remove(&key);
+            }
+
+        }
+
+        self.assets.remove(&id);
+        self.events.push(AssetEvent::Unloaded(id));
+
+    }
+    //
+}
+//
+impl Default for AssetServer {
+
+    fn default() -> Self { Self::new() }
+
+}
+//
+//
@@ -0,0 +1,124 @@
+// Command line tool to bake a directory of sprites into a texture atlas
+//
+// usage:
+//      cobia-atlas build <directory> <atlas.png> <table> [--padding <n>] [--extrude <n>] [--max-size <n>]
+//      cobia-atlas list <table>
+//
+use std::env;
+use std::process::ExitCode;
+
+use cobia::image::{AtlasBuilder, AtlasTable, save_png};
+use cobia::core::error_handler::EImage;
+
+use error_stack::Result;
+
+const USAGE: &str = "usage:
+    cobia-atlas build <directory> <atlas.png> <table> [options]     pack every image of a directory
+    cobia-atlas list <table>                                        list the sprites of an atlas table
+
+options:
+    --padding <n>       empty pixels between the sprites (default 1)
+    --extrude <n>       repeat the border of the sprites n times (default 0)
+    --max-size <n>      biggest width and height of the atlas (default 4096)";
+//
+//
+fn main() -> ExitCode {
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let result = match args.as_slice() {
+
+        ["build", dir, atlas, table, options @ ..] => match builder(options) {
+            Some(builder) => build(builder, dir, atlas, table),
+            None => {
+
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+
+            }
+        },
+        ["list", table] => list(table),
+        _ => {
+
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+
+        }
+
+    };
+
+    match result {
+
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+
+            eprintln!("{:?}", e);
+            ExitCode::FAILURE
+
+        }
+
+    }
+
+}
+//
+//
+/// a builder configured with the options of the command line, None if they are invalid
+fn builder(options:&[&str]) -> Option<AtlasBuilder> {
+
+    let mut builder = AtlasBuilder::new();
+
+    for pair in options.chunks(2) {
+
+        let value = pair.get(1)?.parse().ok()?;
+
+        match pair[0] {
+            "--padding" => builder.set_padding(value),
+            "--extrude" => builder.set_extrude(value),
+            "--max-size" => builder.set_max_size(value),
+            _ => return None
+        }
+
+    }
+
+    Some(builder)
+
+}
+//
+fn build(mut builder:AtlasBuilder, dir:&str, atlas:&str, table:&str) -> Result<(),EImage> {
+
+    builder.add_directory(dir)?;
+
+    let (image, sprites) = builder.build()?.into_parts();
+
+    save_png(&image, atlas)?;
+    sprites.save(table)?;
+
+    println!(
+        "packed {} sprites from {} into a {}x{} atlas {}",
+        sprites.len(),
+        dir,
+        sprites.width(),
+        sprites.height(),
+        atlas
+    );
+
+    Ok(())
+
+}
+//
+fn list(table:&str) -> Result<(),EImage> {
+
+    let table = AtlasTable::load(table)?;
+
+    for (name, rect) in table.iter() {
+
+        println!("{:>6} {:>6} {:>6} {:>6}  {}", rect.x, rect.y, rect.width, rect.height, name);
+
+    }
+
+    println!("{} sprites in a {}x{} atlas", table.len(), table.width(), table.height());
+
+    Ok(())
+
+}
@@ -0,0 +1,130 @@
+// Command line tool to cook a directory of source assets for the runtime
+//
+// usage:
+//      cobia-cook cook <source> <output> [--cache <directory>] [--force]
+//      cobia-cook list <manifest>
+//
+use std::env;
+use std::process::ExitCode;
+
+use cobia::cook::{Cooker, Manifest};
+use cobia::core::error_handler::ECook;
+
+use error_stack::Result;
+
+const USAGE: &str = "usage:
+    cobia-cook cook <source> <output> [options]     cook every asset of a directory
+    cobia-cook list <manifest>                      list the cooked assets of a manifest
+
+options:
+    --cache <directory>     where the cooked files are cached (default <output>/.cache)
+    --force                 cook every asset again, even the cached ones
+
+the shaders are compiled with glslc, found in the PATH or with the COBIA_GLSLC variable";
+//
+//
+fn main() -> ExitCode {
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let result = match args.as_slice() {
+
+        ["cook", source, output, options @ ..] => match cooker(options) {
+            Some(cooker) => cook(cooker, source, output),
+            None => {
+
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+
+            }
+        },
+        ["list", manifest] => list(manifest),
+        _ => {
+
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+
+        }
+
+    };
+
+    match result {
+
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+
+            eprintln!("{:?}", e);
+            ExitCode::FAILURE
+
+        }
+
+    }
+
+}
+//
+//
+/// a cooker configured with the options of the command line, None if they are invalid
+fn cooker(mut options:&[&str]) -> Option<Cooker> {
+
+    let mut cooker = Cooker::with_default_importers();
+
+    while let Some(option) = options.first() {
+
+        match *option {
+            "--force" => {
+                cooker.set_force(true);
+                options = &options[1..];
+            },
+            "--cache" => {
+                cooker.set_cache_dir(options.get(1)?);
+                options = &options[2..];
+            },
+            _ => return None
+        }
+
+    }
+
+    Some(cooker)
+
+}
+//
+/// cook the assets, false if some of them failed
+fn cook(cooker:Cooker, source:&str, output:&str) -> Result<bool,ECook> {
+
+    let report = cooker.cook(source, output)?;
+
+    for (path, e) in report.failed() {
+        eprintln!("failed to cook {}: {:?}", path, e);
+    }
+
+    println!(
+        "cooked {} assets from {} into {} ({} imported, {} cached, {} failed)",
+        report.manifest().len(),
+        source,
+        output,
+        report.imported(),
+        report.cached(),
+        report.failed().len()
+    );
+
+    Ok(report.failed().is_empty())
+
+}
+//
+fn list(manifest:&str) -> Result<bool,ECook> {
+
+    let manifest = Manifest::load(manifest)?;
+
+    for (source, entry) in manifest.iter() {
+        println!("{:<40} {:<40} {} v{} {:016x}", source, entry.output, entry.importer, entry.version, entry.source_hash);
+    }
+
+    println!("{} assets", manifest.len());
+
+    Ok(true)
+
+}
+//
+//
@@ -0,0 +1,124 @@
+// Command line tool to create, inspect and extract Cobia pack archives
+//
+// usage:
+//      cobia-pack pack <directory> <archive> [--store]
+//      cobia-pack list <archive>
+//      cobia-pack extract <archive> <directory>
+//      cobia-pack verify <archive>
+//
+use std::env;
+use std::process::ExitCode;
+
+use cobia::core::pack::{Compression, PackArchive, PackWriter};
+use cobia::core::error_handler::EPack;
+
+use error_stack::Result;
+
+const USAGE: &str = "usage:
+    cobia-pack pack <directory> <archive> [--store]     pack every file of a directory
+    cobia-pack list <archive>                           list the entries of an archive
+    cobia-pack extract <archive> <directory>            extract every entry of an archive
+    cobia-pack verify <archive>                         validate the checksum of every entry";
+//
+//
+fn main() -> ExitCode {
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let result = match args.as_slice() {
+
+        ["pack", dir, archive] => pack(dir, archive, Compression::Deflate),
+        ["pack", dir, archive, "--store"] => pack(dir, archive, Compression::None),
+        ["list", archive] => list(archive),
+        ["extract", archive, dir] => extract(archive, dir),
+        ["verify", archive] => verify(archive),
+        _ => {
+
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+
+        }
+
+    };
+
+    match result {
+
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+
+            eprintln!("{:?}", e);
+            ExitCode::FAILURE
+
+        }
+
+    }
+
+}
+//
+//
+fn pack(dir:&str, archive:&str, compression:Compression) -> Result<(),EPack> {
+
+    let mut writer = PackWriter::new();
+
+    writer.add_directory(dir, compression)?;
+    writer.write(archive)?;
+
+    println!("packed {} files from {} into {}", writer.len(), dir, archive);
+
+    Ok(())
+
+}
+//
+fn list(archive:&str) -> Result<(),EPack> {
+
+    let pack = PackArchive::open(archive)?;
+
+    let mut entries = pack.entries().to_vec();
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+
+    for entry in entries.iter() {
+
+        println!(
+            "{:>12} {:>12} {:>8} {:08x}  {}",
+            entry.raw_size(),
+            entry.stored_size(),
+            match entry.compression() {
+                Compression::None => "stored",
+                Compression::Deflate => "deflate"
+            },
+            entry.crc32(),
+            entry.path()
+        );
+
+    }
+
+    println!("{} entries", entries.len());
+
+    Ok(())
+
+}
+//
+fn extract(archive:&str, dir:&str) -> Result<(),EPack> {
+
+    let mut pack = PackArchive::open(archive)?;
+
+    pack.extract_all(dir)?;
+
+    println!("extracted {} files into {}", pack.entries().len(), dir);
+
+    Ok(())
+
+}
+//
+fn verify(archive:&str) -> Result<(),EPack> {
+
+    let mut pack = PackArchive::open(archive)?;
+
+    pack.verify()?;
+
+    println!("{}: {} entries OK", archive, pack.entries().len());
+
+    Ok(())
+
+}
@@ -0,0 +1,172 @@
+// Importers of the engine: images to KTX2 textures, GLSL shaders to SPIR-V and OBJ meshes to the
+// engine binary mesh format
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::Importer;
+use crate::core::error_handler::ECook;
+use crate::image::{TextureData, decode_image, encode_ktx2};
+use crate::mesh::decode_obj;
+
+use error_stack::{Result, ResultExt};
+//
+//
+/// environment variable that can give the path of the glslc compiler
+pub const GLSLC_ENV: &str = "COBIA_GLSLC";
+//
+//
+/// lowercase extension of a path, empty if there is none
+fn extension(path:&Path) -> String {
+
+    path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).unwrap_or_default()
+
+}
+//
+//
+/// Decode an image and store it uncompressed with its mip chain, ready to be copied to the gpu
+pub struct TextureImporter;
+//
+impl Importer for TextureImporter {
+
+    fn name(&self) -> &str { "texture" }
+
+    fn version(&self) -> u32 { 1 }
+
+    fn extensions(&self) -> &[&str] { &["png", "jpg", "jpeg", "gif", "bmp", "tga", "qoi", "hdr"] }
+
+    fn output_extension(&self) -> &str { "ktx2" }
+
+    fn import(&self, bytes:&[u8], path:&Path) -> Result<Vec<u8>,ECook> {
+
+        let ext = extension(path);
+        let image = decode_image(bytes, &ext).change_context(ECook::Import)?;
+
+        // only the radiance images are linear
+        let texture = TextureData::from_image(&image, ext != "hdr");
+
+        encode_ktx2(&texture).change_context(ECook::Import)
+
+    }
+
+}
+//
+//
+/// Compile a GLSL shader to SPIR-V with glslc, the stage is given by the extension. The compiler
+/// is found in the PATH or with the COBIA_GLSLC environment variable
+pub struct ShaderImporter;
+//
+impl Importer for ShaderImporter {
+
+    fn name(&self) -> &str { "shader" }
+
+    fn version(&self) -> u32 { 1 }
+
+    fn extensions(&self) -> &[&str] { &["vert", "frag", "comp", "geom", "tesc", "tese"] }
+
+    fn output_extension(&self) -> &str { "spv" }
+
+    // the files included, directly or not, relative to the file that include them
+    fn dependencies(&self, bytes:&[u8], path:&Path) -> Vec<PathBuf> {
+
+        let mut found:Vec<PathBuf> = Vec::new();
+        let mut stack = vec![(path.to_path_buf(), bytes.to_vec())];
+
+        while let Some((file, content)) = stack.pop() {
+
+            let dir = file.parent().unwrap_or(Path::new(""));
+
+            for name in String::from_utf8_lossy(&content).lines().filter_map(include_name) {
+
+                let include = dir.join(name);
+
+                if found.contains(&include) {
+                    continue;
+                }
+
+                // a missing include is reported by the compiler
+                if let Ok(content) = fs::read(&include) {
+
+                    found.push(include.clone());
+                    stack.push((include, content));
+
+                }
+
+            }
+
+        }
+
+        found
+
+    }
+
+    fn import(&self, _bytes:&[u8], path:&Path) -> Result<Vec<u8>,ECook> {
+
+        let compiler = std::env::var(GLSLC_ENV).unwrap_or_else(|_| "glslc".to_string());
+
+        // the file is compiled from the disk so its includes are found
+        let output = Command::new(&compiler)
+            .arg(path)
+            .args(["-O", "-o", "-"])
+            .output()
+            .map_err(|e|
+                ECook::Import
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't run the shader compiler '{}'",compiler))
+            )?;
+
+        if !output.status.success() {
+
+            return Err(ECook::Import
+                .as_report()
+                .attach_printable(String::from_utf8_lossy(&output.stderr).trim().to_string())
+            );
+
+        }
+
+        Ok(output.stdout)
+
+    }
+
+}
+//
+//
+/// the file named by an #include line of GLSL
+fn include_name(line:&str) -> Option<&str> {
+
+    let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?.trim();
+
+    let (name, _) = match rest.chars().next()? {
+        '"' => rest[1..].split_once('"')?,
+        '<' => rest[1..].split_once('>')?,
+        _ => return None
+    };
+
+    Some(name).filter(|name| !name.is_empty())
+
+}
+//
+//
+/// Convert a Wavefront OBJ file to the engine binary mesh format
+pub struct MeshImporter;
+//
+impl Importer for MeshImporter {
+
+    fn name(&self) -> &str { "mesh" }
+
+    fn version(&self) -> u32 { 2 }
+
+    fn extensions(&self) -> &[&str] { &["obj"] }
+
+    fn output_extension(&self) -> &str { "cmsh" }
+
+    fn import(&self, bytes:&[u8], _path:&Path) -> Result<Vec<u8>,ECook> {
+
+        decode_obj(bytes).map(|mesh| mesh.encode()).change_context(ECook::Import)
+
+    }
+
+}
+//
+//
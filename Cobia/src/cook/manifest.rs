@@ -0,0 +1,156 @@
+// Manifest written by the cooker: for every source asset, the cooked file that replace it
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::core::error_handler::ECook;
+use crate::core::file::get_file_content;
+use crate::core::paths::write_atomic;
+
+use error_stack::{Result, ResultExt};
+//
+//
+/// Header of a serialized manifest
+const MANIFEST_HEADER: &str = "cobia-manifest 1";
+//
+//
+/// How a source asset has been cooked
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct ManifestEntry {
+    // path of the cooked file, relative to the manifest
+    pub output: String,
+    pub importer: String,
+    pub version: u32,
+    pub source_hash: u64
+}
+//
+//
+/// The cooked assets by source path, the paths are relative to the asset directory
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct Manifest {
+
+    entries: BTreeMap<String, ManifestEntry>
+
+}
+//
+impl Manifest {
+    //
+    pub fn new() -> Self { Self::default() }
+    //
+    /// Add or replace the entry of a source asset
+    ///
+    /// # Parameters
+    ///
+    /// * 'source' - path of the source asset
+    /// * 'entry' - the cooked file
+    ///
+    pub fn insert(&mut self, source:&str, entry:ManifestEntry) { self.entries.insert(source.to_string(), entry); }
+    //
+    /// the cooked file of a source asset
+    pub fn get(&self, source:&str) -> Option<&ManifestEntry> { self.entries.get(source) }
+    //
+    /// the entries sorted by source path
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ManifestEntry)> {
+
+        self.entries.iter().map(|(source, entry)| (source.as_str(), entry))
+
+    }
+    //
+    pub fn len(&self) -> usize { self.entries.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+    //
+    /// Serialize the manifest as text, a line per entry with the fields separated by tabs:
+    ///
+    /// ```text
+    /// cobia-manifest 1
+    /// asset <source> <output> <importer> <version> <source hash>
+    /// ```
+    pub fn to_text(&self) -> String {
+
+        let mut text = format!("{}\n",MANIFEST_HEADER);
+
+        for (source, e) in self.entries.iter() {
+            text.push_str(&format!("asset\t{}\t{}\t{}\t{}\t{:016x}\n",source,e.output,e.importer,e.version,e.source_hash));
+        }
+
+        text
+
+    }
+    //
+    /// Read a manifest serialized with to_text
+    ///
+    /// # Parameters
+    ///
+    /// * 'text' - the serialized manifest
+    ///
+    pub fn parse(text:&str) -> Result<Manifest,ECook> {
+
+        let invalid = |line:usize, msg:&str| {
+            ECook::Manifest.as_report().attach_printable(format!("manifest line {}: {}",line + 1,msg))
+        };
+
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == MANIFEST_HEADER => {},
+            _ => return Err(invalid(0, &format!("expected the header '{}'",MANIFEST_HEADER)))
+        }
+
+        let mut manifest = Manifest::new();
+
+        for (i, line) in lines {
+
+            let (source, output, importer, version, hash) = match line.split('\t').collect::<Vec<&str>>().as_slice() {
+                ["asset", source, output, importer, version, hash] => (*source, *output, *importer, *version, *hash),
+                _ => return Err(invalid(i, "expected 'asset <source> <output> <importer> <version> <hash>'"))
+            };
+
+            let version = version.parse().map_err(|_| invalid(i, "invalid importer version"))?;
+            let source_hash = u64::from_str_radix(hash, 16).map_err(|_| invalid(i, "invalid source hash"))?;
+
+            let entry = ManifestEntry { output: output.to_string(), importer: importer.to_string(), version, source_hash };
+
+            manifest.insert(source, entry);
+
+        }
+
+        Ok(manifest)
+
+    }
+    //
+    /// load a manifest written by the cooker
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the manifest
+    ///
+    pub fn load(fp:&str) -> Result<Manifest,ECook> {
+
+        let content = get_file_content(fp)
+            .change_context(ECook::Io)
+            .attach_printable_lazy(|| format!("Can't load manifest {}",fp))?;
+
+        let text = std::str::from_utf8(&content).map_err(|e| ECook::Manifest.attach_printable_default(e))?;
+
+        Manifest::parse(text).attach_printable_lazy(|| format!("Can't load manifest {}",fp))
+
+    }
+    //
+    /// save the manifest as text
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the new manifest
+    ///
+    pub fn save(&self, fp:&str) -> Result<(),ECook> {
+
+        write_atomic(Path::new(fp), self.to_text().as_bytes())
+            .change_context(ECook::Io)
+            .attach_printable_lazy(|| format!("Can't save manifest {}",fp))
+
+    }
+    //
+}
+//
+//
@@ -0,0 +1,506 @@
+// Offline asset cooking: convert the source assets of a directory to the formats the engine load
+// the fastest, and write a manifest that map every source asset to its cooked file
+//
+// The outputs are cached by the hash of their source, of the files it depends on (the includes of
+// a shader) and the name and version of their importer, an asset that did not change since the
+// last cook (or that went back to a previous content) is copied from the cache instead of being
+// imported again.
+
+mod manifest;
+mod importers;
+
+pub use manifest::{Manifest, ManifestEntry};
+pub use importers::{MeshImporter, ShaderImporter, TextureImporter, GLSLC_ENV};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::error_handler::ECook;
+use crate::core::paths::write_atomic;
+
+use error_stack::{Report, Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::{get_relative_path, tmp_path};
+    use crate::image::{Image, PixelFormat, decode_texture, encode_png};
+    use crate::mesh::Mesh;
+
+    fn sprite(value:u8) -> Vec<u8> {
+
+        let img = Image::new(4, 4, PixelFormat::Rgba8, vec![value; 64]).unwrap();
+        encode_png(&img).unwrap()
+
+    }
+
+    #[test]
+    fn cook_directory() {
+
+        let root = PathBuf::from(tmp_path("cook", "directory"));
+        let source = root.join("source");
+        let output = root.join("output");
+
+        fs::create_dir_all(source.join("models")).unwrap();
+        fs::write(source.join("sprite.png"), sprite(10)).unwrap();
+        fs::copy(get_relative_path("tests/fixtures/image/rgb.jpg"), source.join("sprite.jpg")).unwrap();
+        fs::copy(get_relative_path("tests/fixtures/mesh/quad.obj"), source.join("models/quad.obj")).unwrap();
+        fs::write(source.join("models/broken.obj"), "f 1 2 3").unwrap();
+        fs::write(source.join("notes.txt"), "not an asset").unwrap();
+
+        let cooker = Cooker::with_default_importers();
+        let (src, out) = (source.to_str().unwrap(), output.to_str().unwrap());
+
+        let report = cooker.cook(src, out).unwrap();
+
+        assert_eq!((report.imported(), report.cached()), (3, 0));
+        assert_eq!(report.failed().len(), 1);
+        assert_eq!(report.failed()[0].0, "models/broken.obj");
+
+        let manifest = Manifest::load(output.join(MANIFEST_FILE).to_str().unwrap()).unwrap();
+        assert_eq!(&manifest, report.manifest());
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest.get("sprite.jpg").unwrap().output, "sprite.jpg.ktx2");
+
+        let entry = manifest.get("sprite.png").unwrap();
+        assert_eq!((entry.output.as_str(), entry.importer.as_str(), entry.version), ("sprite.png.ktx2", "texture", 1));
+
+        let texture = decode_texture(&fs::read(output.join("sprite.png.ktx2")).unwrap()).unwrap();
+        assert_eq!(texture.mip_count(), 3);
+
+        let mesh = Mesh::decode(&fs::read(output.join("models/quad.obj.cmsh")).unwrap()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+
+        // nothing changed, everything come from the cache
+        let report = cooker.cook(src, out).unwrap();
+        assert_eq!((report.imported(), report.cached()), (0, 3));
+
+        // a new content is imported, the previous one is still in the cache
+        fs::write(source.join("sprite.png"), sprite(20)).unwrap();
+        assert_eq!(cooker.cook(src, out).unwrap().imported(), 1);
+
+        fs::write(source.join("sprite.png"), sprite(10)).unwrap();
+        let report = cooker.cook(src, out).unwrap();
+        assert_eq!((report.imported(), report.cached()), (0, 3));
+        assert_eq!(report.manifest().get("sprite.png"), Some(entry));
+
+        fs::remove_dir_all(root).unwrap();
+
+    }
+
+    // append to a text the content of the file of the same name with the 'inc' extension
+    struct IncludeImporter;
+
+    impl Importer for IncludeImporter {
+
+        fn name(&self) -> &str { "include" }
+
+        fn version(&self) -> u32 { 1 }
+
+        fn extensions(&self) -> &[&str] { &["txt"] }
+
+        fn output_extension(&self) -> &str { "out" }
+
+        fn dependencies(&self, _bytes:&[u8], path:&Path) -> Vec<PathBuf> { vec![path.with_extension("inc")] }
+
+        fn import(&self, bytes:&[u8], path:&Path) -> Result<Vec<u8>,ECook> {
+            Ok([bytes, &fs::read(path.with_extension("inc")).unwrap()].concat())
+        }
+
+    }
+
+    #[test]
+    fn cook_dependencies() {
+
+        let root = PathBuf::from(tmp_path("cook", "dependencies"));
+        let source = root.join("source");
+        let output = root.join("output");
+
+        fs::create_dir_all(source.join("lib")).unwrap();
+        fs::write(source.join("text.txt"), "a").unwrap();
+        fs::write(source.join("text.inc"), "b").unwrap();
+
+        let mut cooker = Cooker::new();
+        cooker.add_importer(IncludeImporter);
+
+        let (src, out) = (source.to_str().unwrap(), output.to_str().unwrap());
+
+        assert_eq!(cooker.cook(src, out).unwrap().imported(), 1);
+        assert_eq!(cooker.cook(src, out).unwrap().cached(), 1);
+
+        // a change of the dependency alone cook the text again
+        fs::write(source.join("text.inc"), "c").unwrap();
+
+        assert_eq!(cooker.cook(src, out).unwrap().imported(), 1);
+        assert_eq!(fs::read(output.join("text.txt.out")).unwrap(), b"ac");
+
+        // the includes of a shader are found from the file that include them
+        let shader = "#version 450\n#include \"lib/common.glsl\"\n  # include <lib/common.glsl>\nvoid main() {}\n";
+        fs::write(source.join("lib/common.glsl"), "#include \"light.glsl\"\n#include \"missing.glsl\"\n").unwrap();
+        fs::write(source.join("lib/light.glsl"), "").unwrap();
+
+        assert_eq!(
+            ShaderImporter.dependencies(shader.as_bytes(), &source.join("basic.frag")),
+            vec![source.join("lib/common.glsl"), source.join("lib/light.glsl")]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+
+    }
+
+    #[test]
+    fn manifest_serialization() {
+
+        let mut manifest = Manifest::new();
+
+        manifest.insert("textures/hero walk.png", ManifestEntry {
+            output: "textures/hero walk.ktx2".to_string(),
+            importer: "texture".to_string(),
+            version: 3,
+            source_hash: 0x0123_4567_89ab_cdef
+        });
+
+        let text = manifest.to_text();
+
+        assert!(text.starts_with("cobia-manifest 1\n"));
+        assert_eq!(Manifest::parse(&text).unwrap(), manifest);
+
+        assert!(Manifest::parse("cobia-manifest 2\n").is_err());
+        assert!(Manifest::parse("cobia-manifest 1\nasset\ta.png\ta.ktx2\ttexture\tone\t00\n").is_err());
+
+        assert_ne!(content_hash(b"a"), content_hash(b"b"));
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Importer
+//
+/// name of the manifest written at the root of the output directory
+pub const MANIFEST_FILE: &str = "cook.manifest";
+//
+/// name of the cache directory in the output directory, when no other is given
+pub const DEFAULT_CACHE_DIR: &str = ".cache";
+//
+//
+/// Convert a source asset to its cooked form
+pub trait Importer: Send + Sync {
+    //
+    /// name of the importer, saved in the manifest and used for the cache
+    fn name(&self) -> &str;
+    //
+    /// must change every time the output of the importer change, so the cache is not used
+    fn version(&self) -> u32;
+    //
+    /// the source extensions handled, in lowercase and without the dot
+    fn extensions(&self) -> &[&str];
+    //
+    /// extension of the cooked files, appended to the name of their source
+    fn output_extension(&self) -> &str;
+    //
+    /// The other files read by the import of a source asset, their content is part of the cache
+    /// key so a change of one of them cook the asset again
+    ///
+    /// # Parameters
+    ///
+    /// * 'bytes' - the content of the source asset
+    /// * 'path' - path of the source asset
+    ///
+    fn dependencies(&self, _bytes:&[u8], _path:&Path) -> Vec<PathBuf> { Vec::new() }
+    //
+    /// Cook a source asset
+    ///
+    /// # Parameters
+    ///
+    /// * 'bytes' - the content of the source asset
+    /// * 'path' - path of the source asset
+    ///
+    fn import(&self, bytes:&[u8], path:&Path) -> Result<Vec<u8>,ECook>;
+    //
+}
+//
+//
+/// FNV-1a 64 bits hash of the content of a source asset
+///
+/// # Parameters
+///
+/// * 'bytes' - the content to hash
+///
+pub fn content_hash(bytes:&[u8]) -> u64 {
+
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3))
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Cooker
+//
+/// What a cook did
+#[derive(Debug)]
+pub struct CookReport {
+
+    manifest:   Manifest,
+    imported:   usize,
+    cached:     usize,
+    failed:     Vec<(String, Report<ECook>)>
+
+}
+//
+impl CookReport {
+    //
+    /// the manifest written in the output directory
+    pub fn manifest(&self) -> &Manifest { &self.manifest }
+    //
+    /// number of assets run through their importer
+    pub fn imported(&self) -> usize { self.imported }
+    //
+    /// number of assets taken from the cache
+    pub fn cached(&self) -> usize { self.cached }
+    //
+    /// the source assets that could not be cooked and why, they are left out of the manifest
+    pub fn failed(&self) -> &[(String, Report<ECook>)] { &self.failed }
+    //
+}
+//
+//
+/// Run the importers on every asset of a directory
+pub struct Cooker {
+
+    importers:  Vec<Box<dyn Importer>>,
+    cache_dir:  Option<PathBuf>,
+    // ignore the cache and import everything again
+    force:      bool
+
+}
+//
+impl Cooker {
+    //
+    /// a cooker without any importer
+    pub fn new() -> Self { Self { importers: Vec::new(), cache_dir: None, force: false } }
+    //
+    /// a cooker with the importers of the engine (textures, shaders and meshes)
+    pub fn with_default_importers() -> Self {
+
+        let mut cooker = Self::new();
+
+        cooker.add_importer(TextureImporter);
+        cooker.add_importer(ShaderImporter);
+        cooker.add_importer(MeshImporter);
+
+        cooker
+
+    }
+    //
+    /// Register an importer, it take over the extensions of the importers added before
+    ///
+    /// # Parameters
+    ///
+    /// * 'importer' - the new importer
+    ///
+    pub fn add_importer<I:Importer + 'static>(&mut self, importer:I) { self.importers.push(Box::new(importer)); }
+    //
+    /// where the cooked files are cached, the '.cache' directory of the output by default
+    pub fn set_cache_dir(&mut self, dir:&str) { self.cache_dir = Some(PathBuf::from(dir)); }
+    //
+    /// import every asset even if it is in the cache
+    pub fn set_force(&mut self, force:bool) { self.force = force; }
+    //
+    /// the importer of a file, the last registered for its extension
+    fn importer(&self, path:&Path) -> Option<&dyn Importer> {
+
+        let ext = path.extension().and_then(|e| e.to_str())?.to_lowercase();
+
+        self.importers
+            .iter()
+            .rev()
+            .find(|i| i.extensions().contains(&ext.as_str()))
+            .map(|i| i.as_ref())
+
+    }
+    //
+    /// Cook every asset of a directory that has an importer and write the manifest. An asset
+    /// that fail is reported and the others are still cooked
+    ///
+    /// # Parameters
+    ///
+    /// * 'source' - the directory of the source assets
+    /// * 'output' - where the cooked files and the manifest are written
+    ///
+    pub fn cook(&self, source:&str, output:&str) -> Result<CookReport,ECook> {
+
+        let root = Path::new(source);
+        let out = Path::new(output);
+        let cache = self.cache_dir.clone().unwrap_or_else(|| out.join(DEFAULT_CACHE_DIR));
+
+        let mut report = CookReport { manifest: Manifest::new(), imported: 0, cached: 0, failed: Vec::new() };
+
+        for path in source_files(root, out)? {
+
+            let importer = match self.importer(&path) {
+                Some(i) => i,
+                None => continue
+            };
+
+            let relative = path
+                .strip_prefix(root)
+                .map_err(|e| ECook::Io.attach_printable_default(e))?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            match self.cook_file(importer, &path, &relative, out, &cache) {
+
+                Ok((entry, from_cache)) => {
+
+                    match from_cache {
+                        true => report.cached += 1,
+                        false => report.imported += 1
+                    }
+
+                    report.manifest.insert(&relative, entry);
+
+                },
+
+                Err(e) => report.failed.push((relative, e))
+
+            }
+
+        }
+
+        let manifest_path = out.join(MANIFEST_FILE);
+        report.manifest.save(&manifest_path.to_string_lossy())?;
+
+        Ok(report)
+
+    }
+    //
+    /// Cook a single asset, return its manifest entry and if it come from the cache
+    fn cook_file(
+        &self,
+        importer:&dyn Importer,
+        path:&Path,
+        relative:&str,
+        out:&Path,
+        cache:&Path) -> Result<(ManifestEntry, bool),ECook> {
+
+        let bytes = fs::read(path).map_err(|e|
+            ECook::Io
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't read {}",path.display()))
+        )?;
+
+        // the files read by the importer change the output as much as the source
+        let hash = importer
+            .dependencies(&bytes, path)
+            .iter()
+            .filter_map(|dep| fs::read(dep).ok())
+            .fold(content_hash(&bytes), |hash, dep| {
+                content_hash(&[hash.to_le_bytes(), content_hash(&dep).to_le_bytes()].concat())
+            });
+
+        let ext = importer.output_extension();
+
+        let cached = cache.join(format!("{:016x}-{}-{}.{}",hash,importer.name(),importer.version(),ext));
+
+        let (cooked, from_cache) = match fs::read(&cached) {
+
+            Ok(cooked) if !self.force => (cooked, true),
+
+            _ => {
+
+                let cooked = importer
+                    .import(&bytes, path)
+                    .attach_printable_lazy(|| format!("Can't cook {} with the {} importer",relative,importer.name()))?;
+
+                write_atomic(&cached, &cooked).change_context(ECook::Io)?;
+
+                (cooked, false)
+
+            }
+
+        };
+
+        // the source extension is kept so basic.vert and basic.frag don't both give basic.spv
+        let output = format!("{}.{}",relative,ext);
+
+        write_atomic(&out.join(&output), &cooked)
+            .change_context(ECook::Io)
+            .attach_printable_lazy(|| format!("Can't write the cooked {}",relative))?;
+
+        let entry = ManifestEntry {
+                            output,
+            importer:       importer.name().to_string(),
+            version:        importer.version(),
+            source_hash:    hash
+        };
+
+        Ok((entry, from_cache))
+
+    }
+    //
+}
+//
+impl Default for Cooker {
+
+    fn default() -> Self { Self::new() }
+
+}
+//
+//
+/// Every file under the source directory sorted by path, the output directory is skipped when it
+/// is inside the source one
+///
+/// # Parameters
+///
+/// * 'root' - the source directory
+/// * 'output' - the output directory
+///
+fn source_files(root:&Path, output:&Path) -> Result<Vec<PathBuf>,ECook> {
+
+    let output = fs::canonicalize(output).ok();
+    let mut stack = vec![root.to_path_buf()];
+    let mut files = Vec::new();
+
+    while let Some(current) = stack.pop() {
+
+        let read_dir = fs::read_dir(&current).map_err(|e|
+            ECook::Io
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't read directory {}",current.display()))
+        )?;
+
+        for item in read_dir {
+
+            let path = item.map_err(|e| ECook::Io.attach_printable_default(e))?.path();
+
+            if !path.is_dir() {
+
+                files.push(path);
+                continue;
+
+            }
+
+            if output.is_none() || fs::canonicalize(&path).ok() != output {
+                stack.push(path);
+            }
+
+        }
+
+    }
+
+    files.sort();
+
+    Ok(files)
+
+}
+//
+//
@@ -7,11 +7,69 @@ use crate::event::{
     types::{
         CEvent,
         window::WindowEvent,
+        file::FileEvent,
+        input::InputState,
     }
 
 };
 use crate::renderer::RenderingSys;
-use super::logs::{init,CINFO,CDEBUG,CFATAL,CTRACE};
+use crate::asset::AssetServer;
+use crate::ecs::{Scheduler, System, World};
+use super::logs::{init,CINFO,CDEBUG,CERROR,CFATAL,CTRACE};
+use super::watch::{FileWatcher,DEFAULT_DEBOUNCE};
+use super::file;
+use super::paths;
+use super::error_handler::{EEcs, EWatch};
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ecs::SystemContext;
+    use crate::event::types::input::{Key, KeyboardEvent, StateAction};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn input_reach_the_systems() {
+
+        let mut world = World::new();
+        world.insert_resource(InputState::default());
+
+        let mut event_sys = EventSystem::new();
+        let pressed = Arc::new(AtomicBool::new(false));
+        let seen = pressed.clone();
+
+        let mut scheduler = Scheduler::with_threads(1);
+
+        scheduler.add_system(System::new("jump", move |ctx:&mut SystemContext| {
+            seen.store(ctx.resource::<InputState>()?.is_key_pressed(Key::Space), Ordering::Relaxed);
+            Ok(())
+        }).reads_resource::<InputState>()).unwrap();
+
+        event_sys.push_event(CEvent::Keyboard(KeyboardEvent::new(Key::Space, StateAction::Press)));
+        update_input(&mut world, &mut event_sys);
+        scheduler.run(&mut world).unwrap();
+
+        assert!(pressed.load(Ordering::Relaxed));
+
+        // the events are given once, the release of a later frame is seen
+        event_sys.push_event(CEvent::Keyboard(KeyboardEvent::new(Key::Space, StateAction::Release)));
+        update_input(&mut world, &mut event_sys);
+        scheduler.run(&mut world).unwrap();
+
+        assert!(!pressed.load(Ordering::Relaxed));
+        assert_eq!(event_sys.take_new_events().count(), 0);
+
+    }
+
+}
 
 // ------------------------------------------------------------------------------------------------
 // Clock information
@@ -43,6 +101,30 @@ pub fn get_prog_elapsed_time() -> Duration {
     //
 }
 //
+//
+/// The clock of the engine loop, a resource of the engine world updated before the systems run
+#[derive(Debug,Clone,Copy,Default)]
+pub struct Time {
+
+    delta:      Duration,
+    elapsed:    Duration,
+    frame:      u64
+
+}
+//
+impl Time {
+    //
+    /// the time since the previous frame, since the engine initialisation on the first one
+    pub fn delta(&self) -> Duration { self.delta }
+    //
+    /// the time since the program started
+    pub fn elapsed(&self) -> Duration { self.elapsed }
+    //
+    /// number of the frame, the first one is 1
+    pub fn frame(&self) -> u64 { self.frame }
+    //
+}
+//
 // ------------------------------------------------------------------------------------------------
 // Host information
 //
@@ -88,6 +170,9 @@ pub struct Engine {
 
     rendering_sys:          RenderingSys,
     event_sys:              EventSystem,
+    file_watcher:           Option<FileWatcher>,
+    world:                  World,
+    scheduler:              Scheduler,
 
     app_should_close:       bool,
 
@@ -107,6 +192,8 @@ impl Engine {
 
         CINFO("Logging system initialized successfully");
 
+        paths::set_application_name(application_name);
+
         CTRACE("Start engine initialisation");
 
         CTRACE("Start the rendering system initialisation");
@@ -125,15 +212,132 @@ impl Engine {
         CINFO("Event system initialising");
 
 
+        // the state of the engine that the systems can reach
+        let mut world = World::new();
+        world.insert_resource(Time { elapsed: get_prog_elapsed_time(), ..Time::default() });
+        world.insert_resource(AssetServer::with_default_loaders());
+        world.insert_resource(InputState::default());
+
         CINFO("Engine initialisation done");
 
 
         Self {
                                     rendering_sys,
                                     event_sys,
+            file_watcher:           None,
+                                    world,
+            scheduler:              Scheduler::new(),
             app_should_close:       false
         }
 
+    }
+    //
+    /// Watch a directory for changes, every file created, modified or removed under it is sent
+    /// as a CEvent::File by the event system
+    ///
+    /// # Parameters
+    ///
+    /// * 'dir' - the directory to watch
+    ///
+    pub fn watch_directory(&mut self, dir:&str) -> Result<(),EWatch> {
+
+        if self.file_watcher.is_none() {
+
+            CTRACE("Start the file watcher");
+            self.file_watcher = Some(FileWatcher::new(DEFAULT_DEBOUNCE)?);
+
+        }
+
+        if let Some(watcher) = self.file_watcher.as_mut() {
+
+            watcher.watch(dir)?;
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// the asset server of the engine, it already has the loaders of the engine asset types. It is
+    /// a resource of the world, this panic if it has been removed from it
+    pub fn assets(&self) -> &AssetServer {
+
+        self.world.resource::<AssetServer>().expect("the asset server was removed from the engine world")
+
+    }
+    //
+    pub fn assets_mut(&mut self) -> &mut AssetServer {
+
+        self.world.resource_mut::<AssetServer>().expect("the asset server was removed from the engine world")
+
+    }
+    //
+    /// the entities of the engine and their components
+    pub fn world(&self) -> &World { &self.world }
+    //
+    pub fn world_mut(&mut self) -> &mut World { &mut self.world }
+    //
+    /// Add a system run by the engine every frame
+    ///
+    /// # Parameters
+    ///
+    /// * 'system' - the system, its name must be unique
+    ///
+    pub fn add_system(&mut self, system:System) -> Result<(),EEcs> { self.scheduler.add_system(system) }
+    //
+    /// Work that is done once per frame of the engine loop
+    pub fn update(&mut self) {
+
+        file::dispatch_loaded();
+
+        if let Some(watcher) = self.file_watcher.as_mut() {
+
+            for event in watcher.poll_events() {
+
+                // a removed file is not reloaded, the assets keep their last version
+                if !matches!(event, FileEvent::Removed(_)) {
+
+                    if let Some(server) = self.world.resource_mut::<AssetServer>() {
+                        server.file_changed(event.path());
+                    }
+
+                }
+
+                self.event_sys.push_event(CEvent::File(event));
+
+            }
+
+        }
+
+        if let Some(server) = self.world.resource_mut::<AssetServer>() {
+
+            server.update();
+
+            for event in server.take_events() {
+
+                self.event_sys.push_event(CEvent::Asset(event));
+
+            }
+
+        }
+
+        update_input(&mut self.world, &mut self.event_sys);
+
+        let elapsed = get_prog_elapsed_time();
+
+        if let Some(time) = self.world.resource_mut::<Time>() {
+
+            time.delta = elapsed.saturating_sub(time.elapsed);
+            time.elapsed = elapsed;
+            time.frame += 1;
+
+        }
+
+        // the failing systems don't stop the frame, they are run again on the next one
+        if let Err(e) = self.scheduler.run(&mut self.world) {
+            CERROR(&format!("{:?}",e));
+        }
+
     }
     //
     fn close(&self) {
@@ -184,4 +388,20 @@ impl Engine {
     }
 
     //
-}
\ No newline at end of file
+}
+//
+//
+/// Give the input events received since the previous frame to the input state of the world
+fn update_input(world:&mut World, event_sys:&mut EventSystem) {
+
+    let events = event_sys.take_new_events();
+
+    if let Some(input) = world.resource_mut::<InputState>() {
+
+        for event in events {
+            input.handle_event(event);
+        }
+
+    }
+
+}
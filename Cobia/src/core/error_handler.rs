@@ -205,6 +205,443 @@ impl fmt::Display for EFile {
 impl Context for EFile {}
 //
 //
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EPack {
+    Io,
+    Format,
+    Compression,
+    Checksum,
+    Entry
+}
+//
+impl EPack {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EPack {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Io =>             write!(f, "Pack Io Error"),
+            Self::Format =>         write!(f, "Pack Format Error"),
+            Self::Compression =>    write!(f, "Pack Compression Error"),
+            Self::Checksum =>       write!(f, "Pack Checksum Error"),
+            Self::Entry =>          write!(f, "Pack Entry Error")
+
+        }
+    }
+
+}
+//
+impl Context for EPack {}
+//
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EWatch {
+    Init,
+    Path,
+    Backend
+}
+//
+impl EWatch {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EWatch {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Init =>       write!(f, "File Watcher Initialisation Error"),
+            Self::Path =>       write!(f, "File Watcher Path Error"),
+            Self::Backend =>    write!(f, "File Watcher Backend Error")
+
+        }
+    }
+
+}
+//
+impl Context for EWatch {}
+//
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EPaths {
+    Unresolved,
+    Creation,
+    Write
+}
+//
+impl EPaths {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EPaths {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Unresolved => write!(f, "User Directory Resolution Error"),
+            Self::Creation =>   write!(f, "User Directory Creation Error"),
+            Self::Write =>      write!(f, "Atomic Write Error")
+
+        }
+    }
+
+}
+//
+impl Context for EPaths {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Image module (error that occurs when decoding or handling images)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EImage {
+    Load,
+    Decode,
+    Unsupported,
+    Layout,
+    Encode
+}
+//
+impl EImage {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EImage {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Load =>           write!(f, "Image Load Error"),
+            Self::Decode =>         write!(f, "Image Decode Error"),
+            Self::Unsupported =>    write!(f, "Unsupported Image Error"),
+            Self::Layout =>         write!(f, "Image Layout Error"),
+            Self::Encode =>         write!(f, "Image Encode Error")
+
+        }
+    }
+
+}
+//
+impl Context for EImage {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Asset module (error that occurs when loading assets through the asset server)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EAsset {
+    Loader,
+    Load,
+    Decode
+}
+//
+impl EAsset {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EAsset {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Loader =>     write!(f, "Asset Loader Error"),
+            Self::Load =>       write!(f, "Asset Load Error"),
+            Self::Decode =>     write!(f, "Asset Decode Error")
+
+        }
+    }
+
+}
+//
+impl Context for EAsset {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Mesh module (error that occurs when importing or decoding meshes)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EMesh {
+    Load,
+    Parse,
+    Format
+}
+//
+impl EMesh {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EMesh {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Load =>      write!(f, "Mesh Load Error"),
+            Self::Parse =>     write!(f, "Mesh Parse Error"),
+            Self::Format =>    write!(f, "Mesh Format Error")
+
+        }
+    }
+
+}
+//
+impl Context for EMesh {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Cook module (error that occurs when cooking assets offline)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum ECook {
+    Io,
+    Import,
+    Manifest
+}
+//
+impl ECook {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for ECook {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Io =>          write!(f, "Cook Io Error"),
+            Self::Import =>      write!(f, "Cook Import Error"),
+            Self::Manifest =>    write!(f, "Cook Manifest Error")
+
+        }
+    }
+
+}
+//
+impl Context for ECook {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Scene module (error that occurs when importing a scene)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EScene {
+    Load,
+    Parse,
+    Format
+}
+//
+impl EScene {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EScene {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Load =>      write!(f, "Scene Load Error"),
+            Self::Parse =>     write!(f, "Scene Parse Error"),
+            Self::Format =>    write!(f, "Scene Format Error")
+
+        }
+    }
+
+}
+//
+impl Context for EScene {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Font module (error that occurs when loading fonts and rendering glyphs)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EFont {
+    Load,
+    Parse,
+    Atlas
+}
+//
+impl EFont {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for EFont {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Load =>      write!(f, "Font Load Error"),
+            Self::Parse =>     write!(f, "Font Parse Error"),
+            Self::Atlas =>     write!(f, "Glyph Atlas Error")
+
+        }
+    }
+
+}
+//
+impl Context for EFont {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Sprite module (error that occurs with the sprite sheets)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum ESprite {
+    Load,
+    Parse,
+    Save
+}
+//
+impl ESprite {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+    pub fn attach_printable_default<T:std::error::Error>(&self,error:T) -> Report<Self> {
+
+        self.as_report().attach_printable(error.to_string())
+
+    }
+
+}
+//
+impl fmt::Display for ESprite {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Load =>      write!(f, "Sprite Sheet Load Error"),
+            Self::Parse =>     write!(f, "Sprite Sheet Parse Error"),
+            Self::Save =>      write!(f, "Sprite Sheet Save Error")
+
+        }
+    }
+
+}
+//
+impl Context for ESprite {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Ecs module (error that occurs when using the entities of a world)
+//
+//
+#[derive(Debug,Clone, Copy)]
+pub enum EEcs {
+    Entity,
+    Access,
+    Schedule,
+    System,
+    Resource
+}
+//
+impl EEcs {
+
+    pub fn as_report(&self) -> Report<Self> { Report::new(*self)}
+
+}
+//
+impl fmt::Display for EEcs {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+
+            Self::Entity =>    write!(f, "Ecs Entity Error"),
+            Self::Access =>    write!(f, "Ecs Access Error"),
+            Self::Schedule =>  write!(f, "Ecs Schedule Error"),
+            Self::System =>    write!(f, "Ecs System Error"),
+            Self::Resource =>  write!(f, "Ecs Resource Error")
+
+        }
+    }
+
+}
+//
+impl Context for EEcs {}
+//
+//
 // ------------------------------------------------------------------------------------------------
 // Rendering module (High Level error that occurs in the rendering module)
 // 
@@ -1,9 +1,125 @@
 
 use std::path::Path; 
-use std::fs;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::Deref;
 
 use error_stack::{Result, ResultExt};
 use super::error_handler::{EFile,EGeneral};
+use super::pack::{PackArchive, PackEntryReader};
+use super::loader::{FileLoader, LoadHandle, LoadPriority};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::core::pack::{Compression, PackWriter};
+    use crate::test_helper::tmp_path;
+
+    fn check_stream(mut stream:FileStream, expected:&[u8]) {
+
+        assert_eq!(stream.len(), expected.len() as u64);
+
+        let mut head = [0_u8;10];
+        stream.read_exact(&mut head).unwrap();
+        assert_eq!(head, expected[..10]);
+
+        stream.seek(SeekFrom::End(-5)).unwrap();
+        let mut tail = Vec::new();
+        stream.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, expected[expected.len() - 5..]);
+
+        // backward seek
+        stream.seek(SeekFrom::Start(100)).unwrap();
+        let mut middle = [0_u8;4];
+        stream.read_exact(&mut middle).unwrap();
+        assert_eq!(middle, expected[100..104]);
+
+    }
+
+    #[test]
+    fn mapped_and_streamed_file() {
+
+        let path = tmp_path("file", "stream");
+        let data:Vec<u8> = (0..5000_u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &data).unwrap();
+
+        let mapped = map_file(&path).unwrap();
+        assert_eq!(&mapped[..], &data[..]);
+
+        check_stream(open_stream(&path).unwrap(), &data);
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn streamed_pack_entry() {
+
+        let path = tmp_path("file", "stream_pack.cpak");
+        let data:Vec<u8> = (0..5000_u32).map(|i| (i % 7) as u8).collect();
+
+        let mut writer = PackWriter::new();
+        writer.add("deflate.bin", data.clone(), Compression::Deflate).unwrap();
+        writer.add("stored.bin", data.clone(), Compression::None).unwrap();
+        writer.write(&path).unwrap();
+
+        check_stream(open_pack_entry_stream(&path, "deflate.bin").unwrap(), &data);
+        check_stream(open_pack_entry_stream(&path, "stored.bin").unwrap(), &data);
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Asynchronous loading
+//
+// number of worker threads used by the engine file loader
+const LOADER_MAX_WORKERS: usize = 4;
+//
+lazy_static::lazy_static! {
+
+    static ref FILE_LOADER: FileLoader = FileLoader::new(
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(LOADER_MAX_WORKERS)
+    );
+
+}
+//
+//
+/// read a file on the engine worker pool with a normal priority
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to extract his content
+///
+pub fn load_async(fp:&str) -> LoadHandle { FILE_LOADER.load(fp, LoadPriority::Normal) }
+//
+//
+/// read a file on the engine worker pool
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to extract his content
+/// * 'priority' - requests with a higher priority are served first
+///
+pub fn load_async_with_priority(fp:&str, priority:LoadPriority) -> LoadHandle {
+    FILE_LOADER.load(fp, priority)
+}
+//
+//
+/// call the completion callbacks of the asynchronous loads that are finished. Must be called
+/// by the main thread, the engine does it once per frame
+pub fn dispatch_loaded() -> usize { FILE_LOADER.dispatch_completed() }
 //
 //
 //
@@ -157,4 +273,265 @@ pub(crate) fn get_file_content(fp:&str) -> Result<Vec<u8>,EFile> {
 
 }
 //
-//
\ No newline at end of file
+//
+/// get the content of an entry stored in a pack archive and return it as bytes
+///
+/// # Parameters
+///
+/// * 'pack' - A file path to the pack archive
+/// * 'entry' - the path of the entry inside the pack
+///
+pub(crate) fn get_pack_entry_content(pack:&str, entry:&str) -> Result<Vec<u8>,EFile> {
+    //
+    // check first that the pack path passed is valid
+    is_a_correct_path(pack)
+        .change_context(EFile::Content)
+        .attach_printable("Can't get pack entry content")?;
+
+    PackArchive::open(pack)
+        .and_then(|mut archive| archive.read(entry))
+        .change_context(EFile::Content)
+        .attach_printable_lazy(|| format!("cant access the entry {} of the pack {}",entry,pack))
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Memory mapped file
+//
+/// Read only view of a whole file that the os page in on demand. The file should not be
+/// modified by another process while it is mapped
+pub struct MappedFile {
+
+    #[cfg(unix)]
+    ptr:    *const u8,
+    #[cfg(not(unix))]
+    data:   Vec<u8>,
+    len:    usize
+
+}
+//
+// the mapping is read only so it can be shared between threads
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+//
+impl Deref for MappedFile {
+
+    type Target = [u8];
+
+    #[cfg(unix)]
+    fn deref(&self) -> &[u8] {
+
+        if self.len == 0 {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+
+    }
+
+    #[cfg(not(unix))]
+    fn deref(&self) -> &[u8] { &self.data }
+
+}
+//
+#[cfg(unix)]
+impl Drop for MappedFile {
+
+    fn drop(&mut self) {
+
+        if self.len != 0 {
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len); }
+        }
+
+    }
+
+}
+//
+//
+/// map a file in memory instead of reading it. On the platforms without mmap support the file
+/// is read
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to be mapped
+///
+pub fn map_file(fp:&str) -> Result<MappedFile,EFile> {
+    //
+    // check first that the file path passed is valid
+    is_a_correct_path(fp)
+        .change_context(EFile::Content)
+        .attach_printable("Can't map file")?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let file = File::open(fp).map_err(|e|
+            EFile::Content
+                .as_report()
+                .attach_printable(format!("cant open file {} because of {}",fp,e))
+        )?;
+
+        let len = file.metadata().map_err(|e|
+            EFile::Content
+                .as_report()
+                .attach_printable(format!("cant access file {} metadata because of {}",fp,e))
+        )?.len() as usize;
+
+        // mmap refuse empty mapping
+        if len == 0 {
+            return Ok(MappedFile { ptr: std::ptr::null(), len: 0 });
+        }
+
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+
+            return Err(EFile::Content
+                .as_report()
+                .attach_printable(format!(
+                    "cant map file {} because of {}",
+                    fp,
+                    std::io::Error::last_os_error()
+                    )
+                )
+            );
+
+        }
+
+        // the mapping stay valid once the file is closed
+        Ok(MappedFile { ptr: ptr as *const u8, len })
+
+    }
+
+    #[cfg(not(unix))]
+    {
+        let data = get_file_content(fp)?;
+        let len = data.len();
+
+        Ok(MappedFile { data, len })
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Streaming
+//
+enum StreamSource {
+    Disk(BufReader<File>),
+    Pack(PackEntryReader)
+}
+//
+/// Read + Seek over a file on the disk or an entry of a pack, so decoders can pull the data
+/// they need instead of the whole file
+pub struct FileStream {
+
+    source: StreamSource,
+    len:    u64
+
+}
+//
+impl FileStream {
+    //
+    /// size in bytes of what is streamed
+    pub fn len(&self) -> u64 { self.len }
+    //
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+    //
+}
+//
+impl Read for FileStream {
+
+    fn read(&mut self, buf:&mut [u8]) -> std::io::Result<usize> {
+
+        match &mut self.source {
+            StreamSource::Disk(reader) => reader.read(buf),
+            StreamSource::Pack(reader) => reader.read(buf)
+        }
+
+    }
+
+}
+//
+impl Seek for FileStream {
+
+    fn seek(&mut self, pos:SeekFrom) -> std::io::Result<u64> {
+
+        match &mut self.source {
+            StreamSource::Disk(reader) => reader.seek(pos),
+            StreamSource::Pack(reader) => reader.seek(pos)
+        }
+
+    }
+
+}
+//
+//
+/// open a file to be read incrementally
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to be streamed
+///
+pub fn open_stream(fp:&str) -> Result<FileStream,EFile> {
+    //
+    // check first that the file path passed is valid
+    is_a_correct_path(fp)
+        .change_context(EFile::Content)
+        .attach_printable("Can't open file stream")?;
+
+    let file = File::open(fp).map_err(|e|
+        EFile::Content
+            .as_report()
+            .attach_printable(format!("cant open file {} because of {}",fp,e))
+    )?;
+
+    let len = file.metadata().map_err(|e|
+        EFile::Content
+            .as_report()
+            .attach_printable(format!("cant access file {} metadata because of {}",fp,e))
+    )?.len();
+
+    Ok(FileStream { source: StreamSource::Disk(BufReader::new(file)), len })
+
+}
+//
+//
+/// open an entry of a pack archive to be read incrementally
+///
+/// # Parameters
+///
+/// * 'pack' - A file path to the pack archive
+/// * 'entry' - the path of the entry inside the pack
+///
+pub fn open_pack_entry_stream(pack:&str, entry:&str) -> Result<FileStream,EFile> {
+    //
+    // check first that the pack path passed is valid
+    is_a_correct_path(pack)
+        .change_context(EFile::Content)
+        .attach_printable("Can't open pack entry stream")?;
+
+    let reader = PackArchive::open(pack)
+        .and_then(|archive| archive.open_entry_reader(entry))
+        .change_context(EFile::Content)
+        .attach_printable_lazy(|| format!("cant stream the entry {} of the pack {}",entry,pack))?;
+
+    let len = reader.len();
+
+    Ok(FileStream { source: StreamSource::Pack(reader), len })
+
+}
+//
+//
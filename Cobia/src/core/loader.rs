@@ -0,0 +1,584 @@
+// Worker pool that load files away from the main thread
+//
+// A load request is queued by priority, picked by the first worker available and its result is
+// stored in a slot shared with the LoadHandle given back to the caller. Completion callbacks are
+// never called by the workers: they are kept until the main thread call dispatch_completed
+// during the engine loop.
+//
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Ordering as CmpOrdering;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+
+use error_stack::{Report, Result};
+
+use super::error_handler::EFile;
+use super::file::get_file_content;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::tmp_path;
+    use std::fs;
+
+    fn tmp_file(name:&str, content:&[u8]) -> String {
+
+        let path = tmp_path("loader", name);
+        fs::write(&path, content).unwrap();
+        path
+
+    }
+
+    #[test]
+    fn load_and_callback() {
+
+        let loader = FileLoader::new(2);
+        let path = tmp_file("callback", b"content");
+
+        let handle = loader.load(&path, LoadPriority::Normal);
+        let received = Arc::new(Mutex::new(None));
+        let target = received.clone();
+
+        handle.on_complete(move |result| {
+            *target.lock().unwrap() = Some(result.unwrap());
+        });
+
+        handle.wait();
+        assert_eq!(handle.state(), LoadState::Loaded);
+        // the callback only run when the main thread ask for it
+        assert!(received.lock().unwrap().is_none());
+
+        assert_eq!(loader.dispatch_completed(), 1);
+        assert_eq!(received.lock().unwrap().as_deref(), Some(&b"content"[..]));
+        assert!(handle.take().is_none());
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn load_failure_and_cancel() {
+
+        let loader = FileLoader::new(1);
+
+        let missing = loader.load("/this/file/does/not/exist", LoadPriority::High);
+        missing.wait();
+        assert_eq!(missing.state(), LoadState::Failed);
+        assert!(missing.take().unwrap().is_err());
+
+        let path = tmp_file("cancel", b"x");
+        let handle = loader.load(&path, LoadPriority::Low);
+        handle.cancel();
+        handle.wait();
+
+        assert_eq!(handle.state(), LoadState::Cancelled);
+        assert!(handle.take().is_none());
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn cancel_while_loading() {
+
+        let loader = FileLoader::new(0);
+        let handle = loader.load("file", LoadPriority::Normal);
+
+        let called = Arc::new(AtomicBool::new(false));
+        let flag = called.clone();
+        handle.on_complete(move |_| flag.store(true, Ordering::Relaxed));
+
+        // play the worker: the request is cancelled after it was picked
+        let job = loader.shared.queue().pop().unwrap();
+        *job.slot.lock() = SlotContent::Loading;
+
+        handle.cancel();
+        assert!(loader.shared.lock_callbacks().is_empty());
+
+        assert!(!job.slot.finish_loaded(Ok(vec![1])));
+        assert_eq!(handle.state(), LoadState::Cancelled);
+        assert_eq!(loader.dispatch_completed(), 0);
+        assert!(!called.load(Ordering::Relaxed));
+
+    }
+
+    #[test]
+    fn priority_order() {
+
+        // no worker so the queue can be inspected before anything is loaded
+        let loader = FileLoader::new(0);
+
+        loader.load("low", LoadPriority::Low);
+        loader.load("normal_1", LoadPriority::Normal);
+        loader.load("high", LoadPriority::High);
+        loader.load("normal_2", LoadPriority::Normal);
+
+        let order:Vec<String> = std::iter::from_fn(|| loader.shared.queue().pop())
+            .map(|job| job.path)
+            .collect();
+
+        assert_eq!(order, vec!["high","normal_1","normal_2","low"]);
+
+    }
+
+    #[test]
+    fn cancel_on_shutdown() {
+
+        // without worker the request stays queued until the loader is dropped
+        let loader = FileLoader::new(0);
+        let handle = loader.load(&tmp_file("shutdown", b"x"), LoadPriority::Normal);
+
+        assert_eq!(handle.state(), LoadState::Queued);
+
+        drop(loader);
+        handle.wait();
+
+        assert_eq!(handle.state(), LoadState::Cancelled);
+        assert!(handle.take().is_none());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Handle
+//
+/// Order in which the queued requests are served
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord)]
+pub enum LoadPriority {
+    Low,
+    Normal,
+    High
+}
+//
+/// Where a load request is at
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum LoadState {
+    Queued,
+    Loading,
+    Loaded,
+    Failed,
+    Cancelled,
+    // the result has been taken by the handle or a callback
+    Taken
+}
+//
+enum SlotContent {
+    Queued,
+    Loading,
+    Done(Result<Vec<u8>,EFile>),
+    Cancelled,
+    Taken
+}
+//
+/// State of a request shared between the handle and the workers
+struct LoadSlot {
+
+    id:         u64,
+    content:    Mutex<SlotContent>,
+    finished:   Condvar,
+    cancelled:  AtomicBool
+
+}
+//
+impl LoadSlot {
+    //
+    fn lock(&self) -> MutexGuard<'_, SlotContent> {
+
+        // a worker can't panic while holding the lock so the content is always valid
+        self.content.lock().unwrap_or_else(|e| e.into_inner())
+
+    }
+    //
+    fn finish(&self, content:SlotContent) {
+
+        *self.lock() = content;
+        self.finished.notify_all();
+
+    }
+    //
+    /// store the result of the load, false if the request was cancelled in the meantime. The
+    /// flag is read under the lock so a cancel can't slip between the check and the store
+    fn finish_loaded(&self, result:Result<Vec<u8>,EFile>) -> bool {
+
+        let mut content = self.lock();
+        let cancelled = self.cancelled.load(Ordering::Relaxed);
+
+        *content = match cancelled {
+            true => SlotContent::Cancelled,
+            false => SlotContent::Done(result)
+        };
+
+        self.finished.notify_all();
+
+        !cancelled
+
+    }
+    //
+}
+//
+type Callback = Box<dyn FnOnce(Result<Vec<u8>,EFile>) + Send>;
+//
+/// Given back by a load request, used to follow it and get its result
+#[derive(Clone)]
+pub struct LoadHandle {
+
+    slot:   Arc<LoadSlot>,
+    loader: Arc<LoaderShared>
+
+}
+//
+impl LoadHandle {
+    //
+    /// unique id of the request
+    pub fn id(&self) -> u64 { self.slot.id }
+    //
+    pub fn state(&self) -> LoadState {
+
+        match *self.slot.lock() {
+            SlotContent::Queued => LoadState::Queued,
+            SlotContent::Loading => LoadState::Loading,
+            SlotContent::Done(Ok(_)) => LoadState::Loaded,
+            SlotContent::Done(Err(_)) => LoadState::Failed,
+            SlotContent::Cancelled => LoadState::Cancelled,
+            SlotContent::Taken => LoadState::Taken
+        }
+
+    }
+    //
+    /// true once the request can't change anymore
+    pub fn is_finished(&self) -> bool {
+
+        !matches!(self.state(), LoadState::Queued | LoadState::Loading)
+
+    }
+    //
+    /// Block the calling thread until the request is finished
+    pub fn wait(&self) {
+
+        let mut content = self.slot.lock();
+
+        while matches!(*content, SlotContent::Queued | SlotContent::Loading) {
+
+            content = self.slot.finished.wait(content).unwrap_or_else(|e| e.into_inner());
+
+        }
+
+    }
+    //
+    /// Take the result of a finished request, None if it is not finished, has been cancelled
+    /// or has already been taken
+    pub fn take(&self) -> Option<Result<Vec<u8>,EFile>> {
+
+        let mut content = self.slot.lock();
+
+        match std::mem::replace(&mut *content, SlotContent::Taken) {
+
+            SlotContent::Done(result) => Some(result),
+            other => {
+                *content = other;
+                None
+            }
+
+        }
+
+    }
+    //
+    /// Ask to drop the request. A queued request is never loaded and the result of a request
+    /// that is loading is thrown away
+    pub fn cancel(&self) {
+
+        self.slot.cancelled.store(true, Ordering::Relaxed);
+        self.loader.lock_callbacks().remove(&self.slot.id);
+
+        let mut content = self.slot.lock();
+
+        if matches!(*content, SlotContent::Queued | SlotContent::Done(_)) {
+
+            *content = SlotContent::Cancelled;
+            self.slot.finished.notify_all();
+
+        }
+
+    }
+    //
+    /// Register a function that receive the result once the request is finished. It is called
+    /// by the main thread when the loader dispatch the completed requests, never if the request
+    /// is cancelled
+    ///
+    /// # Parameters
+    ///
+    /// * 'callback' - the function that take the result
+    ///
+    pub fn on_complete<F>(&self, callback:F)
+        where F: FnOnce(Result<Vec<u8>,EFile>) + Send + 'static {
+
+        self.loader.lock_callbacks().insert(self.slot.id, Box::new(callback));
+
+        // the request may already be finished and dispatched
+        if self.is_finished() {
+            self.loader.lock_completed().push(self.slot.clone());
+        }
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loader
+//
+struct Job {
+
+    priority:   LoadPriority,
+    sequence:   u64,
+    path:       String,
+    slot:       Arc<LoadSlot>
+
+}
+//
+impl Ord for Job {
+
+    // highest priority first, then first come first served
+    fn cmp(&self, other:&Self) -> CmpOrdering {
+
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+
+    }
+
+}
+//
+impl PartialOrd for Job {
+
+    fn partial_cmp(&self, other:&Self) -> Option<CmpOrdering> { Some(self.cmp(other)) }
+
+}
+//
+impl PartialEq for Job {
+
+    fn eq(&self, other:&Self) -> bool { self.sequence == other.sequence }
+
+}
+//
+impl Eq for Job {}
+//
+//
+/// Part of the loader shared with the workers and the handles
+struct LoaderShared {
+
+    queue:          Mutex<BinaryHeap<Job>>,
+    job_available:  Condvar,
+    shutdown:       AtomicBool,
+    next_id:        AtomicU64,
+    completed:      Mutex<Vec<Arc<LoadSlot>>>,
+    callbacks:      Mutex<HashMap<u64, Callback>>
+
+}
+//
+impl LoaderShared {
+
+    fn queue(&self) -> MutexGuard<'_, BinaryHeap<Job>> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_completed(&self) -> MutexGuard<'_, Vec<Arc<LoadSlot>>> {
+        self.completed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_callbacks(&self) -> MutexGuard<'_, HashMap<u64, Callback>> {
+        self.callbacks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+}
+//
+/// Pool of worker threads that read files, the requests still queued when it is dropped are
+/// cancelled
+pub struct FileLoader {
+
+    shared:     Arc<LoaderShared>,
+    workers:    Vec<JoinHandle<()>>
+
+}
+//
+impl FileLoader {
+    //
+    /// Start the worker threads
+    ///
+    /// # Parameters
+    ///
+    /// * 'workers' - number of threads in the pool
+    ///
+    pub fn new(workers:usize) -> Self {
+
+        let shared = Arc::new(LoaderShared {
+            queue:          Mutex::new(BinaryHeap::new()),
+            job_available:  Condvar::new(),
+            shutdown:       AtomicBool::new(false),
+            next_id:        AtomicU64::new(1),
+            completed:      Mutex::new(Vec::new()),
+            callbacks:      Mutex::new(HashMap::new())
+        });
+
+        let workers = (0..workers)
+            .map(|i| {
+
+                let shared = shared.clone();
+
+                thread::Builder::new()
+                    .name(format!("cobia-loader-{}",i))
+                    .spawn(move || worker_loop(shared))
+                    .expect("failed to spawn a file loader thread")
+
+            })
+            .collect();
+
+        Self { shared, workers }
+
+    }
+    //
+    /// Queue a file to be read by the pool
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - the file to read
+    /// * 'priority' - requests with a higher priority are served first
+    ///
+    pub fn load(&self, path:&str, priority:LoadPriority) -> LoadHandle {
+
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let slot = Arc::new(LoadSlot {
+                        id,
+            content:    Mutex::new(SlotContent::Queued),
+            finished:   Condvar::new(),
+            cancelled:  AtomicBool::new(false)
+        });
+
+        self.shared.queue().push(Job {
+                        priority,
+            sequence:   id,
+            path:       path.to_string(),
+            slot:       slot.clone()
+        });
+
+        self.shared.job_available.notify_one();
+
+        LoadHandle { slot, loader: self.shared.clone() }
+
+    }
+    //
+    /// Call the callbacks of the finished requests on the calling thread, return how many have
+    /// been called
+    pub fn dispatch_completed(&self) -> usize {
+
+        let completed:Vec<Arc<LoadSlot>> = self.shared.lock_completed().drain(..).collect();
+        let mut called = 0;
+
+        for slot in completed {
+
+            let callback = match self.shared.lock_callbacks().remove(&slot.id) {
+                Some(c) => c,
+                None => continue
+            };
+
+            let handle = LoadHandle { slot, loader: self.shared.clone() };
+
+            if let Some(result) = handle.take() {
+
+                callback(result);
+                called += 1;
+
+            }
+
+        }
+
+        called
+
+    }
+    //
+    /// number of requests that wait for a worker
+    pub fn queued(&self) -> usize { self.shared.queue().len() }
+    //
+}
+//
+impl Drop for FileLoader {
+
+    fn drop(&mut self) {
+
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        self.shared.job_available.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+
+        // the requests no worker took are never loaded, their handles must not wait for them
+        let remaining:Vec<Job> = self.shared.queue().drain().collect();
+
+        for job in remaining {
+            self.shared.lock_callbacks().remove(&job.slot.id);
+            job.slot.finish(SlotContent::Cancelled);
+        }
+
+    }
+
+}
+//
+//
+fn worker_loop(shared:Arc<LoaderShared>) {
+
+    loop {
+
+        let job = {
+
+            let mut queue = shared.queue();
+
+            loop {
+
+                if shared.shutdown.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                match queue.pop() {
+                    Some(job) => break job,
+                    None => {
+                        queue = shared.job_available.wait(queue).unwrap_or_else(|e| e.into_inner())
+                    }
+                }
+
+            }
+
+        };
+
+        {
+            let mut content = job.slot.lock();
+
+            if job.slot.cancelled.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            *content = SlotContent::Loading;
+        }
+
+        let result = get_file_content(&job.path).map_err(|e: Report<EFile>|
+            e.attach_printable(format!("asynchronous load of {} failed",job.path))
+        );
+
+        if job.slot.finish_loaded(result) {
+            shared.lock_completed().push(job.slot);
+        }
+
+    }
+
+}
+//
+//
@@ -4,5 +4,9 @@ pub mod logs;
 pub mod error_handler;
 
 pub mod application;
-pub(crate) mod file;
+pub mod file;
+pub mod loader;
+pub mod paths;
+pub mod pack;
+pub mod watch;
 
@@ -0,0 +1,1038 @@
+//! Cobia pack archive (`.cpak`)
+//!
+//! A pack gathers many asset files in a single file so a shipped game doesn't have to open
+//! thousands of them. Every number is stored in little endian.
+//!
+//! ```text
+//! Header (32 bytes)
+//!     magic           [u8;4]      "CPAK"
+//!     version         u16         PACK_VERSION
+//!     flags           u16         reserved, always 0
+//!     entry_count     u32         number of records in the TOC
+//!     toc_offset      u64         position of the TOC from the start of the file
+//!     toc_size        u64         size in bytes of the TOC
+//!     reserved        u32         always 0
+//!
+//! Data section
+//!     the stored bytes of every entry, back to back, right after the header
+//!
+//! TOC (one record per entry, sorted by path hash)
+//!     path_hash       u64         FNV-1a 64 bits hash of the entry path
+//!     offset          u64         position of the stored bytes from the start of the file
+//!     stored_size     u64         size of the stored (maybe compressed) bytes
+//!     raw_size        u64         size of the entry once decompressed
+//!     crc32           u32         checksum of the decompressed bytes
+//!     compression     u8          0 = stored, 1 = deflate
+//!     path_len        u16         length of the path in bytes
+//!     path            [u8]        utf-8 path relative to the packed directory, '/' separated
+//! ```
+//!
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path};
+
+use error_stack::{Result, ResultExt};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+
+use super::error_handler::EPack;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::tmp_path;
+
+    #[test]
+    fn pack_round_trip() {
+
+        let path = tmp_path("pack", "round_trip.cpak");
+
+        let text = "a text that should be compressed ".repeat(64);
+
+        let mut writer = PackWriter::new();
+        writer.add("shader/base.vert", text.as_bytes().to_vec(), Compression::Deflate).unwrap();
+        writer.add("image\\logo.png", vec![1,2,3,4,5], Compression::Deflate).unwrap();
+        writer.write(&path).unwrap();
+
+        let mut pack = PackArchive::open(&path).unwrap();
+
+        assert_eq!(pack.entries().len(), 2);
+        assert_eq!(pack.read("shader/base.vert").unwrap(), text.as_bytes());
+        assert_eq!(pack.read("image/logo.png").unwrap(), vec![1,2,3,4,5]);
+        // small entries that don't gain anything from deflate are stored as is
+        assert_eq!(pack.find("image/logo.png").unwrap().compression(), Compression::None);
+        assert_eq!(pack.find("shader/base.vert").unwrap().compression(), Compression::Deflate);
+        assert!(pack.read("missing").is_err());
+        pack.verify().unwrap();
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn pack_detect_corruption() {
+
+        let path = tmp_path("pack", "corruption.cpak");
+
+        let mut writer = PackWriter::new();
+        writer.add("data.bin", vec![7;128], Compression::None).unwrap();
+        writer.write(&path).unwrap();
+
+        // flip a byte of the data section
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_SIZE as usize + 3] ^= 0xFF;
+        fs::write(&path, bytes).unwrap();
+
+        let mut pack = PackArchive::open(&path).unwrap();
+
+        assert!(pack.read("data.bin").is_err());
+        assert!(pack.verify().is_err());
+
+        // a header with more records than the TOC can hold
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+
+        assert!(PackArchive::open(&path).is_err());
+
+        // an entry that claim to be bigger than the file
+        let toc = read_u64(&bytes,12) as usize;
+        bytes[8..12].copy_from_slice(&1_u32.to_le_bytes());
+        bytes[toc + 16..toc + 24].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+
+        assert!(PackArchive::open(&path).is_err());
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn seek_past_the_end() {
+
+        let path = tmp_path("pack", "seek.cpak");
+        let data:Vec<u8> = (0..4000_u32).map(|i| (i % 13) as u8).collect();
+
+        let mut writer = PackWriter::new();
+        writer.add("deflate.bin", data.clone(), Compression::Deflate).unwrap();
+        writer.add("stored.bin", data.clone(), Compression::None).unwrap();
+        writer.write(&path).unwrap();
+
+        let pack = PackArchive::open(&path).unwrap();
+
+        for name in ["deflate.bin", "stored.bin"] {
+
+            let mut reader = pack.open_entry_reader(name).unwrap();
+            let mut bytes = Vec::new();
+
+            // nothing to read past the end, a forward seek from there is still fine
+            assert_eq!(reader.seek(SeekFrom::Start(5000)).unwrap(), 5000);
+            assert_eq!(reader.read_to_end(&mut bytes).unwrap(), 0);
+            assert_eq!(reader.seek(SeekFrom::Current(10)).unwrap(), 5010);
+            assert_eq!(reader.read_to_end(&mut bytes).unwrap(), 0);
+
+            // then back in the entry
+            reader.seek(SeekFrom::End(-4)).unwrap();
+            reader.read_to_end(&mut bytes).unwrap();
+            assert_eq!(bytes, data[data.len() - 4..]);
+
+        }
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+    #[test]
+    fn pack_refuse_outer_path() {
+
+        let path = tmp_path("pack", "outer_path.cpak");
+
+        let mut writer = PackWriter::new();
+        assert!(writer.add("../up.bin", vec![1], Compression::None).is_err());
+        writer.add("zz/evil", vec![1], Compression::None).unwrap();
+        writer.write(&path).unwrap();
+
+        // turn the entry path into an absolute one
+        let mut bytes = fs::read(&path).unwrap();
+        let at = bytes.len() - "zz/evil".len();
+        bytes[at..].copy_from_slice(b"/z/evil");
+        fs::write(&path, bytes).unwrap();
+
+        assert!(PackArchive::open(&path).is_err());
+
+        fs::remove_file(path).unwrap();
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Constant
+//
+pub const PACK_MAGIC:       [u8;4]  = *b"CPAK";
+pub const PACK_VERSION:     u16     = 1;
+pub const PACK_EXTENSION:   &str    = "cpak";
+const HEADER_SIZE:          u64     = 32;
+// size of a TOC record without the path
+const RECORD_SIZE:          usize   = 8 + 8 + 8 + 8 + 4 + 1 + 2;
+// the most that deflate can expand the data
+const MAX_DEFLATE_RATIO:    u64     = 1032;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Entry
+//
+/// How the bytes of an entry are stored in the pack
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Compression {
+    None,
+    Deflate
+}
+//
+impl Compression {
+
+    fn from_u8(value:u8) -> Option<Self> {
+
+        match value {
+            0 => Some(Self::None),
+            1 => Some(Self::Deflate),
+            _ => None
+        }
+
+    }
+
+    fn as_u8(&self) -> u8 {
+
+        match self {
+            Self::None => 0,
+            Self::Deflate => 1
+        }
+
+    }
+
+}
+//
+//
+/// A record of the pack table of content
+#[derive(Debug,Clone)]
+pub struct PackEntry {
+
+    path:           String,
+    path_hash:      u64,
+    offset:         u64,
+    stored_size:    u64,
+    raw_size:       u64,
+    crc32:          u32,
+    compression:    Compression
+
+}
+//
+impl PackEntry {
+    //
+    /// the path of the entry relative to the packed directory
+    pub fn path(&self) -> &str { &self.path }
+    //
+    /// size of the entry once decompressed
+    pub fn raw_size(&self) -> u64 { self.raw_size }
+    //
+    /// size that the entry take in the pack
+    pub fn stored_size(&self) -> u64 { self.stored_size }
+    //
+    /// position of the stored bytes from the start of the pack
+    pub fn offset(&self) -> u64 { self.offset }
+    //
+    /// checksum of the decompressed bytes
+    pub fn crc32(&self) -> u32 { self.crc32 }
+    //
+    pub fn compression(&self) -> Compression { self.compression }
+    //
+}
+//
+//
+/// Convert a path to the form used inside a pack ('/' separated, no leading './' or '/')
+///
+/// # Parameters
+///
+/// * 'path' - the path to normalize
+///
+pub fn normalize_entry_path(path:&str) -> String {
+
+    path.replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<&str>>()
+        .join("/")
+
+}
+//
+//
+/// true when a path joined to a directory stays inside it: no root, prefix or '..'
+fn is_inner_path(path:&str) -> bool {
+
+    !path.is_empty() && Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+
+}
+//
+//
+/// FNV-1a 64 bits hash used to index the entries of a pack
+///
+/// # Parameters
+///
+/// * 'path' - a normalized entry path
+///
+pub fn hash_entry_path(path:&str) -> u64 {
+
+    let mut hash:u64 = 0xcbf29ce484222325;
+
+    for byte in path.bytes() {
+
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+
+    }
+
+    hash
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Writer
+//
+/// Gather files in memory and write them as a pack
+pub struct PackWriter {
+
+    entries: Vec<(String, Vec<u8>, Compression)>
+
+}
+//
+impl PackWriter {
+    //
+    pub fn new() -> Self { Self { entries: Vec::new() } }
+    //
+    /// Add an entry to the future pack
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the entry inside the pack
+    /// * 'data' - the content of the entry
+    /// * 'compression' - the compression wanted. Deflate is only kept if it makes the entry smaller
+    ///
+    pub fn add(&mut self, path:&str, data:Vec<u8>, compression:Compression) -> Result<(),EPack> {
+
+        let path = normalize_entry_path(path);
+
+        if !is_inner_path(&path) || path.len() > u16::MAX as usize {
+
+            return Err(EPack::Entry
+                .as_report()
+                .attach_printable(format!("invalid entry path '{}'",path))
+            );
+
+        }
+
+        let hash = hash_entry_path(&path);
+
+        if let Some((other,_,_)) = self.entries.iter().find(|(p,_,_)| hash_entry_path(p) == hash) {
+
+            return Err(EPack::Entry
+                .as_report()
+                .attach_printable(format!("'{}' collide with the entry '{}'",path,other))
+            );
+
+        }
+
+        self.entries.push((path,data,compression));
+
+        Ok(())
+
+    }
+    //
+    /// Add every file under a directory, their entry path is relative to this directory
+    ///
+    /// # Parameters
+    ///
+    /// * 'dir' - the directory to pack
+    /// * 'compression' - the compression wanted for every file
+    ///
+    pub fn add_directory(&mut self, dir:&str, compression:Compression) -> Result<(),EPack> {
+
+        let root = Path::new(dir);
+        let mut stack = vec![root.to_path_buf()];
+
+        while let Some(current) = stack.pop() {
+
+            let read_dir = fs::read_dir(&current).map_err(|e|
+                EPack::Io
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't read directory {}",current.display()))
+            )?;
+
+            for item in read_dir {
+
+                let item = item.map_err(|e| EPack::Io.attach_printable_default(e))?;
+                let path = item.path();
+
+                if path.is_dir() {
+
+                    stack.push(path);
+                    continue;
+
+                }
+
+                let relative = path
+                    .strip_prefix(root)
+                    .map_err(|e| EPack::Entry.attach_printable_default(e))?;
+
+                let name = relative.to_str().ok_or_else(||
+                    EPack::Entry
+                        .as_report()
+                        .attach_printable(format!("{} is not a valid utf-8 path",path.display()))
+                )?;
+
+                let data = fs::read(&path).map_err(|e|
+                    EPack::Io
+                        .attach_printable_default(e)
+                        .attach_printable(format!("Can't read {}",path.display()))
+                )?;
+
+                self.add(name,data,compression)?;
+
+            }
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// Number of entries added so far
+    pub fn len(&self) -> usize { self.entries.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+    //
+    /// Write the pack to a file
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - where the pack will be written
+    ///
+    pub fn write(&self, path:&str) -> Result<(),EPack> {
+
+        let mut data:Vec<u8> = Vec::new();
+        let mut records:Vec<PackEntry> = Vec::with_capacity(self.entries.len());
+
+        for (name, raw, compression) in self.entries.iter() {
+
+            let (stored, compression) = match compression {
+
+                Compression::None => (raw.clone(), Compression::None),
+
+                Compression::Deflate => {
+
+                    let packed = deflate(raw)?;
+
+                    if packed.len() < raw.len() {
+                        (packed, Compression::Deflate)
+                    } else {
+                        (raw.clone(), Compression::None)
+                    }
+
+                }
+
+            };
+
+            records.push(PackEntry {
+                path:           name.clone(),
+                path_hash:      hash_entry_path(name),
+                offset:         HEADER_SIZE + data.len() as u64,
+                stored_size:    stored.len() as u64,
+                raw_size:       raw.len() as u64,
+                crc32:          crc32fast::hash(raw),
+                                compression
+            });
+
+            data.extend_from_slice(&stored);
+
+        }
+
+        records.sort_by_key(|r| r.path_hash);
+
+        let mut toc:Vec<u8> = Vec::new();
+
+        for r in records.iter() {
+
+            toc.extend_from_slice(&r.path_hash.to_le_bytes());
+            toc.extend_from_slice(&r.offset.to_le_bytes());
+            toc.extend_from_slice(&r.stored_size.to_le_bytes());
+            toc.extend_from_slice(&r.raw_size.to_le_bytes());
+            toc.extend_from_slice(&r.crc32.to_le_bytes());
+            toc.push(r.compression.as_u8());
+            toc.extend_from_slice(&(r.path.len() as u16).to_le_bytes());
+            toc.extend_from_slice(r.path.as_bytes());
+
+        }
+
+        let mut header:Vec<u8> = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend_from_slice(&PACK_MAGIC);
+        header.extend_from_slice(&PACK_VERSION.to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes());
+        header.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(HEADER_SIZE + data.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(toc.len() as u64).to_le_bytes());
+        header.extend_from_slice(&0_u32.to_le_bytes());
+
+        let mut file = File::create(path).map_err(|e|
+            EPack::Io
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't create pack {}",path))
+        )?;
+
+        file.write_all(&header)
+            .and_then(|_| file.write_all(&data))
+            .and_then(|_| file.write_all(&toc))
+            .map_err(|e|
+                EPack::Io
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't write pack {}",path))
+            )?;
+
+        Ok(())
+
+    }
+    //
+}
+//
+impl Default for PackWriter {
+
+    fn default() -> Self { Self::new() }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Reader
+//
+/// An opened pack, only the TOC is kept in memory
+pub struct PackArchive {
+
+    path:       String,
+    file:       File,
+    entries:    Vec<PackEntry>
+
+}
+//
+impl PackArchive {
+    //
+    /// Open a pack and read its table of content
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - the path to the pack file
+    ///
+    pub fn open(path:&str) -> Result<Self,EPack> {
+
+        let mut file = File::open(path).map_err(|e|
+            EPack::Io
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't open pack {}",path))
+        )?;
+
+        let mut header = [0_u8;HEADER_SIZE as usize];
+
+        file.read_exact(&mut header).map_err(|e|
+            EPack::Format
+                .attach_printable_default(e)
+                .attach_printable(format!("{} is too small to be a pack",path))
+        )?;
+
+        if header[0..4] != PACK_MAGIC {
+
+            return Err(EPack::Format
+                .as_report()
+                .attach_printable(format!("{} is not a cobia pack",path))
+            );
+
+        }
+
+        let version = read_u16(&header,4);
+
+        if version != PACK_VERSION {
+
+            return Err(EPack::Format
+                .as_report()
+                .attach_printable(format!(
+                    "{} use the pack version {} but only the version {} is supported",
+                    path,
+                    version,
+                    PACK_VERSION
+                    )
+                )
+            );
+
+        }
+
+        let count = read_u32(&header,8) as usize;
+        let toc_offset = read_u64(&header,12);
+        let toc_size = read_u64(&header,20);
+
+        let file_size = file.metadata().map_err(|e| EPack::Io.attach_printable_default(e))?.len();
+
+        if !matches!(toc_offset.checked_add(toc_size), Some(end) if end <= file_size) {
+
+            return Err(EPack::Format
+                .as_report()
+                .attach_printable(format!("the table of content of {} is out of the file",path))
+            );
+
+        }
+
+        // every record take at least RECORD_SIZE bytes of the TOC
+        if count as u64 > toc_size / RECORD_SIZE as u64 {
+
+            return Err(EPack::Format
+                .as_report()
+                .attach_printable(format!("the table of content of {} is truncated",path))
+            );
+
+        }
+
+        let mut toc = vec![0_u8; toc_size as usize];
+
+        file.seek(SeekFrom::Start(toc_offset))
+            .and_then(|_| file.read_exact(&mut toc))
+            .map_err(|e|
+                EPack::Format
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't read the table of content of {}",path))
+            )?;
+
+        let mut entries = Vec::with_capacity(count);
+        let mut cursor = 0;
+
+        for _ in 0..count {
+
+            if cursor + RECORD_SIZE > toc.len() {
+
+                return Err(EPack::Format
+                    .as_report()
+                    .attach_printable(format!("the table of content of {} is truncated",path))
+                );
+
+            }
+
+            let path_len = read_u16(&toc,cursor + 37) as usize;
+            let name_start = cursor + RECORD_SIZE;
+
+            let name = toc
+                .get(name_start..name_start + path_len)
+                .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                .ok_or_else(||
+                    EPack::Format
+                        .as_report()
+                        .attach_printable(format!("{} contain an invalid entry path",path))
+                )?;
+
+            // an absolute path or a '..' would escape the directory the pack is extracted to
+            if !is_inner_path(name) {
+
+                return Err(EPack::Format
+                    .as_report()
+                    .attach_printable(format!("the entry {} of {} is outside the pack",name,path))
+                );
+
+            }
+
+            let compression = Compression::from_u8(toc[cursor + 36]).ok_or_else(||
+                EPack::Format
+                    .as_report()
+                    .attach_printable(format!("unknown compression for the entry {}",name))
+            )?;
+
+            let offset = read_u64(&toc,cursor + 8);
+            let stored_size = read_u64(&toc,cursor + 16);
+
+            if !matches!(offset.checked_add(stored_size), Some(end) if end <= file_size) {
+
+                return Err(EPack::Format
+                    .as_report()
+                    .attach_printable(format!("the entry {} of {} is out of the file",name,path))
+                );
+
+            }
+
+            entries.push(PackEntry {
+                path:           name.to_string(),
+                path_hash:      read_u64(&toc,cursor),
+                                offset,
+                                stored_size,
+                raw_size:       read_u64(&toc,cursor + 24),
+                crc32:          read_u32(&toc,cursor + 32),
+                                compression
+            });
+
+            cursor = name_start + path_len;
+
+        }
+
+        Ok(Self { path: path.to_string(), file, entries })
+
+    }
+    //
+    /// the path of the pack file
+    pub fn path(&self) -> &str { &self.path }
+    //
+    /// every entry of the pack, sorted by path hash
+    pub fn entries(&self) -> &[PackEntry] { &self.entries }
+    //
+    /// Find an entry by its path
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the entry inside the pack
+    ///
+    pub fn find(&self, path:&str) -> Option<&PackEntry> {
+
+        let path = normalize_entry_path(path);
+        let hash = hash_entry_path(&path);
+
+        self.entries
+            .binary_search_by_key(&hash, |e| e.path_hash)
+            .ok()
+            .map(|i| &self.entries[i])
+            .filter(|e| e.path == path)
+
+    }
+    //
+    /// Read and decompress an entry, its checksum is validated
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the entry inside the pack
+    ///
+    pub fn read(&mut self, path:&str) -> Result<Vec<u8>,EPack> {
+
+        let entry = match self.find(path) {
+
+            Some(e) => e.clone(),
+            None => return Err(EPack::Entry
+                .as_report()
+                .attach_printable(format!("{} has no entry {}",self.path,path))
+            )
+
+        };
+
+        self.read_entry(&entry)
+
+    }
+    //
+    /// Read and decompress an entry, its checksum is validated
+    ///
+    /// # Parameters
+    ///
+    /// * 'entry' - an entry of this pack
+    ///
+    pub fn read_entry(&mut self, entry:&PackEntry) -> Result<Vec<u8>,EPack> {
+
+        let stored = self.read_stored(entry)?;
+
+        let raw = match entry.compression {
+
+            Compression::None => stored,
+            Compression::Deflate => inflate(&stored, entry.raw_size)
+                .attach_printable_lazy(|| format!("Can't decompress {}",entry.path))?
+
+        };
+
+        if raw.len() as u64 != entry.raw_size || crc32fast::hash(&raw) != entry.crc32 {
+
+            return Err(EPack::Checksum
+                .as_report()
+                .attach_printable(format!("the entry {} of {} is corrupted",entry.path,self.path))
+            );
+
+        }
+
+        Ok(raw)
+
+    }
+    //
+    /// Validate the checksum of every entry
+    pub fn verify(&mut self) -> Result<(),EPack> {
+
+        for entry in self.entries.clone().iter() {
+
+            self.read_entry(entry)?;
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// Extract every entry to a directory
+    ///
+    /// # Parameters
+    ///
+    /// * 'dir' - the destination directory, it is created if needed
+    ///
+    pub fn extract_all(&mut self, dir:&str) -> Result<(),EPack> {
+
+        for entry in self.entries.clone().iter() {
+
+            let data = self.read_entry(entry)?;
+
+            // the entries that would escape the destination are refused when the pack is opened
+            let dest = Path::new(dir).join(&entry.path);
+
+            if let Some(parent) = dest.parent() {
+
+                fs::create_dir_all(parent).map_err(|e| EPack::Io.attach_printable_default(e))?;
+
+            }
+
+            fs::write(&dest, data).map_err(|e|
+                EPack::Io
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't write {}",dest.display()))
+            )?;
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// Open a reader over an entry that decompress it as it is read, so big entries never have
+    /// to be fully in memory. The checksum is not validated
+    ///
+    /// # Parameters
+    ///
+    /// * 'path' - path of the entry inside the pack
+    ///
+    pub fn open_entry_reader(&self, path:&str) -> Result<PackEntryReader,EPack> {
+
+        let entry = self.find(path).ok_or_else(||
+            EPack::Entry
+                .as_report()
+                .attach_printable(format!("{} has no entry {}",self.path,path))
+        )?;
+
+        // the reader need its own cursor in the file
+        let file = File::open(&self.path).map_err(|e|
+            EPack::Io
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't open pack {}",self.path))
+        )?;
+
+        PackEntryReader::new(file, entry.clone())
+
+    }
+    //
+    fn read_stored(&mut self, entry:&PackEntry) -> Result<Vec<u8>,EPack> {
+
+        let mut stored = vec![0_u8; entry.stored_size as usize];
+
+        self.file.seek(SeekFrom::Start(entry.offset))
+            .and_then(|_| self.file.read_exact(&mut stored))
+            .map_err(|e|
+                EPack::Io
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't read the entry {}",entry.path))
+            )?;
+
+        Ok(stored)
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Entry reader
+//
+/// Read and seek inside a single entry of a pack. A seek backward in a deflate entry restart the
+/// decompression from the start of the entry
+pub struct PackEntryReader {
+
+    entry:      PackEntry,
+    // position in the decompressed bytes
+    position:   u64,
+    source:     EntrySource
+
+}
+//
+enum EntrySource {
+    Stored(File),
+    Deflate(DeflateDecoder<std::io::Take<File>>)
+}
+//
+impl PackEntryReader {
+    //
+    fn new(file:File, entry:PackEntry) -> Result<Self,EPack> {
+
+        let source = Self::open_source(file, &entry).map_err(|e|
+            EPack::Io
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't read the entry {}",entry.path))
+        )?;
+
+        Ok(Self { entry, position: 0, source })
+
+    }
+    //
+    fn open_source(mut file:File, entry:&PackEntry) -> std::io::Result<EntrySource> {
+
+        file.seek(SeekFrom::Start(entry.offset))?;
+
+        Ok(match entry.compression {
+            Compression::None => EntrySource::Stored(file),
+            Compression::Deflate => EntrySource::Deflate(
+                DeflateDecoder::new(file.take(entry.stored_size))
+            )
+        })
+
+    }
+    //
+    /// the entry that is read
+    pub fn entry(&self) -> &PackEntry { &self.entry }
+    //
+    /// size of the decompressed entry
+    pub fn len(&self) -> u64 { self.entry.raw_size }
+    //
+    pub fn is_empty(&self) -> bool { self.entry.raw_size == 0 }
+    //
+}
+//
+impl Read for PackEntryReader {
+
+    fn read(&mut self, buf:&mut [u8]) -> std::io::Result<usize> {
+
+        let left = self.entry.raw_size.saturating_sub(self.position);
+        let max = buf.len().min(left as usize);
+
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let read = match &mut self.source {
+            EntrySource::Stored(file) => file.read(&mut buf[..max])?,
+            EntrySource::Deflate(decoder) => decoder.read(&mut buf[..max])?
+        };
+
+        self.position += read as u64;
+
+        Ok(read)
+
+    }
+
+}
+//
+impl Seek for PackEntryReader {
+
+    fn seek(&mut self, pos:SeekFrom) -> std::io::Result<u64> {
+
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(p) => self.entry.raw_size.checked_add_signed(p),
+            SeekFrom::Current(p) => self.position.checked_add_signed(p)
+        };
+
+        let target = target.ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "seek before the start of the entry"
+        ))?;
+
+        match &mut self.source {
+
+            EntrySource::Stored(file) => {
+
+                // past the end the reads give nothing, the file stays at the end of the entry
+                file.seek(SeekFrom::Start(self.entry.offset + target.min(self.entry.raw_size)))?;
+                self.position = target;
+
+            },
+
+            EntrySource::Deflate(decoder) => {
+
+                // the position can be past the end of the entry, the stream never is
+                let mut current = self.position.min(self.entry.raw_size);
+
+                if target < current {
+
+                    let file = decoder.get_mut().get_mut().try_clone()?;
+                    self.source = Self::open_source(file, &self.entry)?;
+                    current = 0;
+
+                }
+
+                // a deflate stream can only be walked forward
+                self.position = current;
+                let skip = target.min(self.entry.raw_size) - current;
+                std::io::copy(&mut self.by_ref().take(skip), &mut std::io::sink())?;
+                self.position = target;
+
+            }
+
+        }
+
+        Ok(target)
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Helper
+//
+fn deflate(raw:&[u8]) -> Result<Vec<u8>,EPack> {
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+
+    encoder.write_all(raw)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| EPack::Compression.attach_printable_default(e))
+
+}
+//
+fn inflate(stored:&[u8], raw_size:u64) -> Result<Vec<u8>,EPack> {
+
+    // raw_size come from the file, deflate can't expand the data more than MAX_DEFLATE_RATIO
+    let capacity = raw_size.min(stored.len() as u64 * MAX_DEFLATE_RATIO);
+    let mut raw = Vec::with_capacity(capacity as usize);
+
+    // a byte more than expected is enough for the caller to see the size mismatch
+    DeflateDecoder::new(stored)
+        .take(raw_size.saturating_add(1))
+        .read_to_end(&mut raw)
+        .map_err(|e| EPack::Compression.attach_printable_default(e))?;
+
+    Ok(raw)
+
+}
+//
+fn read_u16(bytes:&[u8], at:usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+//
+fn read_u32(bytes:&[u8], at:usize) -> u32 {
+    let mut b = [0_u8;4];
+    b.copy_from_slice(&bytes[at..at + 4]);
+    u32::from_le_bytes(b)
+}
+//
+fn read_u64(bytes:&[u8], at:usize) -> u64 {
+    let mut b = [0_u8;8];
+    b.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(b)
+}
+//
+//
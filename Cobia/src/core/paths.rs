@@ -0,0 +1,370 @@
+// Per application user directories (config, data, cache, logs and saves)
+//
+// On linux and the other unix the XDG base directory specification is followed, windows use
+// %APPDATA% / %LOCALAPPDATA% and macOS the usual ~/Library folders. Every directory end with the
+// application name given to Engine::init and is created the first time it is asked for.
+//
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use error_stack::Result;
+
+use super::error_handler::EPaths;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::tmp_path;
+
+    fn fake_env(vars:&'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+
+        move |key| vars.iter().find(|(k,_)| *k == key).map(|(_,v)| v.to_string())
+
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn xdg_resolution() {
+
+        let env = fake_env(&[
+            ("HOME", "/home/cobia"),
+            ("XDG_CONFIG_HOME", "/custom/config"),
+            // relative path must be ignored
+            ("XDG_DATA_HOME", "relative/data"),
+        ]);
+
+        assert_eq!(
+            resolve_dir(UserDir::Config, "My Game", &env),
+            Some(PathBuf::from("/custom/config/My Game"))
+        );
+        assert_eq!(
+            resolve_dir(UserDir::Data, "My Game", &env),
+            Some(PathBuf::from("/home/cobia/.local/share/My Game"))
+        );
+        assert_eq!(
+            resolve_dir(UserDir::Cache, "My Game", &env),
+            Some(PathBuf::from("/home/cobia/.cache/My Game"))
+        );
+        assert_eq!(
+            resolve_dir(UserDir::Logs, "My Game", &env),
+            Some(PathBuf::from("/home/cobia/.local/state/My Game/logs"))
+        );
+        assert_eq!(
+            resolve_dir(UserDir::Saves, "My Game", &env),
+            Some(PathBuf::from("/home/cobia/.local/share/My Game/saves"))
+        );
+
+        assert_eq!(resolve_dir(UserDir::Config, "My Game", &fake_env(&[])), None);
+
+    }
+
+    #[test]
+    fn application_name_sanitized() {
+
+        assert_eq!(sanitize_name("../My:Game/2"), "My_Game_2");
+        assert_eq!(sanitize_name("..."), DEFAULT_APPLICATION_NAME);
+
+    }
+
+    #[test]
+    fn atomic_write() {
+
+        let dir = PathBuf::from(tmp_path("paths", "atomic"));
+        let file = dir.join("save").join("slot1.sav");
+
+        write_atomic(&file, b"first").unwrap();
+        write_atomic(&file, b"second").unwrap();
+
+        assert_eq!(fs::read(&file).unwrap(), b"second");
+        // the temporary file has been renamed
+        assert_eq!(fs::read_dir(file.parent().unwrap()).unwrap().count(), 1);
+
+        // concurrent writes all succeed and one of them wins
+        let contents:Vec<Vec<u8>> = (0..8).map(|i| vec![i; 4096]).collect();
+
+        std::thread::scope(|s| {
+            for content in contents.iter() {
+                s.spawn(|| write_atomic(&file, content).unwrap());
+            }
+        });
+
+        assert!(contents.contains(&fs::read(&file).unwrap()));
+        assert_eq!(fs::read_dir(file.parent().unwrap()).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Application name
+//
+const DEFAULT_APPLICATION_NAME: &str = "cobia";
+//
+lazy_static::lazy_static! {
+
+    static ref APPLICATION_NAME: Mutex<String> = Mutex::new(DEFAULT_APPLICATION_NAME.to_string());
+
+}
+//
+//
+/// Set the name used for the user directories, called by Engine::init
+///
+/// # Parameters
+///
+/// * 'name' - the application name
+///
+pub fn set_application_name(name:&str) {
+
+    let name = sanitize_name(name);
+
+    match APPLICATION_NAME.lock() {
+        Ok(mut app) => *app = name,
+        Err(e) => *e.into_inner() = name
+    }
+
+}
+//
+//
+/// the name used for the user directories
+pub fn application_name() -> String {
+
+    match APPLICATION_NAME.lock() {
+        Ok(app) => app.clone(),
+        Err(e) => e.into_inner().clone()
+    }
+
+}
+//
+//
+/// Remove the characters that can't be in a directory name on every platform
+fn sanitize_name(name:&str) -> String {
+
+    let cleaned:String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c
+        })
+        .collect();
+
+    let cleaned = cleaned.trim_matches(|c:char| c == '.' || c == '_' || c.is_whitespace());
+
+    if cleaned.is_empty() {
+        return DEFAULT_APPLICATION_NAME.to_string();
+    }
+
+    cleaned.to_string()
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Directories
+//
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum UserDir {
+    // settings of the user
+    Config,
+    // data that the application create and that must be kept
+    Data,
+    // data that can be recreated at any time
+    Cache,
+    Logs,
+    // save games, inside the data directory
+    Saves
+}
+//
+//
+/// Find where a user directory should be, None if the platform gives no clue
+///
+/// # Parameters
+///
+/// * 'kind' - the directory wanted
+/// * 'app' - the application name
+/// * 'var' - read an environment variable
+///
+fn resolve_dir(kind:UserDir, app:&str, var:&dyn Fn(&str) -> Option<String>) -> Option<PathBuf> {
+
+    // only absolute paths are valid in the environment variables
+    let absolute = |key:&str| var(key).map(PathBuf::from).filter(|p| p.is_absolute());
+
+    #[cfg(windows)]
+    let base = {
+        let roaming = absolute("APPDATA");
+        let local = absolute("LOCALAPPDATA").or_else(|| roaming.clone());
+
+        match kind {
+            UserDir::Config | UserDir::Data | UserDir::Saves => roaming?.join(app),
+            UserDir::Cache => local?.join(app).join("cache"),
+            UserDir::Logs => local?.join(app).join("logs")
+        }
+    };
+
+    #[cfg(target_os = "macos")]
+    let base = {
+        let library = absolute("HOME")?.join("Library");
+
+        match kind {
+            UserDir::Config => library.join("Preferences").join(app),
+            UserDir::Data | UserDir::Saves => library.join("Application Support").join(app),
+            UserDir::Cache => library.join("Caches").join(app),
+            UserDir::Logs => library.join("Logs").join(app)
+        }
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let base = {
+        let xdg = |key:&str, fallback:&str| {
+            absolute(key).or_else(|| absolute("HOME").map(|home| home.join(fallback)))
+        };
+
+        match kind {
+            UserDir::Config => xdg("XDG_CONFIG_HOME", ".config")?.join(app),
+            UserDir::Data | UserDir::Saves => xdg("XDG_DATA_HOME", ".local/share")?.join(app),
+            UserDir::Cache => xdg("XDG_CACHE_HOME", ".cache")?.join(app),
+            UserDir::Logs => xdg("XDG_STATE_HOME", ".local/state")?.join(app).join("logs")
+        }
+    };
+
+    match kind {
+        UserDir::Saves => Some(base.join("saves")),
+        _ => Some(base)
+    }
+
+}
+//
+//
+/// Return a user directory of the application, it is created if it doesn't exist
+///
+/// # Parameters
+///
+/// * 'kind' - the directory wanted
+///
+pub fn user_dir(kind:UserDir) -> Result<PathBuf,EPaths> {
+
+    let app = application_name();
+
+    let dir = resolve_dir(kind, &app, &|key| env::var(key).ok()).ok_or_else(||
+        EPaths::Unresolved
+            .as_report()
+            .attach_printable(format!("Can't find where the {:?} directory of {} is",kind,app))
+    )?;
+
+    fs::create_dir_all(&dir).map_err(|e|
+        EPaths::Creation
+            .attach_printable_default(e)
+            .attach_printable(format!("Can't create {}",dir.display()))
+    )?;
+
+    Ok(dir)
+
+}
+//
+pub fn config_dir() -> Result<PathBuf,EPaths> { user_dir(UserDir::Config) }
+//
+pub fn data_dir() -> Result<PathBuf,EPaths> { user_dir(UserDir::Data) }
+//
+pub fn cache_dir() -> Result<PathBuf,EPaths> { user_dir(UserDir::Cache) }
+//
+pub fn log_dir() -> Result<PathBuf,EPaths> { user_dir(UserDir::Logs) }
+//
+pub fn saves_dir() -> Result<PathBuf,EPaths> { user_dir(UserDir::Saves) }
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Atomic write
+//
+/// Write a file so it is either fully replaced or left untouched: the data goes to a temporary
+/// file in the same directory that is then renamed over the destination. On unix the directory
+/// is synced after the rename so the new file survive a crash
+///
+/// # Parameters
+///
+/// * 'path' - the file to write, its parent directories are created if needed
+/// * 'data' - the new content of the file
+///
+pub fn write_atomic(path:&Path, data:&[u8]) -> Result<(),EPaths> {
+
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from(".")
+    };
+
+    let name = path.file_name().ok_or_else(||
+        EPaths::Write
+            .as_report()
+            .attach_printable(format!("{} has no file name",path.display()))
+    )?;
+
+    fs::create_dir_all(&parent).map_err(|e|
+        EPaths::Creation
+            .attach_printable_default(e)
+            .attach_printable(format!("Can't create {}",parent.display()))
+    )?;
+
+    // unique per call, so concurrent writes of the same file don't share their temporary file
+    static NEXT_WRITE: AtomicU64 = AtomicU64::new(0);
+
+    let mut tmp_name = name.to_os_string();
+    tmp_name.push(format!(".{}.{}.tmp",std::process::id(),NEXT_WRITE.fetch_add(1, Ordering::Relaxed)));
+    let tmp = parent.join(tmp_name);
+
+    let written = File::create(&tmp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+
+        let _ = fs::remove_file(&tmp);
+
+        return Err(EPaths::Write
+            .attach_printable_default(e)
+            .attach_printable(format!("Can't write {}",path.display()))
+        );
+
+    }
+
+    // the rename is only on the disk once the directory entry is
+    #[cfg(unix)]
+    File::open(&parent).and_then(|dir| dir.sync_all()).map_err(|e|
+        EPaths::Write
+            .attach_printable_default(e)
+            .attach_printable(format!("Can't sync the directory of {}",path.display()))
+    )?;
+
+    Ok(())
+
+}
+//
+/// Write a save file in the saves directory of the application
+///
+/// # Parameters
+///
+/// * 'name' - the file name of the save
+/// * 'data' - the content of the save
+///
+pub fn write_save(name:&str, data:&[u8]) -> Result<PathBuf,EPaths> {
+
+    let path = saves_dir()?.join(sanitize_name(name));
+
+    write_atomic(&path, data)?;
+
+    Ok(path)
+
+}
+//
+//
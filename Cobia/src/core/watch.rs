@@ -0,0 +1,735 @@
+// File watching service used to reload assets when they change on the disk
+//
+// On linux the kernel inotify api is used, every other platform (or a linux machine where
+// inotify can't be initialised) fallback on a thread that scan the watched directories at a
+// fixed interval. Raw changes are debounced: a path is only reported once it stopped changing
+// for the debounce duration, and the changes that happened in between are merged together.
+//
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use error_stack::Result;
+
+use super::error_handler::EWatch;
+use super::logs::CWARN;
+use crate::event::types::file::FileEvent;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::tmp_path;
+
+    fn tmp_dir(name:&str) -> PathBuf {
+
+        let dir = PathBuf::from(tmp_path("watch", name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+
+    }
+
+    fn wait_events(watcher:&mut FileWatcher) -> Vec<FileEvent> {
+
+        let start = Instant::now();
+        let mut events = Vec::new();
+
+        while start.elapsed() < Duration::from_secs(2) {
+
+            events.extend(watcher.poll_events());
+
+            if !events.is_empty() && !watcher.has_pending() {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+
+        }
+
+        events
+
+    }
+
+    fn check_watcher(mut watcher:FileWatcher, dir:PathBuf) {
+
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        watcher.watch(dir.to_str().unwrap()).unwrap();
+
+        let file = dir.join("sub").join("a.txt");
+
+        // several writes in a row only produce one event
+        fs::write(&file, "1").unwrap();
+        fs::write(&file, "22").unwrap();
+        assert_eq!(wait_events(&mut watcher), vec![FileEvent::Created(file.clone())]);
+
+        fs::write(&file, "333").unwrap();
+        assert_eq!(wait_events(&mut watcher), vec![FileEvent::Modified(file.clone())]);
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(wait_events(&mut watcher), vec![FileEvent::Removed(file.clone())]);
+
+        // the files of a directory deleted or moved away are removed
+        let other = dir.join("other").join("b.txt");
+
+        fs::create_dir_all(other.parent().unwrap()).unwrap();
+        fs::write(&file, "4").unwrap();
+        fs::write(&other, "4").unwrap();
+        assert_eq!(wait_events(&mut watcher), vec![FileEvent::Created(other.clone()), FileEvent::Created(file.clone())]);
+
+        fs::remove_dir_all(other.parent().unwrap()).unwrap();
+        assert_eq!(wait_events(&mut watcher), vec![FileEvent::Removed(other)]);
+
+        let moved = dir.with_extension("moved");
+
+        fs::rename(dir.join("sub"), &moved).unwrap();
+        assert_eq!(wait_events(&mut watcher), vec![FileEvent::Removed(file)]);
+
+        // it is not watched anymore
+        fs::write(moved.join("a.txt"), "5").unwrap();
+        assert!(wait_events(&mut watcher).is_empty());
+
+        fs::remove_dir_all(moved).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+    }
+
+    #[test]
+    fn polling_watcher() {
+
+        let watcher = FileWatcher::polling(Duration::from_millis(20), Duration::from_millis(60));
+        assert!(watcher.is_polling());
+
+        check_watcher(watcher, tmp_dir("polling"));
+
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn inotify_watcher() {
+
+        let watcher = FileWatcher::new(Duration::from_millis(60)).unwrap();
+        assert!(!watcher.is_polling());
+
+        check_watcher(watcher, tmp_dir("inotify"));
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Constant
+//
+pub const DEFAULT_DEBOUNCE:         Duration = Duration::from_millis(100);
+pub const DEFAULT_POLL_INTERVAL:    Duration = Duration::from_millis(500);
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Watcher
+//
+/// Kind of change reported by a backend before the debouncing
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum Change {
+    Created,
+    Modified,
+    Removed
+}
+//
+/// Changes of a path that are waiting for the debounce delay to pass
+struct Pending {
+
+    first:  Change,
+    last:   Change,
+    time:   Instant
+
+}
+//
+impl Pending {
+    //
+    /// merge every change received for a path in a single event, None if nothing changed at the end
+    fn into_event(self, path:PathBuf) -> Option<FileEvent> {
+
+        match (self.first, self.last) {
+
+            (Change::Created, Change::Removed) => None,
+            (Change::Created, _) => Some(FileEvent::Created(path)),
+            (_, Change::Removed) => Some(FileEvent::Removed(path)),
+            _ => Some(FileEvent::Modified(path))
+
+        }
+
+    }
+    //
+}
+//
+enum Backend {
+    #[cfg(target_os = "linux")]
+    Inotify(inotify::InotifyBackend),
+    Polling(PollingBackend)
+}
+//
+/// Watch directories and report the files that changed in them
+pub struct FileWatcher {
+
+    backend:    Backend,
+    debounce:   Duration,
+    pending:    HashMap<PathBuf, Pending>
+
+}
+//
+impl FileWatcher {
+    //
+    /// Create a watcher with the best backend available on this platform
+    ///
+    /// # Parameters
+    ///
+    /// * 'debounce' - how long a file must stay untouched before its change is reported
+    ///
+    pub fn new(debounce:Duration) -> Result<Self,EWatch> {
+
+        #[cfg(target_os = "linux")]
+        match inotify::InotifyBackend::new() {
+
+            Ok(backend) => return Ok(Self {
+                backend:    Backend::Inotify(backend),
+                            debounce,
+                pending:    HashMap::new()
+            }),
+
+            Err(e) => CWARN(&format!("inotify unavailable, fallback on polling: {:?}",e))
+
+        }
+
+        Ok(Self::polling(DEFAULT_POLL_INTERVAL, debounce))
+
+    }
+    //
+    /// Create a watcher that scan the watched directories at a fixed interval
+    ///
+    /// # Parameters
+    ///
+    /// * 'interval' - time between two scans
+    /// * 'debounce' - how long a file must stay untouched before its change is reported
+    ///
+    pub fn polling(interval:Duration, debounce:Duration) -> Self {
+
+        Self {
+            backend:    Backend::Polling(PollingBackend::new(interval)),
+                        debounce,
+            pending:    HashMap::new()
+        }
+
+    }
+    //
+    /// true if the watcher scan the disk instead of receiving notification from the os
+    pub fn is_polling(&self) -> bool { matches!(self.backend, Backend::Polling(_)) }
+    //
+    /// Start watching a directory and all of its sub directories
+    ///
+    /// # Parameters
+    ///
+    /// * 'dir' - the directory to watch
+    ///
+    pub fn watch(&mut self, dir:&str) -> Result<(),EWatch> {
+
+        let path = Path::new(dir);
+
+        if !path.is_dir() {
+
+            return Err(EWatch::Path
+                .as_report()
+                .attach_printable(format!("{} is not a directory",dir))
+            );
+
+        }
+
+        match &mut self.backend {
+
+            #[cfg(target_os = "linux")]
+            Backend::Inotify(b) => b.add_recursive(path, &mut Vec::new()),
+            Backend::Polling(b) => b.add(path)
+
+        }
+
+    }
+    //
+    /// true if some changes are still waiting for the debounce delay
+    pub fn has_pending(&self) -> bool { !self.pending.is_empty() }
+    //
+    /// Collect the changes reported by the backend and return the ones that are settled
+    pub fn poll_events(&mut self) -> Vec<FileEvent> {
+
+        let mut raw = Vec::new();
+
+        match &mut self.backend {
+
+            #[cfg(target_os = "linux")]
+            Backend::Inotify(b) => b.read(&mut raw),
+            Backend::Polling(b) => b.read(&mut raw)
+
+        }
+
+        let now = Instant::now();
+
+        for (change, path) in raw {
+
+            self.pending
+                .entry(path)
+                .and_modify(|p| { p.last = change; p.time = now; })
+                .or_insert(Pending { first: change, last: change, time: now });
+
+        }
+
+        let ready:Vec<PathBuf> = self.pending
+            .iter()
+            .filter(|(_, p)| now.duration_since(p.time) >= self.debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let mut events:Vec<FileEvent> = ready
+            .into_iter()
+            .filter_map(|path| {
+                let pending = self.pending.remove(&path)?;
+                pending.into_event(path)
+            })
+            .collect();
+
+        events.sort_by(|a, b| a.path().cmp(b.path()));
+
+        events
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Inotify backend
+//
+#[cfg(target_os = "linux")]
+mod inotify {
+
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::{CString, OsStr};
+    use std::fs;
+    use std::mem::size_of;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    use error_stack::Result;
+
+    use super::Change;
+    use crate::core::error_handler::EWatch;
+    use crate::core::logs::CWARN;
+
+    const WATCH_MASK: u32 = libc::IN_CREATE | libc::IN_MODIFY | libc::IN_CLOSE_WRITE |
+        libc::IN_DELETE | libc::IN_MOVED_FROM | libc::IN_MOVED_TO | libc::IN_DELETE_SELF;
+
+    pub(super) struct InotifyBackend {
+
+        fd:         i32,
+        watches:    HashMap<i32, PathBuf>,
+        // the files under the watched directories, reported as removed with their directory
+        files:      HashSet<PathBuf>
+
+    }
+    //
+    impl InotifyBackend {
+        //
+        pub(super) fn new() -> Result<Self,EWatch> {
+
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+
+            if fd < 0 {
+
+                return Err(EWatch::Init.attach_printable_default(std::io::Error::last_os_error()));
+
+            }
+
+            Ok(Self { fd, watches: HashMap::new(), files: HashSet::new() })
+
+        }
+        //
+        /// Watch a directory and its sub directories, the files found in them are pushed in
+        /// 'found' so a directory that appear while watching can report its content
+        pub(super) fn add_recursive(
+            &mut self,
+            dir:&Path,
+            found:&mut Vec<(Change,PathBuf)>) -> Result<(),EWatch> {
+
+            let c_path = CString::new(dir.as_os_str().as_bytes()).map_err(|e|
+                EWatch::Path.attach_printable_default(e)
+            )?;
+
+            let wd = unsafe { libc::inotify_add_watch(self.fd, c_path.as_ptr(), WATCH_MASK) };
+
+            if wd < 0 {
+
+                return Err(EWatch::Backend
+                    .attach_printable_default(std::io::Error::last_os_error())
+                    .attach_printable(format!("Can't watch {}",dir.display()))
+                );
+
+            }
+
+            self.watches.insert(wd, dir.to_path_buf());
+
+            let read_dir = fs::read_dir(dir).map_err(|e| EWatch::Path.attach_printable_default(e))?;
+
+            for item in read_dir.flatten() {
+
+                let path = item.path();
+
+                if path.is_dir() {
+                    self.add_recursive(&path, found)?;
+                } else {
+                    self.files.insert(path.clone());
+                    found.push((Change::Created, path));
+                }
+
+            }
+
+            Ok(())
+
+        }
+        //
+        /// Read every notification available without blocking
+        pub(super) fn read(&mut self, out:&mut Vec<(Change,PathBuf)>) {
+
+            // enough room for a bunch of events, aligned like the kernel struct
+            let mut buffer = [0_u64; 1024];
+            let header = size_of::<libc::inotify_event>();
+
+            loop {
+
+                let len = unsafe {
+                    libc::read(self.fd, buffer.as_mut_ptr() as *mut libc::c_void, size_of::<[u64;1024]>())
+                };
+
+                if len <= 0 {
+                    break;
+                }
+
+                let bytes = unsafe {
+                    std::slice::from_raw_parts(buffer.as_ptr() as *const u8, len as usize)
+                };
+
+                let mut offset = 0;
+
+                while offset + header <= bytes.len() {
+
+                    let event = unsafe {
+                        std::ptr::read_unaligned(bytes[offset..].as_ptr() as *const libc::inotify_event)
+                    };
+
+                    let name_bytes = &bytes[offset + header..offset + header + event.len as usize];
+                    let name_end = name_bytes.iter().position(|b| *b == 0).unwrap_or(name_bytes.len());
+                    let name = OsStr::from_bytes(&name_bytes[..name_end]);
+
+                    offset += header + event.len as usize;
+
+                    self.handle(event.wd, event.mask, name, out);
+
+                }
+
+            }
+
+        }
+        //
+        fn handle(&mut self, wd:i32, mask:u32, name:&OsStr, out:&mut Vec<(Change,PathBuf)>) {
+
+            if mask & libc::IN_Q_OVERFLOW != 0 {
+
+                CWARN("inotify queue overflow, some file changes have been lost");
+                return;
+
+            }
+
+            if mask & libc::IN_IGNORED != 0 {
+
+                self.watches.remove(&wd);
+                return;
+
+            }
+
+            if mask & libc::IN_DELETE_SELF != 0 {
+
+                if let Some(dir) = self.watches.get(&wd).cloned() {
+                    self.forget_dir(&dir, out);
+                }
+
+                return;
+
+            }
+
+            let path = match self.watches.get(&wd) {
+                Some(dir) => dir.join(name),
+                None => return
+            };
+
+            let is_dir = mask & libc::IN_ISDIR != 0;
+
+            if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+
+                if is_dir {
+
+                    if let Err(e) = self.add_recursive(&path, out) {
+                        CWARN(&format!("Can't watch the new directory {}: {:?}",path.display(),e));
+                    }
+
+                } else {
+
+                    self.files.insert(path.clone());
+                    out.push((Change::Created, path));
+
+                }
+
+            } else if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+
+                // no event come for the content of a directory moved away
+                if is_dir {
+                    self.forget_dir(&path, out);
+                } else {
+                    self.files.remove(&path);
+                    out.push((Change::Removed, path));
+                }
+
+            } else if mask & (libc::IN_MODIFY | libc::IN_CLOSE_WRITE) != 0 && !is_dir {
+
+                self.files.insert(path.clone());
+                out.push((Change::Modified, path));
+
+            }
+
+        }
+        //
+        /// Stop watching a directory that was deleted or moved away and its sub directories, the
+        /// files known under it are reported as removed
+        fn forget_dir(&mut self, dir:&Path, out:&mut Vec<(Change,PathBuf)>) {
+
+            let fd = self.fd;
+
+            self.watches.retain(|wd, path| {
+
+                let kept = !path.starts_with(dir);
+
+                // the watch of a deleted directory is already gone, the error is expected
+                if !kept {
+                    unsafe { libc::inotify_rm_watch(fd, *wd); }
+                }
+
+                kept
+
+            });
+
+            self.files.retain(|file| {
+
+                let kept = !file.starts_with(dir);
+
+                if !kept {
+                    out.push((Change::Removed, file.clone()));
+                }
+
+                kept
+
+            });
+
+        }
+        //
+    }
+    //
+    impl Drop for InotifyBackend {
+
+        fn drop(&mut self) {
+
+            unsafe { libc::close(self.fd); }
+
+        }
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Polling backend
+//
+/// Last modification time and size of every file of a watched directory
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+//
+struct PollingBackend {
+
+    snapshots:  Arc<Mutex<HashMap<PathBuf, Snapshot>>>,
+    receiver:   Receiver<(Change,PathBuf)>,
+    stop:       Arc<AtomicBool>,
+    thread:     Option<JoinHandle<()>>
+
+}
+//
+impl PollingBackend {
+    //
+    fn new(interval:Duration) -> Self {
+
+        let snapshots = Arc::new(Mutex::new(HashMap::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        let thread_snapshots = snapshots.clone();
+        let thread_stop = stop.clone();
+
+        let thread = thread::spawn(move ||
+            scan_loop(thread_snapshots, sender, thread_stop, interval)
+        );
+
+        Self { snapshots, receiver, stop, thread: Some(thread) }
+
+    }
+    //
+    /// Add a directory, its first snapshot is taken right away so only the later changes
+    /// are reported
+    fn add(&mut self, dir:&Path) -> Result<(),EWatch> {
+
+        let mut snapshot = Snapshot::new();
+        scan_dir(dir, &mut snapshot);
+
+        match self.snapshots.lock() {
+
+            Ok(mut snapshots) => {
+
+                snapshots.insert(dir.to_path_buf(), snapshot);
+                Ok(())
+
+            },
+
+            Err(e) => Err(EWatch::Backend
+                .as_report()
+                .attach_printable(format!("Can't access the watched directories: {}",e))
+            )
+
+        }
+
+    }
+    //
+    fn read(&mut self, out:&mut Vec<(Change,PathBuf)>) {
+
+        out.extend(self.receiver.try_iter());
+
+    }
+    //
+}
+//
+impl Drop for PollingBackend {
+
+    fn drop(&mut self) {
+
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+    }
+
+}
+//
+//
+/// Body of the polling thread, compare a new snapshot of every watched directory with the
+/// previous one
+fn scan_loop(
+    snapshots:Arc<Mutex<HashMap<PathBuf, Snapshot>>>,
+    sender:Sender<(Change,PathBuf)>,
+    stop:Arc<AtomicBool>,
+    interval:Duration) {
+
+    let step = Duration::from_millis(10).min(interval);
+
+    while !stop.load(Ordering::Relaxed) {
+
+        let start = Instant::now();
+
+        while start.elapsed() < interval && !stop.load(Ordering::Relaxed) {
+            thread::sleep(step);
+        }
+
+        let mut snapshots = match snapshots.lock() {
+            Ok(s) => s,
+            Err(_) => return
+        };
+
+        for (root, previous) in snapshots.iter_mut() {
+
+            let mut snapshot = Snapshot::new();
+            scan_dir(root, &mut snapshot);
+
+            for (path, stamp) in snapshot.iter() {
+
+                let change = match previous.get(path) {
+                    None => Some(Change::Created),
+                    Some(old) if old != stamp => Some(Change::Modified),
+                    _ => None
+                };
+
+                if let Some(change) = change {
+                    if sender.send((change, path.clone())).is_err() {
+                        return;
+                    }
+                }
+
+            }
+
+            for path in previous.keys().filter(|p| !snapshot.contains_key(*p)) {
+
+                if sender.send((Change::Removed, path.clone())).is_err() {
+                    return;
+                }
+
+            }
+
+            *previous = snapshot;
+
+        }
+
+    }
+
+}
+//
+fn scan_dir(dir:&Path, snapshot:&mut Snapshot) {
+
+    let read_dir = match fs::read_dir(dir) {
+        Ok(r) => r,
+        Err(_) => return
+    };
+
+    for item in read_dir.flatten() {
+
+        let path = item.path();
+
+        match item.metadata() {
+
+            Ok(meta) if meta.is_dir() => scan_dir(&path, snapshot),
+
+            Ok(meta) => {
+
+                let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                snapshot.insert(path, (modified, meta.len()));
+
+            },
+
+            Err(_) => {}
+
+        }
+
+    }
+
+}
+//
+//
@@ -0,0 +1,392 @@
+// Archetypes: the entities that have exactly the same component types are stored together, each
+// component type in its own contiguous column, so iterating a component touch memory in order
+
+use std::any::{Any, TypeId};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+
+use super::{Component, Entity};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Column
+//
+/// The components of a type for every entity of an archetype, in the order of the entities
+pub trait Column: Send + Sync {
+    //
+    /// Remove the component of a row, the last one take its place
+    fn swap_remove(&mut self, row:usize);
+    //
+    /// Move the component of a row to the end of another column of the same type, the last one
+    /// take its place
+    fn move_row(&mut self, row:usize, to:&mut dyn Column);
+    //
+    /// an empty column of the same type
+    fn empty(&self) -> Box<dyn Column>;
+    //
+    fn as_any(&self) -> &dyn Any;
+    //
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    //
+}
+//
+impl<T:Component> Column for Vec<T> {
+
+    fn swap_remove(&mut self, row:usize) { Vec::swap_remove(self, row); }
+
+    fn move_row(&mut self, row:usize, to:&mut dyn Column) {
+
+        let component = Vec::swap_remove(self, row);
+        to.as_any_mut().downcast_mut::<Vec<T>>().unwrap().push(component);
+
+    }
+
+    fn empty(&self) -> Box<dyn Column> { Box::new(Vec::<T>::new()) }
+
+    fn as_any(&self) -> &dyn Any { self }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Ticks
+//
+/// When a component was added and last changed, in ticks of its world
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct ComponentTicks {
+
+    added:      u64,
+    changed:    u64
+
+}
+//
+impl ComponentTicks {
+    //
+    pub(crate) fn new(tick:u64) -> Self { Self { added: tick, changed: tick } }
+    //
+    /// the tick when the component was added to its entity
+    pub fn added(&self) -> u64 { self.added }
+    //
+    /// the tick when the component was last changed, the one when it was added if it wasn't
+    pub fn changed(&self) -> u64 { self.changed }
+    //
+    pub(crate) fn set_changed(&mut self, tick:u64) { self.changed = tick; }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Archetype
+//
+/// The entities with a set of component types
+pub struct Archetype {
+
+    // sorted, the columns are in the same order. The queries change the columns behind a shared
+    // reference, the world and the scheduler make sure that their accesses don't overlap
+    types:          Vec<TypeId>,
+    columns:        Vec<UnsafeCell<Box<dyn Column>>>,
+    // the ticks of the components, a column of them for every column
+    ticks:          Vec<UnsafeCell<Vec<ComponentTicks>>>,
+    entities:       Vec<Entity>,
+    // the archetype reached by adding or removing a type, filled the first time it is needed
+    add_edges:      HashMap<TypeId, usize>,
+    remove_edges:   HashMap<TypeId, usize>
+
+}
+//
+impl Archetype {
+    //
+    /// an archetype without entity, the columns are sorted with the types
+    pub(crate) fn new(columns:Vec<(TypeId, Box<dyn Column>)>) -> Self {
+
+        let mut columns = columns;
+        columns.sort_by_key(|(t, _)| *t);
+
+        let (types, columns):(Vec<TypeId>, Vec<_>) = columns.into_iter().map(|(t, c)| (t, UnsafeCell::new(c))).unzip();
+        let ticks = types.iter().map(|_| UnsafeCell::new(Vec::new())).collect();
+
+        Self { types, columns, ticks, entities: Vec::new(), add_edges: HashMap::new(), remove_edges: HashMap::new() }
+
+    }
+    //
+    /// the component types of the entities, sorted by type id
+    pub fn types(&self) -> &[TypeId] { &self.types }
+    //
+    /// the entities in the order of their components in the columns
+    pub fn entities(&self) -> &[Entity] { &self.entities }
+    //
+    pub fn len(&self) -> usize { self.entities.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.entities.is_empty() }
+    //
+    pub fn has<T:Component>(&self) -> bool { self.column_index(TypeId::of::<T>()).is_some() }
+    //
+    /// the components of a type of every entity, None if the archetype doesn't have the type
+    pub fn column<T:Component>(&self) -> Option<&[T]> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        // SAFETY: a shared reference is only given when nothing change the column
+        unsafe { &*self.columns[index].get() }.as_any().downcast_ref::<Vec<T>>().map(|c| c.as_slice())
+
+    }
+    //
+    /// the components of a type of every entity, to change them
+    pub fn column_mut<T:Component>(&mut self) -> Option<&mut [T]> {
+
+        self.typed_column::<T>().map(|c| c.as_mut_slice())
+
+    }
+    //
+    /// the ticks of the components of a type of every entity, None if the archetype doesn't have
+    /// the type
+    pub fn ticks<T:Component>(&self) -> Option<&[ComponentTicks]> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        // SAFETY: a shared reference is only given when nothing change the column
+        Some(unsafe { &*self.ticks[index].get() })
+
+    }
+    //
+    pub(crate) fn column_index(&self, t:TypeId) -> Option<usize> { self.types.binary_search(&t).ok() }
+    //
+    pub(crate) fn typed_column<T:Component>(&mut self) -> Option<&mut Vec<T>> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        self.columns[index].get_mut().as_any_mut().downcast_mut::<Vec<T>>()
+
+    }
+    //
+    /// the entities with the components of a type and their ticks, to change them
+    pub(crate) fn entities_and_column_mut<T:Component>(&mut self) -> Option<(&[Entity], &mut [T], &mut [ComponentTicks])> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+        let column = self.columns[index].get_mut().as_any_mut().downcast_mut::<Vec<T>>()?;
+
+        Some((&self.entities, column.as_mut_slice(), self.ticks[index].get_mut().as_mut_slice()))
+
+    }
+    //
+    /// the ticks of a component of a type, to change them
+    pub(crate) fn ticks_mut<T:Component>(&mut self, row:usize) -> Option<&mut ComponentTicks> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        self.ticks[index].get_mut().get_mut(row)
+
+    }
+    //
+    /// Pointer to the first component of a type to read them, None if the archetype doesn't have
+    /// the type
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that nothing write in the column while it read
+    pub(crate) unsafe fn column_ptr<T:Component>(&self) -> Option<*const T> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        (*self.columns[index].get()).as_any().downcast_ref::<Vec<T>>().map(|c| c.as_ptr())
+
+    }
+    //
+    /// Pointer to the first component of a type to change them, None if the archetype doesn't
+    /// have the type
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that nothing else use the column while it write
+    pub(crate) unsafe fn column_mut_ptr<T:Component>(&self) -> Option<*mut T> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        (*self.columns[index].get()).as_any_mut().downcast_mut::<Vec<T>>().map(|c| c.as_mut_ptr())
+
+    }
+    //
+    /// Pointer to the ticks of the first component of a type to read them
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that nothing write in the column while it read
+    pub(crate) unsafe fn ticks_ptr<T:Component>(&self) -> Option<*const ComponentTicks> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        Some((*self.ticks[index].get()).as_ptr())
+
+    }
+    //
+    /// Pointer to the ticks of the first component of a type to change them
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that nothing else use the column while it write
+    pub(crate) unsafe fn ticks_mut_ptr<T:Component>(&self) -> Option<*mut ComponentTicks> {
+
+        let index = self.column_index(TypeId::of::<T>())?;
+
+        Some((*self.ticks[index].get()).as_mut_ptr())
+
+    }
+    //
+    /// empty columns of the same types
+    pub(crate) fn empty_columns(&self) -> Vec<(TypeId, Box<dyn Column>)> {
+
+        self.types.iter().zip(self.columns.iter()).map(|(t, c)| (*t, unsafe { &*c.get() }.empty())).collect()
+
+    }
+    //
+    pub(crate) fn add_edge(&self, t:TypeId) -> Option<usize> { self.add_edges.get(&t).copied() }
+    //
+    pub(crate) fn remove_edge(&self, t:TypeId) -> Option<usize> { self.remove_edges.get(&t).copied() }
+    //
+    pub(crate) fn set_add_edge(&mut self, t:TypeId, archetype:usize) { self.add_edges.insert(t, archetype); }
+    //
+    pub(crate) fn set_remove_edge(&mut self, t:TypeId, archetype:usize) { self.remove_edges.insert(t, archetype); }
+    //
+    /// Add an entity, its components must be pushed in the columns right after
+    ///
+    /// # Parameters
+    ///
+    /// * 'entity' - the new entity
+    /// * 'tick' - the tick when its components are added
+    ///
+    pub(crate) fn push_entity(&mut self, entity:Entity, tick:u64) -> usize {
+
+        for ticks in self.ticks.iter_mut() {
+            ticks.get_mut().push(ComponentTicks::new(tick));
+        }
+
+        self.entities.push(entity);
+        self.entities.len() - 1
+
+    }
+    //
+    /// Drop the components of a row, return the entity moved in the row if there is one
+    pub(crate) fn remove_row(&mut self, row:usize) -> Option<Entity> {
+
+        for (column, ticks) in self.columns.iter_mut().zip(self.ticks.iter_mut()) {
+            column.get_mut().swap_remove(row);
+            ticks.get_mut().swap_remove(row);
+        }
+
+        self.entities.swap_remove(row);
+        self.entities.get(row).copied()
+
+    }
+    //
+    /// Move a row to another archetype. Return the row in the other archetype and the entity
+    /// moved in the row if there is one
+    ///
+    /// # Parameters
+    ///
+    /// * 'row' - the row to move
+    /// * 'to' - the other archetype
+    /// * 'removed' - receive the component of the type that the other archetype doesn't have,
+    ///   the components of these types are dropped without it
+    /// * 'tick' - the tick when the components of the types that this archetype doesn't have are
+    ///   added, they must be pushed in the columns right after
+    ///
+    pub(crate) fn move_row(&mut self, row:usize, to:&mut Archetype, mut removed:Option<&mut dyn Column>, tick:u64) -> (usize, Option<Entity>) {
+
+        for (i, t) in self.types.iter().enumerate() {
+
+            let column = self.columns[i].get_mut();
+            let ticks = self.ticks[i].get_mut().swap_remove(row);
+
+            match (to.column_index(*t), removed.as_deref_mut()) {
+                (Some(index), _) => {
+                    column.move_row(row, to.columns[index].get_mut().as_mut());
+                    to.ticks[index].get_mut().push(ticks);
+                },
+                (None, Some(removed)) => column.move_row(row, removed),
+                (None, None) => column.swap_remove(row)
+            }
+
+        }
+
+        let entity = self.entities.swap_remove(row);
+
+        to.entities.push(entity);
+
+        for ticks in to.ticks.iter_mut().map(|t| t.get_mut()) {
+            if ticks.len() < to.entities.len() {
+                ticks.push(ComponentTicks::new(tick));
+            }
+        }
+
+        (to.entities.len() - 1, self.entities.get(row).copied())
+
+    }
+    //
+}
+//
+// SAFETY: the columns are only changed behind a shared reference by the queries, which check
+// their accesses
+unsafe impl Sync for Archetype {}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Bundle
+//
+/// Components added together to an entity, the tuples of components up to 12 of them
+pub trait Bundle: Send + Sync + 'static {
+    //
+    /// the types of the components, in the order of the tuple
+    fn types() -> Vec<TypeId>;
+    //
+    /// an empty column for every component
+    fn columns() -> Vec<(TypeId, Box<dyn Column>)>;
+    //
+    /// push every component in its column of an archetype with the same types
+    fn push(self, archetype:&mut Archetype);
+    //
+}
+//
+//
+macro_rules! impl_bundle {
+    ($($name:ident),*) => {
+
+        impl<$($name:Component),*> Bundle for ($($name,)*) {
+
+            fn types() -> Vec<TypeId> { vec![$(TypeId::of::<$name>()),*] }
+
+            fn columns() -> Vec<(TypeId, Box<dyn Column>)> {
+
+                vec![$((TypeId::of::<$name>(), Box::new(Vec::<$name>::new()) as Box<dyn Column>)),*]
+
+            }
+
+            #[allow(non_snake_case, unused_variables)]
+            fn push(self, archetype:&mut Archetype) {
+
+                let ($($name,)*) = self;
+                $(archetype.typed_column::<$name>().unwrap().push($name);)*
+
+            }
+
+        }
+
+    };
+}
+//
+impl_bundle!();
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+impl_bundle!(A, B, C, D, E, F, G);
+impl_bundle!(A, B, C, D, E, F, G, H);
+impl_bundle!(A, B, C, D, E, F, G, H, I);
+impl_bundle!(A, B, C, D, E, F, G, H, I, J);
+impl_bundle!(A, B, C, D, E, F, G, H, I, J, K);
+impl_bundle!(A, B, C, D, E, F, G, H, I, J, K, L);
+//
+//
@@ -0,0 +1,281 @@
+// Commands: the changes of the structure of the world asked by the systems while they run. They
+// are recorded by every system and applied at the end of the stage, in the order of the systems
+// of the stage then in the order they were recorded
+
+use std::cell::RefCell;
+
+use super::{Bundle, Component, Entity, World};
+use crate::core::error_handler::EEcs;
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ecs::{Scheduler, Stage, System, SystemContext, With};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug,PartialEq)]
+    struct Health(u32);
+
+    #[derive(Debug,PartialEq)]
+    struct Name(&'static str);
+
+    struct Bullet;
+
+    #[test]
+    fn apply_at_stage_end() {
+
+        let mut world = World::new();
+
+        let a = world.spawn((Health(0),));
+        let b = world.spawn((Health(5),));
+
+        let counts = Arc::new(Mutex::new(Vec::new()));
+        let log = counts.clone();
+
+        let mut scheduler = Scheduler::with_threads(2);
+
+        // the systems iterate and record the changes at the same time
+        scheduler.add_system(System::new("reaper", |ctx:&mut SystemContext| {
+            let commands = ctx.commands();
+            for (e, h) in ctx.query::<(Entity, &Health), ()>()?.iter() {
+                if h.0 == 0 {
+                    commands.despawn(e);
+                }
+            }
+            Ok(())
+        }).reads::<Health>()).unwrap();
+
+        scheduler.add_system(System::new("gun", |ctx:&mut SystemContext| {
+            let commands = ctx.commands();
+            let bullet = commands.spawn((Bullet,));
+            commands.insert(bullet, Name("first"));
+            Ok(())
+        })).unwrap();
+
+        // in the same batch as gun, its commands are applied after
+        scheduler.add_system(System::new("rename", |ctx:&mut SystemContext| {
+            let commands = ctx.commands();
+            for e in ctx.query::<Entity, With<Bullet>>()?.iter() {
+                commands.insert(e, Name("renamed"));
+                commands.remove::<Bullet>(e);
+            }
+            Ok(())
+        }).reads::<Bullet>()).unwrap();
+
+        scheduler.add_system(System::new("count", move |ctx:&mut SystemContext| {
+            let bullets = ctx.query::<&Bullet, ()>()?.len();
+            let alive = ctx.query::<&Health, ()>()?.len();
+            log.lock().unwrap().push((bullets, alive));
+            Ok(())
+        }).stage(Stage::PostUpdate).reads::<Bullet>().reads::<Health>()).unwrap();
+
+        scheduler.run(&mut world).unwrap();
+
+        // the spawn is seen in the next stage
+        assert_eq!(*counts.lock().unwrap(), vec![(1, 1)]);
+        assert!(!world.is_alive(a) && world.is_alive(b));
+
+        scheduler.run(&mut world).unwrap();
+
+        // the bullet of the first frame was renamed then the gun spawned another
+        let mut names:Vec<&str> = world.query::<&Name, ()>().unwrap().iter().map(|n| n.0).collect();
+        names.sort();
+
+        assert_eq!(names, vec!["first", "renamed"]);
+        assert_eq!(*counts.lock().unwrap(), vec![(1, 1), (1, 1)]);
+
+    }
+
+    #[test]
+    fn dead_entities() {
+
+        let mut world = World::new();
+        let a = world.spawn((Health(1),));
+
+        let queue = RefCell::new(CommandQueue::default());
+        let commands = Commands { queue: &queue, world: &world };
+
+        let b = commands.spawn((Health(2),));
+        commands.despawn(b);
+        commands.despawn(a);
+        commands.insert(a, Name("a"));
+
+        // the insert on the despawned entity fails, the other commands are applied
+        assert!(queue.into_inner().apply(&mut world).is_err());
+        assert!(world.is_empty());
+        assert!(!world.is_alive(b));
+
+    }
+
+    #[test]
+    fn reuse_despawned_slots() {
+
+        let mut world = World::new();
+        let queue = RefCell::new(CommandQueue::default());
+
+        let mut bullet:Option<Entity> = None;
+        let mut indices = Vec::new();
+
+        // a bullet replaced every frame only ever need two slots
+        for _ in 0..10 {
+
+            let commands = Commands::new(&queue, &world);
+
+            if let Some(old) = bullet {
+                commands.despawn(old);
+            }
+
+            let new = commands.spawn((Bullet,));
+            indices.push(new.index());
+            bullet = Some(new);
+
+            queue.borrow_mut().apply(&mut world).unwrap();
+
+        }
+
+        assert!(indices.iter().all(|i| *i < 2));
+        assert_eq!(world.len(), 1);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Queue
+//
+type Command = Box<dyn FnOnce(&mut World) -> Result<(),EEcs> + Send>;
+//
+//
+/// The commands recorded by a system
+#[derive(Default)]
+pub(crate) struct CommandQueue {
+
+    commands:   Vec<Command>
+
+}
+//
+impl CommandQueue {
+    //
+    /// Apply the commands in the order they were recorded, error with every command that failed.
+    /// A failing command doesn't stop the others
+    ///
+    /// # Parameters
+    ///
+    /// * 'world' - the world changed
+    ///
+    pub(crate) fn apply(&mut self, world:&mut World) -> Result<(),EEcs> {
+
+        world.flush();
+
+        let mut result:Result<(),EEcs> = Ok(());
+
+        for command in self.commands.drain(..) {
+
+            if let Err(e) = command(world) {
+                match result.as_mut() {
+                    Ok(()) => result = Err(e),
+                    Err(report) => report.extend_one(e)
+                }
+            }
+
+        }
+
+        result
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Commands
+//
+/// Record the changes of the structure of the world during a system, they are applied at the
+/// end of its stage
+#[derive(Clone,Copy)]
+pub struct Commands<'w> {
+
+    queue:  &'w RefCell<CommandQueue>,
+    world:  &'w World
+
+}
+//
+impl<'w> Commands<'w> {
+    //
+    pub(crate) fn new(queue:&'w RefCell<CommandQueue>, world:&'w World) -> Self { Self { queue, world } }
+    //
+    /// Spawn an entity with some components, its id can be used by the next commands right away
+    ///
+    /// # Parameters
+    ///
+    /// * 'bundle' - a tuple of components of different types, () for an entity without component
+    ///
+    pub fn spawn<B:Bundle>(&self, bundle:B) -> Entity {
+
+        let entity = self.world.reserve();
+
+        self.push(move |world| world.spawn_reserved(entity, bundle));
+
+        entity
+
+    }
+    //
+    /// Despawn an entity, nothing happens if it is already dead
+    pub fn despawn(&self, entity:Entity) {
+
+        self.push(move |world| {
+            world.despawn(entity);
+            Ok(())
+        });
+
+    }
+    //
+    /// Add a component to an entity, it replace the one of the same type. The command fails if
+    /// the entity is dead
+    pub fn insert<T:Component>(&self, entity:Entity, component:T) {
+
+        self.push(move |world| world.insert(entity, component).map(|_| ()));
+
+    }
+    //
+    /// Remove a component of an entity, nothing happens if it doesn't have one
+    pub fn remove<T:Component>(&self, entity:Entity) {
+
+        self.push(move |world| {
+            world.remove::<T>(entity);
+            Ok(())
+        });
+
+    }
+    //
+    /// Record a change of the world
+    ///
+    /// # Parameters
+    ///
+    /// * 'command' - called with the world at the end of the stage
+    ///
+    pub fn add<F>(&self, command:F)
+    where F:FnOnce(&mut World) -> Result<(),EEcs> + Send + 'static {
+
+        self.push(command);
+
+    }
+    //
+    fn push<F>(&self, command:F)
+    where F:FnOnce(&mut World) -> Result<(),EEcs> + Send + 'static {
+
+        self.queue.borrow_mut().commands.push(Box::new(command));
+
+    }
+    //
+}
+//
+//
@@ -0,0 +1,263 @@
+// Entity ids and their allocation: the index of a despawned entity is reused with a new
+// generation, so the ids kept on the old entity are detected as dead
+
+use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn reuse_index() {
+
+        let mut entities = Entities::new();
+
+        let a = entities.alloc();
+        let b = entities.alloc();
+
+        assert_eq!((a.index(), b.index()), (0, 1));
+        assert_eq!(entities.len(), 2);
+
+        assert!(entities.free(a));
+        assert!(!entities.free(a));
+        assert!(!entities.is_alive(a));
+
+        // the index come back with another generation
+        let c = entities.alloc();
+        assert_eq!((c.index(), c.generation()), (0, 1));
+        assert_ne!(a, c);
+        assert!(entities.is_alive(c) && entities.is_alive(b));
+
+        assert_eq!(Entity::from_bits(c.to_bits()), c);
+        assert_eq!(format!("{}",c), "0v1");
+        assert_eq!(entities.iter().collect::<Vec<_>>(), vec![c, b]);
+
+        // the reserved entities are alive once flushed
+        let d = entities.reserve();
+        let e = entities.reserve();
+
+        assert_eq!((d.index(), e.index()), (2, 3));
+        assert!(!entities.is_alive(d));
+
+        assert_eq!(entities.flush().collect::<Vec<_>>(), vec![d, e]);
+        assert!(entities.is_alive(e));
+        assert_eq!(entities.len(), 4);
+
+        // the reservations take the free slots first
+        entities.free(b);
+        entities.free(d);
+
+        let f = entities.reserve();
+        let g = entities.reserve();
+        let h = entities.reserve();
+
+        assert_eq!((f.index(), f.generation()), (2, 1));
+        assert_eq!((g.index(), g.generation()), (1, 1));
+        assert_eq!((h.index(), h.generation()), (4, 0));
+
+        assert_eq!(entities.flush().collect::<Vec<_>>(), vec![f, g, h]);
+        assert!(entities.is_alive(f) && entities.is_alive(g) && !entities.is_alive(b));
+        assert_eq!(entities.len(), 5);
+        assert_eq!(entities.alloc().index(), 5);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Entity
+//
+/// Id of an entity of a world, the index of its slot and the generation of the slot when it was
+/// spawned
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct Entity {
+
+    index:      u32,
+    generation: u32
+
+}
+//
+impl Entity {
+    //
+    /// index of the slot of the entity, reused after a despawn
+    pub fn index(&self) -> u32 { self.index }
+    //
+    /// number of times the slot was used before this entity
+    pub fn generation(&self) -> u32 { self.generation }
+    //
+    /// the entity as a single number, to store it outside of the engine
+    pub fn to_bits(&self) -> u64 { (self.generation as u64) << 32 | self.index as u64 }
+    //
+    /// the entity stored with to_bits
+    pub fn from_bits(bits:u64) -> Self { Self { index: bits as u32, generation: (bits >> 32) as u32 } }
+    //
+}
+//
+impl fmt::Display for Entity {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{}v{}", self.index, self.generation) }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Allocator
+//
+/// The slots of the entities of a world
+#[derive(Debug,Default)]
+pub(crate) struct Entities {
+
+    // generation of every slot and if an entity use it
+    slots:      Vec<(u32, bool)>,
+    free:       Vec<u32>,
+    len:        usize,
+    // the entities reserved behind a shared reference take the free slots from the end of the
+    // list, then new slots once the cursor is negative
+    free_cursor: AtomicI64
+
+}
+//
+impl Entities {
+    //
+    pub(crate) fn new() -> Self { Self::default() }
+    //
+    /// a new entity, in a free slot when there is one. The reserved entities must be flushed
+    pub(crate) fn alloc(&mut self) -> Entity {
+
+        debug_assert_eq!(*self.free_cursor.get_mut(), self.free.len() as i64, "the reserved entities must be flushed first");
+
+        self.len += 1;
+
+        let free = self.free.pop();
+        *self.free_cursor.get_mut() = self.free.len() as i64;
+
+        match free {
+
+            Some(index) => {
+
+                let slot = &mut self.slots[index as usize];
+                slot.1 = true;
+
+                Entity { index, generation: slot.0 }
+
+            },
+
+            None => {
+
+                self.slots.push((0, true));
+
+                Entity { index: self.slots.len() as u32 - 1, generation: 0 }
+
+            }
+
+        }
+
+    }
+    //
+    /// an entity that is alive once flushed, in a free slot when there is one
+    pub(crate) fn reserve(&self) -> Entity {
+
+        let cursor = self.free_cursor.fetch_sub(1, Ordering::Relaxed);
+
+        match cursor > 0 {
+
+            true => {
+
+                let index = self.free[cursor as usize - 1];
+
+                Entity { index, generation: self.slots[index as usize].0 }
+
+            },
+
+            // the slots after the last one, in the order they were reserved
+            false => Entity { index: self.slots.len() as u32 + (-cursor) as u32, generation: 0 }
+
+        }
+
+    }
+    //
+    /// make the reserved entities alive, return them
+    pub(crate) fn flush(&mut self) -> impl Iterator<Item = Entity> + '_ {
+
+        let cursor = std::mem::replace(self.free_cursor.get_mut(), 0);
+
+        // the free slots taken are at the end of the list
+        let kept = cursor.max(0) as usize;
+        let created = (-cursor).max(0) as u32;
+        let first = self.slots.len() as u32;
+
+        for index in self.free[kept..].iter() {
+            self.slots[*index as usize].1 = true;
+        }
+
+        self.slots.extend((0..created).map(|_| (0, true)));
+        self.len += self.free.len() - kept + created as usize;
+
+        *self.free_cursor.get_mut() = kept as i64;
+
+        let slots = &self.slots;
+
+        self.free
+            .drain(kept..)
+            .rev()
+            .map(|index| Entity { index, generation: slots[index as usize].0 })
+            .chain((first..first + created).map(|index| Entity { index, generation: 0 }))
+
+    }
+    //
+    /// Free the slot of an entity, false if it was already dead
+    ///
+    /// # Parameters
+    ///
+    /// * 'entity' - the entity to free
+    ///
+    pub(crate) fn free(&mut self, entity:Entity) -> bool {
+
+        debug_assert_eq!(*self.free_cursor.get_mut(), self.free.len() as i64, "the reserved entities must be flushed first");
+
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let slot = &mut self.slots[entity.index as usize];
+        *slot = (slot.0.wrapping_add(1), false);
+
+        self.free.push(entity.index);
+        *self.free_cursor.get_mut() = self.free.len() as i64;
+        self.len -= 1;
+
+        true
+
+    }
+    //
+    /// true if the entity was spawned and not despawned since
+    pub(crate) fn is_alive(&self, entity:Entity) -> bool {
+
+        self.slots.get(entity.index as usize) == Some(&(entity.generation, true))
+
+    }
+    //
+    /// number of living entities
+    pub(crate) fn len(&self) -> usize { self.len }
+    //
+    /// the living entities by index
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.1)
+            .map(|(i, s)| Entity { index: i as u32, generation: s.0 })
+
+    }
+    //
+}
+//
+//
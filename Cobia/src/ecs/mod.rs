@@ -1,124 +1,30 @@
-#![allow(dead_code)]
-
-
-pub mod types;
-
-use types::Component;
-use thiserror::Error;
-use ShaderParser::EParser;
-
-
-
-//
-//
-// ------------------------------------------------------------------------------------------------
-// Error 
+// Entity component system: entities are generational ids and their data is made of typed
+// components kept by the world, the systems of the scheduler query them every frame
 //
-//
-#[allow(non_camel_case_types)]
-#[derive(Debug, Error)]
-pub enum EComponent {
-
-    #[error("Opengl Api cause an error {source}")]
-    GL{ 
-        #[from]
-        source: crate::renderer::opengl::EOpenGL
-    },
-
-    #[error("General Error caused by: {source}")]
-    GENERAL {
-        #[from]
-        source: crate::ECobia,
-    },
-
-    #[error("No component have the id {0}")]
-    BAD_ID(u32),
-
-    #[error("The component got a reference to the component with id {0} but got {1}")]
-    BAD_REF(u32, u32),
-
-    //
-    // --------------------------------------------------------------------------------------------
-    // CFILE Error
-    //
-    //
-    #[error("Cant get file access contents because: {0}")]
-    FILE_ACCESS(String,String),
-
-    #[error("Cant open/load the file {file} because: {cause}")]
-    LOADING_FILE { file: String, cause: String },
+// Any type that can be shared between threads is a component, there is nothing to implement.
 
-    #[error(
-        "Cant access the file {0} extension. Possible cause: 
-        - there is no file name
-        - there is no embedded '.'
-        - other 
-    "
-    )]
-    FILE_EXT(String),
+mod archetype;
+mod command;
+mod entity;
+mod pool;
+mod query;
+mod resource;
+mod schedule;
+mod world;
 
-    #[error("Cant get file contents because: {0}")]
-    FILE_CONTENT(String),
-    //
-    // --------------------------------------------------------------------------------------------
-    // CImage 
-    //
-    //
-    #[error("Unable to load image file {0} because {1}")]
-    LOAD_IMAGE(String, String),
-    //
-    // --------------------------------------------------------------------------------------------
-    // Shader 
-    //
-    //
-    #[error("unsupported shader file with extension: {0}")]
-    SHADER_TYPE(String),
-    
-    #[error("Error when parsing shader: {source} ")]
-    PARSER {
-        #[from]
-        source: EParser
+pub use archetype::{Archetype, Bundle, Column, ComponentTicks};
+pub use command::Commands;
+pub use entity::Entity;
+pub use query::{Access, Added, Changed, Fetch, Filter, Query, QueryIter, TickRange, With, Without};
+pub use schedule::{Scheduler, Stage, System, SystemContext};
+pub use world::World;
 
-    },
-
-    //
-    // --------------------------------------------------------------------------------------------
-    //
-    //
-    //
-}
-//
-//
-// ------------------------------------------------------------------------------------------------
-// Subsystem
-//
-//
-//
-struct ComponentSystem {
-    
-    id_counter: u32,
-    components: Vec<Box<dyn Component>>,
-    current_shader: u32 
-
-}
+pub(crate) use entity::Entities;
 //
-impl ComponentSystem {
-
-    fn init() -> Self { ComponentSystem { id_counter:1, components: Vec::new(), current_shader: 0 } }
-
-    fn push(&mut self,component:Box<dyn Component>)  { self.components.push(component); }
-
-    fn get(&self,id:u32) -> &Box<dyn Component> { &self.components[id as usize] } 
-
-    pub fn set_curent_shader(&mut self, id:u32) { self.current_shader = id }
-
-    
-}
 //
+/// A piece of data of an entity
+pub trait Component: Send + Sync + 'static {}
 //
-// ------------------------------------------------------------------------------------------------
-// Function Subsystem Access
+impl<T:Send + Sync + 'static> Component for T {}
 //
 //
-
-
@@ -0,0 +1,281 @@
+// Worker threads that run the systems of the scheduler. A scope of tasks borrows its data: it
+// doesn't return before every task is finished, the calling thread runs tasks while it waits
+
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn scope_borrows() {
+
+        let pool = TaskPool::new(3);
+        let counter = AtomicUsize::new(0);
+        let mut values = [0; 16];
+        let counter = &counter;
+
+        for _ in 0..4 {
+
+            let tasks:Vec<Task<'_>> = values
+                .iter_mut()
+                .map(|v| Box::new(move || {
+                    *v += 1;
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }) as Task<'_>)
+                .collect();
+
+            pool.scope(tasks);
+
+        }
+
+        assert_eq!(counter.load(Ordering::Relaxed), 64);
+        assert!(values.iter().all(|v| *v == 4));
+
+        // without worker the calling thread does everything
+        let mut ran = false;
+        TaskPool::new(0).scope(vec![Box::new(|| ran = true)]);
+        assert!(ran);
+
+    }
+
+    #[test]
+    #[should_panic(expected = "task failed")]
+    fn panic_after_scope() {
+
+        let pool = TaskPool::new(2);
+        let done = AtomicUsize::new(0);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.scope(vec![
+                Box::new(|| panic!("task failed")),
+                Box::new(|| { done.fetch_add(1, Ordering::Relaxed); }),
+                Box::new(|| { done.fetch_add(1, Ordering::Relaxed); })
+            ]);
+        }));
+
+        // the other tasks finished before the panic was resumed
+        assert_eq!(done.load(Ordering::Relaxed), 2);
+
+        panic::resume_unwind(result.unwrap_err());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Pool
+//
+/// A task of a scope, it can borrow what outlives the scope
+pub(crate) type Task<'s> = Box<dyn FnOnce() + Send + 's>;
+//
+type StaticTask = Box<dyn FnOnce() + Send + 'static>;
+//
+//
+struct PoolShared {
+
+    queue:      Mutex<VecDeque<StaticTask>>,
+    available:  Condvar,
+    shutdown:   AtomicBool
+
+}
+//
+impl PoolShared {
+
+    fn queue(&self) -> MutexGuard<'_, VecDeque<StaticTask>> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+}
+//
+//
+/// The tasks of a scope that are not finished and the first panic of one of them
+#[derive(Default)]
+struct Latch {
+
+    state:      Mutex<(usize, Option<Box<dyn std::any::Any + Send>>)>,
+    finished:   Condvar
+
+}
+//
+impl Latch {
+
+    fn lock(&self) -> MutexGuard<'_, (usize, Option<Box<dyn std::any::Any + Send>>)> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+}
+//
+//
+/// Pool of worker threads
+pub(crate) struct TaskPool {
+
+    shared:     Arc<PoolShared>,
+    workers:    Vec<JoinHandle<()>>
+
+}
+//
+impl TaskPool {
+    //
+    /// Start the worker threads
+    ///
+    /// # Parameters
+    ///
+    /// * 'workers' - number of threads in the pool, the scopes also use the calling thread
+    ///
+    pub(crate) fn new(workers:usize) -> Self {
+
+        let shared = Arc::new(PoolShared {
+            queue:      Mutex::new(VecDeque::new()),
+            available:  Condvar::new(),
+            shutdown:   AtomicBool::new(false)
+        });
+
+        let workers = (0..workers)
+            .map(|i| {
+
+                let shared = shared.clone();
+
+                thread::Builder::new()
+                    .name(format!("cobia-system-{}",i))
+                    .spawn(move || worker_loop(shared))
+                    .expect("failed to spawn a system thread")
+
+            })
+            .collect();
+
+        Self { shared, workers }
+
+    }
+    //
+    /// number of worker threads
+    pub(crate) fn workers(&self) -> usize { self.workers.len() }
+    //
+    /// Run tasks on the workers and the calling thread, return when they are all finished. A
+    /// panic of a task is resumed on the calling thread once the others are finished
+    ///
+    /// # Parameters
+    ///
+    /// * 'tasks' - the tasks to run
+    ///
+    pub(crate) fn scope(&self, tasks:Vec<Task<'_>>) {
+
+        let latch = Arc::new(Latch::default());
+        latch.lock().0 = tasks.len();
+
+        {
+            let mut queue = self.shared.queue();
+
+            for task in tasks {
+
+                // SAFETY: the scope waits below for every task, so what they borrow outlives them
+                let task:StaticTask = unsafe { std::mem::transmute::<Task<'_>, StaticTask>(task) };
+                let latch = latch.clone();
+
+                queue.push_back(Box::new(move || {
+
+                    let result = panic::catch_unwind(AssertUnwindSafe(task));
+
+                    let mut state = latch.lock();
+                    state.0 -= 1;
+
+                    if let Err(payload) = result {
+                        state.1.get_or_insert(payload);
+                    }
+
+                    latch.finished.notify_all();
+
+                }));
+
+            }
+        }
+
+        self.shared.available.notify_all();
+
+        // help the workers, the queue only has tasks of this scope
+        loop {
+
+            let task = self.shared.queue().pop_front();
+
+            match task {
+                Some(task) => task(),
+                None => break
+            }
+
+        }
+
+        let mut state = latch.lock();
+
+        while state.0 > 0 {
+            state = latch.finished.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+
+        if let Some(payload) = state.1.take() {
+
+            drop(state);
+            panic::resume_unwind(payload);
+
+        }
+
+    }
+    //
+}
+//
+impl Drop for TaskPool {
+
+    fn drop(&mut self) {
+
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        self.shared.available.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+
+    }
+
+}
+//
+//
+fn worker_loop(shared:Arc<PoolShared>) {
+
+    loop {
+
+        let task = {
+
+            let mut queue = shared.queue();
+
+            loop {
+
+                if shared.shutdown.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                if let Some(task) = queue.pop_front() {
+                    break task;
+                }
+
+                queue = shared.available.wait(queue).unwrap_or_else(|e| e.into_inner());
+
+            }
+
+        };
+
+        task();
+
+    }
+
+}
+//
+//
@@ -0,0 +1,728 @@
+// Typed queries: the components of the entities of every archetype that has the types asked and
+// passes the filters, in the order of the archetypes
+//
+// world.query::<(Entity, &Transform, &mut Velocity, Option<&Name>), Without<Frozen>>()
+
+use std::any::{type_name, TypeId};
+use std::marker::PhantomData;
+
+use super::{Archetype, Component, ComponentTicks, Entity, World};
+use crate::core::error_handler::EEcs;
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[derive(Debug,PartialEq)]
+    struct Position(f32);
+
+    #[derive(Debug,PartialEq)]
+    struct Velocity(f32);
+
+    #[derive(Debug,PartialEq)]
+    struct Name(&'static str);
+
+    struct Frozen;
+
+    #[test]
+    fn query_and_filters() {
+
+        let mut world = World::new();
+
+        let a = world.spawn((Position(0.0), Velocity(1.0), Name("a")));
+        let b = world.spawn((Position(0.0), Velocity(2.0)));
+        let c = world.spawn((Position(0.0), Velocity(3.0), Frozen));
+        let d = world.spawn((Position(0.0),));
+
+        {
+            let mut query = world.query::<(&mut Position, &Velocity), Without<Frozen>>().unwrap();
+
+            for (p, v) in query.iter() {
+                p.0 += v.0;
+            }
+
+            assert!(query.get(c).is_none());
+            assert_eq!(query.get(b).map(|(p, _)| p.0), Some(2.0));
+        }
+
+        assert_eq!(world.get::<Position>(a), Some(&Position(1.0)));
+        assert_eq!(world.get::<Position>(c), Some(&Position(0.0)));
+        assert_eq!(world.get::<Position>(d), Some(&Position(0.0)));
+
+        // the optional components don't restrict the entities
+        let mut query = world.query::<(Entity, Option<&Name>), With<Velocity>>().unwrap();
+        let mut found:Vec<(Entity, Option<&Name>)> = query.iter().collect();
+        found.sort_by_key(|(e, _)| *e);
+
+        assert_eq!(found, vec![(a, Some(&Name("a"))), (b, None), (c, None)]);
+
+        let mut query = world.query::<&Position, (With<Velocity>, Without<Name>)>().unwrap();
+        assert_eq!(query.iter().count(), 2);
+
+    }
+
+    #[test]
+    fn aliasing_access() {
+
+        let mut world = World::new();
+        world.spawn((Position(0.0),));
+
+        assert!(world.query::<(&mut Position, &Position), ()>().is_err());
+        assert!(world.query::<(&mut Position, Option<&mut Position>), ()>().is_err());
+        assert!(world.query::<(&Position, &Position), ()>().is_ok());
+
+        let mut access = Access::default();
+        <&mut Position as Fetch>::access(&mut access);
+
+        let mut other = Access::default();
+        <(&Velocity, &Position) as Fetch>::access(&mut other);
+
+        assert!(access.conflicts(&other));
+        assert!(!access.conflicts(&Access::of::<&Velocity>()));
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Access
+//
+/// The types read and written by a query or a system
+#[derive(Debug,Default,Clone)]
+struct AccessSet {
+
+    reads:  Vec<TypeId>,
+    writes: Vec<TypeId>
+
+}
+//
+impl AccessSet {
+    //
+    /// add a read, true if the type is already written
+    fn read(&mut self, t:TypeId) -> bool {
+
+        self.reads.push(t);
+        self.writes.contains(&t)
+
+    }
+    //
+    /// add a write, true if the type is already used
+    fn write(&mut self, t:TypeId) -> bool {
+
+        let used = self.writes.contains(&t) || self.reads.contains(&t);
+        self.writes.push(t);
+
+        used
+
+    }
+    //
+    fn conflicts(&self, other:&AccessSet) -> bool {
+
+        self.writes.iter().any(|t| other.writes.contains(t) || other.reads.contains(t))
+            || other.writes.iter().any(|t| self.reads.contains(t))
+
+    }
+    //
+    fn is_subset(&self, other:&AccessSet) -> bool {
+
+        self.writes.iter().all(|t| other.writes.contains(t))
+            && self.reads.iter().all(|t| other.reads.contains(t) || other.writes.contains(t))
+
+    }
+    //
+}
+//
+//
+/// The component and resource types read and written by a query or a system
+#[derive(Debug,Default,Clone)]
+pub struct Access {
+
+    components: AccessSet,
+    resources:  AccessSet,
+    // the name of the first component used by two fetches where one of them writes
+    conflict:   Option<&'static str>
+
+}
+//
+impl Access {
+    //
+    /// the access of a fetch
+    pub fn of<Q:Fetch>() -> Self {
+
+        let mut access = Self::default();
+        Q::access(&mut access);
+
+        access
+
+    }
+    //
+    /// add a read of a component type
+    pub fn read<T:Component>(&mut self) {
+
+        if self.components.read(TypeId::of::<T>()) {
+            self.conflict.get_or_insert(type_name::<T>());
+        }
+
+    }
+    //
+    /// add a write of a component type
+    pub fn write<T:Component>(&mut self) {
+
+        if self.components.write(TypeId::of::<T>()) {
+            self.conflict.get_or_insert(type_name::<T>());
+        }
+
+    }
+    //
+    /// add a read of a resource type
+    pub fn read_resource<R:Component>(&mut self) { self.resources.read(TypeId::of::<R>()); }
+    //
+    /// add a write of a resource type
+    pub fn write_resource<R:Component>(&mut self) { self.resources.write(TypeId::of::<R>()); }
+    //
+    /// the component types read
+    pub fn reads(&self) -> &[TypeId] { &self.components.reads }
+    //
+    /// the component types written
+    pub fn writes(&self) -> &[TypeId] { &self.components.writes }
+    //
+    /// true if the resource type is read or written
+    pub fn reads_resource<R:Component>(&self) -> bool {
+
+        let t = TypeId::of::<R>();
+
+        self.resources.reads.contains(&t) || self.resources.writes.contains(&t)
+
+    }
+    //
+    /// true if the resource type is written
+    pub fn writes_resource<R:Component>(&self) -> bool { self.resources.writes.contains(&TypeId::of::<R>()) }
+    //
+    /// true if one of them writes a type that the other uses
+    pub fn conflicts(&self, other:&Access) -> bool {
+
+        self.components.conflicts(&other.components) || self.resources.conflicts(&other.resources)
+
+    }
+    //
+    /// true if every type used is used by the other one in the same way or by writing it
+    pub fn is_subset(&self, other:&Access) -> bool {
+
+        self.components.is_subset(&other.components) && self.resources.is_subset(&other.resources)
+
+    }
+    //
+    /// Error if the same component type was written twice or read and written
+    pub(crate) fn check(&self) -> Result<(),EEcs> {
+
+        match self.conflict {
+            Some(name) => Err(EEcs::Access
+                .as_report()
+                .attach_printable(format!("the component {} is borrowed mutably more than once",name))),
+            None => Ok(())
+        }
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Ticks
+//
+/// The ticks of a query: the changes made after the last run are detected, and the writes are
+/// marked at this run
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct TickRange {
+
+    pub last_run:   u64,
+    pub this_run:   u64
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Fetch
+//
+/// What a query gives for every entity: `&T`, `&mut T`, `Option<F>`, `Entity` or a tuple of them
+///
+/// # Safety
+///
+/// The access must declare every column read and written by fetch
+pub unsafe trait Fetch {
+    //
+    /// the value given for an entity
+    type Item<'q>;
+    //
+    /// what is needed to fetch the rows of an archetype
+    type State;
+    //
+    /// add the types read and written
+    fn access(access:&mut Access);
+    //
+    /// true if the entities of the archetype can be fetched
+    fn matches(archetype:&Archetype) -> bool;
+    //
+    /// # Safety
+    ///
+    /// The archetype must match and the accesses must be checked
+    unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State;
+    //
+    /// # Safety
+    ///
+    /// The row must exist in the archetype of the state, it must not be fetched mutably twice.
+    /// The components written are marked as changed
+    unsafe fn fetch<'q>(state:&Self::State, row:usize) -> Self::Item<'q>;
+    //
+}
+//
+unsafe impl<T:Component> Fetch for &T {
+
+    type Item<'q> = &'q T;
+    type State = *const T;
+
+    fn access(access:&mut Access) { access.read::<T>(); }
+
+    fn matches(archetype:&Archetype) -> bool { archetype.has::<T>() }
+
+    unsafe fn state(archetype:&Archetype, _ticks:TickRange) -> Self::State { archetype.column_ptr::<T>().unwrap() }
+
+    unsafe fn fetch<'q>(state:&Self::State, row:usize) -> Self::Item<'q> { &*state.add(row) }
+
+}
+//
+unsafe impl<T:Component> Fetch for &mut T {
+
+    type Item<'q> = &'q mut T;
+    type State = (*mut T, *mut ComponentTicks, u64);
+
+    fn access(access:&mut Access) { access.write::<T>(); }
+
+    fn matches(archetype:&Archetype) -> bool { archetype.has::<T>() }
+
+    unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State {
+
+        (archetype.column_mut_ptr::<T>().unwrap(), archetype.ticks_mut_ptr::<T>().unwrap(), ticks.this_run)
+
+    }
+
+    unsafe fn fetch<'q>(state:&Self::State, row:usize) -> Self::Item<'q> {
+
+        (*state.1.add(row)).set_changed(state.2);
+        &mut *state.0.add(row)
+
+    }
+
+}
+//
+unsafe impl<Q:Fetch> Fetch for Option<Q> {
+
+    type Item<'q> = Option<Q::Item<'q>>;
+    type State = Option<Q::State>;
+
+    fn access(access:&mut Access) { Q::access(access); }
+
+    fn matches(_archetype:&Archetype) -> bool { true }
+
+    unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State {
+
+        match Q::matches(archetype) {
+            true => Some(Q::state(archetype, ticks)),
+            false => None
+        }
+
+    }
+
+    unsafe fn fetch<'q>(state:&Self::State, row:usize) -> Self::Item<'q> { state.as_ref().map(|s| Q::fetch(s, row)) }
+
+}
+//
+unsafe impl Fetch for Entity {
+
+    type Item<'q> = Entity;
+    type State = *const Entity;
+
+    fn access(_access:&mut Access) {}
+
+    fn matches(_archetype:&Archetype) -> bool { true }
+
+    unsafe fn state(archetype:&Archetype, _ticks:TickRange) -> Self::State { archetype.entities().as_ptr() }
+
+    unsafe fn fetch<'q>(state:&Self::State, row:usize) -> Self::Item<'q> { *state.add(row) }
+
+}
+//
+//
+macro_rules! impl_fetch {
+    ($($name:ident),*) => {
+
+        #[allow(non_snake_case, unused_variables, clippy::unused_unit)]
+        unsafe impl<$($name:Fetch),*> Fetch for ($($name,)*) {
+
+            type Item<'q> = ($($name::Item<'q>,)*);
+            type State = ($($name::State,)*);
+
+            fn access(access:&mut Access) { $($name::access(access);)* }
+
+            fn matches(archetype:&Archetype) -> bool { true $(&& $name::matches(archetype))* }
+
+            unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State { ($($name::state(archetype, ticks),)*) }
+
+            unsafe fn fetch<'q>(state:&Self::State, row:usize) -> Self::Item<'q> {
+
+                let ($($name,)*) = state;
+                ($($name::fetch($name, row),)*)
+
+            }
+
+        }
+
+    };
+}
+//
+impl_fetch!();
+impl_fetch!(A);
+impl_fetch!(A, B);
+impl_fetch!(A, B, C);
+impl_fetch!(A, B, C, D);
+impl_fetch!(A, B, C, D, E);
+impl_fetch!(A, B, C, D, E, F);
+impl_fetch!(A, B, C, D, E, F, G);
+impl_fetch!(A, B, C, D, E, F, G, H);
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Filter
+//
+/// Restrict the entities of a query without fetching anything: `With<T>`, `Without<T>`,
+/// `Added<T>`, `Changed<T>` or a tuple of them that must all pass, () for no filter
+///
+/// # Safety
+///
+/// The access must declare every column read by filter
+pub unsafe trait Filter {
+    //
+    /// what is needed to filter the rows of an archetype
+    type State;
+    //
+    /// add the types read
+    fn access(access:&mut Access);
+    //
+    /// true if some entities of the archetype can pass the filter
+    fn matches(archetype:&Archetype) -> bool;
+    //
+    /// # Safety
+    ///
+    /// The archetype must match and the accesses must be checked
+    unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State;
+    //
+    /// # Safety
+    ///
+    /// The row must exist in the archetype of the state
+    unsafe fn filter(state:&Self::State, row:usize) -> bool;
+    //
+}
+//
+/// the entities that have a component of this type
+pub struct With<T>(PhantomData<T>);
+//
+unsafe impl<T:Component> Filter for With<T> {
+
+    type State = ();
+
+    fn access(_access:&mut Access) {}
+
+    fn matches(archetype:&Archetype) -> bool { archetype.has::<T>() }
+
+    unsafe fn state(_archetype:&Archetype, _ticks:TickRange) -> Self::State {}
+
+    unsafe fn filter(_state:&Self::State, _row:usize) -> bool { true }
+
+}
+//
+/// the entities that don't have a component of this type
+pub struct Without<T>(PhantomData<T>);
+//
+unsafe impl<T:Component> Filter for Without<T> {
+
+    type State = ();
+
+    fn access(_access:&mut Access) {}
+
+    fn matches(archetype:&Archetype) -> bool { !archetype.has::<T>() }
+
+    unsafe fn state(_archetype:&Archetype, _ticks:TickRange) -> Self::State {}
+
+    unsafe fn filter(_state:&Self::State, _row:usize) -> bool { true }
+
+}
+//
+/// the entities whose component of this type was added since the last run
+pub struct Added<T>(PhantomData<T>);
+//
+unsafe impl<T:Component> Filter for Added<T> {
+
+    type State = (*const ComponentTicks, u64);
+
+    fn access(access:&mut Access) { access.read::<T>(); }
+
+    fn matches(archetype:&Archetype) -> bool { archetype.has::<T>() }
+
+    unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State {
+
+        (archetype.ticks_ptr::<T>().unwrap(), ticks.last_run)
+
+    }
+
+    unsafe fn filter(state:&Self::State, row:usize) -> bool { (*state.0.add(row)).added() > state.1 }
+
+}
+//
+/// the entities whose component of this type was added or changed since the last run
+pub struct Changed<T>(PhantomData<T>);
+//
+unsafe impl<T:Component> Filter for Changed<T> {
+
+    type State = (*const ComponentTicks, u64);
+
+    fn access(access:&mut Access) { access.read::<T>(); }
+
+    fn matches(archetype:&Archetype) -> bool { archetype.has::<T>() }
+
+    unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State {
+
+        (archetype.ticks_ptr::<T>().unwrap(), ticks.last_run)
+
+    }
+
+    unsafe fn filter(state:&Self::State, row:usize) -> bool { (*state.0.add(row)).changed() > state.1 }
+
+}
+//
+//
+macro_rules! impl_filter {
+    ($($name:ident),*) => {
+
+        #[allow(non_snake_case, unused_variables, clippy::unused_unit)]
+        unsafe impl<$($name:Filter),*> Filter for ($($name,)*) {
+
+            type State = ($($name::State,)*);
+
+            fn access(access:&mut Access) { $($name::access(access);)* }
+
+            fn matches(archetype:&Archetype) -> bool { true $(&& $name::matches(archetype))* }
+
+            unsafe fn state(archetype:&Archetype, ticks:TickRange) -> Self::State { ($($name::state(archetype, ticks),)*) }
+
+            unsafe fn filter(state:&Self::State, row:usize) -> bool {
+
+                let ($($name,)*) = state;
+                true $(&& $name::filter($name, row))*
+
+            }
+
+        }
+
+    };
+}
+//
+impl_filter!();
+impl_filter!(A);
+impl_filter!(A, B);
+impl_filter!(A, B, C);
+impl_filter!(A, B, C, D);
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Query
+//
+/// The entities of a world that match a fetch and a filter
+pub struct Query<'w, Q:Fetch, F:Filter = ()> {
+
+    world:      &'w World,
+    ticks:      TickRange,
+    // the matching archetypes, sorted
+    archetypes: Vec<usize>,
+    marker:     PhantomData<fn() -> (Q, F)>
+
+}
+//
+impl<'w, Q:Fetch, F:Filter> Query<'w, Q, F> {
+    //
+    /// Create a query, error if the fetch writes a type that it also uses elsewhere
+    ///
+    /// # Safety
+    ///
+    /// Nothing else can use the components written by the fetch or write the ones it reads
+    /// while the query exists
+    ///
+    /// # Parameters
+    ///
+    /// * 'world' - the world queried
+    /// * 'ticks' - the changes after the last run pass Added and Changed
+    ///
+    pub(crate) unsafe fn new(world:&'w World, ticks:TickRange) -> Result<Self,EEcs> {
+
+        Access::of::<Q>().check()?;
+
+        let archetypes = world
+            .archetypes()
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| Q::matches(a) && F::matches(a))
+            .map(|(i, _)| i)
+            .collect();
+
+        Ok(Self { world, ticks, archetypes, marker: PhantomData })
+
+    }
+    //
+    /// the types used by the fetch and the filter
+    pub fn access() -> Access {
+
+        let mut access = Access::of::<Q>();
+        F::access(&mut access);
+
+        access
+
+    }
+    //
+    /// the fetch of every entity that match
+    pub fn iter(&mut self) -> QueryIter<'_, Q, F> {
+
+        QueryIter {
+            archetypes: self.world.archetypes(),
+            matched:    &self.archetypes,
+            ticks:      self.ticks,
+            current:    0,
+            state:      None,
+            row:        0,
+            len:        0
+        }
+
+    }
+    //
+    /// the fetch of an entity, None if it is dead or doesn't match
+    pub fn get(&mut self, entity:Entity) -> Option<Q::Item<'_>> {
+
+        let (archetype, row) = self.world.location(entity)?;
+
+        self.archetypes.binary_search(&archetype).ok()?;
+
+        let archetype = &self.world.archetypes()[archetype];
+
+        // SAFETY: the archetype matches, the access was checked when the query was created
+        unsafe {
+
+            if !F::filter(&F::state(archetype, self.ticks), row) {
+                return None;
+            }
+
+            Some(Q::fetch(&Q::state(archetype, self.ticks), row))
+
+        }
+
+    }
+    //
+    /// number of entities that match
+    pub fn len(&self) -> usize {
+
+        self.archetypes
+            .iter()
+            .map(|a| {
+
+                let archetype = &self.world.archetypes()[*a];
+
+                // SAFETY: the filter only reads, the access was checked when the query was created
+                let state = unsafe { F::state(archetype, self.ticks) };
+
+                (0..archetype.len()).filter(|row| unsafe { F::filter(&state, *row) }).count()
+
+            })
+            .sum()
+
+    }
+    //
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    //
+}
+//
+impl<'q, 'w, Q:Fetch, F:Filter> IntoIterator for &'q mut Query<'w, Q, F> {
+
+    type Item = Q::Item<'q>;
+    type IntoIter = QueryIter<'q, Q, F>;
+
+    fn into_iter(self) -> Self::IntoIter { self.iter() }
+
+}
+//
+//
+/// Iterator on the entities of a query, archetype after archetype
+pub struct QueryIter<'q, Q:Fetch, F:Filter> {
+
+    archetypes: &'q [Archetype],
+    matched:    &'q [usize],
+    ticks:      TickRange,
+    // the next archetype in matched
+    current:    usize,
+    state:      Option<(Q::State, F::State)>,
+    row:        usize,
+    len:        usize
+
+}
+//
+impl<'q, Q:Fetch, F:Filter> Iterator for QueryIter<'q, Q, F> {
+
+    type Item = Q::Item<'q>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        loop {
+
+            while self.row >= self.len {
+
+                let archetype = &self.archetypes[*self.matched.get(self.current)?];
+
+                // SAFETY: the archetype matches, the access was checked when the query was created
+                self.state = Some(unsafe { (Q::state(archetype, self.ticks), F::state(archetype, self.ticks)) });
+                self.row = 0;
+                self.len = archetype.len();
+                self.current += 1;
+
+            }
+
+            self.row += 1;
+
+            let (fetch, filter) = self.state.as_ref()?;
+
+            // SAFETY: every row is fetched once while the query is borrowed
+            unsafe {
+                if F::filter(filter, self.row - 1) {
+                    return Some(Q::fetch(fetch, self.row - 1));
+                }
+            }
+
+        }
+
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+
+        let left = self.len - self.row
+            + self.matched[self.current.min(self.matched.len())..].iter().map(|a| self.archetypes[*a].len()).sum::<usize>();
+
+        (0, Some(left))
+
+    }
+
+}
+//
+//
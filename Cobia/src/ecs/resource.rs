@@ -0,0 +1,147 @@
+// Resources: the data of a world that doesn't belong to an entity, at most one of each type
+
+use std::any::{Any, TypeId};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+
+use super::{Component, ComponentTicks};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[derive(Debug,PartialEq)]
+    struct Gravity(f32);
+
+    #[test]
+    fn insert_and_change() {
+
+        let mut resources = Resources::default();
+
+        assert_eq!(resources.insert(Gravity(9.8), 1), None);
+        assert_eq!(resources.insert(Gravity(1.6), 2), Some(Gravity(9.8)));
+        assert_eq!(resources.ticks::<Gravity>(), Some(ComponentTicks::new(2)));
+
+        resources.get_mut::<Gravity>(5).unwrap().0 = 3.7;
+
+        assert_eq!(resources.get::<Gravity>(), Some(&Gravity(3.7)));
+        assert_eq!(resources.ticks::<Gravity>().map(|t| (t.added(), t.changed())), Some((2, 5)));
+
+        assert_eq!(resources.remove::<Gravity>(), Some(Gravity(3.7)));
+        assert!(!resources.contains::<Gravity>());
+        assert!(resources.get_mut::<Gravity>(6).is_none());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Resources
+//
+struct ResourceCell {
+
+    // the systems change the resources behind a shared reference, the scheduler make sure that
+    // their accesses don't overlap
+    value:  UnsafeCell<Box<dyn Any + Send + Sync>>,
+    ticks:  UnsafeCell<ComponentTicks>
+
+}
+//
+//
+/// The resources of a world by type
+#[derive(Default)]
+pub(crate) struct Resources {
+
+    cells:  HashMap<TypeId, ResourceCell>
+
+}
+//
+impl Resources {
+    //
+    /// Add a resource, return the one of the same type that it replace
+    ///
+    /// # Parameters
+    ///
+    /// * 'resource' - the new resource
+    /// * 'tick' - the tick when it is added
+    ///
+    pub(crate) fn insert<R:Component>(&mut self, resource:R, tick:u64) -> Option<R> {
+
+        let cell = ResourceCell {
+            value:  UnsafeCell::new(Box::new(resource)),
+            ticks:  UnsafeCell::new(ComponentTicks::new(tick))
+        };
+
+        let previous = self.cells.insert(TypeId::of::<R>(), cell)?;
+
+        previous.value.into_inner().downcast::<R>().ok().map(|r| *r)
+
+    }
+    //
+    /// take the resource of a type
+    pub(crate) fn remove<R:Component>(&mut self) -> Option<R> {
+
+        let cell = self.cells.remove(&TypeId::of::<R>())?;
+
+        cell.value.into_inner().downcast::<R>().ok().map(|r| *r)
+
+    }
+    //
+    pub(crate) fn contains<R:Component>(&self) -> bool { self.cells.contains_key(&TypeId::of::<R>()) }
+    //
+    /// the resource of a type
+    pub(crate) fn get<R:Component>(&self) -> Option<&R> {
+
+        let cell = self.cells.get(&TypeId::of::<R>())?;
+
+        // SAFETY: a shared reference is only given when nothing change the resource
+        unsafe { &*cell.value.get() }.downcast_ref::<R>()
+
+    }
+    //
+    /// the resource of a type, it is marked as changed at the tick
+    pub(crate) fn get_mut<R:Component>(&mut self, tick:u64) -> Option<&mut R> {
+
+        let cell = self.cells.get_mut(&TypeId::of::<R>())?;
+
+        cell.ticks.get_mut().set_changed(tick);
+        cell.value.get_mut().downcast_mut::<R>()
+
+    }
+    //
+    /// The resource of a type behind a shared reference, it is marked as changed at the tick
+    ///
+    /// # Safety
+    ///
+    /// The caller must make sure that nothing else use the resource while it is borrowed
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn get_unchecked_mut<R:Component>(&self, tick:u64) -> Option<&mut R> {
+
+        let cell = self.cells.get(&TypeId::of::<R>())?;
+
+        (*cell.ticks.get()).set_changed(tick);
+        (*cell.value.get()).downcast_mut::<R>()
+
+    }
+    //
+    /// when the resource of a type was added and last changed
+    pub(crate) fn ticks<R:Component>(&self) -> Option<ComponentTicks> {
+
+        // SAFETY: the ticks are only read when nothing change the resource
+        self.cells.get(&TypeId::of::<R>()).map(|c| unsafe { *c.ticks.get() })
+
+    }
+    //
+}
+//
+// SAFETY: the resources are only changed behind a shared reference by the systems, which check
+// their accesses
+unsafe impl Sync for Resources {}
+//
+//
@@ -0,0 +1,740 @@
+// Systems and their scheduler: a system is a function with the component and resource types that
+// it reads and writes. The systems of a stage are ordered by their before/after constraints then by the order
+// they were added, and the ones whose accesses don't conflict run together on the worker threads.
+// The commands of the systems are applied at the end of every stage
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::command::CommandQueue;
+use super::pool::{Task, TaskPool};
+use super::{Access, Commands, Component, Fetch, Filter, Query, TickRange, World};
+use crate::core::error_handler::EEcs;
+
+use error_stack::{Report, Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ecs::{Added, Changed};
+    use std::sync::Arc;
+
+    struct Position(f32);
+
+    struct Velocity(f32);
+
+    struct Health(u32);
+
+    fn noop(_ctx:&mut SystemContext) -> Result<(),EEcs> { Ok(()) }
+
+    #[test]
+    fn batches() {
+
+        let mut scheduler = Scheduler::with_threads(2);
+
+        scheduler.add_system(System::new("render", noop).stage(Stage::Render).reads::<Position>()).unwrap();
+        scheduler.add_system(System::new("movement", noop).writes::<Position>().reads::<Velocity>()).unwrap();
+        scheduler.add_system(System::new("regen", noop).writes::<Health>()).unwrap();
+        scheduler.add_system(System::new("damage", noop).writes::<Health>().reads::<Position>()).unwrap();
+        scheduler.add_system(System::new("input", noop).writes::<Velocity>().before("movement")).unwrap();
+        scheduler.add_system(System::new("log", noop).after("damage")).unwrap();
+
+        // movement waits for input, damage follows regen that also writes the health, then
+        // movement follows damage that reads the position
+        assert_eq!(scheduler.batches(Stage::Update).unwrap(), vec![
+            vec!["regen", "input"],
+            vec!["damage"],
+            vec!["movement", "log"]
+        ]);
+
+        assert_eq!(scheduler.batches(Stage::Render).unwrap(), vec![vec!["render"]]);
+        assert!(scheduler.batches(Stage::PreUpdate).unwrap().is_empty());
+
+        assert!(scheduler.add_system(System::new("log", noop)).is_err());
+
+        scheduler.add_system(System::new("a", noop).after("b")).unwrap();
+        scheduler.add_system(System::new("b", noop).after("a")).unwrap();
+        assert!(scheduler.batches(Stage::Update).is_err());
+
+        let mut scheduler = Scheduler::with_threads(0);
+        scheduler.add_system(System::new("a", noop).after("missing")).unwrap();
+        assert!(scheduler.batches(Stage::Update).is_err());
+
+    }
+
+    #[test]
+    fn change_detection() {
+
+        struct Time(u32);
+
+        let mut world = World::new();
+        world.insert_resource(Time(0));
+
+        let a = world.spawn((Position(0.0),));
+        world.spawn((Position(0.0),));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+
+        let mut scheduler = Scheduler::with_threads(1);
+
+        scheduler.add_system(System::new("clock", |ctx:&mut SystemContext| {
+            ctx.resource_mut::<Time>()?.0 += 1;
+            Ok(())
+        }).stage(Stage::PreUpdate).writes_resource::<Time>()).unwrap();
+
+        scheduler.add_system(System::new("upload", move |ctx:&mut SystemContext| {
+            let time = ctx.resource::<Time>()?.0;
+            let changed = ctx.query::<&Position, Changed<Position>>()?.iter().count();
+            let added = ctx.query::<&Position, Added<Position>>()?.iter().count();
+            log.lock().unwrap().push((time, changed, added));
+            Ok(())
+        }).reads::<Position>().reads_resource::<Time>()).unwrap();
+
+        scheduler.run(&mut world).unwrap();
+        scheduler.run(&mut world).unwrap();
+
+        world.get_mut::<Position>(a).unwrap().0 = 1.0;
+        world.spawn((Position(0.0),));
+
+        scheduler.run(&mut world).unwrap();
+
+        // everything is new on the first run, then only what changed between the runs
+        assert_eq!(*seen.lock().unwrap(), vec![(1, 2, 2), (2, 0, 0), (3, 2, 1)]);
+
+        scheduler.add_system(System::new("cheat", |ctx:&mut SystemContext| {
+            ctx.resource_mut::<Time>()?;
+            Ok(())
+        }).reads_resource::<Time>()).unwrap();
+
+        assert!(scheduler.run(&mut world).is_err());
+
+    }
+
+    #[test]
+    fn run_systems() {
+
+        let mut world = World::new();
+
+        for i in 0..100 {
+            world.spawn((Position(0.0), Velocity(i as f32), Health(10)));
+        }
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let log = order.clone();
+
+        let mut scheduler = Scheduler::with_threads(3);
+
+        scheduler.add_system(System::new("movement", |ctx:&mut SystemContext| {
+            for (p, v) in ctx.query::<(&mut Position, &Velocity), ()>()?.iter() {
+                p.0 += v.0;
+            }
+            Ok(())
+        }).access::<(&mut Position, &Velocity)>()).unwrap();
+
+        scheduler.add_system(System::new("damage", |ctx:&mut SystemContext| {
+            ctx.query::<&mut Health, ()>()?.iter().for_each(|h| h.0 -= 1);
+            Ok(())
+        }).writes::<Health>()).unwrap();
+
+        scheduler.add_system(System::new("check", move |ctx:&mut SystemContext| {
+            let total:f32 = ctx.query::<&Position, ()>()?.iter().map(|p| p.0).sum();
+            log.lock().unwrap().push(total);
+            Ok(())
+        }).stage(Stage::PostUpdate).reads::<Position>()).unwrap();
+
+        scheduler.run(&mut world).unwrap();
+        scheduler.run(&mut world).unwrap();
+
+        assert_eq!(*order.lock().unwrap(), vec![4950.0, 9900.0]);
+        assert!(world.query::<&Health, ()>().unwrap().iter().all(|h| h.0 == 8));
+
+        // a query outside of the declared access fails the system, the others still run
+        scheduler.add_system(System::new("cheat", |ctx:&mut SystemContext| {
+            ctx.query::<&mut Velocity, ()>()?;
+            Ok(())
+        }).reads::<Velocity>()).unwrap();
+
+        assert!(scheduler.run(&mut world).is_err());
+        assert_eq!(order.lock().unwrap().len(), 3);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Stage
+//
+/// The steps of a frame, run in this order
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub enum Stage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+    Render
+}
+//
+impl Stage {
+
+    /// every stage in the order they are run
+    pub const ALL: [Stage; 4] = [Stage::PreUpdate, Stage::Update, Stage::PostUpdate, Stage::Render];
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// System
+//
+type SystemFn = Box<dyn FnMut(&mut SystemContext) -> Result<(),EEcs> + Send>;
+//
+//
+/// A function run once per frame with the world, declared with the components that it uses and
+/// its order relative to the other systems of its stage
+pub struct System {
+
+    name:       String,
+    stage:      Stage,
+    access:     Access,
+    before:     Vec<String>,
+    after:      Vec<String>,
+    function:   SystemFn,
+    // the tick of the last run, its queries detect the changes made after it
+    last_run:   u64,
+    commands:   RefCell<CommandQueue>
+
+}
+//
+impl System {
+    //
+    /// A system of the Update stage that doesn't use any component
+    ///
+    /// # Parameters
+    ///
+    /// * 'name' - unique name of the system, used by the constraints of the other systems
+    /// * 'function' - run once per frame, it can query the components declared
+    ///
+    pub fn new<F>(name:&str, function:F) -> Self
+    where F:FnMut(&mut SystemContext) -> Result<(),EEcs> + Send + 'static {
+
+        Self {
+            name:       name.to_string(),
+            stage:      Stage::Update,
+            access:     Access::default(),
+            before:     Vec::new(),
+            after:      Vec::new(),
+            function:   Box::new(function),
+            last_run:   0,
+            commands:   RefCell::new(CommandQueue::default())
+        }
+
+    }
+    //
+    /// run in another stage
+    pub fn stage(mut self, stage:Stage) -> Self {
+
+        self.stage = stage;
+        self
+
+    }
+    //
+    /// the system reads the components of a type
+    pub fn reads<T:Component>(mut self) -> Self {
+
+        self.access.read::<T>();
+        self
+
+    }
+    //
+    /// the system writes the components of a type
+    pub fn writes<T:Component>(mut self) -> Self {
+
+        self.access.write::<T>();
+        self
+
+    }
+    //
+    /// the system reads the resource of a type
+    pub fn reads_resource<R:Component>(mut self) -> Self {
+
+        self.access.read_resource::<R>();
+        self
+
+    }
+    //
+    /// the system writes the resource of a type
+    pub fn writes_resource<R:Component>(mut self) -> Self {
+
+        self.access.write_resource::<R>();
+        self
+
+    }
+    //
+    /// the system uses the components of a fetch, like (&mut A, &B)
+    pub fn access<Q:Fetch>(mut self) -> Self {
+
+        Q::access(&mut self.access);
+        self
+
+    }
+    //
+    /// run before another system of the same stage
+    pub fn before(mut self, system:&str) -> Self {
+
+        self.before.push(system.to_string());
+        self
+
+    }
+    //
+    /// run after another system of the same stage
+    pub fn after(mut self, system:&str) -> Self {
+
+        self.after.push(system.to_string());
+        self
+
+    }
+    //
+    pub fn name(&self) -> &str { &self.name }
+    //
+    fn run(&mut self, world:&World) -> Result<(),EEcs> {
+
+        let ticks = TickRange { last_run: self.last_run, this_run: world.increment_change_tick() };
+        self.last_run = ticks.this_run;
+
+        let mut ctx = SystemContext { world, access: &self.access, name: &self.name, ticks, commands: &self.commands };
+
+        (self.function)(&mut ctx)
+            .change_context(EEcs::System)
+            .attach_printable_lazy(|| format!("the system '{}' failed",self.name))
+
+    }
+    //
+}
+//
+//
+/// What a system can use of the world while it runs
+pub struct SystemContext<'w> {
+
+    world:  &'w World,
+    access: &'w Access,
+    name:       &'w str,
+    ticks:      TickRange,
+    commands:   &'w RefCell<CommandQueue>
+
+}
+//
+impl<'w> SystemContext<'w> {
+    //
+    /// Query the components of the entities, error if the system didn't declare one of the types
+    /// of the fetch and the filter or if it writes a type that it also uses elsewhere. A system
+    /// has a single query at a time, Added and Changed detect the changes made since its last run
+    ///
+    /// # Parameters
+    ///
+    /// * 'Q' - what is fetched for every entity
+    /// * 'F' - the filters on the entities, () for none
+    ///
+    pub fn query<Q:Fetch, F:Filter>(&mut self) -> Result<Query<'_, Q, F>,EEcs> {
+
+        if !Query::<Q, F>::access().is_subset(self.access) {
+
+            return Err(EEcs::Access
+                .as_report()
+                .attach_printable(format!("the system '{}' didn't declare the components of {}",self.name,std::any::type_name::<Q>())));
+
+        }
+
+        // SAFETY: the scheduler doesn't run together systems whose accesses conflict, and the
+        // world is borrowed mutably by the scheduler while they run
+        unsafe { Query::new(self.world, self.ticks) }
+
+    }
+    //
+    /// The resource of a type, error if the system didn't declare it or if the world doesn't
+    /// have one
+    pub fn resource<R:Component>(&self) -> Result<&R,EEcs> {
+
+        if !self.access.reads_resource::<R>() {
+            return Err(self.undeclared::<R>());
+        }
+
+        self.world.resource::<R>().ok_or_else(|| self.missing::<R>())
+
+    }
+    //
+    /// The resource of a type to change it, error if the system didn't declare that it writes it
+    /// or if the world doesn't have one. It is marked as changed
+    pub fn resource_mut<R:Component>(&mut self) -> Result<&mut R,EEcs> {
+
+        if !self.access.writes_resource::<R>() {
+            return Err(self.undeclared::<R>());
+        }
+
+        // SAFETY: the resource is borrowed with the context, the scheduler doesn't run together
+        // the systems that use it
+        unsafe { self.world.resources().get_unchecked_mut::<R>(self.ticks.this_run) }.ok_or_else(|| self.missing::<R>())
+
+    }
+    //
+    /// true if the resource of a type was added or changed since the last run of the system
+    pub fn is_resource_changed<R:Component>(&self) -> Result<bool,EEcs> {
+
+        if !self.access.reads_resource::<R>() {
+            return Err(self.undeclared::<R>());
+        }
+
+        let ticks = self.world.resource_ticks::<R>().ok_or_else(|| self.missing::<R>())?;
+
+        Ok(ticks.changed() > self.ticks.last_run)
+
+    }
+    //
+    /// the commands of the system, they are applied at the end of the stage. They can be recorded
+    /// while a query is used
+    pub fn commands(&self) -> Commands<'w> { Commands::new(self.commands, self.world) }
+    //
+    /// the tick of the last run of the system, 0 if it is the first one
+    pub fn last_run(&self) -> u64 { self.ticks.last_run }
+    //
+    fn undeclared<R>(&self) -> Report<EEcs> {
+
+        EEcs::Access
+            .as_report()
+            .attach_printable(format!("the system '{}' didn't declare the resource {}",self.name,std::any::type_name::<R>()))
+
+    }
+    //
+    fn missing<R>(&self) -> Report<EEcs> {
+
+        EEcs::Resource
+            .as_report()
+            .attach_printable(format!("the world doesn't have the resource {}",std::any::type_name::<R>()))
+
+    }
+    //
+    /// name of the system running
+    pub fn name(&self) -> &str { self.name }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Scheduler
+//
+// the worker threads used when the number isn't given, the calling thread also runs systems
+const SCHEDULER_MAX_WORKERS: usize = 7;
+//
+//
+/// The systems by stage, run once per frame
+pub struct Scheduler {
+
+    systems:    Vec<System>,
+    // the batches of every stage, computed again when a system is added
+    batches:    Option<HashMap<Stage, Vec<Vec<usize>>>>,
+    pool:       TaskPool
+
+}
+//
+impl Scheduler {
+    //
+    /// a scheduler with a worker per core but one
+    pub fn new() -> Self {
+
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get() - 1)
+            .unwrap_or(0)
+            .min(SCHEDULER_MAX_WORKERS);
+
+        Self::with_threads(workers)
+
+    }
+    //
+    /// A scheduler with a number of worker threads
+    ///
+    /// # Parameters
+    ///
+    /// * 'workers' - 0 to run every system on the calling thread
+    ///
+    pub fn with_threads(workers:usize) -> Self {
+
+        Self { systems: Vec::new(), batches: None, pool: TaskPool::new(workers) }
+
+    }
+    //
+    /// number of worker threads
+    pub fn workers(&self) -> usize { self.pool.workers() }
+    //
+    /// Add a system, error if one has the same name
+    ///
+    /// # Parameters
+    ///
+    /// * 'system' - the new system
+    ///
+    pub fn add_system(&mut self, system:System) -> Result<(),EEcs> {
+
+        if self.systems.iter().any(|s| s.name == system.name) {
+
+            return Err(EEcs::Schedule
+                .as_report()
+                .attach_printable(format!("a system is already named '{}'",system.name)));
+
+        }
+
+        self.systems.push(system);
+        self.batches = None;
+
+        Ok(())
+
+    }
+    //
+    /// The names of the systems of a stage by batch, the systems of a batch run together
+    ///
+    /// # Parameters
+    ///
+    /// * 'stage' - the stage
+    ///
+    pub fn batches(&mut self, stage:Stage) -> Result<Vec<Vec<&str>>,EEcs> {
+
+        self.build()?;
+
+        let batches = self.batches.as_ref().and_then(|b| b.get(&stage));
+
+        Ok(batches
+            .map(|b| b.iter().map(|batch| batch.iter().map(|s| self.systems[*s].name.as_str()).collect()).collect())
+            .unwrap_or_default())
+
+    }
+    //
+    /// Run every stage, error if the order of the systems can't be solved or if systems failed.
+    /// A failing system doesn't stop the others. The trackers of the world are cleared after
+    ///
+    /// # Parameters
+    ///
+    /// * 'world' - the world used by the systems
+    ///
+    pub fn run(&mut self, world:&mut World) -> Result<(),EEcs> {
+
+        self.build()?;
+
+        let mut result = Ok(());
+
+        for stage in Stage::ALL {
+            merge(&mut result, self.run_stage(stage, world));
+        }
+
+        world.clear_trackers();
+
+        result
+
+    }
+    //
+    /// Run the systems of a stage then apply their commands, in the order of the batches
+    ///
+    /// # Parameters
+    ///
+    /// * 'stage' - the stage
+    /// * 'world' - the world used by the systems
+    ///
+    pub fn run_stage(&mut self, stage:Stage, world:&mut World) -> Result<(),EEcs> {
+
+        self.build()?;
+
+        let batches = match self.batches.as_ref().and_then(|b| b.get(&stage)) {
+            Some(batches) => batches,
+            None => return Ok(())
+        };
+
+        let shared:&World = world;
+        let errors = Mutex::new(Ok(()));
+
+        for batch in batches {
+
+            // a single system doesn't need the workers
+            if let [single] = batch.as_slice() {
+
+                let failed = self.systems[*single].run(shared);
+                merge(&mut errors.lock().unwrap_or_else(|e| e.into_inner()), failed);
+
+                continue;
+
+            }
+
+            let errors = &errors;
+
+            let tasks:Vec<Task<'_>> = self.systems
+                .iter_mut()
+                .enumerate()
+                .filter(|(i, _)| batch.contains(i))
+                .map(|(_, system)| Box::new(move || {
+                    let failed = system.run(shared);
+                    merge(&mut errors.lock().unwrap_or_else(|e| e.into_inner()), failed);
+                }) as Task<'_>)
+                .collect();
+
+            self.pool.scope(tasks);
+
+        }
+
+        let mut result = errors.into_inner().unwrap_or_else(|e| e.into_inner());
+
+        for system in batches.iter().flatten() {
+
+            let applied = self.systems[*system].commands.get_mut().apply(world);
+            merge(&mut result, applied.attach_printable_lazy(|| format!("a command of the system '{}' failed",self.systems[*system].name)));
+
+        }
+
+        result
+
+    }
+    //
+    /// Order the systems of every stage and put them in batches. A system goes in the batch
+    /// after the last one of the systems it must follow and of the systems placed before it that
+    /// conflict with it
+    fn build(&mut self) -> Result<(),EEcs> {
+
+        if self.batches.is_some() {
+            return Ok(());
+        }
+
+        let mut batches = HashMap::new();
+
+        for stage in Stage::ALL {
+
+            let systems:Vec<usize> = (0..self.systems.len()).filter(|i| self.systems[*i].stage == stage).collect();
+
+            if systems.is_empty() {
+                continue;
+            }
+
+            let order = self.sort(&systems)?;
+
+            let mut placed:Vec<(usize, usize)> = Vec::new();
+            let mut stage_batches:Vec<Vec<usize>> = Vec::new();
+
+            for system in order {
+
+                let s = &self.systems[system];
+
+                let batch = placed
+                    .iter()
+                    .filter(|(other, _)| {
+                        let o = &self.systems[*other];
+                        s.after.contains(&o.name) || o.before.contains(&s.name) || s.access.conflicts(&o.access)
+                    })
+                    .map(|(_, batch)| batch + 1)
+                    .max()
+                    .unwrap_or(0);
+
+                if batch == stage_batches.len() {
+                    stage_batches.push(Vec::new());
+                }
+
+                stage_batches[batch].push(system);
+                placed.push((system, batch));
+
+            }
+
+            batches.insert(stage, stage_batches);
+
+        }
+
+        self.batches = Some(batches);
+
+        Ok(())
+
+    }
+    //
+    /// The systems of a stage in an order that respects their constraints, the ones without
+    /// constraint between them keep the order they were added
+    fn sort(&self, systems:&[usize]) -> Result<Vec<usize>,EEcs> {
+
+        let index:HashMap<&str, usize> = systems.iter().enumerate().map(|(i, s)| (self.systems[*s].name.as_str(), i)).collect();
+
+        // the systems that must run before every system
+        let mut previous:Vec<Vec<usize>> = vec![Vec::new(); systems.len()];
+
+        for (i, s) in systems.iter().enumerate() {
+
+            let system = &self.systems[*s];
+
+            let find = |name:&String| index.get(name.as_str()).copied().ok_or_else(|| {
+                Report::new(EEcs::Schedule).attach_printable(format!(
+                    "the system '{}' is ordered with '{}' that isn't in the stage {:?}",system.name,name,system.stage))
+            });
+
+            for name in system.after.iter() {
+                previous[i].push(find(name)?);
+            }
+
+            for name in system.before.iter() {
+                previous[find(name)?].push(i);
+            }
+
+        }
+
+        let mut order = Vec::new();
+        let mut done = vec![false; systems.len()];
+
+        while order.len() < systems.len() {
+
+            let next = (0..systems.len()).find(|i| !done[*i] && previous[*i].iter().all(|p| done[*p]));
+
+            match next {
+
+                Some(i) => {
+                    done[i] = true;
+                    order.push(systems[i]);
+                },
+
+                None => {
+
+                    let cycle:Vec<&str> = (0..systems.len())
+                        .filter(|i| !done[*i])
+                        .map(|i| self.systems[systems[i]].name.as_str())
+                        .collect();
+
+                    return Err(EEcs::Schedule
+                        .as_report()
+                        .attach_printable(format!("the order of the systems {:?} is a cycle",cycle)));
+
+                }
+
+            }
+
+        }
+
+        Ok(order)
+
+    }
+    //
+}
+//
+impl Default for Scheduler {
+
+    fn default() -> Self { Self::new() }
+
+}
+//
+//
+/// keep every error of the systems in a single report
+fn merge(result:&mut Result<(),EEcs>, other:Result<(),EEcs>) {
+
+    if let Err(other) = other {
+
+        match result {
+            Ok(()) => *result = Err(other),
+            Err(report) => report.extend_one(other)
+        }
+
+    }
+
+}
+//
+//
@@ -0,0 +1,657 @@
+// The world: the entities and their components, stored in archetypes
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::resource::Resources;
+use super::{Archetype, Bundle, Column, Component, ComponentTicks, Entities, Entity, Fetch, Filter, Query, TickRange};
+use crate::core::error_handler::EEcs;
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ecs::{Added, Changed};
+
+    #[derive(Debug,PartialEq)]
+    struct Position(f32, f32);
+
+    #[derive(Debug,PartialEq)]
+    struct Name(&'static str);
+
+    #[derive(Debug,PartialEq)]
+    struct Frozen;
+
+    #[test]
+    fn spawn_and_despawn() {
+
+        let mut world = World::new();
+
+        let a = world.spawn((Position(1.0, 2.0), Name("a")));
+        let b = world.spawn((Position(3.0, 4.0),));
+
+        assert_eq!(world.len(), 2);
+        assert_eq!(world.get::<Position>(a), Some(&Position(1.0, 2.0)));
+        assert_eq!(world.get::<Name>(b), None);
+        assert!(world.contains::<Name>(a) && !world.contains::<Name>(b));
+
+        world.get_mut::<Position>(b).unwrap().0 = 5.0;
+        assert_eq!(world.get::<Position>(b), Some(&Position(5.0, 4.0)));
+
+        // a component replace the one of the same type
+        assert_eq!(world.insert(a, Name("first")).unwrap(), Some(Name("a")));
+        assert_eq!(world.remove::<Name>(a), Some(Name("first")));
+        assert_eq!(world.remove::<Name>(a), None);
+
+        assert!(world.despawn(a));
+        assert!(!world.despawn(a));
+        assert!(!world.is_alive(a));
+        assert_eq!(world.len(), 1);
+
+        // the id of a despawned entity doesn't reach the entity that took its slot
+        let c = world.spawn(());
+        assert_eq!(c.index(), a.index());
+
+        assert_eq!(world.get::<Position>(a), None);
+        assert_eq!(world.get::<Position>(c), None);
+        assert!(world.insert(a, Position(0.0, 0.0)).is_err());
+
+        assert_eq!(world.entities().collect::<Vec<_>>(), vec![c, b]);
+
+    }
+
+    #[test]
+    fn move_between_archetypes() {
+
+        let mut world = World::new();
+
+        let entities:Vec<Entity> = (0..10).map(|i| world.spawn((Position(i as f32, 0.0),))).collect();
+
+        // half of them move to the archetype (Position, Frozen), the others are moved in the
+        // rows they leave
+        for e in entities.iter().step_by(2) {
+            world.insert(*e, Frozen).unwrap();
+        }
+
+        let frozen = world.archetypes().iter().find(|a| a.has::<Frozen>()).unwrap();
+        assert_eq!(frozen.len(), 5);
+        assert!(frozen.has::<Position>());
+
+        for (i, e) in entities.iter().enumerate() {
+            assert_eq!(world.get::<Position>(*e), Some(&Position(i as f32, 0.0)));
+            assert_eq!(world.contains::<Frozen>(*e), i % 2 == 0);
+        }
+
+        // and back, the archetype is found from the edges
+        let count = world.archetypes().len();
+
+        world.remove::<Frozen>(entities[4]);
+        world.despawn(entities[0]);
+
+        assert_eq!(world.archetypes().len(), count);
+        assert_eq!(world.get::<Position>(entities[4]), Some(&Position(4.0, 0.0)));
+        assert_eq!(world.get::<Position>(entities[8]), Some(&Position(8.0, 0.0)));
+
+        // the components are contiguous in their column
+        let sum:f32 = world.iter::<Position>().map(|(_, p)| p.0).sum();
+        assert_eq!(sum, 45.0);
+
+        world.iter_mut::<Position>().for_each(|(_, p)| p.1 = 1.0);
+        assert!(world.iter::<Position>().all(|(e, p)| p.1 == 1.0 && world.is_alive(e)));
+
+    }
+
+    #[test]
+    fn change_ticks() {
+
+        let mut world = World::new();
+
+        let a = world.spawn((Position(0.0, 0.0),));
+        let b = world.spawn((Position(0.0, 0.0),));
+
+        world.clear_trackers();
+
+        world.get_mut::<Position>(a).unwrap().0 = 1.0;
+        world.insert(b, Name("b")).unwrap();
+
+        assert_eq!(world.query::<Entity, Changed<Position>>().unwrap().iter().collect::<Vec<_>>(), vec![a]);
+        assert_eq!(world.query::<Entity, Added<Name>>().unwrap().iter().collect::<Vec<_>>(), vec![b]);
+
+        // the ticks move with the components to the other archetype
+        let ticks = world.ticks::<Position>(b).unwrap();
+        assert!(ticks.added() < world.change_tick());
+
+        world.clear_trackers();
+        assert!(world.query::<Entity, Changed<Position>>().unwrap().is_empty());
+
+        world.insert_resource(Name("world"));
+        assert_eq!(world.resource::<Name>(), Some(&Name("world")));
+
+        world.clear_trackers();
+        world.resource_mut::<Name>().unwrap().0 = "changed";
+        assert_eq!(world.resource_ticks::<Name>().unwrap().changed(), world.change_tick());
+
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicated_bundle_type() {
+
+        World::new().spawn((Frozen, Frozen));
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// World
+//
+/// Where the components of an entity are
+#[derive(Debug,Clone,Copy)]
+struct Location {
+    archetype: usize,
+    row: usize
+}
+//
+//
+/// The entities and their components, an entity has at most one component of each type. The
+/// entities with the same component types share an archetype. The resources are the data that
+/// doesn't belong to an entity
+///
+/// The changes are marked with the tick of the world, incremented every time a system runs
+pub struct World {
+
+    entities:   Entities,
+    // by entity index
+    locations:  Vec<Location>,
+    archetypes: Vec<Archetype>,
+    // the archetypes by their sorted types
+    index:      HashMap<Vec<TypeId>, usize>,
+    resources:  Resources,
+    tick:       AtomicU64,
+    // the changes after it are detected by the queries of the world
+    last_tick:  u64
+
+}
+//
+impl World {
+    //
+    /// a world without entity, it only has the archetype without component
+    pub fn new() -> Self {
+
+        Self {
+            entities:   Entities::new(),
+            locations:  Vec::new(),
+            archetypes: vec![Archetype::new(Vec::new())],
+            index:      HashMap::from([(Vec::new(), 0)]),
+            resources:  Resources::default(),
+            tick:       AtomicU64::new(1),
+            last_tick:  0
+        }
+
+    }
+    //
+    /// Create an entity with some components
+    ///
+    /// # Parameters
+    ///
+    /// * 'bundle' - a tuple of components of different types, () for an entity without component
+    ///
+    pub fn spawn<B:Bundle>(&mut self, bundle:B) -> Entity {
+
+        self.flush();
+
+        let entity = self.entities.alloc();
+        self.place(entity, bundle);
+
+        entity
+
+    }
+    //
+    /// Give its components to an entity reserved by commands, error if it was despawned since
+    ///
+    /// # Parameters
+    ///
+    /// * 'entity' - a reserved entity
+    /// * 'bundle' - a tuple of components of different types
+    ///
+    pub(crate) fn spawn_reserved<B:Bundle>(&mut self, entity:Entity, bundle:B) -> Result<(),EEcs> {
+
+        self.flush();
+
+        let location = match self.location(entity) {
+            Some((0, row)) => Location { archetype: 0, row },
+            _ => return Err(EEcs::Entity.as_report().attach_printable(format!("the entity {} was despawned before its spawn",entity)))
+        };
+
+        if let Some(moved) = self.archetypes[0].remove_row(location.row) {
+            self.locations[moved.index() as usize].row = location.row;
+        }
+
+        self.place(entity, bundle);
+
+        Ok(())
+
+    }
+    //
+    /// an entity that is spawned without component by the next change of the world
+    pub(crate) fn reserve(&self) -> Entity { self.entities.reserve() }
+    //
+    /// spawn the reserved entities
+    pub(crate) fn flush(&mut self) {
+
+        let tick = self.change_tick();
+        let archetype = &mut self.archetypes[0];
+
+        for entity in self.entities.flush() {
+
+            let row = archetype.push_entity(entity, tick);
+            set_location(&mut self.locations, entity, Location { archetype: 0, row });
+
+        }
+
+    }
+    //
+    /// push the components of a living entity that isn't in an archetype
+    fn place<B:Bundle>(&mut self, entity:Entity, bundle:B) {
+
+        let mut types = B::types();
+        types.sort();
+
+        let count = types.len();
+        types.dedup();
+
+        assert_eq!(count, types.len(), "a bundle can't have two components of the same type");
+
+        let archetype = self.archetype_for(types, B::columns);
+
+        let tick = self.change_tick();
+        let target = &mut self.archetypes[archetype];
+        let row = target.push_entity(entity, tick);
+        bundle.push(target);
+
+        set_location(&mut self.locations, entity, Location { archetype, row });
+
+    }
+    //
+    /// Remove an entity and drop its components, false if it was already dead
+    ///
+    /// # Parameters
+    ///
+    /// * 'entity' - the entity to remove
+    ///
+    pub fn despawn(&mut self, entity:Entity) -> bool {
+
+        if !self.entities.free(entity) {
+            return false;
+        }
+
+        let location = self.locations[entity.index() as usize];
+
+        if let Some(moved) = self.archetypes[location.archetype].remove_row(location.row) {
+            self.locations[moved.index() as usize].row = location.row;
+        }
+
+        true
+
+    }
+    //
+    /// true if the entity was spawned and not despawned since
+    pub fn is_alive(&self, entity:Entity) -> bool { self.entities.is_alive(entity) }
+    //
+    /// number of living entities
+    pub fn len(&self) -> usize { self.entities.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.entities.len() == 0 }
+    //
+    /// the living entities by index
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ { self.entities.iter() }
+    //
+    /// every archetype created so far, some may be empty
+    pub fn archetypes(&self) -> &[Archetype] { &self.archetypes }
+    //
+    /// Add a component to an entity, it replace and return the component of the same type. The
+    /// entity move to the archetype that has the type when it doesn't
+    ///
+    /// # Parameters
+    ///
+    /// * 'entity' - a living entity
+    /// * 'component' - the new component
+    ///
+    pub fn insert<T:Component>(&mut self, entity:Entity, component:T) -> Result<Option<T>,EEcs> {
+
+        if !self.is_alive(entity) {
+
+            return Err(EEcs::Entity.as_report().attach_printable(format!("the entity {} is dead",entity)));
+
+        }
+
+        let t = TypeId::of::<T>();
+        let location = self.locations[entity.index() as usize];
+
+        let tick = self.change_tick();
+
+        // the new component is added even if it replace one
+        if let Some(column) = self.archetypes[location.archetype].typed_column::<T>() {
+
+            let previous = std::mem::replace(&mut column[location.row], component);
+            *self.archetypes[location.archetype].ticks_mut::<T>(location.row).unwrap() = ComponentTicks::new(tick);
+
+            return Ok(Some(previous));
+
+        }
+
+        let target = match self.archetypes[location.archetype].add_edge(t) {
+
+            Some(target) => target,
+
+            None => {
+
+                let source = &self.archetypes[location.archetype];
+
+                let mut types = source.types().to_vec();
+                types.push(t);
+                types.sort();
+
+                let mut columns = source.empty_columns();
+                columns.push((t, Box::new(Vec::<T>::new())));
+
+                let target = self.archetype_for(types, || columns);
+
+                self.archetypes[location.archetype].set_add_edge(t, target);
+                self.archetypes[target].set_remove_edge(t, location.archetype);
+
+                target
+
+            }
+
+        };
+
+        let row = self.move_entity(location, target, None, tick);
+        self.archetypes[target].typed_column::<T>().unwrap().push(component);
+        set_location(&mut self.locations, entity, Location { archetype: target, row });
+
+        Ok(None)
+
+    }
+    //
+    /// Take a component from an entity, None if it is dead or doesn't have one. The entity move
+    /// to the archetype without the type
+    ///
+    /// # Parameters
+    ///
+    /// * 'entity' - the entity
+    ///
+    pub fn remove<T:Component>(&mut self, entity:Entity) -> Option<T> {
+
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        let t = TypeId::of::<T>();
+        let location = self.locations[entity.index() as usize];
+
+        if !self.archetypes[location.archetype].has::<T>() {
+            return None;
+        }
+
+        let target = match self.archetypes[location.archetype].remove_edge(t) {
+
+            Some(target) => target,
+
+            None => {
+
+                let source = &self.archetypes[location.archetype];
+
+                let types:Vec<TypeId> = source.types().iter().copied().filter(|s| *s != t).collect();
+                let columns = source.empty_columns().into_iter().filter(|(s, _)| *s != t).collect();
+
+                let target = self.archetypes_for_columns(types, columns);
+
+                self.archetypes[location.archetype].set_remove_edge(t, target);
+                self.archetypes[target].set_add_edge(t, location.archetype);
+
+                target
+
+            }
+
+        };
+
+        let mut removed = Vec::<T>::with_capacity(1);
+
+        let tick = self.change_tick();
+        let row = self.move_entity(location, target, Some(&mut removed), tick);
+        set_location(&mut self.locations, entity, Location { archetype: target, row });
+
+        removed.pop()
+
+    }
+    //
+    /// the component of an entity
+    pub fn get<T:Component>(&self, entity:Entity) -> Option<&T> {
+
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        let location = self.locations[entity.index() as usize];
+
+        self.archetypes[location.archetype].column::<T>()?.get(location.row)
+
+    }
+    //
+    /// the component of an entity, to change it. It is marked as changed
+    pub fn get_mut<T:Component>(&mut self, entity:Entity) -> Option<&mut T> {
+
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        let tick = self.change_tick();
+        let location = self.locations[entity.index() as usize];
+        let archetype = &mut self.archetypes[location.archetype];
+
+        archetype.ticks_mut::<T>(location.row)?.set_changed(tick);
+        archetype.column_mut::<T>()?.get_mut(location.row)
+
+    }
+    //
+    /// when the component of an entity was added and last changed
+    pub fn ticks<T:Component>(&self, entity:Entity) -> Option<ComponentTicks> {
+
+        let (archetype, row) = self.location(entity)?;
+
+        self.archetypes[archetype].ticks::<T>()?.get(row).copied()
+
+    }
+    //
+    /// true if the entity is alive and has a component of this type
+    pub fn contains<T:Component>(&self, entity:Entity) -> bool { self.get::<T>(entity).is_some() }
+    //
+    /// every component of a type with its entity, archetype after archetype
+    pub fn iter<T:Component>(&self) -> impl Iterator<Item = (Entity, &T)> + '_ {
+
+        self.archetypes
+            .iter()
+            .filter_map(|a| Some(a.entities().iter().copied().zip(a.column::<T>()?.iter())))
+            .flatten()
+
+    }
+    //
+    /// every component of a type with its entity, to change them. They are marked as changed
+    pub fn iter_mut<T:Component>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> + '_ {
+
+        let tick = self.change_tick();
+
+        self.archetypes
+            .iter_mut()
+            .filter_map(|a| a.entities_and_column_mut::<T>())
+            .flat_map(move |(entities, column, ticks)| {
+                entities.iter().copied().zip(column.iter_mut()).zip(ticks.iter_mut()).map(move |(item, ticks)| {
+                    ticks.set_changed(tick);
+                    item
+                })
+            })
+
+    }
+    //
+    /// Query the components of the entities, error if the fetch writes a type that it also uses
+    /// elsewhere. Added and Changed detect the changes made since the last clear_trackers
+    ///
+    /// # Parameters
+    ///
+    /// * 'Q' - what is fetched for every entity, like (Entity, &A, &mut B, Option<&C>)
+    /// * 'F' - the filters on the entities, like (With<D>, Without<E>), () for none
+    ///
+    pub fn query<Q:Fetch, F:Filter>(&mut self) -> Result<Query<'_, Q, F>,EEcs> {
+
+        let ticks = TickRange { last_run: self.last_tick, this_run: self.change_tick() };
+
+        // SAFETY: the world is borrowed mutably by the query
+        unsafe { Query::new(self, ticks) }
+
+    }
+    //
+    /// the current tick, the changes made outside of the systems are marked with it
+    pub fn change_tick(&self) -> u64 { self.tick.load(Ordering::Relaxed) }
+    //
+    /// Start a new tick, the changes made before aren't detected anymore by the queries of the
+    /// world. The scheduler does it after every run
+    pub fn clear_trackers(&mut self) {
+
+        self.last_tick = *self.tick.get_mut();
+        *self.tick.get_mut() += 1;
+
+    }
+    //
+    /// the tick of a system that start to run
+    pub(crate) fn increment_change_tick(&self) -> u64 { self.tick.fetch_add(1, Ordering::Relaxed) }
+    //
+    /// Add a resource, return the one of the same type that it replace
+    ///
+    /// # Parameters
+    ///
+    /// * 'resource' - the new resource
+    ///
+    pub fn insert_resource<R:Component>(&mut self, resource:R) -> Option<R> {
+
+        let tick = self.change_tick();
+
+        self.resources.insert(resource, tick)
+
+    }
+    //
+    /// take the resource of a type
+    pub fn remove_resource<R:Component>(&mut self) -> Option<R> { self.resources.remove::<R>() }
+    //
+    pub fn contains_resource<R:Component>(&self) -> bool { self.resources.contains::<R>() }
+    //
+    /// the resource of a type
+    pub fn resource<R:Component>(&self) -> Option<&R> { self.resources.get::<R>() }
+    //
+    /// the resource of a type, to change it. It is marked as changed
+    pub fn resource_mut<R:Component>(&mut self) -> Option<&mut R> {
+
+        let tick = self.change_tick();
+
+        self.resources.get_mut::<R>(tick)
+
+    }
+    //
+    /// when the resource of a type was added and last changed
+    pub fn resource_ticks<R:Component>(&self) -> Option<ComponentTicks> { self.resources.ticks::<R>() }
+    //
+    pub(crate) fn resources(&self) -> &Resources { &self.resources }
+    //
+    /// the archetype and the row of a living entity
+    pub(crate) fn location(&self, entity:Entity) -> Option<(usize, usize)> {
+
+        if !self.is_alive(entity) {
+            return None;
+        }
+
+        let location = self.locations[entity.index() as usize];
+
+        Some((location.archetype, location.row))
+
+    }
+    //
+    /// the archetype of these sorted types, created with the columns when it doesn't exist
+    fn archetype_for<F>(&mut self, types:Vec<TypeId>, columns:F) -> usize
+    where F:FnOnce() -> Vec<(TypeId, Box<dyn Column>)> {
+
+        match self.index.get(&types) {
+            Some(found) => *found,
+            None => self.archetypes_for_columns(types, columns())
+        }
+
+    }
+    //
+    fn archetypes_for_columns(&mut self, types:Vec<TypeId>, columns:Vec<(TypeId, Box<dyn Column>)>) -> usize {
+
+        if let Some(found) = self.index.get(&types) {
+            return *found;
+        }
+
+        self.archetypes.push(Archetype::new(columns));
+        self.index.insert(types, self.archetypes.len() - 1);
+
+        self.archetypes.len() - 1
+
+    }
+    //
+    /// Move the components of an entity to another archetype, return its new row
+    fn move_entity(&mut self, from:Location, to:usize, removed:Option<&mut dyn Column>, tick:u64) -> usize {
+
+        let (source, target) = match from.archetype < to {
+            true => {
+                let (left, right) = self.archetypes.split_at_mut(to);
+                (&mut left[from.archetype], &mut right[0])
+            },
+            false => {
+                let (left, right) = self.archetypes.split_at_mut(from.archetype);
+                (&mut right[0], &mut left[to])
+            }
+        };
+
+        let (row, moved) = source.move_row(from.row, target, removed, tick);
+
+        if let Some(moved) = moved {
+            self.locations[moved.index() as usize].row = from.row;
+        }
+
+        row
+
+    }
+    //
+}
+//
+impl Default for World {
+
+    fn default() -> Self { Self::new() }
+
+}
+//
+//
+/// the location of an entity, the locations grow with the slots of the entities
+fn set_location(locations:&mut Vec<Location>, entity:Entity, location:Location) {
+
+    let index = entity.index() as usize;
+
+    if index >= locations.len() {
+        locations.resize(index + 1, Location { archetype: 0, row: 0 });
+    }
+
+    locations[index] = location;
+
+}
+//
+//
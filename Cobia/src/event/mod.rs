@@ -73,6 +73,21 @@ impl EventSystem {
         }
 
 
+    }
+    //
+    /// Add an event that doesn't come from winit to the queue
+    ///
+    /// # Parameters
+    ///
+    /// * event - the event to be added
+    ///
+    pub(crate) fn push_event(&mut self, event:CEvent) {
+
+        match event {
+            CEvent::Ignored => {},
+            _ => self.queue.add_event(event)
+        }
+
     }
     //
     pub(crate) fn get_last_event(&self) -> &CEvent {
@@ -86,6 +101,15 @@ impl EventSystem {
 
     }
     //
+    /// the events added since the previous call, oldest first
+    pub(crate) fn take_new_events(&mut self) -> impl Iterator<Item = &CEvent> {
+
+        let new = std::mem::take(&mut self.queue.unread);
+
+        self.queue.queue[self.queue.queue.len() - new..].iter()
+
+    }
+    //
 }
 //
 //
@@ -95,6 +119,8 @@ impl EventSystem {
 pub(crate) struct EventQueue {
 
     queue:      Vec<CEvent>,
+    // number of events at the end of the queue not taken yet
+    unread:     usize
 
 }
 //
@@ -104,8 +130,8 @@ impl EventQueue {
 
         match maxsize {
 
-            Some(max) => Self { queue: Vec::with_capacity(max) },
-            None => Self { queue: Vec::with_capacity(10000) }
+            Some(max) => Self { queue: Vec::with_capacity(max), unread: 0 },
+            None => Self { queue: Vec::with_capacity(10000), unread: 0 }
 
         }
 
@@ -120,11 +146,13 @@ impl EventQueue {
             let half_capacity = self.queue.capacity() / 2;
 
             self.queue.drain(0 .. half_capacity);
+            self.unread = self.unread.min(self.queue.len());
 
 
         }
 
-        self.queue.push(event)
+        self.queue.push(event);
+        self.unread += 1;
 
     }
     //
@@ -0,0 +1,50 @@
+// Event about the assets of the asset server, sent once per update of the server
+
+use std::fmt::{Display, Formatter, Result};
+
+use crate::asset::AssetId;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AssetEvent {
+
+    Loaded(AssetId),
+    // the asset or one of its dependencies changed on the disk and the asset has been decoded again
+    Reloaded(AssetId),
+    // a reload that fail keep the previous version of the asset
+    Failed(AssetId),
+    Unloaded(AssetId),
+
+}
+//
+impl AssetEvent {
+    //
+    /// the asset concerned by the event
+    pub fn id(&self) -> AssetId {
+
+        match self {
+            Self::Loaded(id) | Self::Reloaded(id) | Self::Failed(id) | Self::Unloaded(id) => *id
+        }
+
+    }
+    //
+}
+//
+impl Display for AssetEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+
+        match self {
+
+            Self::Loaded(id) =>
+                write!(f,"Asset {} has been loaded", id.0),
+            Self::Reloaded(id) =>
+                write!(f,"Asset {} has been reloaded", id.0),
+            Self::Failed(id) =>
+                write!(f,"Asset {} failed to load", id.0),
+            Self::Unloaded(id) =>
+                write!(f,"Asset {} has been unloaded", id.0)
+
+        }
+
+    }
+
+}
@@ -0,0 +1,44 @@
+// Event about files that changed on the disk, sent by the file watcher
+
+use std::path::PathBuf;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum FileEvent {
+
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+
+}
+//
+impl FileEvent {
+    //
+    /// the path of the file that changed
+    pub fn path(&self) -> &PathBuf {
+
+        match self {
+            Self::Created(p) | Self::Modified(p) | Self::Removed(p) => p
+        }
+
+    }
+    //
+}
+//
+impl Display for FileEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+
+        match self {
+
+            Self::Created(p) =>
+                write!(f,"File '{}' has been created", p.display()),
+            Self::Modified(p) =>
+                write!(f,"File '{}' has been modified", p.display()),
+            Self::Removed(p) =>
+                write!(f,"File '{}' has been removed", p.display())
+
+        }
+
+    }
+
+}
@@ -2,8 +2,40 @@
 
 use super::CEvent;
 
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter, Result, write};
 
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn input_state() {
+
+        let mut input = InputState::default();
+
+        input.handle_event(&CEvent::Keyboard(KeyboardEvent::new(Key::W, StateAction::Press)));
+        input.handle_event(&CEvent::Keyboard(KeyboardEvent::new(Key::Space, StateAction::Press)));
+        input.handle_event(&CEvent::Keyboard(KeyboardEvent::new(Key::Space, StateAction::Release)));
+        input.handle_event(&CEvent::Mouse(MouseEvent::MouseButton(MouseButtonEvent::new(MouseButton::Left, StateAction::Press))));
+        input.handle_event(&CEvent::Mouse(MouseEvent::MouseMovement(MouseMovedEvent::new(12.0, 30.5))));
+
+        assert!(input.is_key_pressed(Key::W) && !input.is_key_pressed(Key::Space));
+        assert!(input.is_button_pressed(MouseButton::Left));
+        assert_eq!(input.cursor(), (12.0, 30.5));
+
+        input.handle_event(&CEvent::Mouse(MouseEvent::MouseEscape));
+        assert!(!input.is_button_pressed(MouseButton::Left));
+
+    }
+
+}
 //
 //
 // ------------------------------------------------------------------------------------------------
@@ -154,6 +186,7 @@ impl Display for MouseEvent {
 }
 //
 //
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
 pub enum MouseButton {
 
     Left,
@@ -297,6 +330,7 @@ impl KeyboardEvent {
 
 }
 //
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
 pub enum Key {
     //
     // Functionality
@@ -661,4 +695,61 @@ impl Display for Key {
 
     }
 
-}
\ No newline at end of file
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Input state
+//
+/// The keys and mouse buttons held and the position of the cursor, a resource of the engine world
+/// kept up to date with the window events
+#[derive(Debug,Default)]
+pub struct InputState {
+
+    keys:       HashSet<Key>,
+    buttons:    HashSet<MouseButton>,
+    cursor:     (f64, f64)
+
+}
+//
+impl InputState {
+    //
+    pub fn is_key_pressed(&self, key:Key) -> bool { self.keys.contains(&key) }
+    //
+    pub fn is_button_pressed(&self, button:MouseButton) -> bool { self.buttons.contains(&button) }
+    //
+    /// position of the cursor in pixels from the top left of the window
+    pub fn cursor(&self) -> (f64, f64) { self.cursor }
+    //
+    /// Update the state with an event, the events that are not input are ignored
+    ///
+    /// # Parameters
+    ///
+    /// * 'event' - an event of the event system
+    ///
+    pub(crate) fn handle_event(&mut self, event:&CEvent) {
+
+        match event {
+
+            CEvent::Keyboard(KeyboardEvent { key, action: StateAction::Press }) => { self.keys.insert(*key); },
+            CEvent::Keyboard(KeyboardEvent { key, action: StateAction::Release }) => { self.keys.remove(key); },
+
+            CEvent::Mouse(MouseEvent::MouseButton(MouseButtonEvent { button, action })) => match action {
+                StateAction::Press => { self.buttons.insert(*button); },
+                StateAction::Release => { self.buttons.remove(button); }
+            },
+
+            CEvent::Mouse(MouseEvent::MouseMovement(MouseMovedEvent { x, y })) => self.cursor = (*x, *y),
+
+            // the buttons released outside of the window are never received
+            CEvent::Mouse(MouseEvent::MouseEscape) => self.buttons.clear(),
+
+            _ => {}
+
+        }
+
+    }
+    //
+}
+//
+//
@@ -2,8 +2,12 @@
 
 pub mod input;
 pub mod window;
+pub mod file;
+pub mod asset;
 
 use window::WindowEvent;
+use file::FileEvent;
+use asset::AssetEvent;
 use input::{KeyboardEvent,TextInputEvent,MouseEvent,ModifierChangeEvent};
 
 use std::fmt::{Debug, Display, Formatter, Result, write};
@@ -26,7 +30,11 @@ pub enum CEvent {
     ReceivedChar(char),
     TextInput(TextInputEvent),
     TextInputModeEnabled,
-    TextInputModeDisabled
+    TextInputModeDisabled,
+    // a watched file changed on the disk
+    File(FileEvent),
+    // an asset of the asset server has been loaded, reloaded or unloaded
+    Asset(AssetEvent)
 
     // TODO: add other
 
@@ -67,7 +75,11 @@ impl Display for CEvent {
             Self::TextInputModeEnabled =>
                 write!(f,"Text input mode enabled"),
             Self::TextInputModeDisabled =>
-                write!(f,"Text input mode disabled")
+                write!(f,"Text input mode disabled"),
+            Self::File(event) =>
+                write!(f,"File: {}",event),
+            Self::Asset(event) =>
+                write!(f,"Asset: {}",event)
 
 
         }
@@ -0,0 +1,307 @@
+// Glyph atlas: the glyphs are drawn the first time they are displayed and packed in a single
+// image that grow when it is full
+
+use std::collections::HashMap;
+
+use super::{Font, RenderMode};
+use crate::core::error_handler::EFont;
+use crate::image::{AtlasRect, Image, PixelFormat, Skyline, UvRect};
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::font::test::shapes;
+
+    #[test]
+    fn grow_atlas() {
+
+        let font = shapes();
+        let mut atlas = GlyphAtlas::new(32, 32);
+
+        let rects:Vec<AtlasRect> = "AVIO"
+            .chars()
+            .map(|c| atlas.glyph(&font, font.glyph_index(c).unwrap(), 100.0, RenderMode::Coverage).unwrap().rect)
+            .collect();
+
+        // the glyphs don't fit in 32x32, the atlas doubled until they did
+        assert_eq!(atlas.len(), 4);
+        assert!(atlas.width() > 32 && atlas.width().is_power_of_two());
+        assert!(atlas.height() > 32 && atlas.height().is_power_of_two());
+
+        for (i, a) in rects.iter().enumerate() {
+
+            assert!(a.x >= 1 && a.y >= 1);
+            assert!(a.x + a.width < atlas.width() && a.y + a.height < atlas.height());
+
+            for b in rects[i + 1..].iter() {
+                let apart = a.x + a.width < b.x
+                    || b.x + b.width < a.x
+                    || a.y + a.height < b.y
+                    || b.y + b.height < a.y;
+                assert!(apart, "{:?} and {:?} overlap", a, b);
+            }
+
+        }
+
+        // the pixels of 'I' were copied and kept when the atlas grew
+        let bar = rects[2];
+        assert_eq!((bar.width, bar.height), (20, 70));
+
+        let image = atlas.to_image();
+        assert!((bar.y..bar.y + bar.height).all(|y| image.row(y)[bar.x as usize..(bar.x + bar.width) as usize].iter().all(|c| *c == 255)));
+        assert_eq!(image.pixel(bar.x - 1, bar.y), &[0]);
+
+        // a glyph already in the atlas doesn't change it
+        let revision = atlas.revision();
+        let again = atlas.glyph(&font, font.glyph_index('I').unwrap(), 100.0, RenderMode::Coverage).unwrap();
+        assert_eq!((again.rect, again.offset), (bar, [10.0, -70.0]));
+        assert_eq!(atlas.revision(), revision);
+
+        // but the same glyph at another size or as a distance field is a new one
+        atlas.glyph(&font, font.glyph_index('I').unwrap(), 50.0, RenderMode::Coverage).unwrap();
+        atlas.glyph(&font, font.glyph_index('I').unwrap(), 100.0, RenderMode::Sdf(4)).unwrap();
+        assert_eq!(atlas.len(), 6);
+        assert!(atlas.revision() > revision);
+
+        let uv = atlas.uv(&bar);
+        assert_eq!(uv.min, [bar.x as f32 / atlas.width() as f32, bar.y as f32 / atlas.height() as f32]);
+
+        // a space take no room
+        let space = atlas.glyph(&font, font.glyph_index(' ').unwrap(), 100.0, RenderMode::Coverage).unwrap();
+        assert_eq!((space.rect.width, space.rect.height), (0, 0));
+
+    }
+
+    #[test]
+    fn atlas_too_small() {
+
+        let font = shapes();
+
+        let mut atlas = GlyphAtlas::new(16, 16);
+        atlas.set_max_size(64);
+
+        assert!(atlas.glyph(&font, font.glyph_index('O').unwrap(), 200.0, RenderMode::Coverage).is_err());
+        assert!(atlas.glyph(&font, font.glyph_index('O').unwrap(), 20.0, RenderMode::Coverage).is_ok());
+        assert_eq!(atlas.len(), 1);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Atlas
+//
+/// A glyph in the atlas
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct AtlasGlyph {
+    // where the glyph is in the atlas, empty for the glyphs without outline
+    pub rect: AtlasRect,
+    // position of the top left corner of the rect from the pen on the baseline
+    pub offset: [f32;2]
+}
+//
+//
+/// What identify a glyph in the atlas
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+struct GlyphKey {
+    font: u64,
+    glyph: u16,
+    // bits of the size in pixels per em
+    size: u32,
+    mode: RenderMode
+}
+//
+//
+/// R8 image holding the glyphs of any number of fonts, sizes and render modes
+pub struct GlyphAtlas {
+
+    width:      u32,
+    height:     u32,
+    max_size:   u32,
+    padding:    u32,
+    data:       Vec<u8>,
+    skyline:    Skyline,
+    glyphs:     HashMap<GlyphKey, AtlasGlyph>,
+    revision:   u64
+
+}
+//
+impl GlyphAtlas {
+    //
+    /// An empty atlas
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - width of the image before it grow
+    /// * 'height' - height of the image before it grow
+    ///
+    pub fn new(width:u32, height:u32) -> Self {
+
+        let padding = 1;
+
+        Self {
+            width,
+            height,
+            max_size:   4096,
+            padding,
+            data:       vec![0; width as usize * height as usize],
+            // the left and top padding are kept out of the packing area
+            skyline:    Skyline::new(width.saturating_sub(padding), height.saturating_sub(padding)),
+            glyphs:     HashMap::new(),
+            revision:   0
+        }
+
+    }
+    //
+    /// the atlas is never bigger than this on each side, 4096 by default
+    pub fn set_max_size(&mut self, max_size:u32) { self.max_size = max_size; }
+    //
+    pub fn width(&self) -> u32 { self.width }
+    //
+    pub fn height(&self) -> u32 { self.height }
+    //
+    /// the pixels of the atlas, a byte per pixel
+    pub fn data(&self) -> &[u8] { &self.data }
+    //
+    /// a copy of the atlas as an R8 image
+    pub fn to_image(&self) -> Image { Image::new(self.width, self.height, PixelFormat::R8, self.data.clone()).unwrap() }
+    //
+    /// increase every time the pixels or the size of the atlas change, so it is uploaded again
+    pub fn revision(&self) -> u64 { self.revision }
+    //
+    /// number of glyphs in the atlas
+    pub fn len(&self) -> usize { self.glyphs.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.glyphs.is_empty() }
+    //
+    /// texture coordinates of a rect with the current size of the atlas
+    pub fn uv(&self, rect:&AtlasRect) -> UvRect {
+
+        let (w, h) = (self.width as f32, self.height as f32);
+
+        UvRect {
+            min: [rect.x as f32 / w, rect.y as f32 / h],
+            max: [(rect.x + rect.width) as f32 / w, (rect.y + rect.height) as f32 / h]
+        }
+
+    }
+    //
+    /// Find a glyph in the atlas, it is drawn and added the first time
+    ///
+    /// # Parameters
+    ///
+    /// * 'font' - the font of the glyph
+    /// * 'glyph' - index of the glyph in the font
+    /// * 'size' - pixels per em
+    /// * 'mode' - how the glyph is drawn
+    ///
+    pub fn glyph(&mut self, font:&Font, glyph:u16, size:f32, mode:RenderMode) -> Result<AtlasGlyph,EFont> {
+
+        let key = GlyphKey { font: font.id(), glyph, size: size.to_bits(), mode };
+
+        if let Some(found) = self.glyphs.get(&key) {
+            return Ok(*found);
+        }
+
+        let bitmap = match mode {
+            RenderMode::Coverage => font.rasterize(glyph, size),
+            RenderMode::Sdf(spread) => font.rasterize_sdf(glyph, size, spread)
+        };
+
+        let (width, height) = (bitmap.image.width(), bitmap.image.height());
+
+        let (x, y) = match width == 0 || height == 0 {
+            true => (0, 0),
+            false => self.place(width, height)?
+        };
+
+        let rect = AtlasRect { x, y, width, height };
+
+        for row in 0..height {
+            let at = (y + row) as usize * self.width as usize + x as usize;
+            self.data[at..at + width as usize].copy_from_slice(&bitmap.image.row(row)[..width as usize]);
+        }
+
+        let found = AtlasGlyph { rect, offset: bitmap.offset };
+
+        self.glyphs.insert(key, found);
+        self.revision += 1;
+
+        Ok(found)
+
+    }
+    //
+    /// Find room for a glyph, the atlas is made twice bigger until it fit
+    fn place(&mut self, width:u32, height:u32) -> Result<(u32, u32),EFont> {
+
+        // a cell is the glyph and the padding on its right and bottom side
+        let (cell_width, cell_height) = (width + self.padding, height + self.padding);
+
+        loop {
+
+            if let Some((x, y)) = self.skyline.insert(cell_width, cell_height) {
+                return Ok((x + self.padding, y + self.padding));
+            }
+
+            let (width, height) = match self.height < self.width {
+                true => (self.width, self.height * 2),
+                false => (self.width * 2, self.height)
+            };
+
+            if width > self.max_size || height > self.max_size {
+
+                return Err(EFont::Atlas
+                    .as_report()
+                    .attach_printable(format!(
+                        "a glyph of {}x{} doesn't fit in an atlas of {}x{}",
+                        cell_width - self.padding,
+                        cell_height - self.padding,
+                        self.max_size,
+                        self.max_size
+                        )
+                    )
+                );
+
+            }
+
+            self.resize(width.max(1), height.max(1));
+
+        }
+
+    }
+    //
+    /// Make the image bigger, the glyphs keep their position
+    fn resize(&mut self, width:u32, height:u32) {
+
+        let mut data = vec![0; width as usize * height as usize];
+
+        for (y, row) in self.data.chunks_exact(self.width.max(1) as usize).enumerate() {
+            let at = y * width as usize;
+            data[at..at + row.len()].copy_from_slice(row);
+        }
+
+        self.skyline.grow(width - self.padding, height - self.padding);
+        self.data = data;
+        self.width = width;
+        self.height = height;
+        self.revision += 1;
+
+    }
+    //
+}
+//
+impl Default for GlyphAtlas {
+
+    fn default() -> Self { Self::new(256, 256) }
+
+}
+//
+//
@@ -0,0 +1,380 @@
+// Text layout: place the glyphs of a text on lines, break the lines that are too long and align
+// them, then turn the glyphs in quads of the glyph atlas
+
+use std::ops::Range;
+
+use super::{Font, GlyphAtlas, RenderMode};
+use crate::core::error_handler::EFont;
+use crate::image::UvRect;
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::font::test::shapes;
+
+    fn settings(max_width:Option<f32>, align:Align) -> LayoutSettings {
+        LayoutSettings { size: 100.0, max_width, align, line_spacing: 1.0 }
+    }
+
+    fn xs(layout:&TextLayout, line:usize) -> Vec<f32> {
+        layout.glyphs()[layout.lines()[line].glyphs.clone()].iter().map(|g| g.x).collect()
+    }
+
+    #[test]
+    fn kerning_and_lines() {
+
+        let font = shapes();
+
+        // V is moved closer to A by the kerning
+        let layout = layout_text(&font, "AV", &settings(None, Align::Left));
+
+        assert_eq!(layout.lines().len(), 1);
+        assert_eq!(xs(&layout, 0), vec![0.0, 50.0]);
+        assert_eq!((layout.width(), layout.height()), (110.0, 100.0));
+        assert_eq!(layout.glyphs()[1].y, 80.0);
+
+        // explicit line breaks, the missing characters use the glyph 0
+        let layout = layout_text(&font, "A\nz", &settings(None, Align::Left));
+
+        assert_eq!(layout.lines().len(), 2);
+        assert_eq!(layout.lines()[1].baseline, 189.0);
+        assert_eq!(layout.height(), 209.0);
+        assert_eq!((layout.glyphs()[1].glyph, layout.glyphs()[1].index), (0, 2));
+
+    }
+
+    #[test]
+    fn wrap_and_align() {
+
+        let font = shapes();
+
+        // "AV " is 135 wide, 'I' go on the next line with the rest of the word
+        let layout = layout_text(&font, "AV IA", &settings(Some(150.0), Align::Left));
+
+        assert_eq!(layout.lines().len(), 2);
+        assert_eq!(xs(&layout, 0), vec![0.0, 50.0, 110.0]);
+        assert_eq!(xs(&layout, 1), vec![0.0, 40.0]);
+
+        // the trailing space is not part of the width of the line
+        assert_eq!((layout.lines()[0].width, layout.lines()[1].width), (110.0, 100.0));
+        assert_eq!(layout.width(), 150.0);
+
+        let center = layout_text(&font, "AV IA", &settings(Some(150.0), Align::Center));
+        assert_eq!((xs(&center, 0)[0], xs(&center, 1)[0]), (20.0, 25.0));
+
+        let right = layout_text(&font, "AV IA", &settings(Some(150.0), Align::Right));
+        assert_eq!((xs(&right, 0)[0], xs(&right, 1)[0]), (40.0, 50.0));
+
+        // a word longer than a line is cut between its characters
+        let layout = layout_text(&font, "IIII", &settings(Some(100.0), Align::Left));
+        assert_eq!(layout.lines().len(), 2);
+        assert_eq!(xs(&layout, 1), vec![0.0, 40.0]);
+
+        // without a width the widest line is the reference for the alignment
+        let layout = layout_text(&font, "I\nIII", &settings(None, Align::Right));
+        assert_eq!((xs(&layout, 0)[0], layout.width()), (80.0, 120.0));
+
+    }
+
+    #[test]
+    fn glyph_quads() {
+
+        let font = shapes();
+        let mut atlas = GlyphAtlas::default();
+
+        let layout = layout_text(&font, "I I", &settings(None, Align::Left));
+        let quads = layout.quads(&font, &mut atlas, RenderMode::Coverage).unwrap();
+
+        // the space has no quad
+        assert_eq!(quads.len(), 2);
+        assert_eq!((quads[0].min, quads[0].max), ([10.0, 10.0], [30.0, 80.0]));
+        assert_eq!((quads[1].min, quads[1].max), ([75.0, 10.0], [95.0, 80.0]));
+        assert_eq!(quads[1].index, 2);
+
+        // both use the same glyph of the atlas
+        assert_eq!(quads[0].uv, quads[1].uv);
+        assert_eq!(atlas.len(), 2);
+
+        let rect = atlas.glyph(&font, font.glyph_index('I').unwrap(), 100.0, RenderMode::Coverage).unwrap().rect;
+        assert_eq!(quads[0].uv, atlas.uv(&rect));
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Settings
+//
+/// Horizontal alignment of the lines
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right
+}
+//
+//
+/// How a text is laid out
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct LayoutSettings {
+    // pixels per em
+    pub size: f32,
+    // the lines are broken between the words to stay under this width, None to only break them
+    // on the line feeds
+    pub max_width: Option<f32>,
+    pub align: Align,
+    // multiply the line height of the font
+    pub line_spacing: f32
+}
+//
+impl Default for LayoutSettings {
+
+    fn default() -> Self { Self { size: 16.0, max_width: None, align: Align::Left, line_spacing: 1.0 } }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Layout
+//
+/// A glyph placed by the layout
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct PositionedGlyph {
+    pub glyph: u16,
+    // byte index of the character in the text
+    pub index: usize,
+    // position of the pen on the baseline, from the top left corner of the text
+    pub x: f32,
+    pub y: f32
+}
+//
+//
+/// A line of the layout
+#[derive(Debug,Clone,PartialEq)]
+pub struct TextLine {
+    // the glyphs of the line in the glyphs of the layout
+    pub glyphs: Range<usize>,
+    // without the spaces at the end of the line
+    pub width: f32,
+    pub baseline: f32
+}
+//
+//
+/// A quad to draw a glyph, in the space of the layout
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct GlyphQuad {
+    pub min: [f32;2],
+    pub max: [f32;2],
+    pub uv: UvRect,
+    // byte index of the character in the text
+    pub index: usize
+}
+//
+//
+/// A text laid out on lines, y go down from the top of the first line
+#[derive(Debug,Clone,PartialEq)]
+pub struct TextLayout {
+
+    size:       f32,
+    glyphs:     Vec<PositionedGlyph>,
+    lines:      Vec<TextLine>,
+    width:      f32,
+    height:     f32
+
+}
+//
+impl TextLayout {
+    //
+    pub fn glyphs(&self) -> &[PositionedGlyph] { &self.glyphs }
+    //
+    pub fn lines(&self) -> &[TextLine] { &self.lines }
+    //
+    /// the max width of the settings, or the width of the widest line without one
+    pub fn width(&self) -> f32 { self.width }
+    //
+    /// from the ascent of the first line to the descent of the last one
+    pub fn height(&self) -> f32 { self.height }
+    //
+    /// Get the quads that draw the text, the glyphs missing from the atlas are added to it
+    ///
+    /// # Parameters
+    ///
+    /// * 'font' - the font used for the layout
+    /// * 'atlas' - where the glyphs are
+    /// * 'mode' - how the glyphs are drawn
+    ///
+    pub fn quads(&self, font:&Font, atlas:&mut GlyphAtlas, mode:RenderMode) -> Result<Vec<GlyphQuad>,EFont> {
+
+        let mut placed = Vec::with_capacity(self.glyphs.len());
+
+        for g in self.glyphs.iter() {
+
+            let found = atlas.glyph(font, g.glyph, self.size, mode)?;
+
+            if found.rect.width > 0 && found.rect.height > 0 {
+                placed.push((g, found));
+            }
+
+        }
+
+        // the uvs are computed once every glyph is in, the atlas may have grown
+        let quads = placed
+            .into_iter()
+            .map(|(g, found)| {
+
+                let min = [g.x + found.offset[0], g.y + found.offset[1]];
+
+                GlyphQuad {
+                    min,
+                    max: [min[0] + found.rect.width as f32, min[1] + found.rect.height as f32],
+                    uv: atlas.uv(&found.rect),
+                    index: g.index
+                }
+
+            })
+            .collect();
+
+        Ok(quads)
+
+    }
+    //
+}
+//
+//
+/// A glyph of the line being laid out
+struct Pending {
+    glyph: u16,
+    index: usize,
+    x: f32,
+    advance: f32,
+    space: bool
+}
+//
+//
+/// Lay out a text
+///
+/// # Parameters
+///
+/// * 'font' - the font of the text
+/// * 'text' - the text, '\n' start a new line
+/// * 'settings' - size, width and alignment
+///
+pub fn layout_text(font:&Font, text:&str, settings:&LayoutSettings) -> TextLayout {
+
+    let size = settings.size;
+    let mut lines:Vec<Vec<Pending>> = Vec::new();
+
+    for (start, paragraph) in paragraphs(text) {
+
+        let mut line:Vec<Pending> = Vec::new();
+        // where the line can be broken, after the last space
+        let mut breakable:Option<usize> = None;
+
+        for (i, c) in paragraph.char_indices() {
+
+            if c.is_control() {
+                continue;
+            }
+
+            let glyph = font.glyph_index(c).unwrap_or(0);
+            let advance = font.advance(glyph, size);
+            let space = c.is_whitespace();
+
+            let mut pen = line.last().map_or(0.0, |p| p.x + p.advance + font.kerning(p.glyph, glyph, size));
+
+            let too_long = settings.max_width.is_some_and(|max| pen + advance > max);
+
+            if too_long && !space && !line.is_empty() {
+
+                // the word go on the next line, or only this character if the word is the line
+                let rest = line.split_off(breakable.unwrap_or(line.len()));
+                lines.push(std::mem::replace(&mut line, rest));
+
+                let shift = line.first().map_or(0.0, |p| p.x);
+                line.iter_mut().for_each(|p| p.x -= shift);
+
+                pen = line.last().map_or(0.0, |p| p.x + p.advance + font.kerning(p.glyph, glyph, size));
+                breakable = None;
+
+            }
+
+            line.push(Pending { glyph, index: start + i, x: pen, advance, space });
+
+            if space {
+                breakable = Some(line.len());
+            }
+
+        }
+
+        lines.push(line);
+
+    }
+
+    let metrics = font.line_metrics(size);
+    let line_height = metrics.line_height() * settings.line_spacing;
+
+    let widths:Vec<f32> = lines
+        .iter()
+        .map(|line| line.iter().rev().find(|p| !p.space).map_or(0.0, |p| p.x + p.advance))
+        .collect();
+
+    let width = settings.max_width.unwrap_or_else(|| widths.iter().fold(0.0, |a, b| a.max(*b)));
+
+    let mut layout = TextLayout {
+        size,
+        glyphs: Vec::new(),
+        lines: Vec::new(),
+        width,
+        height: (lines.len() - 1) as f32 * line_height + metrics.ascent - metrics.descent
+    };
+
+    for (i, (line, line_width)) in lines.into_iter().zip(widths).enumerate() {
+
+        let baseline = metrics.ascent + i as f32 * line_height;
+
+        let offset = match settings.align {
+            Align::Left => 0.0,
+            Align::Center => (width - line_width) / 2.0,
+            Align::Right => width - line_width
+        };
+
+        let first = layout.glyphs.len();
+
+        layout.glyphs.extend(line.into_iter().map(|p| PositionedGlyph {
+            glyph:  p.glyph,
+            index:  p.index,
+            x:      p.x + offset,
+            y:      baseline
+        }));
+
+        layout.lines.push(TextLine { glyphs: first..layout.glyphs.len(), width: line_width, baseline });
+
+    }
+
+    layout
+
+}
+//
+//
+/// the lines of a text separated by line feeds, with the byte index where they start
+fn paragraphs(text:&str) -> impl Iterator<Item = (usize, &str)> {
+
+    text.split('\n').scan(0, |start, paragraph| {
+
+        let found = (*start, paragraph);
+        *start += paragraph.len() + 1;
+        Some(found)
+
+    })
+
+}
+//
+//
@@ -0,0 +1,350 @@
+// TrueType and OpenType fonts: glyph rasterization (coverage or signed distance field), a glyph
+// atlas that grow with the text displayed, and the layout of text in positioned quads
+//
+// Everything run on the CPU, the atlas image only has to be uploaded when its revision change.
+
+mod sdf;
+mod atlas;
+mod layout;
+
+pub use atlas::{AtlasGlyph, GlyphAtlas};
+pub use layout::{Align, GlyphQuad, LayoutSettings, PositionedGlyph, TextLayout, TextLine, layout_text};
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::core::error_handler::EFont;
+use crate::core::file::get_file_content;
+use crate::image::{Image, PixelFormat};
+
+use ab_glyph::{Font as _, FontVec, GlyphId, PxScale};
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::get_relative_path;
+
+    // the glyphs of the fixture are simple shapes on a 1000 units em:
+    // 'A' and 'V' are triangles, 'I' a rectangle from (100,0) to (300,700) and 'O' a rounded
+    // square with a square hole, the pairs AV and VA are kerned
+    pub(super) fn shapes() -> Font {
+        load_font(&get_relative_path("tests/fixtures/font/shapes.ttf")).unwrap()
+    }
+
+    #[test]
+    fn font_metrics() {
+
+        let font = shapes();
+
+        assert_eq!(font.glyph_count(), 6);
+        assert_eq!(font.units_per_em(), 1000.0);
+
+        let metrics = font.line_metrics(100.0);
+        assert_eq!((metrics.ascent, metrics.descent, metrics.line_gap), (80.0, -20.0, 9.0));
+        assert_eq!(metrics.line_height(), 109.0);
+
+        let (a, v) = (font.glyph_index('A').unwrap(), font.glyph_index('V').unwrap());
+
+        assert_eq!(font.advance(a, 1000.0), 600.0);
+        assert_eq!(font.advance(font.glyph_index(' ').unwrap(), 100.0), 25.0);
+        assert_eq!(font.kerning(a, v, 1000.0), -100.0);
+        assert_eq!(font.kerning(v, a, 100.0), -8.0);
+        assert_eq!(font.kerning(a, a, 100.0), 0.0);
+
+        assert!(font.glyph_index('z').is_none());
+        assert!(decode_font(b"not a font".to_vec()).is_err());
+        assert_ne!(font.id(), shapes().id());
+
+    }
+
+    #[test]
+    fn rasterize_glyphs() {
+
+        let font = shapes();
+
+        // a rectangle on the pixel grid is fully covered
+        let bar = font.rasterize(font.glyph_index('I').unwrap(), 100.0);
+
+        assert_eq!((bar.image.width(), bar.image.height()), (20, 70));
+        assert_eq!(bar.image.format(), PixelFormat::R8);
+        assert_eq!((bar.offset, bar.advance), ([10.0, -70.0], 40.0));
+        assert!(bar.image.data().iter().all(|c| *c == 255));
+
+        // the curves of the outer contour and the hole of 'O'
+        let ring = font.rasterize(font.glyph_index('O').unwrap(), 100.0);
+
+        assert_eq!((ring.image.width(), ring.image.height()), (50, 70));
+        assert_eq!(ring.offset, [5.0, -70.0]);
+        assert_eq!(ring.image.pixel(25, 35), &[0]);
+        assert_eq!(ring.image.pixel(5, 35), &[255]);
+        assert_eq!(ring.image.pixel(0, 0), &[0]);
+
+        // nothing to draw for a space
+        let space = font.rasterize(font.glyph_index(' ').unwrap(), 100.0);
+        assert_eq!((space.image.width(), space.image.height(), space.advance), (0, 0, 25.0));
+
+    }
+
+    #[test]
+    fn signed_distance_field() {
+
+        let font = shapes();
+        let sdf = font.rasterize_sdf(font.glyph_index('I').unwrap(), 100.0, 4);
+
+        // the field go beyond the outline by the spread
+        assert_eq!((sdf.image.width(), sdf.image.height()), (28, 78));
+        assert_eq!(sdf.offset, [6.0, -74.0]);
+
+        let row:Vec<u8> = (0..28).map(|x| sdf.image.pixel(x, 39)[0]).collect();
+
+        // the outline is between the pixels 3 and 4, 0.5 is the edge
+        assert!(row[3] < 128 && row[4] >= 128);
+        assert_eq!(row[14], 255);
+        assert!(row[0] < 32);
+        assert!(row[..14].windows(2).all(|w| w[0] <= w[1]));
+        assert!(row[14..].windows(2).all(|w| w[0] >= w[1]));
+
+        // the field is the same on both sides
+        assert_eq!(row[3] as u32 + row[4] as u32, 255);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Font
+//
+/// source of the ids of the fonts
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(0);
+//
+//
+/// A TrueType or OpenType font. The sizes are in pixels per em, the positions in pixels with y
+/// going down and the origin on the baseline
+pub struct Font {
+
+    id:         u64,
+    font:       FontVec
+
+}
+//
+impl fmt::Debug for Font {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        f.debug_struct("Font").field("id", &self.id).field("glyphs", &self.glyph_count()).finish()
+
+    }
+
+}
+//
+impl Font {
+    //
+    /// unique to this font, it identify its glyphs in an atlas
+    pub fn id(&self) -> u64 { self.id }
+    //
+    pub fn glyph_count(&self) -> usize { self.font.glyph_count() }
+    //
+    /// size of the em square in font units
+    pub fn units_per_em(&self) -> f32 { self.font.units_per_em().unwrap_or(1000.0) }
+    //
+    /// the glyph of a character, None if the font doesn't have it
+    pub fn glyph_index(&self, c:char) -> Option<u16> {
+
+        match self.font.glyph_id(c) {
+            GlyphId(0) => None,
+            GlyphId(id) => Some(id)
+        }
+
+    }
+    //
+    /// the vertical metrics of the font at a size
+    pub fn line_metrics(&self, size:f32) -> LineMetrics {
+
+        let scale = self.scale(size);
+
+        LineMetrics {
+            ascent:     self.font.ascent_unscaled() * scale,
+            descent:    self.font.descent_unscaled() * scale,
+            line_gap:   self.font.line_gap_unscaled() * scale
+        }
+
+    }
+    //
+    /// how much the pen move after a glyph
+    pub fn advance(&self, glyph:u16, size:f32) -> f32 { self.font.h_advance_unscaled(GlyphId(glyph)) * self.scale(size) }
+    //
+    /// Adjustment of the space between two glyphs, from the kern table
+    ///
+    /// # Parameters
+    ///
+    /// * 'left' - the first glyph
+    /// * 'right' - the glyph that follow it
+    /// * 'size' - pixels per em
+    ///
+    pub fn kerning(&self, left:u16, right:u16, size:f32) -> f32 {
+
+        self.font.kern_unscaled(GlyphId(left), GlyphId(right)) * self.scale(size)
+
+    }
+    //
+    /// Draw the coverage of a glyph, 255 where the glyph is fully inside a pixel
+    ///
+    /// # Parameters
+    ///
+    /// * 'glyph' - the glyph to draw
+    /// * 'size' - pixels per em
+    ///
+    pub fn rasterize(&self, glyph:u16, size:f32) -> GlyphBitmap { self.rasterize_padded(glyph, size, 0) }
+    //
+    /// Draw the signed distance field of a glyph: 128 on the outline, then up to 255 inside and
+    /// down to 0 outside at 'spread' pixels from it. The field can be scaled far more than a
+    /// coverage bitmap
+    ///
+    /// # Parameters
+    ///
+    /// * 'glyph' - the glyph to draw
+    /// * 'size' - pixels per em
+    /// * 'spread' - distance to the outline covered by the field, in pixels
+    ///
+    pub fn rasterize_sdf(&self, glyph:u16, size:f32, spread:u32) -> GlyphBitmap {
+
+        let coverage = self.rasterize_padded(glyph, size, spread);
+
+        if coverage.image.width() == 0 {
+            return coverage;
+        }
+
+        let (width, height) = (coverage.image.width(), coverage.image.height());
+        let field = sdf::distance_field(coverage.image.data(), width, height, spread);
+
+        GlyphBitmap {
+            image:  Image::new(width, height, PixelFormat::R8, field).unwrap(),
+            ..coverage
+        }
+
+    }
+    //
+    /// Draw the coverage of a glyph with an empty border around it
+    fn rasterize_padded(&self, glyph:u16, size:f32, padding:u32) -> GlyphBitmap {
+
+        let advance = self.advance(glyph, size);
+
+        // the scale of ab_glyph is the height from the descent to the ascent
+        let scale = PxScale::from(self.font.height_unscaled() * self.scale(size));
+
+        let outlined = match self.font.outline_glyph(GlyphId(glyph).with_scale(scale)) {
+            Some(outlined) => outlined,
+            None => return GlyphBitmap {
+                image:  Image::new(0, 0, PixelFormat::R8, Vec::new()).unwrap(),
+                offset: [0.0; 2],
+                advance
+            }
+        };
+
+        let bounds = outlined.px_bounds();
+        let width = bounds.width() as u32 + 2 * padding;
+        let height = bounds.height() as u32 + 2 * padding;
+
+        let mut data = vec![0; width as usize * height as usize];
+
+        outlined.draw(|x, y, c| {
+            let at = (y + padding) as usize * width as usize + (x + padding) as usize;
+            data[at] = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+
+        GlyphBitmap {
+            image:  Image::new(width, height, PixelFormat::R8, data).unwrap(),
+            offset: [bounds.min.x - padding as f32, bounds.min.y - padding as f32],
+            advance
+        }
+
+    }
+    //
+    /// pixels per font unit
+    fn scale(&self, size:f32) -> f32 { size / self.units_per_em() }
+    //
+}
+//
+//
+/// Vertical metrics of a font at a size, in pixels from the baseline with y going up
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct LineMetrics {
+    // top of the highest glyphs
+    pub ascent: f32,
+    // bottom of the lowest glyphs, usually negative
+    pub descent: f32,
+    // space between the descent of a line and the ascent of the next
+    pub line_gap: f32
+}
+//
+impl LineMetrics {
+    //
+    /// distance between the baselines of two lines
+    pub fn line_height(&self) -> f32 { self.ascent - self.descent + self.line_gap }
+    //
+}
+//
+//
+/// A drawn glyph
+#[derive(Debug,Clone,PartialEq)]
+pub struct GlyphBitmap {
+    // R8 image, empty for the glyphs without outline like the space
+    pub image: Image,
+    // position of the top left corner of the image from the pen on the baseline
+    pub offset: [f32;2],
+    pub advance: f32
+}
+//
+//
+/// How the glyphs are drawn in an atlas
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum RenderMode {
+    // the coverage, for text displayed at the size it was drawn
+    Coverage,
+    // a signed distance field with the spread in pixels
+    Sdf(u32)
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load a font from the disk
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the ttf or otf file
+///
+pub fn load_font(fp:&str) -> Result<Font,EFont> {
+
+    let content = get_file_content(fp)
+        .change_context(EFont::Load)
+        .attach_printable_lazy(|| format!("Can't load font {}",fp))?;
+
+    decode_font(content).attach_printable_lazy(|| format!("Can't load font {}",fp))
+
+}
+//
+//
+/// Decode a font already in memory, the first one of a collection
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the ttf, otf or ttc file
+///
+pub fn decode_font(bytes:Vec<u8>) -> Result<Font,EFont> {
+
+    let font = FontVec::try_from_vec(bytes).map_err(|e| EFont::Parse.attach_printable_default(e))?;
+
+    Ok(Font { id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed), font })
+
+}
+//
+//
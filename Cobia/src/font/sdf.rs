@@ -0,0 +1,121 @@
+// Signed distance fields computed from the coverage of a glyph
+
+/// offset given to the pixels that did not find a seed yet, far bigger than any glyph
+const FAR: i32 = 1 << 14;
+//
+//
+/// Compute the signed distance field of a coverage bitmap, the pixels covered at least by half are
+/// inside the glyph
+///
+/// # Parameters
+///
+/// * 'coverage' - one byte per pixel, row after row
+/// * 'width' - width in pixels
+/// * 'height' - height in pixels
+/// * 'spread' - distance to the outline where the field reach 0 or 255
+///
+pub(super) fn distance_field(coverage:&[u8], width:u32, height:u32, spread:u32) -> Vec<u8> {
+
+    let inside:Vec<bool> = coverage.iter().map(|c| *c >= 128).collect();
+    let outside:Vec<bool> = inside.iter().map(|i| !i).collect();
+
+    // from every pixel to the nearest pixel on the other side of the outline
+    let to_inside = distances(&inside, width, height);
+    let to_outside = distances(&outside, width, height);
+
+    let spread = spread.max(1) as f32;
+
+    (0..inside.len())
+        .map(|i| {
+
+            // the outline is half a pixel from the center of the pixels along it
+            let distance = match inside[i] {
+                true => 0.5 - to_outside[i],
+                false => to_inside[i] - 0.5
+            };
+
+            ((0.5 - distance / (2.0 * spread)).clamp(0.0, 1.0) * 255.0).round() as u8
+
+        })
+        .collect()
+
+}
+//
+//
+/// Euclidean distance from every pixel to the nearest seed, by propagating the offset to the
+/// seed in two passes over the image (8SSEDT)
+///
+/// # Parameters
+///
+/// * 'seeds' - true for the pixels the distance is measured to
+/// * 'width' - width in pixels
+/// * 'height' - height in pixels
+///
+fn distances(seeds:&[bool], width:u32, height:u32) -> Vec<f32> {
+
+    let (w, h) = (width as i32, height as i32);
+
+    let mut offsets:Vec<(i32, i32)> = seeds
+        .iter()
+        .map(|s| match s {
+            true => (0, 0),
+            false => (FAR, FAR)
+        })
+        .collect();
+
+    // take the seed of a neighbour if it is closer than the current one
+    let mut compare = |x:i32, y:i32, dx:i32, dy:i32| {
+
+        let (nx, ny) = (x + dx, y + dy);
+
+        if nx < 0 || ny < 0 || nx >= w || ny >= h {
+            return;
+        }
+
+        let (ox, oy) = offsets[(ny * w + nx) as usize];
+        let candidate = (ox + dx, oy + dy);
+        let current = &mut offsets[(y * w + x) as usize];
+
+        let length = |o:(i32, i32)| o.0 as i64 * o.0 as i64 + o.1 as i64 * o.1 as i64;
+
+        if length(candidate) < length(*current) {
+            *current = candidate;
+        }
+
+    };
+
+    for y in 0..h {
+
+        for x in 0..w {
+            compare(x, y, -1, 0);
+            compare(x, y, 0, -1);
+            compare(x, y, -1, -1);
+            compare(x, y, 1, -1);
+        }
+
+        for x in (0..w).rev() {
+            compare(x, y, 1, 0);
+        }
+
+    }
+
+    for y in (0..h).rev() {
+
+        for x in (0..w).rev() {
+            compare(x, y, 1, 0);
+            compare(x, y, 0, 1);
+            compare(x, y, 1, 1);
+            compare(x, y, -1, 1);
+        }
+
+        for x in 0..w {
+            compare(x, y, -1, 0);
+        }
+
+    }
+
+    offsets.iter().map(|(x, y)| ((*x as f32).powi(2) + (*y as f32).powi(2)).sqrt()).collect()
+
+}
+//
+//
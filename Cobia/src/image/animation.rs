@@ -0,0 +1,328 @@
+// Animated images (APNG and GIF). Every frame is composited on the full canvas so it can be
+// shown as is, without knowing anything about the blend and dispose operations of the format
+
+use std::time::Duration;
+
+use super::{Image, PixelFormat, checked_size};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Frames
+//
+/// A full frame of an animation and how long it stay on screen
+#[derive(Debug,Clone,PartialEq)]
+pub struct AnimationFrame {
+
+    image:  Image,
+    delay:  Duration
+
+}
+//
+impl AnimationFrame {
+    //
+    pub(crate) fn new(image:Image, delay:Duration) -> Self { Self { image, delay } }
+    //
+    /// the frame, always a Rgba8 image of the size of the animation
+    pub fn image(&self) -> &Image { &self.image }
+    //
+    pub fn into_image(self) -> Image { self.image }
+    //
+    pub fn delay(&self) -> Duration { self.delay }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Animated image
+//
+#[derive(Debug,Clone,PartialEq)]
+pub struct AnimatedImage {
+
+    width:  u32,
+    height: u32,
+    frames: Vec<AnimationFrame>,
+    // 0 means that the animation loop forever
+    plays:  u32
+
+}
+//
+impl AnimatedImage {
+    //
+    pub(crate) fn new(width:u32, height:u32, frames:Vec<AnimationFrame>, plays:u32) -> Self {
+
+        Self { width, height, frames, plays }
+
+    }
+    //
+    /// An animation of a single frame that never end, used for the formats that can't be animated
+    ///
+    /// # Parameters
+    ///
+    /// * 'image' - the only frame
+    ///
+    pub fn from_image(image:Image) -> Self {
+
+        let image = match image.format() {
+            PixelFormat::Rgba8 => image,
+            _ => image.to_rgba8()
+        };
+
+        Self {
+            width:  image.width(),
+            height: image.height(),
+            frames: vec![AnimationFrame::new(image, Duration::ZERO)],
+            plays:  0
+        }
+
+    }
+    //
+    pub fn width(&self) -> u32 { self.width }
+    //
+    pub fn height(&self) -> u32 { self.height }
+    //
+    pub fn frames(&self) -> &[AnimationFrame] { &self.frames }
+    //
+    pub fn into_frames(self) -> Vec<AnimationFrame> { self.frames }
+    //
+    pub fn frame_count(&self) -> usize { self.frames.len() }
+    //
+    /// number of times the animation is played, 0 if it loop forever
+    pub fn plays(&self) -> u32 { self.plays }
+    //
+    /// duration of one play of the animation
+    pub fn total_duration(&self) -> Duration { self.frames.iter().map(|f| f.delay).sum() }
+    //
+    /// Find the frame to show after some time, the animation loop the number of plays given by
+    /// the file and then stay on its last frame
+    ///
+    /// # Parameters
+    ///
+    /// * 'time' - the time since the animation started
+    ///
+    pub fn frame_at(&self, time:Duration) -> &AnimationFrame {
+
+        let total = self.total_duration().as_nanos();
+
+        if total == 0 {
+            return &self.frames[0];
+        }
+
+        let elapsed = time.as_nanos();
+
+        if self.plays != 0 && elapsed >= total * self.plays as u128 {
+            return &self.frames[self.frames.len() - 1];
+        }
+
+        let mut remaining = elapsed % total;
+
+        for frame in self.frames.iter() {
+
+            if remaining < frame.delay.as_nanos() {
+                return frame;
+            }
+
+            remaining -= frame.delay.as_nanos();
+
+        }
+
+        &self.frames[self.frames.len() - 1]
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Compositing
+//
+/// How the pixels of a frame are put on the canvas
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub(crate) enum Blend {
+    // replace the pixels
+    Source,
+    // alpha blend over the pixels
+    Over
+}
+//
+/// What is done with the region of a frame once it has been shown
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub(crate) enum Dispose {
+    // leave it on the canvas
+    None,
+    // clear it to transparent black
+    Background,
+    // restore what was there before the frame
+    Previous
+}
+//
+//
+/// Hold the canvas of an animation while its frames are decoded
+pub(crate) struct Compositor {
+
+    width:  u32,
+    height: u32,
+    canvas: Vec<u8>,
+    first:  bool
+
+}
+//
+impl Compositor {
+    //
+    /// a transparent canvas, error if its size overflow
+    pub(crate) fn new(width:u32, height:u32) -> Result<Self,EImage> {
+
+        let size = checked_size(&[width as usize, height as usize, 4], "animation canvas size")?;
+
+        Ok(Self { width, height, canvas: vec![0; size], first: true })
+
+    }
+    //
+    /// Draw a frame on the canvas and return the result, the dispose operation is applied after
+    ///
+    /// # Parameters
+    ///
+    /// * 'frame' - the pixels of the frame, in Rgba8
+    /// * 'x' - the column where the frame start on the canvas
+    /// * 'y' - the row where the frame start on the canvas
+    /// * 'blend' - how the frame is put on the canvas
+    /// * 'dispose' - what to do with the region of the frame once it has been shown
+    ///
+    pub(crate) fn compose(
+        &mut self,
+        frame:&Image,
+        x:u32,
+        y:u32,
+        blend:Blend,
+        dispose:Dispose) -> Result<Image,EImage> {
+
+        if frame.format() != PixelFormat::Rgba8 {
+
+            return Err(EImage::Layout
+                .as_report()
+                .attach_printable(format!("frames must be composited in Rgba8 not {:?}",frame.format()))
+            );
+
+        }
+
+        let fits = x as u64 + frame.width() as u64 <= self.width as u64
+            && y as u64 + frame.height() as u64 <= self.height as u64;
+
+        if !fits {
+
+            return Err(EImage::Decode
+                .as_report()
+                .attach_printable(format!(
+                    "a {}x{} frame at ({},{}) goes out of the {}x{} canvas",
+                    frame.width(),
+                    frame.height(),
+                    x,
+                    y,
+                    self.width,
+                    self.height
+                    )
+                )
+            );
+
+        }
+
+        // the first frame has nothing to restore
+        let dispose = match (dispose, self.first) {
+            (Dispose::Previous, true) => Dispose::Background,
+            (d, _) => d
+        };
+
+        self.first = false;
+
+        let previous = match dispose {
+            Dispose::Previous => Some(self.canvas.clone()),
+            _ => None
+        };
+
+        let stride = self.width as usize * 4;
+
+        for row in 0..frame.height() {
+
+            let start = (y + row) as usize * stride + x as usize * 4;
+            let dst = &mut self.canvas[start..start + frame.width() as usize * 4];
+
+            match blend {
+
+                Blend::Source => dst.copy_from_slice(frame.row(row)),
+
+                Blend::Over => {
+
+                    for (d, s) in dst.chunks_exact_mut(4).zip(frame.row(row).chunks_exact(4)) {
+                        blend_over(d, s);
+                    }
+
+                }
+
+            }
+
+        }
+
+        let image = Image::new(self.width, self.height, PixelFormat::Rgba8, self.canvas.clone())?;
+
+        match dispose {
+
+            Dispose::None => {},
+
+            Dispose::Background => {
+
+                for row in 0..frame.height() {
+                    let start = (y + row) as usize * stride + x as usize * 4;
+                    self.canvas[start..start + frame.width() as usize * 4].fill(0);
+                }
+
+            },
+
+            Dispose::Previous => {
+
+                if let Some(previous) = previous {
+                    self.canvas = previous;
+                }
+
+            }
+
+        }
+
+        Ok(image)
+
+    }
+    //
+}
+//
+//
+/// Blend a pixel with straight alpha over an other one
+///
+/// # Parameters
+///
+/// * 'dst' - the pixel of the canvas, replaced by the result
+/// * 'src' - the pixel of the frame
+///
+fn blend_over(dst:&mut [u8], src:&[u8]) {
+
+    let src_a = src[3] as u32;
+
+    match src_a {
+        0 => return,
+        255 => { dst.copy_from_slice(src); return },
+        _ => {}
+    }
+
+    // weight of the canvas that is still visible through the frame
+    let dst_a = dst[3] as u32 * (255 - src_a) / 255;
+    let out_a = src_a + dst_a;
+
+    for c in 0..3 {
+        dst[c] = ((src[c] as u32 * src_a + dst[c] as u32 * dst_a + out_a / 2) / out_a) as u8;
+    }
+
+    dst[3] = out_a as u8;
+
+}
+//
+//
@@ -0,0 +1,727 @@
+// Texture atlas: pack many small images in a single one and find them back by name
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::{Image, ImageFormat, PixelFormat, load_image};
+use crate::core::error_handler::EImage;
+use crate::core::file::get_file_content;
+use crate::core::paths::write_atomic;
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    // plain image where every pixel is (value, value, value, 255)
+    fn plain(width:u32, height:u32, value:u8) -> Image {
+
+        Image::new(width, height, PixelFormat::Rgba8, [value, value, value, 255].repeat((width * height) as usize))
+            .unwrap()
+
+    }
+
+    #[test]
+    fn pack_sprites() {
+
+        let mut builder = AtlasBuilder::new();
+        builder.set_padding(2);
+        builder.set_extrude(1);
+
+        builder.add("big", plain(30, 20, 10)).unwrap();
+        builder.add("tall", plain(6, 28, 20)).unwrap();
+        builder.add("small", plain(4, 4, 30)).unwrap();
+        builder.add("wide", plain(40, 3, 40)).unwrap();
+
+        // names are unique
+        assert!(builder.add("small", plain(1, 1, 0)).is_err());
+
+        let atlas = builder.build().unwrap();
+        let table = atlas.table();
+
+        assert_eq!(table.len(), 4);
+        assert_eq!((atlas.image().width(), atlas.image().height()), (table.width(), table.height()));
+
+        let rects:Vec<&AtlasRect> = table.iter().map(|(_, r)| r).collect();
+
+        for (i, a) in rects.iter().enumerate() {
+
+            // the padding and extrusion are kept from the edge of the atlas
+            assert!(a.x >= 3 && a.y >= 3);
+            assert!(a.x + a.width + 3 <= table.width() && a.y + a.height + 3 <= table.height());
+
+            // and between every sprite
+            for b in rects[i + 1..].iter() {
+                let apart = a.x + a.width + 4 <= b.x
+                    || b.x + b.width + 4 <= a.x
+                    || a.y + a.height + 4 <= b.y
+                    || b.y + b.height + 4 <= a.y;
+                assert!(apart, "{:?} and {:?} overlap", a, b);
+            }
+
+        }
+
+        let tall = table.get("tall").unwrap();
+        assert_eq!((tall.width, tall.height), (6, 28));
+        assert_eq!(atlas.image().pixel(tall.x, tall.y), &[20, 20, 20, 255]);
+
+        // the border is extruded by a pixel
+        assert_eq!(atlas.image().pixel(tall.x - 1, tall.y - 1), &[20, 20, 20, 255]);
+        assert_eq!(atlas.image().pixel(tall.x + 6, tall.y + 28), &[20, 20, 20, 255]);
+        assert_eq!(atlas.image().pixel(tall.x - 2, tall.y), &[0, 0, 0, 0]);
+
+        let uv = table.uv("tall").unwrap();
+        assert_eq!(uv.min, [tall.x as f32 / table.width() as f32, tall.y as f32 / table.height() as f32]);
+        assert_eq!(uv.max[1], (tall.y + 28) as f32 / table.height() as f32);
+        assert!(table.uv("missing").is_none());
+
+    }
+
+    #[test]
+    fn atlas_too_small() {
+
+        let mut builder = AtlasBuilder::new();
+        builder.set_max_size(32);
+
+        builder.add("a", plain(20, 20, 0)).unwrap();
+        builder.add("b", plain(20, 20, 0)).unwrap();
+
+        assert!(builder.build().is_err());
+        assert!(AtlasBuilder::new().build().is_err());
+
+    }
+
+    #[test]
+    fn table_serialization() {
+
+        let mut builder = AtlasBuilder::new();
+
+        builder.add("player/idle 0", plain(8, 8, 0)).unwrap();
+        builder.add("coin", plain(5, 7, 0)).unwrap();
+
+        let table = builder.build().unwrap().into_table();
+        let text = table.to_text();
+
+        assert!(text.starts_with("cobia-atlas 1\n"));
+        assert_eq!(AtlasTable::parse(&text).unwrap(), table);
+
+        assert!(AtlasTable::parse("cobia-atlas 2\nsize 4 4\n").is_err());
+        assert!(AtlasTable::parse("cobia-atlas 1\nsize 4 4\nsprite 0 0 8 8 outside\n").is_err());
+        assert!(AtlasTable::parse("cobia-atlas 1\nsize 4 4\nsprite 0 0 two\n").is_err());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Table
+//
+/// Header of a serialized atlas table
+const TABLE_HEADER: &str = "cobia-atlas 1";
+//
+//
+/// Position of a sprite in the atlas, in pixels
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+//
+//
+/// Position of a sprite in the atlas as texture coordinates, from 0 to 1
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct UvRect {
+    pub min: [f32;2],
+    pub max: [f32;2]
+}
+//
+//
+/// The sprites of an atlas by name
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct AtlasTable {
+
+    width: u32,
+    height: u32,
+    sprites: BTreeMap<String, AtlasRect>
+
+}
+//
+impl AtlasTable {
+    //
+    /// width of the atlas image
+    pub fn width(&self) -> u32 { self.width }
+    //
+    /// height of the atlas image
+    pub fn height(&self) -> u32 { self.height }
+    //
+    /// number of sprites
+    pub fn len(&self) -> usize { self.sprites.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.sprites.is_empty() }
+    //
+    /// the sprites sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AtlasRect)> {
+
+        self.sprites.iter().map(|(name, rect)| (name.as_str(), rect))
+
+    }
+    //
+    /// the pixels of a sprite
+    ///
+    /// # Parameters
+    ///
+    /// * 'name' - name given to the sprite when the atlas was built
+    ///
+    pub fn get(&self, name:&str) -> Option<AtlasRect> { self.sprites.get(name).copied() }
+    //
+    /// the texture coordinates of a sprite
+    ///
+    /// # Parameters
+    ///
+    /// * 'name' - name given to the sprite when the atlas was built
+    ///
+    pub fn uv(&self, name:&str) -> Option<UvRect> {
+
+        let rect = self.sprites.get(name)?;
+        let (w, h) = (self.width as f32, self.height as f32);
+
+        Some(UvRect {
+            min: [rect.x as f32 / w, rect.y as f32 / h],
+            max: [(rect.x + rect.width) as f32 / w, (rect.y + rect.height) as f32 / h]
+        })
+
+    }
+    //
+    /// Serialize the table as text, a line for the size then one per sprite:
+    ///
+    /// ```text
+    /// cobia-atlas 1
+    /// size <width> <height>
+    /// sprite <x> <y> <width> <height> <name>
+    /// ```
+    pub fn to_text(&self) -> String {
+
+        let mut text = format!("{}\nsize {} {}\n",TABLE_HEADER,self.width,self.height);
+
+        for (name, r) in self.sprites.iter() {
+            text.push_str(&format!("sprite {} {} {} {} {}\n",r.x,r.y,r.width,r.height,name));
+        }
+
+        text
+
+    }
+    //
+    /// Read a table serialized with to_text
+    ///
+    /// # Parameters
+    ///
+    /// * 'text' - the serialized table
+    ///
+    pub fn parse(text:&str) -> Result<AtlasTable,EImage> {
+
+        let invalid = |line:usize, msg:&str| {
+            EImage::Decode.as_report().attach_printable(format!("atlas table line {}: {}",line + 1,msg))
+        };
+
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == TABLE_HEADER => {},
+            _ => return Err(invalid(0, &format!("expected the header '{}'",TABLE_HEADER)))
+        }
+
+        let (i, size) = lines.next().ok_or_else(|| invalid(1, "missing the size of the atlas"))?;
+
+        let (width, height) = match size.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["size", w, h] => match (w.parse(), h.parse()) {
+                (Ok(w), Ok(h)) => (w, h),
+                _ => return Err(invalid(i, "invalid atlas size"))
+            },
+            _ => return Err(invalid(i, "expected 'size <width> <height>'"))
+        };
+
+        let mut sprites = BTreeMap::new();
+
+        for (i, line) in lines {
+
+            // the name is the rest of the line, it can have spaces
+            let mut parts = line.trim().splitn(6, ' ');
+
+            if parts.next() != Some("sprite") {
+                return Err(invalid(i, "expected 'sprite <x> <y> <width> <height> <name>'"));
+            }
+
+            let mut number = || -> Result<u32,EImage> {
+                parts
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| invalid(i, "expected 'sprite <x> <y> <width> <height> <name>'"))
+            };
+
+            let rect = AtlasRect { x: number()?, y: number()?, width: number()?, height: number()? };
+
+            let name = parts.next().unwrap_or_default();
+
+            if name.is_empty() {
+                return Err(invalid(i, "a sprite has no name"));
+            }
+
+            if rect.x as u64 + rect.width as u64 > width as u64 || rect.y as u64 + rect.height as u64 > height as u64 {
+                return Err(invalid(i, &format!("the sprite '{}' goes out of the atlas",name)));
+            }
+
+            if sprites.insert(name.to_string(), rect).is_some() {
+                return Err(invalid(i, &format!("the sprite '{}' is defined twice",name)));
+            }
+
+        }
+
+        Ok(AtlasTable { width, height, sprites })
+
+    }
+    //
+    /// load a table saved with save
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the table
+    ///
+    pub fn load(fp:&str) -> Result<AtlasTable,EImage> {
+
+        let content = get_file_content(fp)
+            .change_context(EImage::Load)
+            .attach_printable_lazy(|| format!("Can't load atlas table {}",fp))?;
+
+        let text = std::str::from_utf8(&content).map_err(|e| EImage::Decode.attach_printable_default(e))?;
+
+        AtlasTable::parse(text).attach_printable_lazy(|| format!("Can't load atlas table {}",fp))
+
+    }
+    //
+    /// save the table as text
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the new table
+    ///
+    pub fn save(&self, fp:&str) -> Result<(),EImage> {
+
+        write_atomic(Path::new(fp), self.to_text().as_bytes())
+            .change_context(EImage::Encode)
+            .attach_printable_lazy(|| format!("Can't save atlas table {}",fp))
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Atlas
+//
+/// An atlas image with the position of its sprites
+pub struct Atlas {
+
+    image: Image,
+    table: AtlasTable
+
+}
+//
+impl Atlas {
+    //
+    /// the atlas image, always Rgba8
+    pub fn image(&self) -> &Image { &self.image }
+    //
+    pub fn table(&self) -> &AtlasTable { &self.table }
+    //
+    pub fn into_table(self) -> AtlasTable { self.table }
+    //
+    pub fn into_parts(self) -> (Image, AtlasTable) { (self.image, self.table) }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Builder
+//
+/// Gather images and pack them in an atlas
+pub struct AtlasBuilder {
+
+    images: Vec<(String, Image)>,
+    // empty pixels between the sprites and around the edge of the atlas
+    padding: u32,
+    // pixels of the sprite border repeated around it, avoid bleeding when filtering
+    extrude: u32,
+    max_size: u32
+
+}
+//
+impl AtlasBuilder {
+    //
+    pub fn new() -> Self { Self { images: Vec::new(), padding: 1, extrude: 0, max_size: 4096 } }
+    //
+    /// empty pixels between the sprites, 1 by default
+    pub fn set_padding(&mut self, padding:u32) { self.padding = padding; }
+    //
+    /// number of time the border of the sprites are repeated around them, 0 by default
+    pub fn set_extrude(&mut self, extrude:u32) { self.extrude = extrude; }
+    //
+    /// the biggest width and height the atlas can have, 4096 by default
+    pub fn set_max_size(&mut self, max_size:u32) { self.max_size = max_size; }
+    //
+    /// Add an image to the future atlas
+    ///
+    /// # Parameters
+    ///
+    /// * 'name' - name used to find the sprite in the table
+    /// * 'image' - the sprite, converted to Rgba8
+    ///
+    pub fn add(&mut self, name:&str, image:Image) -> Result<(),EImage> {
+
+        if name.is_empty() || name.contains('\n') {
+
+            return Err(EImage::Layout.as_report().attach_printable(format!("invalid sprite name {:?}",name)));
+
+        }
+
+        if self.images.iter().any(|(n, _)| n == name) {
+
+            return Err(EImage::Layout.as_report().attach_printable(format!("the sprite '{}' is added twice",name)));
+
+        }
+
+        let image = match image.format() {
+            PixelFormat::Rgba8 => image,
+            _ => image.to_rgba8()
+        };
+
+        self.images.push((name.to_string(), image));
+
+        Ok(())
+
+    }
+    //
+    /// Add every image under a directory, their name is the path relative to this directory
+    /// without the extension. The files that are not images are ignored
+    ///
+    /// # Parameters
+    ///
+    /// * 'dir' - the directory of the sprites
+    ///
+    pub fn add_directory(&mut self, dir:&str) -> Result<(),EImage> {
+
+        let root = Path::new(dir);
+        let mut stack = vec![root.to_path_buf()];
+
+        while let Some(current) = stack.pop() {
+
+            let read_dir = fs::read_dir(&current).map_err(|e|
+                EImage::Load
+                    .attach_printable_default(e)
+                    .attach_printable(format!("Can't read directory {}",current.display()))
+            )?;
+
+            for item in read_dir {
+
+                let path = item.map_err(|e| EImage::Load.attach_printable_default(e))?.path();
+
+                if path.is_dir() {
+
+                    stack.push(path);
+                    continue;
+
+                }
+
+                let is_image = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(|e| ImageFormat::from_extension(&e.to_lowercase()))
+                    .is_some();
+
+                let fp = path.to_str();
+
+                let (fp, relative) = match (is_image, fp, path.strip_prefix(root).ok()) {
+                    (true, Some(fp), Some(relative)) => (fp, relative.with_extension("")),
+                    _ => continue
+                };
+
+                let name = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                self.add(&name, load_image(fp)?)?;
+
+            }
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// Number of images added so far
+    pub fn len(&self) -> usize { self.images.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.images.is_empty() }
+    //
+    /// Pack the images in the smallest power of two atlas that can hold them
+    pub fn build(&self) -> Result<Atlas,EImage> {
+
+        if self.images.is_empty() {
+
+            return Err(EImage::Layout.as_report().attach_printable("an atlas need at least one image"));
+
+        }
+
+        // a cell is the sprite, its extrusion and the padding on its right and bottom side
+        let border = 2 * self.extrude + self.padding;
+        let cells:Vec<(u32, u32)> = self.images
+            .iter()
+            .map(|(_, img)| (img.width() + border, img.height() + border))
+            .collect();
+
+        // the biggest images are placed first
+        let mut order:Vec<usize> = (0..cells.len()).collect();
+        order.sort_by_key(|i| std::cmp::Reverse((cells[*i].1, cells[*i].0)));
+
+        let area:u64 = cells.iter().map(|(w, h)| *w as u64 * *h as u64).sum();
+        let widest = cells.iter().map(|(w, _)| *w).max().unwrap_or(1) + self.padding;
+        let tallest = cells.iter().map(|(_, h)| *h).max().unwrap_or(1) + self.padding;
+
+        let mut width = ((area as f64).sqrt() as u32).max(widest).next_power_of_two();
+        let mut height = tallest.next_power_of_two();
+
+        let positions = loop {
+
+            if width > self.max_size || height > self.max_size {
+
+                return Err(EImage::Layout
+                    .as_report()
+                    .attach_printable(format!(
+                        "{} images don't fit in an atlas of {}x{}",
+                        self.images.len(),
+                        self.max_size,
+                        self.max_size
+                        )
+                    )
+                );
+
+            }
+
+            // the left and top padding are kept out of the packing area
+            let mut skyline = Skyline::new(width - self.padding, height - self.padding);
+
+            let placed:Option<Vec<(usize, u32, u32)>> = order
+                .iter()
+                .map(|i| skyline.insert(cells[*i].0, cells[*i].1).map(|(x, y)| (*i, x, y)))
+                .collect();
+
+            match placed {
+                Some(placed) => break placed,
+                None if height < width => height *= 2,
+                None => width *= 2
+            }
+
+        };
+
+        let mut data = vec![0; width as usize * height as usize * 4];
+        let mut sprites = BTreeMap::new();
+
+        for (i, x, y) in positions {
+
+            let (name, img) = &self.images[i];
+
+            let rect = AtlasRect {
+                x: x + self.padding + self.extrude,
+                y: y + self.padding + self.extrude,
+                width: img.width(),
+                height: img.height()
+            };
+
+            blit(&mut data, width, img, rect, self.extrude);
+            sprites.insert(name.clone(), rect);
+
+        }
+
+        Ok(Atlas {
+            image: Image::new(width, height, PixelFormat::Rgba8, data)?,
+            table: AtlasTable { width, height, sprites }
+        })
+
+    }
+    //
+}
+//
+impl Default for AtlasBuilder {
+
+    fn default() -> Self { Self::new() }
+
+}
+//
+//
+/// Copy a sprite in the atlas and repeat its border around it
+///
+/// # Parameters
+///
+/// * 'data' - the Rgba8 pixels of the atlas
+/// * 'atlas_width' - width of the atlas
+/// * 'img' - the Rgba8 sprite
+/// * 'rect' - where the sprite go
+/// * 'extrude' - number of time the border is repeated
+///
+fn blit(data:&mut [u8], atlas_width:u32, img:&Image, rect:AtlasRect, extrude:u32) {
+
+    if img.width() == 0 || img.height() == 0 {
+        return;
+    }
+
+    let e = extrude as i64;
+
+    for dy in -e..img.height() as i64 + e {
+
+        let sy = dy.clamp(0, img.height() as i64 - 1) as u32;
+        let row = img.row(sy);
+        let ay = (rect.y as i64 + dy) as usize;
+
+        for dx in -e..img.width() as i64 + e {
+
+            let sx = dx.clamp(0, img.width() as i64 - 1) as usize;
+            let at = (ay * atlas_width as usize + (rect.x as i64 + dx) as usize) * 4;
+
+            data[at..at + 4].copy_from_slice(&row[sx * 4..sx * 4 + 4]);
+
+        }
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Packing
+//
+/// Bottom left skyline packer, the top of the placed rectangles is kept as a list of segments
+pub(crate) struct Skyline {
+
+    width: u32,
+    height: u32,
+    // x, y and width of every segment, from left to right
+    segments: Vec<(u32, u32, u32)>
+
+}
+//
+impl Skyline {
+    //
+    pub(crate) fn new(width:u32, height:u32) -> Self { Self { width, height, segments: vec![(0, 0, width)] } }
+    //
+    /// Make the packing area bigger, the rectangles already placed keep their position
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - the new width, not smaller than the current one
+    /// * 'height' - the new height, not smaller than the current one
+    ///
+    pub(crate) fn grow(&mut self, width:u32, height:u32) {
+
+        if width > self.width {
+            match self.segments.last_mut() {
+                Some(last) if last.1 == 0 => last.2 += width - self.width,
+                _ => self.segments.push((self.width, 0, width - self.width))
+            }
+        }
+
+        self.width = self.width.max(width);
+        self.height = self.height.max(height);
+
+    }
+    //
+    /// the lowest y where a rectangle can be placed from the start of a segment
+    fn fit(&self, index:usize, width:u32, height:u32) -> Option<u32> {
+
+        let x = self.segments[index].0;
+
+        if x + width > self.width {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut covered = 0;
+
+        for segment in self.segments[index..].iter() {
+
+            if covered >= width {
+                break;
+            }
+
+            y = y.max(segment.1);
+            covered += segment.2;
+
+        }
+
+        match y + height <= self.height {
+            true => Some(y),
+            false => None
+        }
+
+    }
+    //
+    /// Place a rectangle as low as possible then as left as possible
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - width of the rectangle
+    /// * 'height' - height of the rectangle
+    ///
+    pub(crate) fn insert(&mut self, width:u32, height:u32) -> Option<(u32, u32)> {
+
+        let (index, y) = (0..self.segments.len())
+            .filter_map(|i| self.fit(i, width, height).map(|y| (i, y)))
+            .min_by_key(|(i, y)| (*y, self.segments[*i].0))?;
+
+        let x = self.segments[index].0;
+        let end = x + width;
+
+        self.segments.insert(index, (x, y + height, width));
+
+        // the segments under the rectangle are shortened or removed
+        let mut i = index + 1;
+
+        while i < self.segments.len() && self.segments[i].0 < end {
+
+            let (sx, sy, sw) = self.segments[i];
+
+            match sx + sw <= end {
+                true => { self.segments.remove(i); },
+                false => {
+                    self.segments[i] = (end, sy, sx + sw - end);
+                    break;
+                }
+            }
+
+        }
+
+        // merge the neighbours at the same height
+        self.segments.dedup_by(|next, prev| {
+            let merge = prev.1 == next.1;
+            if merge { prev.2 += next.2; }
+            merge
+        });
+
+        Some((x, y))
+
+    }
+    //
+}
+//
+//
@@ -0,0 +1,249 @@
+// BMP decoder, uncompressed and bitfields bitmaps of 1 to 32 bits per pixel
+
+use super::{Image, PixelFormat, checked_size};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+const FILE_HEADER_SIZE: usize = 14;
+// header of the old OS/2 bitmaps, the sizes are on 16 bits and the palette entries on 3 bytes
+const CORE_HEADER_SIZE: usize = 12;
+//
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+//
+//
+fn read_u16(bytes:&[u8], at:usize) -> Result<u16,EImage> {
+
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| EImage::Decode.as_report().attach_printable("the bmp file is truncated"))
+
+}
+//
+fn read_u32(bytes:&[u8], at:usize) -> Result<u32,EImage> {
+
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| EImage::Decode.as_report().attach_printable("the bmp file is truncated"))
+
+}
+//
+//
+/// A channel packed in a 16 or 32 bits pixel
+#[derive(Clone,Copy)]
+struct Mask {
+    shift:  u32,
+    max:    u32,
+    mask:   u32
+}
+//
+impl Mask {
+
+    fn new(mask:u32) -> Self {
+
+        let shift = match mask { 0 => 0, m => m.trailing_zeros() };
+        let max = match mask { 0 => 0, m => (m >> shift) };
+
+        Self { shift, max, mask }
+
+    }
+
+    // the value of the channel, scaled to 8 bits
+    fn extract(&self, px:u32) -> u8 {
+
+        match self.max {
+            0 => 0,
+            max => (((px & self.mask) >> self.shift) * 255 / max) as u8
+        }
+
+    }
+
+}
+//
+//
+/// decode a bmp image
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a bmp file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    if bytes.get(0..2) != Some(b"BM") {
+
+        return Err(EImage::Decode.as_report().attach_printable("missing the BM signature of a bmp file"));
+
+    }
+
+    let data_offset = read_u32(bytes, 10)? as usize;
+    let header_size = read_u32(bytes, FILE_HEADER_SIZE)? as usize;
+
+    let (width, height, bpp, compression) = match header_size {
+
+        CORE_HEADER_SIZE => (
+            read_u16(bytes, FILE_HEADER_SIZE + 4)? as i32,
+            read_u16(bytes, FILE_HEADER_SIZE + 6)? as i32,
+            read_u16(bytes, FILE_HEADER_SIZE + 10)?,
+            BI_RGB
+        ),
+
+        s if s >= 40 => (
+            read_u32(bytes, FILE_HEADER_SIZE + 4)? as i32,
+            read_u32(bytes, FILE_HEADER_SIZE + 8)? as i32,
+            read_u16(bytes, FILE_HEADER_SIZE + 14)?,
+            read_u32(bytes, FILE_HEADER_SIZE + 16)?
+        ),
+
+        s => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("bmp header of {} bytes is not supported",s))
+        )
+
+    };
+
+    if width <= 0 || height == 0 {
+
+        return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("invalid bmp size {}x{}",width,height))
+        );
+
+    }
+
+    // a negative height means that the rows are stored from the top
+    let top_down = height < 0;
+    let (width, height) = (width as u32, height.unsigned_abs());
+
+    // the masks follow the info header, or are part of it since the V2 header
+    let masks_at = FILE_HEADER_SIZE + 40;
+
+    let masks = match (compression, bpp) {
+
+        (BI_BITFIELDS, 16 | 32) | (BI_ALPHABITFIELDS, 16 | 32) => {
+
+            let alpha = match compression == BI_ALPHABITFIELDS || header_size >= 56 {
+                true => read_u32(bytes, masks_at + 12)?,
+                false => 0
+            };
+
+            [
+                Mask::new(read_u32(bytes, masks_at)?),
+                Mask::new(read_u32(bytes, masks_at + 4)?),
+                Mask::new(read_u32(bytes, masks_at + 8)?),
+                Mask::new(alpha)
+            ]
+
+        },
+
+        (BI_RGB, 16) => [Mask::new(0x7c00), Mask::new(0x03e0), Mask::new(0x001f), Mask::new(0)],
+
+        (BI_RGB, 32) => [Mask::new(0x00ff0000), Mask::new(0x0000ff00), Mask::new(0x000000ff), Mask::new(0)],
+
+        (BI_RGB, 1 | 4 | 8 | 24) => [Mask::new(0); 4],
+
+        (compression, bpp) => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("bmp of {} bits with the compression {} is not supported",bpp,compression))
+        )
+
+    };
+
+    // the palette follow the headers
+    let palette:Vec<[u8;3]> = match bpp {
+
+        1 | 4 | 8 => {
+
+            let entry_size = match header_size { CORE_HEADER_SIZE => 3, _ => 4 };
+
+            let used = match header_size {
+                CORE_HEADER_SIZE => 0,
+                _ => read_u32(bytes, FILE_HEADER_SIZE + 32)? as usize
+            };
+
+            let count = match used { 0 => 1 << bpp, n => n.min(1 << bpp) };
+            let start = FILE_HEADER_SIZE + header_size;
+
+            let table = bytes.get(start..start + count * entry_size).ok_or_else(||
+                EImage::Decode.as_report().attach_printable("the palette of the bmp file is truncated")
+            )?;
+
+            table.chunks_exact(entry_size).map(|e| [e[2], e[1], e[0]]).collect()
+
+        },
+
+        _ => Vec::new()
+
+    };
+
+    let format = match masks[3].max {
+        0 => PixelFormat::Rgb8,
+        _ => PixelFormat::Rgba8
+    };
+
+    // every row is padded to 4 bytes
+    let row_size = checked_size(&[width as usize, bpp as usize], "bmp row size")?.div_ceil(32) * 4;
+    let size = checked_size(&[row_size, height as usize], "bmp size")?;
+
+    // the pixels must be in the file, which bound the size of the decoded image
+    let pixels = data_offset
+        .checked_add(size)
+        .and_then(|end| bytes.get(data_offset..end))
+        .ok_or_else(||
+            EImage::Decode.as_report().attach_printable("the pixels of the bmp file are truncated")
+        )?;
+
+    let mut data = Vec::with_capacity(width as usize * height as usize * format.bytes_per_pixel());
+
+    for y in 0..height as usize {
+
+        let src_y = match top_down { true => y, false => height as usize - 1 - y };
+        let row = &pixels[src_y * row_size..(src_y + 1) * row_size];
+
+        for x in 0..width as usize {
+
+            let px = match bpp {
+
+                1 | 4 | 8 => {
+
+                    let bit = x * bpp as usize;
+                    let index = (row[bit / 8] >> (8 - bpp as usize - bit % 8)) & ((1 << bpp) - 1) as u8;
+
+                    let color = palette.get(index as usize).ok_or_else(||
+                        EImage::Decode
+                            .as_report()
+                            .attach_printable(format!("palette index {} out of the bmp palette",index))
+                    )?;
+
+                    [color[0], color[1], color[2], 255]
+
+                },
+
+                24 => [row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255],
+
+                _ => {
+
+                    let value = match bpp {
+                        16 => u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32,
+                        _ => u32::from_le_bytes([row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]])
+                    };
+
+                    [masks[0].extract(value), masks[1].extract(value), masks[2].extract(value), masks[3].extract(value)]
+
+                }
+
+            };
+
+            data.extend_from_slice(&px[..format.bytes_per_pixel()]);
+
+        }
+
+    }
+
+    Image::new(width, height, format, data)
+
+}
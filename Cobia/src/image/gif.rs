@@ -0,0 +1,93 @@
+// GIF decoder, built on the gif crate
+
+use std::time::Duration;
+
+use super::{Image, PixelFormat};
+use super::animation::{AnimatedImage, AnimationFrame, Blend, Compositor, Dispose};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+/// decode the first frame of a gif
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a gif file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    let mut frames = read(bytes, Some(1))?.into_frames();
+
+    Ok(frames.remove(0).into_image())
+
+}
+//
+//
+/// decode all the frames of a gif
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a gif file
+///
+pub(crate) fn decode_animated(bytes:&[u8]) -> Result<AnimatedImage,EImage> { read(bytes, None) }
+//
+//
+/// Decode and composite the frames of a gif. The gif crate doesn't give the loop count of the
+/// NETSCAPE extension so the animation always loop forever
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a gif file
+/// * 'limit' - the maximum number of frames to decode
+///
+fn read(bytes:&[u8], limit:Option<usize>) -> Result<AnimatedImage,EImage> {
+
+    let mut options = ::gif::DecodeOptions::new();
+    options.set_color_output(::gif::ColorOutput::RGBA);
+
+    let mut decoder = options.read_info(bytes).map_err(|e| EImage::Decode.attach_printable_default(e))?;
+
+    let (width, height) = (decoder.width() as u32, decoder.height() as u32);
+
+    let mut compositor = Compositor::new(width, height)?;
+    let mut frames = Vec::new();
+
+    while frames.len() < limit.unwrap_or(usize::MAX) {
+
+        let frame = match decoder.read_next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(e) => return Err(EImage::Decode.attach_printable_default(e))
+        };
+
+        let image = Image::new(
+            frame.width as u32,
+            frame.height as u32,
+            PixelFormat::Rgba8,
+            frame.buffer.to_vec()
+        )?;
+
+        let dispose = match frame.dispose {
+            ::gif::DisposalMethod::Any | ::gif::DisposalMethod::Keep => Dispose::None,
+            ::gif::DisposalMethod::Background => Dispose::Background,
+            ::gif::DisposalMethod::Previous => Dispose::Previous
+        };
+
+        // transparent pixels have an alpha of 0 so blending keep what is under them
+        let canvas = compositor.compose(&image, frame.left as u32, frame.top as u32, Blend::Over, dispose)?;
+
+        // the delay is in hundredths of a second
+        frames.push(AnimationFrame::new(canvas, Duration::from_millis(frame.delay as u64 * 10)));
+
+    }
+
+    if frames.is_empty() {
+
+        return Err(EImage::Decode.as_report().attach_printable("the gif doesn't have any frame"));
+
+    }
+
+    Ok(AnimatedImage::new(width, height, frames, 0))
+
+}
@@ -0,0 +1,259 @@
+// Radiance HDR decoder (.hdr / .pic), RGBE pixels converted to float RGB
+
+use super::{Image, PixelFormat, checked_size};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+pub(crate) const MAGICS: [&[u8];2] = [b"#?RADIANCE", b"#?RGBE"];
+//
+//
+/// decode a radiance hdr image to Rgb32F
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a hdr file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    if !MAGICS.iter().any(|m| bytes.starts_with(m)) {
+
+        return Err(EImage::Decode.as_report().attach_printable("missing the signature of a radiance hdr file"));
+
+    }
+
+    let mut at = 0;
+
+    // the header is made of text lines and end with an empty one
+    loop {
+
+        let line = read_line(bytes, &mut at)?;
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+
+            if format != "32-bit_rle_rgbe" {
+
+                return Err(EImage::Unsupported
+                    .as_report()
+                    .attach_printable(format!("radiance hdr format {} is not supported",format))
+                );
+
+            }
+
+        }
+
+    }
+
+    // only the standard orientations are supported, rows from the top or the bottom
+    let resolution = read_line(bytes, &mut at)?;
+    let parts:Vec<&str> = resolution.split_whitespace().collect();
+
+    let (top_down, height, width) = match parts.as_slice() {
+
+        ["-Y", h, "+X", w] => (true, h.parse::<u32>(), w.parse::<u32>()),
+        ["+Y", h, "+X", w] => (false, h.parse::<u32>(), w.parse::<u32>()),
+
+        _ => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("radiance hdr orientation '{}' is not supported",resolution))
+        )
+
+    };
+
+    let (width, height) = match (width, height) {
+
+        (Ok(w), Ok(h)) => (w, h),
+
+        _ => return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("invalid radiance hdr resolution '{}'",resolution))
+        )
+
+    };
+
+    let row_size = checked_size(&[width as usize, 3, 4], "radiance hdr row size")?;
+    let size = checked_size(&[row_size, height as usize], "radiance hdr size")?;
+
+    // a run of 127 pixels take 2 bytes for each of the 4 components, a pixel use at least 1/16 byte
+    if size / (12 * 16) > bytes.len() - at {
+
+        return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("a radiance hdr file of {} bytes can't hold {}x{} pixels",bytes.len(),width,height))
+        );
+
+    }
+
+    let mut data = vec![0; size];
+    let mut scanline = vec![[0u8;4]; width as usize];
+
+    for y in 0..height as usize {
+
+        read_scanline(bytes, &mut at, &mut scanline)?;
+
+        let dst_y = match top_down { true => y, false => height as usize - 1 - y };
+        let row = &mut data[dst_y * row_size..(dst_y + 1) * row_size];
+
+        for (px, rgbe) in row.chunks_exact_mut(12).zip(scanline.iter()) {
+
+            let rgb = rgbe_to_rgb(*rgbe);
+
+            for c in 0..3 {
+                px[c * 4..(c + 1) * 4].copy_from_slice(&rgb[c].to_ne_bytes());
+            }
+
+        }
+
+    }
+
+    Image::new(width, height, PixelFormat::Rgb32F, data)
+
+}
+//
+//
+/// read a line of the text header
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the file
+/// * 'at' - position of the line, moved after it
+///
+fn read_line<'a>(bytes:&'a [u8], at:&mut usize) -> Result<&'a str,EImage> {
+
+    let rest = bytes.get(*at..).unwrap_or_default();
+
+    let end = rest.iter().position(|b| *b == b'\n').ok_or_else(||
+        EImage::Decode.as_report().attach_printable("the radiance hdr header is truncated")
+    )?;
+
+    *at += end + 1;
+
+    std::str::from_utf8(&rest[..end])
+        .map(|line| line.trim_end_matches('\r'))
+        .map_err(|e| EImage::Decode.attach_printable_default(e))
+
+}
+//
+//
+/// Read a scanline, flat or run length encoded
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the file
+/// * 'at' - position of the scanline, moved after it
+/// * 'scanline' - receive the RGBE pixels
+///
+fn read_scanline(bytes:&[u8], at:&mut usize, scanline:&mut [[u8;4]]) -> Result<(),EImage> {
+
+    let truncated = || EImage::Decode.as_report().attach_printable("the radiance hdr pixels are truncated");
+
+    let width = scanline.len();
+    let start = bytes.get(*at..*at + 4).ok_or_else(truncated)?;
+
+    // the run length encoding start with 2 2 and the width on 16 bits
+    let rle = (8..0x8000).contains(&width)
+        && start[0] == 2
+        && start[1] == 2
+        && start[2] & 0x80 == 0;
+
+    if !rle {
+
+        let flat = bytes.get(*at..*at + width * 4).ok_or_else(truncated)?;
+
+        for (px, rgbe) in scanline.iter_mut().zip(flat.chunks_exact(4)) {
+            px.copy_from_slice(rgbe);
+        }
+
+        *at += width * 4;
+
+        return Ok(());
+
+    }
+
+    if ((start[2] as usize) << 8 | start[3] as usize) != width {
+
+        return Err(EImage::Decode.as_report().attach_printable("radiance hdr scanline of the wrong width"));
+
+    }
+
+    *at += 4;
+
+    // every component is encoded one after the other
+    for c in 0..4 {
+
+        let mut x = 0;
+
+        while x < width {
+
+            let count = *bytes.get(*at).ok_or_else(truncated)? as usize;
+            *at += 1;
+
+            match count > 128 {
+
+                true => {
+
+                    let count = count - 128;
+                    let value = *bytes.get(*at).ok_or_else(truncated)?;
+                    *at += 1;
+
+                    let run = scanline.get_mut(x..x + count).ok_or_else(truncated)?;
+                    run.iter_mut().for_each(|px| px[c] = value);
+
+                    x += count;
+
+                },
+
+                false => {
+
+                    let values = bytes.get(*at..*at + count).ok_or_else(truncated)?;
+                    *at += count;
+
+                    let dump = scanline.get_mut(x..x + count).ok_or_else(truncated)?;
+                    dump.iter_mut().zip(values).for_each(|(px, v)| px[c] = *v);
+
+                    x += count;
+
+                }
+
+            }
+
+            if count == 0 {
+
+                return Err(EImage::Decode.as_report().attach_printable("empty run in a radiance hdr scanline"));
+
+            }
+
+        }
+
+    }
+
+    Ok(())
+
+}
+//
+//
+/// Convert a pixel with a shared exponent to float RGB
+///
+/// # Parameters
+///
+/// * 'rgbe' - the mantissa of red, green and blue then the exponent
+///
+fn rgbe_to_rgb(rgbe:[u8;4]) -> [f32;3] {
+
+    match rgbe[3] {
+
+        0 => [0.0; 3],
+
+        e => {
+            let scale = 2f32.powi(e as i32 - (128 + 8));
+            [rgbe[0] as f32 * scale, rgbe[1] as f32 * scale, rgbe[2] as f32 * scale]
+        }
+
+    }
+
+}
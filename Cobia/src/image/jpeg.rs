@@ -0,0 +1,72 @@
+// JPEG decoder, built on the jpeg-decoder crate
+
+use super::{Image, PixelFormat};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+/// decode a jpeg image
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a jpeg file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+
+    let data = decoder.decode().map_err(|e|
+        EImage::Decode
+            .attach_printable_default(e)
+            .attach_printable("Unable to access jpeg data by the decoder")
+    )?;
+
+    let info = decoder.info().ok_or_else(||
+        EImage::Decode
+            .as_report()
+            .attach_printable("unable to access metadata of a jpeg/jpg file. Nothing was found")
+    )?;
+
+    let (width, height) = (info.width as u32, info.height as u32);
+
+    match info.pixel_format {
+
+        jpeg_decoder::PixelFormat::L8 => Image::new(width, height, PixelFormat::L8, data),
+
+        // the decoder already give the 16 bits samples in native endian
+        jpeg_decoder::PixelFormat::L16 => Image::new(width, height, PixelFormat::L16, data),
+
+        jpeg_decoder::PixelFormat::RGB24 => Image::new(width, height, PixelFormat::Rgb8, data),
+
+        jpeg_decoder::PixelFormat::CMYK32 => Image::new(width, height, PixelFormat::Rgb8, cmyk_to_rgb(&data))
+
+    }
+
+}
+//
+//
+/// Convert the CMYK pixels given by the decoder to RGB. The decoder already undo the inversion
+/// done by Adobe so the values are the amount of ink
+///
+/// # Parameters
+///
+/// * 'cmyk' - the pixels, 4 bytes each
+///
+fn cmyk_to_rgb(cmyk:&[u8]) -> Vec<u8> {
+
+    let mut rgb = Vec::with_capacity(cmyk.len() / 4 * 3);
+
+    for px in cmyk.chunks_exact(4) {
+
+        let white = 255 - px[3] as u32;
+
+        for ink in &px[..3] {
+            rgb.push(((255 - *ink as u32) * white / 255) as u8);
+        }
+
+    }
+
+    rgb
+
+}
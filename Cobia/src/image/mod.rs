@@ -0,0 +1,942 @@
+// Engine image type and the decoders that produce it
+
+pub(crate) mod png;
+pub(crate) mod jpeg;
+pub(crate) mod gif;
+pub(crate) mod bmp;
+pub(crate) mod tga;
+pub(crate) mod qoi;
+pub(crate) mod hdr;
+mod animation;
+mod texture;
+mod ops;
+mod atlas;
+
+pub use animation::{AnimatedImage, AnimationFrame};
+pub use atlas::{Atlas, AtlasBuilder, AtlasRect, AtlasTable, UvRect};
+pub(crate) use atlas::Skyline;
+pub use ops::Filter;
+pub use texture::{TextureData, load_texture, decode_texture, encode_ktx2};
+
+use std::path::Path;
+
+use crate::core::error_handler::EImage;
+use crate::core::file::get_file_content;
+use crate::core::paths::write_atomic;
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::{get_relative_path, tmp_path};
+    use std::time::Duration;
+
+    // value of the pixel (x,y) of the png fixtures
+    fn fixture_pixel(x:u32, y:u32) -> [u8;4] {
+        [(x * 60) as u8, (y * 100) as u8, ((x + y) * 30) as u8, (255 - x * 50) as u8]
+    }
+
+    #[test]
+    fn load_png_fixtures() {
+
+        let rgb = load_image(&get_relative_path("tests/fixtures/image/rgb8.png")).unwrap();
+
+        assert_eq!((rgb.width(), rgb.height()), (4, 3));
+        assert_eq!(rgb.format(), PixelFormat::Rgb8);
+        assert_eq!(rgb.stride(), 4 * 3);
+
+        let rgba = load_image(&get_relative_path("tests/fixtures/image/rgba8.png")).unwrap();
+
+        assert_eq!((rgba.width(), rgba.height()), (4, 3));
+        assert_eq!(rgba.format(), PixelFormat::Rgba8);
+
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(rgb.pixel(x, y), &fixture_pixel(x, y)[..3]);
+                assert_eq!(rgba.pixel(x, y), &fixture_pixel(x, y)[..]);
+            }
+        }
+
+    }
+
+    #[test]
+    fn load_jpeg_fixture() {
+
+        let img = load_image(&get_relative_path("tests/fixtures/image/rgb.jpg")).unwrap();
+
+        assert_eq!((img.width(), img.height()), (16, 16));
+        assert_eq!(img.format(), PixelFormat::Rgb8);
+
+        // the jpeg is lossy, compare with some tolerance
+        let close = |a:&[u8], b:[u8;3]| a.iter().zip(b).all(|(a, b)| (*a as i32 - b as i32).abs() < 8);
+
+        assert!(close(img.pixel(2, 2), [200, 40, 40]));
+        assert!(close(img.pixel(13, 2), [40, 40, 200]));
+        assert!(close(img.pixel(5, 13), [40, 200, 40]));
+
+    }
+
+    #[test]
+    fn load_png_color_types() {
+
+        let gray = |x:u32, y:u32| (x * 60 + y * 10) as u8;
+
+        let l8 = load_image(&get_relative_path("tests/fixtures/image/gray8.png")).unwrap();
+        let la8 = load_image(&get_relative_path("tests/fixtures/image/graya8.png")).unwrap();
+        let rgba16 = load_image(&get_relative_path("tests/fixtures/image/rgba16.png")).unwrap();
+
+        assert_eq!(l8.format(), PixelFormat::L8);
+        assert_eq!(la8.format(), PixelFormat::La8);
+        assert_eq!(rgba16.format(), PixelFormat::Rgba16);
+
+        for y in 0..3 {
+            for x in 0..4 {
+
+                let alpha = fixture_pixel(x, y)[3];
+
+                assert_eq!(l8.pixel(x, y), &[gray(x, y)]);
+                assert_eq!(la8.pixel(x, y), &[gray(x, y), alpha]);
+                assert_eq!(la8.to_rgba8().pixel(x, y), &[gray(x, y), gray(x, y), gray(x, y), alpha]);
+
+                // the low byte of the 16 bits fixture encode the position of the pixel
+                let px = rgba16.pixel(x, y);
+                let red = u16::from_ne_bytes([px[0], px[1]]);
+                assert_eq!(red, (fixture_pixel(x, y)[0] as u16) << 8 | (x * 16 + y) as u16);
+                assert_eq!(rgba16.to_rgba8().pixel(x, y), &fixture_pixel(x, y));
+
+            }
+        }
+
+        // 4 colors palette where the first 3 entries are made transparent by a tRNS chunk
+        let palette = [[255, 0, 0, 0], [0, 255, 0, 128], [0, 0, 255, 255], [255, 255, 255, 255]];
+
+        let indexed = load_image(&get_relative_path("tests/fixtures/image/palette.png")).unwrap();
+
+        assert_eq!(indexed.format(), PixelFormat::Rgba8);
+        assert_eq!(indexed.pixel(0, 0), &palette[0]);
+        assert_eq!(indexed.pixel(1, 0), &palette[1]);
+        assert_eq!(indexed.pixel(3, 2), &palette[1]);
+        assert_eq!(indexed.pixel(3, 0), &palette[3]);
+
+    }
+
+    #[test]
+    fn load_jpeg_gray_and_cmyk() {
+
+        let close = |a:&[u8], b:&[u8]| a.iter().zip(b).all(|(a, b)| (*a as i32 - *b as i32).abs() < 8);
+
+        let gray = load_image(&get_relative_path("tests/fixtures/image/gray.jpg")).unwrap();
+
+        assert_eq!(gray.format(), PixelFormat::L8);
+        assert!(close(gray.pixel(2, 5), &[50]));
+        assert!(close(gray.pixel(13, 5), &[200]));
+
+        let cmyk = load_image(&get_relative_path("tests/fixtures/image/cmyk.jpg")).unwrap();
+
+        assert_eq!(cmyk.format(), PixelFormat::Rgb8);
+        assert!(close(cmyk.pixel(2, 5), &[255, 0, 0]));
+        assert!(close(cmyk.pixel(13, 5), &[127, 127, 127]));
+
+        let rgba = load_image_rgba8(&get_relative_path("tests/fixtures/image/cmyk.jpg")).unwrap();
+
+        assert_eq!(rgba.format(), PixelFormat::Rgba8);
+        assert_eq!(rgba.stride(), 16 * 4);
+        assert!(close(rgba.pixel(2, 5), &[255, 0, 0, 255]));
+
+    }
+
+    #[test]
+    fn load_apng_frames() {
+
+        let anim = load_animated_image(&get_relative_path("tests/fixtures/image/animated.png")).unwrap();
+
+        const RED:[u8;4] = [255, 0, 0, 255];
+        const GREEN:[u8;4] = [0, 255, 0, 255];
+
+        assert_eq!((anim.width(), anim.height()), (4, 4));
+        assert_eq!(anim.frame_count(), 4);
+        assert_eq!(anim.plays(), 0);
+
+        let delays:Vec<u64> = anim.frames().iter().map(|f| f.delay().as_millis() as u64).collect();
+        assert_eq!(delays, vec![100, 200, 30, 100]);
+
+        let frame = |i:usize| anim.frames()[i].image();
+
+        assert!(anim.frames().iter().all(|f| f.image().format() == PixelFormat::Rgba8));
+        assert_eq!(frame(0).pixel(2, 2), &RED);
+
+        // half transparent blue blended over the red
+        assert_eq!(frame(1).pixel(1, 1), &[127, 0, 128, 255]);
+        assert_eq!(frame(1).pixel(0, 0), &RED);
+
+        // the second frame is disposed to the previous canvas
+        assert_eq!(frame(2).pixel(1, 1), &GREEN);
+        assert_eq!(frame(2).pixel(2, 2), &RED);
+
+        // the third frame is disposed to the background, the last frame blend nothing
+        assert_eq!(frame(3).pixel(1, 1), &[0, 0, 0, 0]);
+        assert_eq!(frame(3).pixel(3, 3), &RED);
+
+        assert_eq!(anim.frame_at(Duration::from_millis(150)), &anim.frames()[1]);
+        assert_eq!(anim.frame_at(Duration::from_millis(335)), &anim.frames()[3]);
+        // loop forever
+        assert_eq!(anim.frame_at(Duration::from_millis(480)), &anim.frames()[0]);
+
+    }
+
+    #[test]
+    fn load_gif_frames() {
+
+        let anim = load_animated_image(&get_relative_path("tests/fixtures/image/animated.gif")).unwrap();
+
+        const RED:[u8;4] = [255, 0, 0, 255];
+        const GREEN:[u8;4] = [0, 255, 0, 255];
+
+        assert_eq!(anim.frame_count(), 3);
+        assert_eq!(anim.total_duration(), Duration::from_millis(350));
+
+        let frame = |i:usize| anim.frames()[i].image();
+
+        // the transparent index keep the pixels of the previous frame
+        assert_eq!(frame(1).pixel(2, 2), &GREEN);
+        assert_eq!(frame(1).pixel(3, 2), &RED);
+        assert_eq!(frame(1).pixel(3, 3), &GREEN);
+
+        assert_eq!(frame(2).pixel(0, 0), &[0, 0, 255, 255]);
+        assert_eq!(frame(2).pixel(3, 3), &[0, 0, 0, 0]);
+        assert_eq!(frame(2).pixel(1, 1), &RED);
+
+        let first = load_image(&get_relative_path("tests/fixtures/image/animated.gif")).unwrap();
+        assert_eq!(&first, frame(0));
+
+        // an image that can't be animated give a single frame
+        let still = load_animated_image(&get_relative_path("tests/fixtures/image/rgb8.png")).unwrap();
+        assert_eq!(still.frame_count(), 1);
+        assert_eq!(&still.frame_at(Duration::from_secs(10)).image().pixel(1, 1)[..3], &fixture_pixel(1, 1)[..3]);
+
+    }
+
+    #[test]
+    fn load_bmp_fixtures() {
+
+        let rgb = load_image(&get_relative_path("tests/fixtures/image/rgb24.bmp")).unwrap();
+        let rgba = load_image(&get_relative_path("tests/fixtures/image/rgba32.bmp")).unwrap();
+
+        assert_eq!(rgb.format(), PixelFormat::Rgb8);
+        assert_eq!(rgba.format(), PixelFormat::Rgba8);
+
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(rgb.pixel(x, y), &fixture_pixel(x, y)[..3]);
+                assert_eq!(rgba.pixel(x, y), &fixture_pixel(x, y)[..]);
+            }
+        }
+
+        // 4 bits palette with padded rows
+        let palette = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+        let indexed = load_image(&get_relative_path("tests/fixtures/image/indexed4.bmp")).unwrap();
+
+        assert_eq!((indexed.width(), indexed.height()), (3, 2));
+
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(indexed.pixel(x, y), &palette[((x + y) % 4) as usize]);
+            }
+        }
+
+    }
+
+    #[test]
+    fn load_tga_fixtures() {
+
+        let rle = load_image(&get_relative_path("tests/fixtures/image/rle.tga")).unwrap();
+
+        assert_eq!(rle.format(), PixelFormat::Rgba8);
+        assert_eq!(rle.pixel(2, 0), &[10, 20, 30, 40]);
+        assert_eq!(rle.pixel(3, 0), &[50, 60, 70, 80]);
+        assert_eq!(rle.pixel(1, 1), &[11, 22, 33, 44]);
+        assert_eq!(rle.pixel(3, 1), &[200, 100, 50, 255]);
+        assert_eq!(rle.pixel(3, 2), &[9, 8, 7, 6]);
+
+        let path = get_relative_path("tests/fixtures/image/gray.tga");
+        let gray = load_image(&path).unwrap();
+
+        assert_eq!(gray.format(), PixelFormat::L8);
+        assert_eq!(gray.pixel(0, 0), &[0]);
+        assert_eq!(gray.pixel(3, 2), &[200]);
+
+        // a TGA 2.0 file is found by its footer
+        let bytes = std::fs::read(path).unwrap();
+        assert_eq!(ImageFormat::from_magic(&bytes), Some(ImageFormat::Tga));
+        assert_eq!(decode_image(&bytes, "bin").unwrap(), gray);
+
+    }
+
+    #[test]
+    fn load_qoi_fixture() {
+
+        let q = |x:u32, y:u32| -> [u8;4] {
+            match (y, x) {
+                (0, x) => [(x * 2) as u8, (100 + x * 3) as u8, (50 - x) as u8, if x < 6 { 255 } else { 200 }],
+                (1, x) if x < 4 => [(10 + x) as u8, (10 + x) as u8, (10 - x) as u8, 255],
+                (1, _) => [9, 9, 9, 255],
+                (_, x) if x % 2 == 0 => [0, 100, 50, 255],
+                _ => [10, 10, 10, 255]
+            }
+        };
+
+        let img = load_image(&get_relative_path("tests/fixtures/image/image.qoi")).unwrap();
+
+        assert_eq!((img.width(), img.height()), (8, 3));
+        assert_eq!(img.format(), PixelFormat::Rgba8);
+
+        for y in 0..3 {
+            for x in 0..8 {
+                assert_eq!(img.pixel(x, y), &q(x, y));
+            }
+        }
+
+    }
+
+    #[test]
+    fn load_hdr_fixture() {
+
+        let img = load_image(&get_relative_path("tests/fixtures/image/image.hdr")).unwrap();
+
+        assert_eq!((img.width(), img.height()), (8, 2));
+        assert_eq!(img.format(), PixelFormat::Rgb32F);
+
+        let rgb = |x:u32, y:u32| -> Vec<f32> {
+            img.pixel(x, y)
+                .chunks_exact(4)
+                .map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                .collect()
+        };
+
+        assert_eq!(rgb(0, 0), vec![1.0, 0.5, 0.25]);
+        assert_eq!(rgb(7, 0), vec![4.0, 2.0, 0.0]);
+        assert_eq!(rgb(5, 1), vec![5.0, 0.0, 10.0]);
+
+    }
+
+    #[test]
+    fn detect_format_by_magic() {
+
+        let png = std::fs::read(get_relative_path("tests/fixtures/image/rgb8.png")).unwrap();
+
+        assert_eq!(ImageFormat::from_magic(&png), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_magic(b"qoif...."), Some(ImageFormat::Qoi));
+        assert_eq!(ImageFormat::from_magic(b"#?RGBE\n"), Some(ImageFormat::Hdr));
+        assert_eq!(ImageFormat::from_magic(b"not an image"), None);
+
+        // the signature win over a wrong extension
+        assert_eq!(decode_image(&png, "jpg").unwrap().format(), PixelFormat::Rgb8);
+
+    }
+
+    #[test]
+    fn encode_png_round_trip() {
+
+        for name in ["rgb8.png", "rgba16.png", "gray8.png", "graya8.png"] {
+
+            let img = load_image(&get_relative_path(&format!("tests/fixtures/image/{}",name))).unwrap();
+            let decoded = decode_image(&encode_png(&img).unwrap(), "png").unwrap();
+
+            assert_eq!(decoded.format(), img.format());
+            assert!((0..img.height()).all(|y| decoded.row(y) == img.row(y)));
+
+        }
+
+        // no png equivalent, saved as rgba8
+        let hdr = load_image(&get_relative_path("tests/fixtures/image/image.hdr")).unwrap();
+        let fp = tmp_path("image", "save.png");
+        let fp = fp.as_str();
+
+        save_png(&hdr, fp).unwrap();
+
+        let saved = load_image(fp).unwrap();
+        assert_eq!(saved.format(), PixelFormat::Rgba8);
+        assert_eq!(saved.data(), hdr.to_rgba8().data());
+
+        // without extension the signature is enough
+        let bare = fp.trim_end_matches(".png");
+        std::fs::rename(fp, bare).unwrap();
+
+        assert_eq!(load_image(bare).unwrap(), saved);
+        assert_eq!(load_animated_image(bare).unwrap().frames().len(), 1);
+
+        std::fs::remove_file(bare).unwrap();
+
+    }
+
+    #[test]
+    fn invalid_image() {
+
+        assert!(Image::new(2, 2, PixelFormat::Rgb8, vec![0; 11]).is_err());
+        assert!(decode_image(b"not an image", "png").is_err());
+        assert!(decode_image(b"not an image", "txt").is_err());
+
+    }
+
+    #[test]
+    fn oversized_headers() {
+
+        // headers that claim 0xFFFFFFFF x 0xFFFFFFFF pixels with nearly no data
+        let mut qoi = b"qoif".to_vec();
+        qoi.extend_from_slice(&[0xFF;8]);
+        qoi.extend_from_slice(&[4, 0, 0, 0, 0, 0, 0, 0]);
+
+        let hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 4294967295 +X 4294967295\n\x02\x02".to_vec();
+
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&[0;8]);
+        bmp.extend_from_slice(&54_u32.to_le_bytes());
+        bmp.extend_from_slice(&40_u32.to_le_bytes());
+        bmp.extend_from_slice(&i32::MAX.to_le_bytes());
+        bmp.extend_from_slice(&i32::MAX.to_le_bytes());
+        bmp.extend_from_slice(&1_u16.to_le_bytes());
+        bmp.extend_from_slice(&24_u16.to_le_bytes());
+        bmp.extend_from_slice(&[0;24]);
+
+        assert!(decode_image(&qoi, "qoi").is_err());
+        assert!(decode_image(&hdr, "hdr").is_err());
+        assert!(decode_image(&bmp, "bmp").is_err());
+
+        // a run length encoded tga of 65535x65535 pixels with a single packet
+        let mut tga = vec![0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 32, 8];
+        tga.extend_from_slice(&[0xFF, 1, 2, 3, 4]);
+
+        assert!(decode_image(&tga, "tga").is_err());
+        assert!(decode_image(&tga[..12], "tga").is_err());
+
+        // an apng that claim u32::MAX frames but has 4
+        let mut apng = std::fs::read(get_relative_path("tests/fixtures/image/animated.png")).unwrap();
+        let actl = apng.windows(4).position(|w| w == b"acTL").unwrap();
+
+        apng[actl + 4..actl + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+        let crc = crc32fast::hash(&apng[actl..actl + 12]);
+        apng[actl + 12..actl + 16].copy_from_slice(&crc.to_be_bytes());
+
+        assert!(decode_animated_image(&apng, "png").is_err());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Pixel format
+//
+/// Layout of a pixel. Samples of 16 bits are stored in native endian and the float ones as f32.
+/// The L formats are luminance (grayscale), a single value used for the red, green and blue
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum PixelFormat {
+    L8,
+    La8,
+    L16,
+    La16,
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    R16,
+    Rg16,
+    Rgb16,
+    Rgba16,
+    Rgb32F,
+    Rgba32F
+}
+//
+impl PixelFormat {
+    //
+    /// number of channels of a pixel
+    pub fn channels(&self) -> usize {
+
+        match self {
+            Self::L8 | Self::L16 | Self::R8 | Self::R16 => 1,
+            Self::La8 | Self::La16 | Self::Rg8 | Self::Rg16 => 2,
+            Self::Rgb8 | Self::Rgb16 | Self::Rgb32F => 3,
+            Self::Rgba8 | Self::Rgba16 | Self::Rgba32F => 4
+        }
+
+    }
+    //
+    /// size in bytes of one channel
+    pub fn bytes_per_channel(&self) -> usize {
+
+        match self {
+            Self::L8 | Self::La8 | Self::R8 | Self::Rg8 | Self::Rgb8 | Self::Rgba8 => 1,
+            Self::L16 | Self::La16 | Self::R16 | Self::Rg16 | Self::Rgb16 | Self::Rgba16 => 2,
+            Self::Rgb32F | Self::Rgba32F => 4
+        }
+
+    }
+    //
+    /// size in bytes of one pixel
+    pub fn bytes_per_pixel(&self) -> usize { self.channels() * self.bytes_per_channel() }
+    //
+    /// true if the last channel is an alpha channel
+    pub fn has_alpha(&self) -> bool {
+
+        matches!(self, Self::La8 | Self::La16 | Self::Rgba8 | Self::Rgba16 | Self::Rgba32F)
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Image
+//
+/// A decoded image. Rows are stored top to bottom and start every 'stride' bytes
+#[derive(Debug,Clone,PartialEq)]
+pub struct Image {
+
+    width:      u32,
+    height:     u32,
+    format:     PixelFormat,
+    stride:     usize,
+    data:       Vec<u8>
+
+}
+//
+impl Image {
+    //
+    /// Create an image where the rows are tightly packed
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - width in pixels
+    /// * 'height' - height in pixels
+    /// * 'format' - the layout of a pixel
+    /// * 'data' - the pixels, row after row
+    ///
+    pub fn new(width:u32, height:u32, format:PixelFormat, data:Vec<u8>) -> Result<Self,EImage> {
+
+        let stride = width as usize * format.bytes_per_pixel();
+
+        Self::with_stride(width, height, format, stride, data)
+
+    }
+    //
+    /// Create an image where the rows may be padded
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - width in pixels
+    /// * 'height' - height in pixels
+    /// * 'format' - the layout of a pixel
+    /// * 'stride' - number of bytes between the start of two rows
+    /// * 'data' - the pixels, row after row
+    ///
+    pub fn with_stride(
+        width:u32,
+        height:u32,
+        format:PixelFormat,
+        stride:usize,
+        data:Vec<u8>) -> Result<Self,EImage> {
+
+        let row_size = width as usize * format.bytes_per_pixel();
+
+        if stride < row_size {
+
+            return Err(EImage::Layout
+                .as_report()
+                .attach_printable(format!(
+                    "a stride of {} bytes is too small for {} pixels of {:?}",
+                    stride,
+                    width,
+                    format
+                    )
+                )
+            );
+
+        }
+
+        // the last row doesn't need its padding
+        let needed = match height {
+            0 => 0,
+            h => stride * (h as usize - 1) + row_size
+        };
+
+        if data.len() < needed {
+
+            return Err(EImage::Layout
+                .as_report()
+                .attach_printable(format!(
+                    "{} bytes are not enough for a {}x{} {:?} image, {} are needed",
+                    data.len(),
+                    width,
+                    height,
+                    format,
+                    needed
+                    )
+                )
+            );
+
+        }
+
+        Ok(Self { width, height, format, stride, data })
+
+    }
+    //
+    pub fn width(&self) -> u32 { self.width }
+    //
+    pub fn height(&self) -> u32 { self.height }
+    //
+    pub fn format(&self) -> PixelFormat { self.format }
+    //
+    /// number of bytes between the start of two rows
+    pub fn stride(&self) -> usize { self.stride }
+    //
+    /// the raw bytes of the image, padding included
+    pub fn data(&self) -> &[u8] { &self.data }
+    //
+    pub fn into_data(self) -> Vec<u8> { self.data }
+    //
+    /// the pixels of a row, without the padding
+    ///
+    /// # Parameters
+    ///
+    /// * 'y' - the row, 0 is the top of the image
+    ///
+    pub fn row(&self, y:u32) -> &[u8] {
+
+        let start = y as usize * self.stride;
+
+        &self.data[start..start + self.width as usize * self.format.bytes_per_pixel()]
+
+    }
+    //
+    /// the bytes of a single pixel
+    ///
+    /// # Parameters
+    ///
+    /// * 'x' - the column, 0 is the left of the image
+    /// * 'y' - the row, 0 is the top of the image
+    ///
+    pub fn pixel(&self, x:u32, y:u32) -> &[u8] {
+
+        let size = self.format.bytes_per_pixel();
+        let start = y as usize * self.stride + x as usize * size;
+
+        &self.data[start..start + size]
+
+    }
+    //
+    /// Convert the image to tightly packed 8 bits RGBA, the layout expected for a gpu upload.
+    /// 16 bits samples keep their most significant byte, float ones are clamped to [0,1] and
+    /// luminance is copied in the three color channels
+    pub fn to_rgba8(&self) -> Image {
+
+        let mut data = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+
+        let channels = self.format.channels();
+        let bpc = self.format.bytes_per_channel();
+
+        let sample = |px:&[u8], c:usize| -> u8 {
+
+            let bytes = &px[c * bpc..(c + 1) * bpc];
+
+            match bpc {
+                1 => bytes[0],
+                2 => (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+                _ => {
+                    let v = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+                }
+            }
+
+        };
+
+        let luminance = matches!(
+            self.format,
+            PixelFormat::L8 | PixelFormat::La8 | PixelFormat::L16 | PixelFormat::La16
+        );
+
+        for y in 0..self.height {
+            for px in self.row(y).chunks_exact(self.format.bytes_per_pixel()) {
+
+                let alpha = match self.format.has_alpha() {
+                    true => sample(px, channels - 1),
+                    false => 255
+                };
+
+                let rgb = match (luminance, channels) {
+                    (true, _) => [sample(px, 0); 3],
+                    (false, 1) => [sample(px, 0), 0, 0],
+                    (false, 2) => [sample(px, 0), sample(px, 1), 0],
+                    _ => [sample(px, 0), sample(px, 1), sample(px, 2)]
+                };
+
+                data.extend_from_slice(&[rgb[0], rgb[1], rgb[2], alpha]);
+
+            }
+        }
+
+        Image {
+            width:  self.width,
+            height: self.height,
+            format: PixelFormat::Rgba8,
+            stride: self.width as usize * 4,
+            data
+        }
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Image format
+//
+/// The encodings that can be decoded
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Bmp,
+    Tga,
+    Qoi,
+    Hdr
+}
+//
+impl ImageFormat {
+    //
+    /// Find the format of an encoded image with its first bytes. TGA files have no signature at
+    /// the start, only the TGA 2.0 ones can be found with the footer at their end
+    ///
+    /// # Parameters
+    ///
+    /// * 'bytes' - the encoded image
+    ///
+    pub fn from_magic(bytes:&[u8]) -> Option<Self> {
+
+        match bytes {
+            [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => Some(Self::Png),
+            [0xff, 0xd8, 0xff, ..] => Some(Self::Jpeg),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(Self::Gif),
+            [b'B', b'M', ..] => Some(Self::Bmp),
+            b if b.starts_with(qoi::MAGIC) => Some(Self::Qoi),
+            b if hdr::MAGICS.iter().any(|m| b.starts_with(m)) => Some(Self::Hdr),
+            b if b.ends_with(tga::FOOTER_SIGNATURE) => Some(Self::Tga),
+            _ => None
+        }
+
+    }
+    //
+    /// Find the format of an image with the extension of its file
+    ///
+    /// # Parameters
+    ///
+    /// * 'ext' - the extension, in lower case
+    ///
+    pub fn from_extension(ext:&str) -> Option<Self> {
+
+        match ext {
+            "png" | "apng" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "gif" => Some(Self::Gif),
+            "bmp" | "dib" => Some(Self::Bmp),
+            "tga" | "icb" | "vda" | "vst" => Some(Self::Tga),
+            "qoi" => Some(Self::Qoi),
+            "hdr" | "pic" => Some(Self::Hdr),
+            _ => None
+        }
+
+    }
+    //
+}
+//
+//
+/// the format of an image, its signature win over the extension of its file
+///
+/// # Parameters
+///
+/// * 'bytes' - the encoded image
+/// * 'ext' - the extension of the file the image come from
+///
+fn detect_format(bytes:&[u8], ext:&str) -> Result<ImageFormat,EImage> {
+
+    ImageFormat::from_magic(bytes)
+        .or_else(|| ImageFormat::from_extension(ext))
+        .ok_or_else(||
+            EImage::Unsupported
+                .as_report()
+                .attach_printable(format!("{} is not a valid extension for image",ext))
+        )
+
+}
+//
+//
+/// the lowercase extension of a file, empty when it has none so its signature decide the format
+fn file_extension(fp:&str) -> String {
+
+    Path::new(fp)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+
+}
+//
+//
+/// The product of sizes read from the header of a file, error when it overflow
+///
+/// # Parameters
+///
+/// * 'factors' - the sizes to multiply
+/// * 'what' - what is measured, for the error
+///
+pub(crate) fn checked_size(factors:&[usize], what:&str) -> Result<usize,EImage> {
+
+    factors
+        .iter()
+        .try_fold(1_usize, |size, f| size.checked_mul(*f))
+        .ok_or_else(||
+            EImage::Decode
+                .as_report()
+                .attach_printable(format!("the {} of {:?} overflow",what,factors))
+        )
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load an image from the disk, the decoder is chosen with the signature of the file or its
+/// extension when there is none
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the image
+///
+pub fn load_image(fp:&str) -> Result<Image,EImage> {
+
+    let ext = file_extension(fp);
+
+    let content = get_file_content(fp)
+        .change_context(EImage::Load)
+        .attach_printable_lazy(|| format!("Can't load image {}",fp))?;
+
+    decode_image(&content, &ext).attach_printable_lazy(|| format!("Can't load image {}",fp))
+
+}
+//
+//
+/// load an image from the disk and normalize it to 8 bits RGBA
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the image
+///
+pub fn load_image_rgba8(fp:&str) -> Result<Image,EImage> {
+
+    let img = load_image(fp)?;
+
+    match img.format() {
+        PixelFormat::Rgba8 => Ok(img),
+        _ => Ok(img.to_rgba8())
+    }
+
+}
+//
+//
+/// decode an image already in memory
+///
+/// # Parameters
+///
+/// * 'bytes' - the encoded image
+/// * 'ext' - the extension of the file the image come from
+///
+pub fn decode_image(bytes:&[u8], ext:&str) -> Result<Image,EImage> {
+
+    match detect_format(bytes, ext)? {
+        ImageFormat::Png => png::decode(bytes),
+        ImageFormat::Jpeg => jpeg::decode(bytes),
+        ImageFormat::Gif => gif::decode(bytes),
+        ImageFormat::Bmp => bmp::decode(bytes),
+        ImageFormat::Tga => tga::decode(bytes),
+        ImageFormat::Qoi => qoi::decode(bytes),
+        ImageFormat::Hdr => hdr::decode(bytes)
+    }
+
+}
+//
+//
+/// load all the frames of an animated image (APNG or GIF), the other images give a single frame
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the image
+///
+pub fn load_animated_image(fp:&str) -> Result<AnimatedImage,EImage> {
+
+    let ext = file_extension(fp);
+
+    let content = get_file_content(fp)
+        .change_context(EImage::Load)
+        .attach_printable_lazy(|| format!("Can't load image {}",fp))?;
+
+    decode_animated_image(&content, &ext).attach_printable_lazy(|| format!("Can't load image {}",fp))
+
+}
+//
+//
+/// decode all the frames of an animated image already in memory
+///
+/// # Parameters
+///
+/// * 'bytes' - the encoded image
+/// * 'ext' - the extension of the file the image come from
+///
+pub fn decode_animated_image(bytes:&[u8], ext:&str) -> Result<AnimatedImage,EImage> {
+
+    match detect_format(bytes, ext)? {
+        ImageFormat::Png => png::decode_animated(bytes),
+        ImageFormat::Gif => gif::decode_animated(bytes),
+        _ => decode_image(bytes, ext).map(AnimatedImage::from_image)
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Saving
+//
+/// encode an image to png in memory
+///
+/// # Parameters
+///
+/// * 'image' - the image to encode
+///
+pub fn encode_png(image:&Image) -> Result<Vec<u8>,EImage> { png::encode(image) }
+//
+//
+/// Save an image to the disk as a png, the file is replaced atomically so a crash never leave a
+/// half written screenshot
+///
+/// # Parameters
+///
+/// * 'image' - the image to save
+/// * 'fp' - A file path to the new png
+///
+pub fn save_png(image:&Image, fp:&str) -> Result<(),EImage> {
+
+    let bytes = png::encode(image).attach_printable_lazy(|| format!("Can't save image {}",fp))?;
+
+    write_atomic(std::path::Path::new(fp), &bytes)
+        .change_context(EImage::Encode)
+        .attach_printable_lazy(|| format!("Can't save image {}",fp))
+
+}
+//
+//
@@ -0,0 +1,593 @@
+// CPU side operations on the engine image type. Every operation return a new tightly packed image
+// of the same pixel format unless said otherwise
+
+use std::f32::consts::PI;
+
+use super::{Image, PixelFormat};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    // 4x2 Rgba8 image where the pixel (x,y) is (x*60, y*200, 10, 255 - x*50)
+    fn sample_image() -> Image {
+
+        let data = (0..2u32)
+            .flat_map(|y| (0..4u32).flat_map(move |x| [(x * 60) as u8, (y * 200) as u8, 10, (255 - x * 50) as u8]))
+            .collect();
+
+        Image::new(4, 2, PixelFormat::Rgba8, data).unwrap()
+
+    }
+
+    #[test]
+    fn flip_crop_swizzle() {
+
+        let img = sample_image();
+
+        let flipped = img.flip_vertical();
+        assert_eq!(flipped.row(0), img.row(1));
+        assert_eq!(flipped.row(1), img.row(0));
+
+        let cropped = img.crop(1, 1, 2, 1).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (2, 1));
+        assert_eq!(cropped.pixel(0, 0), img.pixel(1, 1));
+        assert!(img.crop(3, 0, 2, 1).is_err());
+
+        let bgra = img.swizzle(&[2, 1, 0, 3]).unwrap();
+        assert_eq!(bgra.pixel(2, 1), &[10, 200, 120, 155]);
+        assert!(img.swizzle(&[0, 1, 4, 3]).is_err());
+
+    }
+
+    #[test]
+    fn resize_filters() {
+
+        let img = sample_image();
+
+        let nearest = img.resize(8, 4, Filter::Nearest).unwrap();
+        assert_eq!(nearest.pixel(3, 3), img.pixel(1, 1));
+        assert_eq!(nearest.pixel(6, 0), img.pixel(3, 0));
+
+        // a plain image stay plain with every filter
+        let plain = Image::new(5, 3, PixelFormat::Rgb8, [90, 20, 200].repeat(15)).unwrap();
+
+        for filter in [Filter::Nearest, Filter::Bilinear, Filter::Lanczos3] {
+            for (w, h) in [(2, 2), (11, 7)] {
+                let resized = plain.resize(w, h, filter).unwrap();
+                assert_eq!((resized.width(), resized.height()), (w, h));
+                assert!(resized.data().chunks_exact(3).all(|px| px == [90, 20, 200]));
+            }
+        }
+
+        // half way between two pixels
+        let line = Image::new(2, 1, PixelFormat::L8, vec![0, 200]).unwrap();
+        let wide = line.resize(4, 1, Filter::Bilinear).unwrap();
+        assert_eq!(wide.data(), &[0, 50, 150, 200]);
+
+    }
+
+    #[test]
+    fn color_conversions() {
+
+        let img = sample_image();
+
+        let linear = img.srgb_to_linear();
+        // the alpha is not touched
+        assert_eq!(linear.pixel(1, 0)[3], 205);
+        assert_eq!(linear.pixel(2, 0)[0], 48);
+
+        let float = Image::from_samples(1, 1, PixelFormat::Rgb32F, &[0.02, 0.5, 0.9]).unwrap();
+        let round_trip = float.srgb_to_linear().linear_to_srgb().to_samples();
+        assert!(round_trip.iter().zip([0.02, 0.5, 0.9]).all(|(a, b)| (a - b).abs() < 1e-5));
+
+        let premultiplied = img.premultiply_alpha();
+        assert_eq!(premultiplied.pixel(2, 1), &[73, 122, 6, 155]);
+
+    }
+
+    #[test]
+    fn mip_chain() {
+
+        let img = Image::new(5, 2, PixelFormat::L8, vec![0, 100, 20, 40, 255, 200, 100, 60, 80, 255]).unwrap();
+
+        let mips = img.mip_chain();
+        let sizes:Vec<(u32, u32)> = mips.iter().map(|m| (m.width(), m.height())).collect();
+
+        assert_eq!(sizes, vec![(5, 2), (2, 1), (1, 1)]);
+        assert_eq!(mips[1].data(), &[88, 134]);
+        assert_eq!(mips[2].data(), &[111]);
+
+        // the last column of an odd width is part of the mips
+        let mut edge = vec![0; 25];
+        edge.iter_mut().skip(4).step_by(5).for_each(|v| *v = 255);
+
+        let mips = Image::new(5, 5, PixelFormat::L8, edge).unwrap().mip_chain();
+
+        assert_eq!((mips[1].width(), mips[1].height()), (2, 2));
+        assert_eq!(mips[1].data(), &[0, 102, 0, 102]);
+        assert_eq!(mips[2].data(), &[51]);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Samples
+//
+impl Image {
+    //
+    /// the samples of every pixel as float, the integer formats are normalized to [0,1]
+    pub(crate) fn to_samples(&self) -> Vec<f32> {
+
+        let bpc = self.format().bytes_per_channel();
+        let mut samples = Vec::with_capacity(self.width() as usize * self.height() as usize * self.format().channels());
+
+        for y in 0..self.height() {
+            for s in self.row(y).chunks_exact(bpc) {
+
+                samples.push(match bpc {
+                    1 => s[0] as f32 / 255.0,
+                    2 => u16::from_ne_bytes([s[0], s[1]]) as f32 / 65535.0,
+                    _ => f32::from_ne_bytes([s[0], s[1], s[2], s[3]])
+                });
+
+            }
+        }
+
+        samples
+
+    }
+    //
+    /// Build an image from float samples, the integer formats are clamped to [0,1]
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - width in pixels
+    /// * 'height' - height in pixels
+    /// * 'format' - the layout of a pixel
+    /// * 'samples' - every sample of the image, row after row
+    ///
+    pub(crate) fn from_samples(width:u32, height:u32, format:PixelFormat, samples:&[f32]) -> Result<Image,EImage> {
+
+        let mut data = Vec::with_capacity(samples.len() * format.bytes_per_channel());
+
+        for s in samples {
+
+            match format.bytes_per_channel() {
+                1 => data.push((s.clamp(0.0, 1.0) * 255.0 + 0.5) as u8),
+                2 => data.extend_from_slice(&((s.clamp(0.0, 1.0) * 65535.0 + 0.5) as u16).to_ne_bytes()),
+                _ => data.extend_from_slice(&s.to_ne_bytes())
+            }
+
+        }
+
+        Image::new(width, height, format, data)
+
+    }
+    //
+    /// index of the color channels, the alpha is left out
+    fn color_channels(&self) -> usize {
+
+        match self.format().has_alpha() {
+            true => self.format().channels() - 1,
+            false => self.format().channels()
+        }
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Geometry
+//
+impl Image {
+    //
+    /// the image upside down, used to go from the gpu origin to the file one
+    pub fn flip_vertical(&self) -> Image {
+
+        let data = (0..self.height()).rev().flat_map(|y| self.row(y).iter().copied()).collect();
+
+        Image::new(self.width(), self.height(), self.format(), data).expect("same size as the image")
+
+    }
+    //
+    /// A region of the image
+    ///
+    /// # Parameters
+    ///
+    /// * 'x' - the first column of the region
+    /// * 'y' - the first row of the region
+    /// * 'width' - width of the region
+    /// * 'height' - height of the region
+    ///
+    pub fn crop(&self, x:u32, y:u32, width:u32, height:u32) -> Result<Image,EImage> {
+
+        let fits = x as u64 + width as u64 <= self.width() as u64 && y as u64 + height as u64 <= self.height() as u64;
+
+        if !fits {
+
+            return Err(EImage::Layout
+                .as_report()
+                .attach_printable(format!(
+                    "a {}x{} region at ({},{}) goes out of a {}x{} image",
+                    width,
+                    height,
+                    x,
+                    y,
+                    self.width(),
+                    self.height()
+                    )
+                )
+            );
+
+        }
+
+        let bpp = self.format().bytes_per_pixel();
+        let start = x as usize * bpp;
+        let end = start + width as usize * bpp;
+
+        let data = (y..y + height).flat_map(|row| self.row(row)[start..end].iter().copied()).collect();
+
+        Image::new(width, height, self.format(), data)
+
+    }
+    //
+    /// Reorder the channels of the pixels, channel i of the result is channel order[i] of the
+    /// image. [2, 1, 0, 3] swap RGBA and BGRA
+    ///
+    /// # Parameters
+    ///
+    /// * 'order' - the source channel of every channel
+    ///
+    pub fn swizzle(&self, order:&[usize]) -> Result<Image,EImage> {
+
+        let channels = self.format().channels();
+
+        if order.len() != channels || order.iter().any(|c| *c >= channels) {
+
+            return Err(EImage::Layout
+                .as_report()
+                .attach_printable(format!("{:?} is not a valid swizzle for {:?}",order,self.format()))
+            );
+
+        }
+
+        let bpc = self.format().bytes_per_channel();
+        let mut data = Vec::with_capacity(self.width() as usize * self.height() as usize * channels * bpc);
+
+        for y in 0..self.height() {
+            for px in self.row(y).chunks_exact(channels * bpc) {
+                for c in order {
+                    data.extend_from_slice(&px[c * bpc..(c + 1) * bpc]);
+                }
+            }
+        }
+
+        Image::new(self.width(), self.height(), self.format(), data)
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Resize
+//
+/// The filter used to resample an image
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Filter {
+    // the closest pixel, no blending
+    Nearest,
+    // linear interpolation between the two closest pixels
+    Bilinear,
+    // windowed sinc over 3 pixels on each side, the sharpest
+    Lanczos3
+}
+//
+impl Filter {
+    //
+    /// distance after which the filter has no weight
+    fn support(&self) -> f32 {
+
+        match self {
+            Self::Nearest => 0.5,
+            Self::Bilinear => 1.0,
+            Self::Lanczos3 => 3.0
+        }
+
+    }
+    //
+    /// weight of a pixel at a distance
+    fn weight(&self, x:f32) -> f32 {
+
+        let x = x.abs();
+
+        match self {
+
+            Self::Nearest => match x < 0.5 { true => 1.0, false => 0.0 },
+
+            Self::Bilinear => (1.0 - x).max(0.0),
+
+            Self::Lanczos3 => match x {
+                x if x < 1e-6 => 1.0,
+                x if x >= 3.0 => 0.0,
+                x => 3.0 * (PI * x).sin() * (PI * x / 3.0).sin() / (PI * PI * x * x)
+            }
+
+        }
+
+    }
+    //
+}
+//
+//
+/// The weights used to compute every destination sample of one axis
+struct Contributions {
+    // first source sample and its weights, for each destination sample
+    samples: Vec<(usize, Vec<f32>)>
+}
+//
+impl Contributions {
+    //
+    fn new(src:u32, dst:u32, filter:Filter) -> Self {
+
+        let scale = src as f32 / dst as f32;
+        // when shrinking, the filter is widened to cover all the source pixels
+        let stretch = scale.max(1.0);
+        let support = filter.support() * stretch;
+
+        let samples = (0..dst).map(|i| {
+
+            let center = (i as f32 + 0.5) * scale;
+
+            if filter == Filter::Nearest {
+                let nearest = (center as usize).min(src as usize - 1);
+                return (nearest, vec![1.0]);
+            }
+
+            let first = (center - support).floor().max(0.0) as usize;
+            let last = ((center + support).ceil() as usize).min(src as usize);
+
+            let mut weights:Vec<f32> = (first..last)
+                .map(|s| filter.weight((s as f32 + 0.5 - center) / stretch))
+                .collect();
+
+            let total:f32 = weights.iter().sum();
+
+            if total.abs() > f32::EPSILON {
+                weights.iter_mut().for_each(|w| *w /= total);
+            }
+
+            (first, weights)
+
+        }).collect();
+
+        Self { samples }
+
+    }
+    //
+}
+//
+impl Image {
+    //
+    /// Resample the image to a new size. The samples are filtered as they are stored, convert an
+    /// sRGB image to linear first for a correct blending
+    ///
+    /// # Parameters
+    ///
+    /// * 'width' - the new width
+    /// * 'height' - the new height
+    /// * 'filter' - how the new pixels are computed
+    ///
+    pub fn resize(&self, width:u32, height:u32, filter:Filter) -> Result<Image,EImage> {
+
+        if width == 0 || height == 0 || self.width() == 0 || self.height() == 0 {
+
+            return Err(EImage::Layout
+                .as_report()
+                .attach_printable(format!("can't resize a {}x{} image to {}x{}",self.width(),self.height(),width,height))
+            );
+
+        }
+
+        let channels = self.format().channels();
+        let src = self.to_samples();
+        let (src_w, src_h) = (self.width() as usize, self.height() as usize);
+
+        // horizontal pass
+        let columns = Contributions::new(self.width(), width, filter);
+        let mut horizontal = vec![0.0; width as usize * src_h * channels];
+
+        for y in 0..src_h {
+            for (x, (first, weights)) in columns.samples.iter().enumerate() {
+                for c in 0..channels {
+
+                    let sum:f32 = weights
+                        .iter()
+                        .enumerate()
+                        .map(|(i, w)| src[((y * src_w) + first + i) * channels + c] * w)
+                        .sum();
+
+                    horizontal[(y * width as usize + x) * channels + c] = sum;
+
+                }
+            }
+        }
+
+        // vertical pass
+        let rows = Contributions::new(self.height(), height, filter);
+        let mut samples = vec![0.0; width as usize * height as usize * channels];
+
+        for (y, (first, weights)) in rows.samples.iter().enumerate() {
+            for x in 0..width as usize {
+                for c in 0..channels {
+
+                    let sum:f32 = weights
+                        .iter()
+                        .enumerate()
+                        .map(|(i, w)| horizontal[((first + i) * width as usize + x) * channels + c] * w)
+                        .sum();
+
+                    samples[(y * width as usize + x) * channels + c] = sum;
+
+                }
+            }
+        }
+
+        Image::from_samples(width, height, self.format(), &samples)
+
+    }
+    //
+    /// Build the mip chain of the image with a box filter, from the image itself to 1x1. An odd
+    /// size is reduced with 3 pixels per mip pixel so the last row or column is not dropped
+    pub fn mip_chain(&self) -> Vec<Image> {
+
+        let mut chain = vec![self.clone()];
+        let channels = self.format().channels();
+
+        while let Some(last) = chain.last() {
+
+            let (w, h) = (last.width() as usize, last.height() as usize);
+
+            if w <= 1 && h <= 1 {
+                break;
+            }
+
+            let (nw, nh) = ((w / 2).max(1), (h / 2).max(1));
+            let src = last.to_samples();
+            let mut samples = Vec::with_capacity(nw * nh * channels);
+
+            for y in 0..nh {
+                for x in 0..nw {
+
+                    let (xs, ys) = (mip_taps(w, nw, x), mip_taps(h, nh, y));
+
+                    for c in 0..channels {
+
+                        let sum:f32 = ys
+                            .iter()
+                            .flat_map(|sy| xs.iter().map(move |sx| (sy, sx)))
+                            .map(|((sy, wy), (sx, wx))| src[(sy * w + sx) * channels + c] * wy * wx)
+                            .sum();
+
+                        samples.push(sum);
+
+                    }
+
+                }
+            }
+
+            let mip = Image::from_samples(nw as u32, nh as u32, self.format(), &samples)
+                .expect("the samples match the size of the mip");
+
+            chain.push(mip);
+
+        }
+
+        chain
+
+    }
+    //
+}
+//
+//
+/// The pixels of an axis that make a pixel of the next mip and their weight
+///
+/// # Parameters
+///
+/// * 'size' - the size of the axis
+/// * 'mip_size' - the size of the axis in the next mip
+/// * 'i' - the pixel of the next mip
+///
+fn mip_taps(size:usize, mip_size:usize, i:usize) -> Vec<(usize, f32)> {
+
+    match size {
+
+        // an axis of size 1 is not halved
+        1 => vec![(0, 1.0)],
+
+        _ if size.is_multiple_of(2) => vec![(2 * i, 0.5), (2 * i + 1, 0.5)],
+
+        // the outer pixels are shared by two mip pixels, the weights give every pixel of the
+        // axis the same part of the mip
+        _ => {
+            let (n, at) = (mip_size as f32, i as f32);
+            let total = 2.0 * n + 1.0;
+            vec![(2 * i, (n - at) / total), (2 * i + 1, n / total), (2 * i + 2, (at + 1.0) / total)]
+        }
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Color
+//
+/// sRGB transfer function, from encoded to linear
+fn srgb_to_linear(v:f32) -> f32 {
+
+    match v <= 0.04045 {
+        true => v / 12.92,
+        false => ((v + 0.055) / 1.055).powf(2.4)
+    }
+
+}
+//
+/// inverse of the sRGB transfer function, from linear to encoded
+fn linear_to_srgb(v:f32) -> f32 {
+
+    match v <= 0.0031308 {
+        true => v * 12.92,
+        false => 1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+
+}
+//
+impl Image {
+    //
+    /// apply a function on the color samples, the alpha is kept as is
+    fn map_colors(&self, f:impl Fn(f32, f32) -> f32) -> Image {
+
+        let channels = self.format().channels();
+        let colors = self.color_channels();
+        let mut samples = self.to_samples();
+
+        for px in samples.chunks_exact_mut(channels) {
+
+            let alpha = match colors < channels { true => px[channels - 1], false => 1.0 };
+
+            for c in px[..colors].iter_mut() {
+                *c = f(*c, alpha);
+            }
+
+        }
+
+        Image::from_samples(self.width(), self.height(), self.format(), &samples)
+            .expect("same size as the image")
+
+    }
+    //
+    /// convert the colors from sRGB to linear, the alpha is not changed
+    pub fn srgb_to_linear(&self) -> Image { self.map_colors(|v, _| srgb_to_linear(v.max(0.0))) }
+    //
+    /// convert the colors from linear to sRGB, the alpha is not changed
+    pub fn linear_to_srgb(&self) -> Image { self.map_colors(|v, _| linear_to_srgb(v.max(0.0))) }
+    //
+    /// multiply the colors by the alpha, an image without alpha is returned unchanged
+    pub fn premultiply_alpha(&self) -> Image { self.map_colors(|v, a| v * a) }
+    //
+}
+//
+//
@@ -0,0 +1,218 @@
+// PNG decoder, built on the png crate
+
+use std::time::Duration;
+
+use super::{Image, PixelFormat};
+use super::animation::{AnimatedImage, AnimationFrame, Blend, Compositor, Dispose};
+use crate::core::error_handler::EImage;
+use crate::core::logs::CWARN;
+
+use error_stack::Result;
+//
+//
+/// decode a png image, only the first frame of an APNG is taken
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a png file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    let mut decoder = ::png::Decoder::new(bytes);
+
+    // palettes become rgb(a), tRNS become an alpha channel and gray under 8 bits is scaled to 8
+    decoder.set_transformations(::png::Transformations::EXPAND);
+
+    let mut reader = decoder.read_info().map_err(|e| EImage::Decode.attach_printable_default(e))?;
+
+    // check if its an APNG file
+    if reader.info().animation_control.is_some() {
+
+        CWARN("only the first frame of an apng is decoded\nuse load_animated_image to get all of them");
+
+    }
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+
+    let output = reader
+        .next_frame(&mut buf)
+        .map_err(|e| EImage::Decode.attach_printable_default(e))?;
+
+    frame_image(&output, buf)
+
+}
+//
+//
+/// decode all the frames of an APNG, a png that is not animated give a single frame
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a png file
+///
+pub(crate) fn decode_animated(bytes:&[u8]) -> Result<AnimatedImage,EImage> {
+
+    let mut decoder = ::png::Decoder::new(bytes);
+
+    decoder.set_transformations(::png::Transformations::EXPAND);
+
+    let mut reader = decoder.read_info().map_err(|e| EImage::Decode.attach_printable_default(e))?;
+
+    let control = match reader.info().animation_control {
+        Some(control) => control,
+        None => return decode(bytes).map(AnimatedImage::from_image)
+    };
+
+    let (width, height) = (reader.info().width, reader.info().height);
+
+    let mut compositor = Compositor::new(width, height)?;
+    // the frame count of the acTL chunk is not trusted to reserve the frames
+    let mut frames = Vec::new();
+    let mut buf = vec![0; reader.output_buffer_size()];
+
+    while frames.len() < control.num_frames as usize {
+
+        let output = reader
+            .next_frame(&mut buf)
+            .map_err(|e| EImage::Decode.attach_printable_default(e))?;
+
+        // without a fcTL chunk before it, the default image is not part of the animation
+        let fctl = match reader.info().frame_control {
+            Some(fctl) => fctl,
+            None => continue
+        };
+
+        let image = frame_image(&output, buf[..output.buffer_size()].to_vec())?.to_rgba8();
+
+        let blend = match fctl.blend_op {
+            ::png::BlendOp::Source => Blend::Source,
+            ::png::BlendOp::Over => Blend::Over
+        };
+
+        let dispose = match fctl.dispose_op {
+            ::png::DisposeOp::None => Dispose::None,
+            ::png::DisposeOp::Background => Dispose::Background,
+            ::png::DisposeOp::Previous => Dispose::Previous
+        };
+
+        let canvas = compositor.compose(&image, fctl.x_offset, fctl.y_offset, blend, dispose)?;
+
+        // a denominator of 0 means hundredths of a second
+        let den = match fctl.delay_den {
+            0 => 100,
+            d => d as u64
+        };
+
+        let delay = Duration::from_micros(fctl.delay_num as u64 * 1_000_000 / den);
+
+        frames.push(AnimationFrame::new(canvas, delay));
+
+    }
+
+    Ok(AnimatedImage::new(width, height, frames, control.num_plays))
+
+}
+//
+//
+/// Build an image from a frame given by the png decoder
+///
+/// # Parameters
+///
+/// * 'output' - the description of the frame
+/// * 'buf' - the buffer where the frame was decoded
+///
+fn frame_image(output:&::png::OutputInfo, mut buf:Vec<u8>) -> Result<Image,EImage> {
+
+    let format = match (output.color_type, output.bit_depth) {
+
+        (::png::ColorType::Grayscale, ::png::BitDepth::Eight) => PixelFormat::L8,
+        (::png::ColorType::GrayscaleAlpha, ::png::BitDepth::Eight) => PixelFormat::La8,
+        (::png::ColorType::Rgb, ::png::BitDepth::Eight) => PixelFormat::Rgb8,
+        (::png::ColorType::Rgba, ::png::BitDepth::Eight) => PixelFormat::Rgba8,
+        (::png::ColorType::Grayscale, ::png::BitDepth::Sixteen) => PixelFormat::L16,
+        (::png::ColorType::GrayscaleAlpha, ::png::BitDepth::Sixteen) => PixelFormat::La16,
+        (::png::ColorType::Rgb, ::png::BitDepth::Sixteen) => PixelFormat::Rgb16,
+        (::png::ColorType::Rgba, ::png::BitDepth::Sixteen) => PixelFormat::Rgba16,
+
+        (color, depth) => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("unsupported png color type {:?} {:?}",color,depth))
+        )
+
+    };
+
+    buf.truncate(output.buffer_size());
+
+    // png store its samples in big endian, the engine in native endian
+    if output.bit_depth == ::png::BitDepth::Sixteen {
+
+        for sample in buf.chunks_exact_mut(2) {
+            let value = u16::from_be_bytes([sample[0], sample[1]]);
+            sample.copy_from_slice(&value.to_ne_bytes());
+        }
+
+    }
+
+    Image::with_stride(output.width, output.height, format, output.line_size, buf)
+
+}
+//
+//
+/// Encode an image to png. The formats without a png equivalent (two channels, float) are
+/// converted to 8 bits RGBA first
+///
+/// # Parameters
+///
+/// * 'image' - the image to encode
+///
+pub(crate) fn encode(image:&Image) -> Result<Vec<u8>,EImage> {
+
+    let converted;
+
+    let (image, color, depth) = match image.format() {
+
+        PixelFormat::L8 | PixelFormat::R8 => (image, ::png::ColorType::Grayscale, ::png::BitDepth::Eight),
+        PixelFormat::La8 => (image, ::png::ColorType::GrayscaleAlpha, ::png::BitDepth::Eight),
+        PixelFormat::Rgb8 => (image, ::png::ColorType::Rgb, ::png::BitDepth::Eight),
+        PixelFormat::Rgba8 => (image, ::png::ColorType::Rgba, ::png::BitDepth::Eight),
+        PixelFormat::L16 | PixelFormat::R16 => (image, ::png::ColorType::Grayscale, ::png::BitDepth::Sixteen),
+        PixelFormat::La16 => (image, ::png::ColorType::GrayscaleAlpha, ::png::BitDepth::Sixteen),
+        PixelFormat::Rgb16 => (image, ::png::ColorType::Rgb, ::png::BitDepth::Sixteen),
+        PixelFormat::Rgba16 => (image, ::png::ColorType::Rgba, ::png::BitDepth::Sixteen),
+
+        _ => {
+            converted = image.to_rgba8();
+            (&converted, ::png::ColorType::Rgba, ::png::BitDepth::Eight)
+        }
+
+    };
+
+    // the rows without the padding of the stride, 16 bits samples back to big endian
+    let mut data = Vec::with_capacity(image.width() as usize * image.height() as usize * image.format().bytes_per_pixel());
+
+    for y in 0..image.height() {
+        data.extend_from_slice(image.row(y));
+    }
+
+    if depth == ::png::BitDepth::Sixteen {
+
+        for sample in data.chunks_exact_mut(2) {
+            let value = u16::from_ne_bytes([sample[0], sample[1]]);
+            sample.copy_from_slice(&value.to_be_bytes());
+        }
+
+    }
+
+    let mut bytes = Vec::new();
+
+    let mut encoder = ::png::Encoder::new(&mut bytes, image.width(), image.height());
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+
+    let mut writer = encoder.write_header().map_err(|e| EImage::Encode.attach_printable_default(e))?;
+
+    writer.write_image_data(&data).map_err(|e| EImage::Encode.attach_printable_default(e))?;
+    writer.finish().map_err(|e| EImage::Encode.attach_printable_default(e))?;
+
+    Ok(bytes)
+
+}
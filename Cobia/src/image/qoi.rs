@@ -0,0 +1,141 @@
+// QOI decoder (the "Quite OK Image" format, https://qoiformat.org)
+
+use super::{Image, PixelFormat, checked_size};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+pub(crate) const MAGIC: &[u8] = b"qoif";
+//
+const HEADER_SIZE: usize = 14;
+// the limit of the specification
+const MAX_PIXELS: usize = 400_000_000;
+//
+const OP_RGB: u8 = 0xfe;
+const OP_RGBA: u8 = 0xff;
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_MASK: u8 = 0xc0;
+//
+//
+/// decode a qoi image
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a qoi file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    let header = bytes.get(..HEADER_SIZE).ok_or_else(||
+        EImage::Decode.as_report().attach_printable("the qoi header is truncated")
+    )?;
+
+    if &header[..4] != MAGIC {
+
+        return Err(EImage::Decode.as_report().attach_printable("missing the qoif signature of a qoi file"));
+
+    }
+
+    let width = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let height = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+
+    let format = match header[12] {
+        3 => PixelFormat::Rgb8,
+        4 => PixelFormat::Rgba8,
+        c => return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("a qoi image can't have {} channels",c))
+        )
+    };
+
+    let count = checked_size(&[width as usize, height as usize], "qoi pixel count")?;
+    let channels = format.channels();
+
+    let truncated = || EImage::Decode.as_report().attach_printable("the qoi data is truncated");
+
+    // a byte encode at most a run of 62 pixels
+    if count > MAX_PIXELS || count / 62 > bytes.len() - HEADER_SIZE {
+
+        return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("a qoi file of {} bytes can't hold {}x{} pixels",bytes.len(),width,height))
+        );
+
+    }
+
+    let mut data = Vec::with_capacity(count * channels);
+    let mut index = [[0u8;4];64];
+    let mut px = [0u8, 0, 0, 255];
+    let mut run = 0;
+    let mut at = HEADER_SIZE;
+
+    let mut next = || -> Result<u8,EImage> {
+        let byte = *bytes.get(at).ok_or_else(truncated)?;
+        at += 1;
+        Ok(byte)
+    };
+
+    for _ in 0..count {
+
+        if run > 0 {
+
+            run -= 1;
+
+        } else {
+
+            let op = next()?;
+
+            match op {
+
+                OP_RGB => {
+                    px[0] = next()?;
+                    px[1] = next()?;
+                    px[2] = next()?;
+                },
+
+                OP_RGBA => {
+                    px[0] = next()?;
+                    px[1] = next()?;
+                    px[2] = next()?;
+                    px[3] = next()?;
+                },
+
+                _ => match op & OP_MASK {
+
+                    OP_INDEX => px = index[op as usize],
+
+                    OP_DIFF => {
+                        px[0] = px[0].wrapping_add((op >> 4) & 3).wrapping_sub(2);
+                        px[1] = px[1].wrapping_add((op >> 2) & 3).wrapping_sub(2);
+                        px[2] = px[2].wrapping_add(op & 3).wrapping_sub(2);
+                    },
+
+                    OP_LUMA => {
+                        let dg = (op & 0x3f).wrapping_sub(32);
+                        let byte = next()?;
+                        px[0] = px[0].wrapping_add(dg).wrapping_add(byte >> 4).wrapping_sub(8);
+                        px[1] = px[1].wrapping_add(dg);
+                        px[2] = px[2].wrapping_add(dg).wrapping_add(byte & 0x0f).wrapping_sub(8);
+                    },
+
+                    // run of the previous pixel, the current one is part of it
+                    _ => run = (op & 0x3f) as usize
+
+                }
+
+            }
+
+            let hash = (px[0] as usize * 3 + px[1] as usize * 5 + px[2] as usize * 7 + px[3] as usize * 11) % 64;
+            index[hash] = px;
+
+        }
+
+        data.extend_from_slice(&px[..channels]);
+
+    }
+
+    Image::new(width, height, format, data)
+
+}
@@ -0,0 +1,922 @@
+// GPU ready texture containers (KTX2 and DDS). The data is kept as stored in the file, compressed
+// or not, so it can be copied to the gpu as is once the device is known to support its format
+
+use vulkano::device::{Device, Features};
+use vulkano::format::{CompressionType, Format};
+
+use super::{Image, PixelFormat};
+use crate::core::error_handler::EImage;
+use crate::core::file::get_file_content;
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::get_relative_path;
+
+    #[test]
+    fn load_ktx2_fixtures() {
+
+        let bc1 = load_texture(&get_relative_path("tests/fixtures/texture/bc1_mips.ktx2")).unwrap();
+
+        assert_eq!(bc1.format(), Format::BC1_RGBA_UNORM_BLOCK);
+        assert_eq!((bc1.width(), bc1.height(), bc1.depth()), (8, 8, 1));
+        assert_eq!((bc1.mip_count(), bc1.layer_count(), bc1.face_count()), (4, 1, 1));
+        assert!(bc1.is_compressed());
+
+        assert_eq!(bc1.level(0).unwrap(), &[0; 32][..]);
+        assert_eq!(bc1.level(3).unwrap(), &[3; 8][..]);
+        assert_eq!(bc1.level_extent(2), [2, 2, 1]);
+
+        let cube = load_texture(&get_relative_path("tests/fixtures/texture/cube.ktx2")).unwrap();
+
+        assert_eq!(cube.format(), Format::R8G8B8A8_SRGB);
+        assert!(cube.is_cubemap());
+        assert!(!cube.is_compressed());
+
+        // texels are (level, face, x, y)
+        assert_eq!(cube.image(0, 0, 4).unwrap(), &[0, 4, 0, 0, 0, 4, 1, 0, 0, 4, 0, 1, 0, 4, 1, 1]);
+        assert_eq!(cube.image(1, 0, 5).unwrap(), &[1, 5, 0, 0]);
+        assert!(cube.image(0, 1, 0).is_none());
+
+    }
+
+    #[test]
+    fn load_dds_fixtures() {
+
+        let dxt5 = load_texture(&get_relative_path("tests/fixtures/texture/dxt5.dds")).unwrap();
+
+        assert_eq!(dxt5.format(), Format::BC3_UNORM_BLOCK);
+        assert_eq!(dxt5.mip_count(), 3);
+        assert_eq!(dxt5.level(2).unwrap(), &[0x52; 16][..]);
+
+        // the layers are stored one after the other with their mip levels
+        let array = load_texture(&get_relative_path("tests/fixtures/texture/bc7_array.dds")).unwrap();
+
+        assert_eq!(array.format(), Format::BC7_SRGB_BLOCK);
+        assert_eq!((array.layer_count(), array.mip_count()), (2, 2));
+        assert_eq!(array.image(0, 1, 0).unwrap(), &[0x80; 32][..]);
+        assert_eq!(array.image(1, 0, 0).unwrap(), &[0x71; 16][..]);
+        assert_eq!(array.level(1).unwrap().len(), 32);
+
+        let cube = load_texture(&get_relative_path("tests/fixtures/texture/rgba_cube.dds")).unwrap();
+
+        assert_eq!(cube.format(), Format::R8G8B8A8_UNORM);
+        assert_eq!(cube.face_count(), 6);
+        assert_eq!(cube.image(0, 0, 3).unwrap(), &[3, 0, 0, 255]);
+
+    }
+
+    #[test]
+    fn malformed_headers() {
+
+        let ktx2 = std::fs::read(get_relative_path("tests/fixtures/texture/bc1_mips.ktx2")).unwrap();
+        let dds = std::fs::read(get_relative_path("tests/fixtures/texture/dxt5.dds")).unwrap();
+
+        let patch = |bytes:&[u8], at:usize, value:&[u8]| {
+            let mut bytes = bytes.to_vec();
+            bytes[at..at + value.len()].copy_from_slice(value);
+            decode_texture(&bytes)
+        };
+
+        // too many mip levels or layers
+        assert!(patch(&ktx2, KTX2_MAGIC.len() + 28, &40_u32.to_le_bytes()).is_err());
+        assert!(patch(&ktx2, KTX2_MAGIC.len() + 20, &u32::MAX.to_le_bytes()).is_err());
+        assert!(patch(&dds, 28, &40_u32.to_le_bytes()).is_err());
+
+        // a mip level that end past the file
+        assert!(patch(&ktx2, KTX2_LEVEL_INDEX, &u64::MAX.to_le_bytes()).is_err());
+
+        // a header size that doesn't match the size of the levels
+        assert!(patch(&ktx2, KTX2_MAGIC.len() + 8, &u32::MAX.to_le_bytes()).is_err());
+
+        assert_eq!(decode_texture(&ktx2).unwrap().level_extent(40), [1, 1, 1]);
+
+    }
+
+    #[test]
+    fn texture_features() {
+
+        let bc1 = load_texture(&get_relative_path("tests/fixtures/texture/bc1_mips.ktx2")).unwrap();
+        let cube = load_texture(&get_relative_path("tests/fixtures/texture/cube.ktx2")).unwrap();
+
+        let bc = Features { texture_compression_bc: true, ..Features::empty() };
+
+        assert!(bc1.check_features(&bc).is_ok());
+        assert!(bc1.check_features(&Features::empty()).is_err());
+        assert!(cube.check_features(&Features::empty()).is_ok());
+
+        assert!(decode_texture(b"not a texture").is_err());
+
+    }
+
+    #[test]
+    fn encode_ktx2_round_trip() {
+
+        let data = (0..6 * 4 * 4).map(|i| i as u8).collect();
+        let img = Image::new(6, 4, PixelFormat::Rgba8, data).unwrap();
+
+        let texture = TextureData::from_image(&img, true);
+
+        assert_eq!(texture.format(), Format::R8G8B8A8_SRGB);
+        assert_eq!(texture.mip_count(), 3);
+        assert_eq!(texture.level_extent(2), [1, 1, 1]);
+        assert_eq!(texture.level(0).unwrap(), img.data());
+
+        let decoded = decode_texture(&encode_ktx2(&texture).unwrap()).unwrap();
+        assert_eq!(decoded, texture);
+
+        // rgb float get an alpha of 1
+        let hdr = Image::from_samples(1, 1, PixelFormat::Rgb32F, &[2.0, 0.5, 0.0]).unwrap();
+        let texture = TextureData::from_image(&hdr, false);
+
+        assert_eq!(texture.format(), Format::R32G32B32A32_SFLOAT);
+        assert_eq!(decode_texture(&encode_ktx2(&texture).unwrap()).unwrap(), texture);
+
+        let bc1 = load_texture(&get_relative_path("tests/fixtures/texture/bc1_mips.ktx2")).unwrap();
+        assert!(encode_ktx2(&bc1).is_err());
+
+    }
+
+}
+//
+//
+pub(crate) const KTX2_MAGIC: &[u8] = &[0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a];
+pub(crate) const DDS_MAGIC: &[u8] = b"DDS ";
+//
+// the most array layers accepted from a file, the limit of the devices is usually 2048
+const MAX_LAYERS: u32 = 2048;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Texture data
+//
+/// The content of a texture container. Every mip level hold all its images, layer after layer
+/// and for every layer face after face (+X, -X, +Y, -Y, +Z, -Z for a cube map)
+#[derive(Debug,Clone,PartialEq)]
+pub struct TextureData {
+
+    format:     Format,
+    width:      u32,
+    height:     u32,
+    depth:      u32,
+    layers:     u32,
+    faces:      u32,
+    levels:     Vec<Vec<u8>>
+
+}
+//
+impl TextureData {
+    //
+    pub fn format(&self) -> Format { self.format }
+    //
+    pub fn width(&self) -> u32 { self.width }
+    //
+    pub fn height(&self) -> u32 { self.height }
+    //
+    pub fn depth(&self) -> u32 { self.depth }
+    //
+    pub fn layer_count(&self) -> u32 { self.layers }
+    //
+    pub fn face_count(&self) -> u32 { self.faces }
+    //
+    pub fn mip_count(&self) -> u32 { self.levels.len() as u32 }
+    //
+    pub fn is_cubemap(&self) -> bool { self.faces == 6 }
+    //
+    pub fn is_compressed(&self) -> bool { self.format.compression().is_some() }
+    //
+    /// size in texels of a mip level
+    ///
+    /// # Parameters
+    ///
+    /// * 'level' - the mip level, 0 is the full size
+    ///
+    pub fn level_extent(&self, level:u32) -> [u32;3] {
+
+        mip_extent([self.width, self.height, self.depth], level)
+
+    }
+    //
+    /// all the images of a mip level
+    ///
+    /// # Parameters
+    ///
+    /// * 'level' - the mip level, 0 is the full size
+    ///
+    pub fn level(&self, level:u32) -> Option<&[u8]> {
+
+        self.levels.get(level as usize).map(|l| l.as_slice())
+
+    }
+    //
+    /// a single image of the texture, all its depth slices for a 3d texture
+    ///
+    /// # Parameters
+    ///
+    /// * 'level' - the mip level
+    /// * 'layer' - the array layer
+    /// * 'face' - the cube face, 0 if the texture is not a cube map
+    ///
+    pub fn image(&self, level:u32, layer:u32, face:u32) -> Option<&[u8]> {
+
+        if layer >= self.layers || face >= self.faces {
+            return None;
+        }
+
+        let [w, h, d] = self.level_extent(level);
+        let size = image_size(self.format, w, h, d)?;
+        let start = (layer * self.faces + face) as usize * size;
+
+        self.level(level)?.get(start..start + size)
+
+    }
+    //
+    /// Check that a device can sample this texture: the feature of the compression scheme must be
+    /// enabled, the format usable with an optimal tiling and the size under the limits
+    ///
+    /// # Parameters
+    ///
+    /// * 'device' - the device the texture will be uploaded to
+    ///
+    pub fn check_device_support(&self, device:&Device) -> Result<(),EImage> {
+
+        self.check_features(device.enabled_features())?;
+
+        let pdevice = device.physical_device();
+
+        let properties = pdevice.format_properties(self.format).map_err(|e|
+            EImage::Unsupported
+                .attach_printable_default(e)
+                .attach_printable(format!("Can't query the properties of {:?}",self.format))
+        )?;
+
+        if !properties.optimal_tiling_features.sampled_image {
+
+            return Err(EImage::Unsupported
+                .as_report()
+                .attach_printable(format!("the device can't sample a {:?} image",self.format))
+            );
+
+        }
+
+        let limits = pdevice.properties();
+
+        let max_size = match (self.is_cubemap(), self.depth > 1, self.height > 1) {
+            (true, _, _) => limits.max_image_dimension_cube,
+            (_, true, _) => limits.max_image_dimension3_d,
+            (_, _, true) => limits.max_image_dimension2_d,
+            _ => limits.max_image_dimension1_d
+        };
+
+        if self.width.max(self.height).max(self.depth) > max_size || self.layers > limits.max_image_array_layers {
+
+            return Err(EImage::Unsupported
+                .as_report()
+                .attach_printable(format!(
+                    "a {}x{}x{} texture of {} layers is over the limits of the device",
+                    self.width,
+                    self.height,
+                    self.depth,
+                    self.layers
+                    )
+                )
+            );
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// check the device feature needed by the compression of the format
+    ///
+    /// # Parameters
+    ///
+    /// * 'features' - the features enabled on the device
+    ///
+    fn check_features(&self, features:&Features) -> Result<(),EImage> {
+
+        let (supported, name) = match self.format.compression() {
+            None => (true, ""),
+            Some(CompressionType::BC) => (features.texture_compression_bc, "texture_compression_bc"),
+            Some(CompressionType::ETC2) | Some(CompressionType::EAC) =>
+                (features.texture_compression_etc2, "texture_compression_etc2"),
+            Some(CompressionType::ASTC_LDR) =>
+                (features.texture_compression_astc_ldr, "texture_compression_astc_ldr"),
+            Some(CompressionType::ASTC_HDR) =>
+                (features.texture_compression_astc_hdr, "texture_compression_astc_hdr"),
+            Some(CompressionType::PVRTC) => (false, "img_format_pvrtc")
+        };
+
+        if !supported {
+
+            return Err(EImage::Unsupported
+                .as_report()
+                .attach_printable(format!("{:?} need the {} feature of the device",self.format,name))
+            );
+
+        }
+
+        Ok(())
+
+    }
+    //
+}
+//
+//
+/// number of bytes of an image, None if the format has no fixed block size
+///
+/// # Parameters
+///
+/// * 'format' - the format of the texels
+/// * 'width' - width of the image in texels
+/// * 'height' - height of the image in texels
+/// * 'depth' - depth of the image in texels
+///
+fn image_size(format:Format, width:u32, height:u32, depth:u32) -> Option<usize> {
+
+    let [bw, bh, bd] = format.block_extent();
+
+    (width.div_ceil(bw) as usize)
+        .checked_mul(height.div_ceil(bh) as usize)?
+        .checked_mul(depth.div_ceil(bd) as usize)?
+        .checked_mul(format.block_size()? as usize)
+
+}
+//
+//
+/// the size of a mip level, a level past the last one is 1x1x1
+///
+/// # Parameters
+///
+/// * 'extent' - width, height and depth of the first level
+/// * 'level' - the mip level
+///
+fn mip_extent(extent:[u32;3], level:u32) -> [u32;3] {
+
+    extent.map(|size| size.checked_shr(level).unwrap_or(0).max(1))
+
+}
+//
+//
+/// Check the counts read from the header of a texture before they size anything
+///
+/// # Parameters
+///
+/// * 'level_count' - number of mip levels
+/// * 'layers' - number of array layers
+/// * 'container' - the file format, for the error
+///
+fn check_counts(level_count:u32, layers:u32, container:&str) -> Result<(),EImage> {
+
+    // a dimension on 32 bits can't have more than 32 mip levels
+    if level_count > 32 || layers > MAX_LAYERS {
+
+        return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!(
+                "a {} texture can't have {} mip levels and {} layers",
+                container,
+                level_count,
+                layers
+                )
+            )
+        );
+
+    }
+
+    Ok(())
+
+}
+//
+//
+fn truncated(container:&str) -> error_stack::Report<EImage> {
+
+    EImage::Decode.as_report().attach_printable(format!("the {} file is truncated",container))
+
+}
+//
+fn read_u32(bytes:&[u8], at:usize, container:&str) -> Result<u32,EImage> {
+
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| truncated(container))
+
+}
+//
+fn read_u64(bytes:&[u8], at:usize, container:&str) -> Result<u64,EImage> {
+
+    Ok(read_u32(bytes, at, container)? as u64 | (read_u32(bytes, at + 4, container)? as u64) << 32)
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load a KTX2 or DDS texture from the disk
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the texture
+///
+pub fn load_texture(fp:&str) -> Result<TextureData,EImage> {
+
+    let content = get_file_content(fp)
+        .change_context(EImage::Load)
+        .attach_printable_lazy(|| format!("Can't load texture {}",fp))?;
+
+    decode_texture(&content).attach_printable_lazy(|| format!("Can't load texture {}",fp))
+
+}
+//
+//
+/// decode a KTX2 or DDS texture already in memory, the container is found by its signature
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the container
+///
+pub fn decode_texture(bytes:&[u8]) -> Result<TextureData,EImage> {
+
+    match bytes {
+        b if b.starts_with(KTX2_MAGIC) => decode_ktx2(bytes),
+        b if b.starts_with(DDS_MAGIC) => decode_dds(bytes),
+        _ => Err(EImage::Unsupported.as_report().attach_printable("not a KTX2 or DDS texture"))
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Creation
+//
+impl TextureData {
+    //
+    /// Build an uncompressed texture with a full mip chain from an image. The float images give
+    /// R32G32B32A32_SFLOAT, the others R8G8B8A8
+    ///
+    /// # Parameters
+    ///
+    /// * 'image' - the first mip level
+    /// * 'srgb' - if the colors of a 8 bits image are in sRGB
+    ///
+    pub fn from_image(image:&Image, srgb:bool) -> TextureData {
+
+        let (image, format) = match image.format() {
+
+            PixelFormat::Rgba32F => (image.clone(), Format::R32G32B32A32_SFLOAT),
+
+            PixelFormat::Rgb32F => {
+
+                let samples:Vec<f32> = image
+                    .to_samples()
+                    .chunks_exact(3)
+                    .flat_map(|px| [px[0], px[1], px[2], 1.0])
+                    .collect();
+
+                let rgba = Image::from_samples(image.width(), image.height(), PixelFormat::Rgba32F, &samples)
+                    .expect("same size as the image");
+
+                (rgba, Format::R32G32B32A32_SFLOAT)
+
+            },
+
+            _ => (image.to_rgba8(), match srgb {
+                true => Format::R8G8B8A8_SRGB,
+                false => Format::R8G8B8A8_UNORM
+            })
+
+        };
+
+        let levels = image
+            .mip_chain()
+            .iter()
+            .map(|mip| (0..mip.height()).flat_map(|y| mip.row(y).iter().copied()).collect())
+            .collect();
+
+        TextureData { format, width: image.width(), height: image.height(), depth: 1, layers: 1, faces: 1, levels }
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// KTX2
+//
+const KTX2_LEVEL_INDEX: usize = 80;
+//
+//
+/// decode a KTX2 texture, the supercompressed ones are not supported
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a ktx2 file
+///
+fn decode_ktx2(bytes:&[u8]) -> Result<TextureData,EImage> {
+
+    let field = |i:usize| read_u32(bytes, KTX2_MAGIC.len() + i * 4, "ktx2");
+
+    let vk_format = field(0)?;
+    let width = field(2)?;
+    // 0 is used for the dimensions that the texture doesn't have
+    let height = field(3)?.max(1);
+    let depth = field(4)?.max(1);
+    let layers = field(5)?.max(1);
+    let faces = field(6)?;
+    // 0 ask the loader to generate the mip levels, only the first one is stored
+    let level_count = field(7)?.max(1);
+    let supercompression = field(8)?;
+
+    if supercompression != 0 {
+
+        return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("ktx2 supercompression scheme {} is not supported",supercompression))
+        );
+
+    }
+
+    if faces != 1 && faces != 6 {
+
+        return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("a ktx2 texture can't have {} faces",faces))
+        );
+
+    }
+
+    let format = format_from_vk(vk_format)?;
+
+    check_counts(level_count, layers, "ktx2")?;
+
+    let mut levels = Vec::with_capacity(level_count as usize);
+
+    for level in 0..level_count {
+
+        let index = KTX2_LEVEL_INDEX + level as usize * 24;
+        let offset = read_u64(bytes, index, "ktx2")? as usize;
+        let length = read_u64(bytes, index + 8, "ktx2")? as usize;
+
+        let [w, h, d] = mip_extent([width, height, depth], level);
+
+        let expected = image_size(format, w, h, d)
+            .and_then(|size| size.checked_mul((layers * faces) as usize))
+            .ok_or_else(|| EImage::Decode.as_report().attach_printable(format!("ktx2 mip level {} is too big",level)))?;
+
+        if length != expected {
+
+            return Err(EImage::Decode
+                .as_report()
+                .attach_printable(format!(
+                    "ktx2 mip level {} has {} bytes, {} were expected",
+                    level,
+                    length,
+                    expected
+                    )
+                )
+            );
+
+        }
+
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| truncated("ktx2"))?;
+
+        levels.push(data.to_vec());
+
+    }
+
+    Ok(TextureData { format, width, height, depth, layers, faces, levels })
+
+}
+//
+//
+/// the vulkano format of a VkFormat value
+///
+/// # Parameters
+///
+/// * 'vk_format' - the raw VkFormat
+///
+fn format_from_vk(vk_format:u32) -> Result<Format,EImage> {
+
+    let format = Format::try_from(ash::vk::Format::from_raw(vk_format as i32)).map_err(|_|
+        EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("unknown VkFormat {}",vk_format))
+    )?;
+
+    if format.block_size().is_none() || format.planes().len() > 1 {
+
+        return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("{:?} can't be used for a texture",format))
+        );
+
+    }
+
+    Ok(format)
+
+}
+//
+//
+/// Encode a texture to KTX2, only the uncompressed RGBA formats built by from_image are
+/// supported
+///
+/// # Parameters
+///
+/// * 'texture' - the texture to encode
+///
+pub fn encode_ktx2(texture:&TextureData) -> Result<Vec<u8>,EImage> {
+
+    // bytes per channel and transfer function (1 linear, 2 sRGB) of the data format descriptor
+    let (channel_size, transfer) = match texture.format {
+        Format::R8G8B8A8_UNORM => (1, 1),
+        Format::R8G8B8A8_SRGB => (1, 2),
+        Format::R32G32B32A32_SFLOAT => (4, 1),
+        format => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("{:?} can't be written to a ktx2 file",format))
+        )
+    };
+
+    let dfd = ktx2_dfd(channel_size, transfer);
+    let level_count = texture.levels.len();
+    let dfd_offset = KTX2_LEVEL_INDEX + level_count * 24;
+    // the levels are aligned on the size of a texel
+    let align = 4 * channel_size as usize;
+
+    let mut bytes = KTX2_MAGIC.to_vec();
+
+    let is_3d = texture.depth > 1;
+    let fields = [
+        texture.format as u32,
+        channel_size,
+        texture.width,
+        texture.height,
+        match is_3d { true => texture.depth, false => 0 },
+        match texture.layers > 1 { true => texture.layers, false => 0 },
+        texture.faces,
+        level_count as u32,
+        0,
+        dfd_offset as u32,
+        dfd.len() as u32,
+        0,
+        0
+    ];
+
+    fields.iter().for_each(|f| bytes.extend_from_slice(&f.to_le_bytes()));
+    // no supercompression global data
+    bytes.extend_from_slice(&[0; 16]);
+
+    // the data of the levels start after the dfd, from the smallest level to the biggest
+    let mut offsets = vec![0; level_count];
+    let mut at = dfd_offset + dfd.len();
+
+    for level in (0..level_count).rev() {
+        at = at.next_multiple_of(align);
+        offsets[level] = at;
+        at += texture.levels[level].len();
+    }
+
+    for (level, data) in texture.levels.iter().enumerate() {
+        bytes.extend_from_slice(&(offsets[level] as u64).to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    }
+
+    bytes.extend_from_slice(&dfd);
+
+    for level in (0..level_count).rev() {
+        bytes.resize(offsets[level], 0);
+        bytes.extend_from_slice(&texture.levels[level]);
+    }
+
+    Ok(bytes)
+
+}
+//
+//
+/// Data format descriptor of a RGBA texture, a basic block with a sample per channel
+///
+/// # Parameters
+///
+/// * 'channel_size' - bytes per channel, 1 for unorm and 4 for float
+/// * 'transfer' - the transfer function, 1 for linear and 2 for sRGB
+///
+fn ktx2_dfd(channel_size:u32, transfer:u8) -> Vec<u8> {
+
+    let block_size = 24 + 16 * 4;
+    let mut dfd = Vec::with_capacity(4 + block_size);
+
+    dfd.extend_from_slice(&(4 + block_size as u32).to_le_bytes());
+    // khronos vendor and basic descriptor type, then version 2 and the size of the block
+    dfd.extend_from_slice(&0u32.to_le_bytes());
+    dfd.extend_from_slice(&(2 | (block_size as u32) << 16).to_le_bytes());
+    // RGBSDA color model, BT709 primaries, straight alpha
+    dfd.extend_from_slice(&[1, 1, transfer, 0]);
+    dfd.extend_from_slice(&[0; 4]);
+    dfd.extend_from_slice(&[4 * channel_size as u8, 0, 0, 0, 0, 0, 0, 0]);
+
+    for (i, channel) in [0u8, 1, 2, 15].iter().enumerate() {
+
+        let bits = channel_size * 8;
+
+        // the alpha is always linear, the float samples are signed
+        let qualifiers = match (channel_size, *channel) {
+            (4, _) => 0xc0,
+            (_, 15) if transfer == 2 => 0x10,
+            _ => 0
+        };
+
+        let (lower, upper) = match channel_size {
+            4 => ((-1.0f32).to_bits(), 1.0f32.to_bits()),
+            _ => (0, 255)
+        };
+
+        dfd.extend_from_slice(&(i as u16 * bits as u16).to_le_bytes());
+        dfd.extend_from_slice(&[(bits - 1) as u8, channel | qualifiers]);
+        dfd.extend_from_slice(&[0; 4]);
+        dfd.extend_from_slice(&lower.to_le_bytes());
+        dfd.extend_from_slice(&upper.to_le_bytes());
+
+    }
+
+    dfd
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// DDS
+//
+const DDS_HEADER_END: usize = 128;
+const DX10_HEADER_SIZE: usize = 20;
+//
+const DDSD_DEPTH: u32 = 0x800000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+//
+//
+/// decode a DDS texture, with or without the DX10 header
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a dds file
+///
+fn decode_dds(bytes:&[u8]) -> Result<TextureData,EImage> {
+
+    let field = |at:usize| read_u32(bytes, at, "dds");
+
+    let flags = field(8)?;
+    let height = field(12)?.max(1);
+    let width = field(16)?.max(1);
+    let depth = match flags & DDSD_DEPTH { 0 => 1, _ => field(24)?.max(1) };
+    let level_count = field(28)?.max(1);
+    let pf_flags = field(80)?;
+    let four_cc = bytes.get(84..88).ok_or_else(|| truncated("dds"))?;
+    let caps2 = field(112)?;
+
+    let (format, layers, cube, data_start) = match (pf_flags & DDPF_FOURCC != 0, four_cc) {
+
+        (true, b"DX10") => {
+
+            let dxgi = field(DDS_HEADER_END)?;
+            let misc = field(DDS_HEADER_END + 8)?;
+            let array_size = field(DDS_HEADER_END + 12)?.max(1);
+
+            (
+                format_from_dxgi(dxgi)?,
+                array_size,
+                misc & DDS_RESOURCE_MISC_TEXTURECUBE != 0,
+                DDS_HEADER_END + DX10_HEADER_SIZE
+            )
+
+        },
+
+        (true, four_cc) => (format_from_four_cc(four_cc)?, 1, caps2 & DDSCAPS2_CUBEMAP != 0, DDS_HEADER_END),
+
+        (false, _) if pf_flags & DDPF_RGB != 0 => {
+
+            let bits = field(88)?;
+            let masks = [field(92)?, field(96)?, field(100)?, field(104)?];
+
+            let format = match (bits, masks) {
+                (32, [0xff, 0xff00, 0xff0000, _]) => Format::R8G8B8A8_UNORM,
+                (32, [0xff0000, 0xff00, 0xff, _]) => Format::B8G8R8A8_UNORM,
+                (bits, masks) => return Err(EImage::Unsupported
+                    .as_report()
+                    .attach_printable(format!("dds rgb layout of {} bits with the masks {:x?} is not supported",bits,masks))
+                )
+            };
+
+            (format, 1, caps2 & DDSCAPS2_CUBEMAP != 0, DDS_HEADER_END)
+
+        },
+
+        _ => return Err(EImage::Unsupported.as_report().attach_printable("dds pixel format not supported"))
+
+    };
+
+    let faces = match cube { true => 6, false => 1 };
+
+    check_counts(level_count, layers, "dds")?;
+
+    // the file store every image with all its mip levels, the texture want the mip levels first
+    let mut levels:Vec<Vec<u8>> = vec![Vec::new(); level_count as usize];
+    let mut at = data_start;
+
+    for _ in 0..layers * faces {
+        for (level, data) in levels.iter_mut().enumerate() {
+
+            let [w, h, d] = mip_extent([width, height, depth], level as u32);
+
+            let image = image_size(format, w, h, d)
+                .and_then(|size| bytes.get(at..at.checked_add(size)?))
+                .ok_or_else(|| truncated("dds"))?;
+            data.extend_from_slice(image);
+            at += image.len();
+
+        }
+    }
+
+    Ok(TextureData { format, width, height, depth, layers, faces, levels })
+
+}
+//
+//
+/// the vulkano format of a legacy dds four character code
+///
+/// # Parameters
+///
+/// * 'four_cc' - the code
+///
+fn format_from_four_cc(four_cc:&[u8]) -> Result<Format,EImage> {
+
+    match four_cc {
+        b"DXT1" => Ok(Format::BC1_RGBA_UNORM_BLOCK),
+        b"DXT2" | b"DXT3" => Ok(Format::BC2_UNORM_BLOCK),
+        b"DXT4" | b"DXT5" => Ok(Format::BC3_UNORM_BLOCK),
+        b"ATI1" | b"BC4U" => Ok(Format::BC4_UNORM_BLOCK),
+        b"BC4S" => Ok(Format::BC4_SNORM_BLOCK),
+        b"ATI2" | b"BC5U" => Ok(Format::BC5_UNORM_BLOCK),
+        b"BC5S" => Ok(Format::BC5_SNORM_BLOCK),
+        other => Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("dds four cc {} is not supported",String::from_utf8_lossy(other)))
+        )
+    }
+
+}
+//
+//
+/// the vulkano format of a DXGI_FORMAT value
+///
+/// # Parameters
+///
+/// * 'dxgi' - the raw DXGI_FORMAT
+///
+fn format_from_dxgi(dxgi:u32) -> Result<Format,EImage> {
+
+    match dxgi {
+        2 => Ok(Format::R32G32B32A32_SFLOAT),
+        10 => Ok(Format::R16G16B16A16_SFLOAT),
+        28 => Ok(Format::R8G8B8A8_UNORM),
+        29 => Ok(Format::R8G8B8A8_SRGB),
+        49 => Ok(Format::R8G8_UNORM),
+        61 => Ok(Format::R8_UNORM),
+        71 => Ok(Format::BC1_RGBA_UNORM_BLOCK),
+        72 => Ok(Format::BC1_RGBA_SRGB_BLOCK),
+        74 => Ok(Format::BC2_UNORM_BLOCK),
+        75 => Ok(Format::BC2_SRGB_BLOCK),
+        77 => Ok(Format::BC3_UNORM_BLOCK),
+        78 => Ok(Format::BC3_SRGB_BLOCK),
+        80 => Ok(Format::BC4_UNORM_BLOCK),
+        81 => Ok(Format::BC4_SNORM_BLOCK),
+        83 => Ok(Format::BC5_UNORM_BLOCK),
+        84 => Ok(Format::BC5_SNORM_BLOCK),
+        87 => Ok(Format::B8G8R8A8_UNORM),
+        91 => Ok(Format::B8G8R8A8_SRGB),
+        95 => Ok(Format::BC6H_UFLOAT_BLOCK),
+        96 => Ok(Format::BC6H_SFLOAT_BLOCK),
+        98 => Ok(Format::BC7_UNORM_BLOCK),
+        99 => Ok(Format::BC7_SRGB_BLOCK),
+        other => Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("DXGI_FORMAT {} is not supported",other))
+        )
+    }
+
+}
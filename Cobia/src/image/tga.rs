@@ -0,0 +1,241 @@
+// TGA decoder, color mapped, true color and grayscale images, raw or run length encoded
+
+use super::{Image, PixelFormat, checked_size};
+use crate::core::error_handler::EImage;
+
+use error_stack::Result;
+//
+//
+const HEADER_SIZE: usize = 18;
+// signature at the end of a TGA 2.0 file
+pub(crate) const FOOTER_SIGNATURE: &[u8] = b"TRUEVISION-XFILE.\0";
+//
+//
+/// decode a tga image
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of a tga file
+///
+pub(crate) fn decode(bytes:&[u8]) -> Result<Image,EImage> {
+
+    let header = bytes.get(..HEADER_SIZE).ok_or_else(||
+        EImage::Decode.as_report().attach_printable("the tga header is truncated")
+    )?;
+
+    let id_length = header[0] as usize;
+    let map_type = header[1];
+    let image_type = header[2];
+    let map_first = u16::from_le_bytes([header[3], header[4]]) as usize;
+    let map_length = u16::from_le_bytes([header[5], header[6]]) as usize;
+    let map_depth = header[7];
+    let width = u16::from_le_bytes([header[12], header[13]]) as u32;
+    let height = u16::from_le_bytes([header[14], header[15]]) as u32;
+    let depth = header[16];
+    let descriptor = header[17];
+
+    let rle = image_type & 8 != 0;
+
+    // the pixels are stored from the bottom left unless the descriptor say otherwise
+    let right_to_left = descriptor & 0x10 != 0;
+    let top_down = descriptor & 0x20 != 0;
+    let alpha_bits = descriptor & 0x0f;
+
+    // depth of the stored value of a pixel, and depth of the color it is converted to
+    let (stored_depth, color_depth) = match (image_type & !8, depth) {
+
+        (1, 8) if map_type == 1 => (8, map_depth),
+        (2, 15 | 16 | 24 | 32) => (depth, depth),
+        (3, 8 | 16) => (depth, depth),
+
+        (kind, depth) => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("tga image of type {} with {} bits per pixel is not supported",kind,depth))
+        )
+
+    };
+
+    let format = match (image_type & !8, color_depth) {
+        (3, 8) => PixelFormat::L8,
+        (3, _) => PixelFormat::La8,
+        (_, 15) => PixelFormat::Rgb8,
+        (_, 16) if alpha_bits == 0 => PixelFormat::Rgb8,
+        (_, 16) => PixelFormat::Rgba8,
+        (_, 24) => PixelFormat::Rgb8,
+        (_, 32) => PixelFormat::Rgba8,
+
+        (_, depth) => return Err(EImage::Unsupported
+            .as_report()
+            .attach_printable(format!("tga color map entries of {} bits are not supported",depth))
+        )
+    };
+
+    let map_start = HEADER_SIZE + id_length;
+    let map_size = match map_type {
+        1 => map_length * (map_depth as usize).div_ceil(8),
+        _ => 0
+    };
+
+    let color_map = bytes.get(map_start..map_start + map_size).ok_or_else(||
+        EImage::Decode.as_report().attach_printable("the tga color map is truncated")
+    )?;
+
+    let pixel_size = (stored_depth as usize).div_ceil(8);
+    let count = checked_size(&[width as usize, height as usize], "tga pixel count")?;
+    let pixels = &bytes[map_start + map_size..];
+
+    // a run length packet is a byte and a pixel for at most 128 pixels
+    let needed = match rle {
+        true => checked_size(&[count.div_ceil(128), 1 + pixel_size], "tga data size")?,
+        false => checked_size(&[count, pixel_size], "tga data size")?
+    };
+
+    if needed > pixels.len() {
+
+        return Err(EImage::Decode
+            .as_report()
+            .attach_printable(format!("a tga file of {} bytes can't hold {}x{} pixels",bytes.len(),width,height))
+        );
+
+    }
+
+    let stored = read_pixels(pixels, pixel_size, count, rle)?;
+
+    let out_size = format.bytes_per_pixel();
+    let mut data = vec![0; checked_size(&[count, out_size], "tga image size")?];
+
+    for (i, px) in stored.chunks_exact(pixel_size).enumerate() {
+
+        let color = match image_type & !8 {
+
+            1 => {
+
+                let index = (px[0] as usize).checked_sub(map_first).ok_or_else(||
+                    EImage::Decode.as_report().attach_printable("tga color map index out of the map")
+                )?;
+
+                let entry_size = (map_depth as usize).div_ceil(8);
+
+                let entry = color_map.get(index * entry_size..(index + 1) * entry_size).ok_or_else(||
+                    EImage::Decode.as_report().attach_printable("tga color map index out of the map")
+                )?;
+
+                to_color(entry, color_depth, alpha_bits)
+
+            },
+
+            // gray and optional alpha, the gray is kept in red
+            3 => [px[0], 0, 0, px.get(1).copied().unwrap_or(255)],
+
+            _ => to_color(px, color_depth, alpha_bits)
+
+        };
+
+        let (x, y) = (i % width as usize, i / width as usize);
+        let x = match right_to_left { true => width as usize - 1 - x, false => x };
+        let y = match top_down { true => y, false => height as usize - 1 - y };
+
+        let at = (y * width as usize + x) * out_size;
+
+        match format {
+            PixelFormat::L8 => data[at] = color[0],
+            PixelFormat::La8 => data[at..at + 2].copy_from_slice(&[color[0], color[3]]),
+            _ => data[at..at + out_size].copy_from_slice(&color[..out_size])
+        }
+
+    }
+
+    Image::new(width, height, format, data)
+
+}
+//
+//
+/// Read the stored values of the pixels, expanding the run length packets
+///
+/// # Parameters
+///
+/// * 'bytes' - the data of the image
+/// * 'pixel_size' - number of bytes of a stored pixel
+/// * 'count' - number of pixels in the image
+/// * 'rle' - if the data is run length encoded
+///
+fn read_pixels(bytes:&[u8], pixel_size:usize, count:usize, rle:bool) -> Result<Vec<u8>,EImage> {
+
+    let size = checked_size(&[count, pixel_size], "tga data size")?;
+
+    let truncated = || EImage::Decode.as_report().attach_printable("the tga pixels are truncated");
+
+    if !rle {
+        return bytes.get(..size).map(|b| b.to_vec()).ok_or_else(truncated);
+    }
+
+    let mut pixels = Vec::with_capacity(size);
+    let mut at = 0;
+
+    while pixels.len() < size {
+
+        let packet = *bytes.get(at).ok_or_else(truncated)?;
+        let repeat = (packet & 0x7f) as usize + 1;
+        at += 1;
+
+        match packet & 0x80 {
+
+            0 => {
+                let raw = bytes.get(at..at + repeat * pixel_size).ok_or_else(truncated)?;
+                pixels.extend_from_slice(raw);
+                at += repeat * pixel_size;
+            },
+
+            _ => {
+                let px = bytes.get(at..at + pixel_size).ok_or_else(truncated)?;
+                for _ in 0..repeat {
+                    pixels.extend_from_slice(px);
+                }
+                at += pixel_size;
+            }
+
+        }
+
+    }
+
+    // a packet may cross the end of the image
+    pixels.truncate(size);
+
+    Ok(pixels)
+
+}
+//
+//
+/// convert a stored color (little endian BGR(A)) to RGBA
+///
+/// # Parameters
+///
+/// * 'px' - the stored color
+/// * 'depth' - its number of bits
+/// * 'alpha_bits' - number of bits of alpha given by the header
+///
+fn to_color(px:&[u8], depth:u8, alpha_bits:u8) -> [u8;4] {
+
+    match depth {
+
+        15 | 16 => {
+
+            let value = u16::from_le_bytes([px[0], px[1]]);
+            let scale = |v:u16| ((v & 0x1f) * 255 / 31) as u8;
+
+            let alpha = match (depth, alpha_bits, value >> 15) {
+                (16, a, 0) if a > 0 => 0,
+                _ => 255
+            };
+
+            [scale(value >> 10), scale(value >> 5), scale(value), alpha]
+
+        },
+
+        24 => [px[2], px[1], px[0], 255],
+
+        _ => [px[2], px[1], px[0], px[3]]
+
+    }
+
+}
@@ -4,10 +4,17 @@
 #[allow(dead_code)]
 
 pub mod define;
-//pub mod ecs;
+pub mod ecs;
 pub mod renderer;
 pub mod event;
 pub mod core;
+pub mod image;
+pub mod asset;
+pub mod mesh;
+pub mod cook;
+pub mod scene;
+pub mod font;
+pub mod sprite;
 //
 
 
@@ -43,6 +50,15 @@ pub(crate) mod test_helper {
         format!("{}/{}",env::current_dir().unwrap().to_str().unwrap(),p)
     }
 
+    /// a path in the temporary directory, unique to the test process
+    pub(crate) fn tmp_path(prefix:&str, name:&str) -> String {
+        env::temp_dir()
+            .join(format!("cobia_{}_{}_{}",prefix,std::process::id(),name))
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
 
 }
 
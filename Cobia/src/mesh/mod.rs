@@ -0,0 +1,572 @@
+// Engine mesh type, its binary format and the importers that produce it
+
+mod obj;
+mod mtl;
+
+pub use obj::{ObjModel, decode_obj, load_obj, load_obj_model};
+pub use mtl::{Material, MaterialLibrary, decode_mtl, load_mtl};
+
+use crate::core::error_handler::EMesh;
+use crate::core::file::get_file_content;
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn quad() -> Mesh {
+
+        Mesh::new(
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+            vec![[0.0, 0.0, 1.0]; 4],
+            vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+            vec![0, 1, 2, 0, 2, 3],
+            vec![SubMesh { start: 0, count: 3, material: Some("red".to_string()) }, SubMesh { start: 3, count: 3, material: None }]
+        ).unwrap()
+
+    }
+
+    #[test]
+    fn binary_round_trip() {
+
+        let mesh = quad();
+        let bytes = mesh.encode();
+
+        assert!(bytes.starts_with(MESH_MAGIC));
+        assert_eq!(Mesh::decode(&bytes).unwrap(), mesh);
+
+        // the optional attributes can be left out
+        let bare = Mesh::new(mesh.positions().to_vec(), Vec::new(), Vec::new(), mesh.indices().to_vec(), Vec::new()).unwrap();
+
+        assert_eq!(bare.submeshes(), &[SubMesh { start: 0, count: 6, material: None }]);
+        assert_eq!(Mesh::decode(&bare.encode()).unwrap(), bare);
+
+        // with every attribute
+        let full = mesh
+            .clone()
+            .with_tangents(vec![[1.0, 0.0, 0.0, 1.0]; 4]).unwrap()
+            .with_uvs1(mesh.uvs().to_vec()).unwrap()
+            .with_colors(vec![[1.0, 0.5, 0.25, 1.0]; 4]).unwrap()
+            .with_skin(vec![[0, 1, 0, 0]; 4], vec![[0.5, 0.5, 0.0, 0.0]; 4]).unwrap();
+
+        assert_eq!(Mesh::decode(&full.encode()).unwrap(), full);
+        assert!(mesh.clone().with_skin(vec![[0; 4]; 4], Vec::new()).is_err());
+
+        assert!(Mesh::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Mesh::decode(b"not a mesh").is_err());
+
+    }
+
+    #[test]
+    fn invalid_mesh() {
+
+        let mesh = quad();
+
+        // an index out of the vertices
+        assert!(Mesh::new(mesh.positions().to_vec(), Vec::new(), Vec::new(), vec![0, 1, 4], Vec::new()).is_err());
+        // an attribute that does not match the positions
+        assert!(Mesh::new(mesh.positions().to_vec(), vec![[0.0; 3]], Vec::new(), vec![0, 1, 2], Vec::new()).is_err());
+        // not made of triangles
+        assert!(Mesh::new(mesh.positions().to_vec(), Vec::new(), Vec::new(), vec![0, 1], Vec::new()).is_err());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Mesh
+//
+/// A range of triangles drawn with the same material
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct SubMesh {
+    // first index of the range
+    pub start: u32,
+    // number of indices, a multiple of 3
+    pub count: u32,
+    pub material: Option<String>
+}
+//
+//
+/// An indexed triangle mesh. The attributes other than the positions are either empty or have a
+/// value per position
+#[derive(Debug,Clone,PartialEq)]
+pub struct Mesh {
+
+    positions:  Vec<[f32;3]>,
+    normals:    Vec<[f32;3]>,
+    uvs:        Vec<[f32;2]>,
+    // xyz and the handedness of the bitangent in w
+    tangents:   Vec<[f32;4]>,
+    uvs1:       Vec<[f32;2]>,
+    colors:     Vec<[f32;4]>,
+    // the 4 joints that move each vertex and their weight
+    joints:     Vec<[u16;4]>,
+    weights:    Vec<[f32;4]>,
+    indices:    Vec<u32>,
+    submeshes:  Vec<SubMesh>
+
+}
+//
+impl Mesh {
+    //
+    /// Build a mesh and check that it is valid
+    ///
+    /// # Parameters
+    ///
+    /// * 'positions' - position of every vertex
+    /// * 'normals' - normal of every vertex, or empty
+    /// * 'uvs' - texture coordinates of every vertex, or empty
+    /// * 'indices' - three indices per triangle
+    /// * 'submeshes' - the ranges of indices per material, empty for a single range without material
+    ///
+    pub fn new(
+        positions:Vec<[f32;3]>,
+        normals:Vec<[f32;3]>,
+        uvs:Vec<[f32;2]>,
+        indices:Vec<u32>,
+        submeshes:Vec<SubMesh>) -> Result<Self,EMesh> {
+
+        let invalid = |msg:String| Err(EMesh::Format.as_report().attach_printable(msg));
+
+        if (!normals.is_empty() && normals.len() != positions.len()) || (!uvs.is_empty() && uvs.len() != positions.len()) {
+
+            return invalid(format!(
+                "{} positions but {} normals and {} uvs",
+                positions.len(),
+                normals.len(),
+                uvs.len()
+            ));
+
+        }
+
+        if !indices.len().is_multiple_of(3) {
+
+            return invalid(format!("{} indices don't make triangles",indices.len()));
+
+        }
+
+        if let Some(i) = indices.iter().find(|i| **i as usize >= positions.len()) {
+
+            return invalid(format!("the index {} is out of the {} vertices",i,positions.len()));
+
+        }
+
+        let submeshes = match submeshes.is_empty() {
+            true => vec![SubMesh { start: 0, count: indices.len() as u32, material: None }],
+            false => submeshes
+        };
+
+        for sub in submeshes.iter() {
+
+            if !sub.count.is_multiple_of(3) || sub.start as u64 + sub.count as u64 > indices.len() as u64 {
+
+                return invalid(format!("invalid submesh of {} indices at {}",sub.count,sub.start));
+
+            }
+
+        }
+
+        Ok(Self {
+            positions,
+            normals,
+            uvs,
+            tangents:   Vec::new(),
+            uvs1:       Vec::new(),
+            colors:     Vec::new(),
+            joints:     Vec::new(),
+            weights:    Vec::new(),
+            indices,
+            submeshes
+        })
+
+    }
+    //
+    /// check that an attribute has a value per vertex
+    fn check_attribute(&self, name:&str, len:usize) -> Result<(),EMesh> {
+
+        match len == 0 || len == self.positions.len() {
+            true => Ok(()),
+            false => Err(EMesh::Format
+                .as_report()
+                .attach_printable(format!("{} positions but {} {}",self.positions.len(),len,name))
+            )
+        }
+
+    }
+    //
+    /// Set the tangents, xyz and the handedness of the bitangent (1 or -1) in w
+    ///
+    /// # Parameters
+    ///
+    /// * 'tangents' - a tangent per vertex, or empty
+    ///
+    pub fn with_tangents(mut self, tangents:Vec<[f32;4]>) -> Result<Self,EMesh> {
+
+        self.check_attribute("tangents", tangents.len())?;
+        self.tangents = tangents;
+        Ok(self)
+
+    }
+    //
+    /// Set a second set of texture coordinates, like the ones of a light map
+    ///
+    /// # Parameters
+    ///
+    /// * 'uvs' - texture coordinates per vertex, or empty
+    ///
+    pub fn with_uvs1(mut self, uvs:Vec<[f32;2]>) -> Result<Self,EMesh> {
+
+        self.check_attribute("uvs1", uvs.len())?;
+        self.uvs1 = uvs;
+        Ok(self)
+
+    }
+    //
+    /// Set the vertex colors, in linear rgba
+    ///
+    /// # Parameters
+    ///
+    /// * 'colors' - a color per vertex, or empty
+    ///
+    pub fn with_colors(mut self, colors:Vec<[f32;4]>) -> Result<Self,EMesh> {
+
+        self.check_attribute("colors", colors.len())?;
+        self.colors = colors;
+        Ok(self)
+
+    }
+    //
+    /// Set the joints that move every vertex of a skinned mesh
+    ///
+    /// # Parameters
+    ///
+    /// * 'joints' - 4 joints per vertex, or empty
+    /// * 'weights' - the weight of each joint, their sum is 1
+    ///
+    pub fn with_skin(mut self, joints:Vec<[u16;4]>, weights:Vec<[f32;4]>) -> Result<Self,EMesh> {
+
+        self.check_attribute("joints", joints.len())?;
+        self.check_attribute("weights", weights.len())?;
+
+        if joints.len() != weights.len() {
+
+            return Err(EMesh::Format
+                .as_report()
+                .attach_printable(format!("{} joints but {} weights",joints.len(),weights.len()))
+            );
+
+        }
+
+        self.joints = joints;
+        self.weights = weights;
+        Ok(self)
+
+    }
+    //
+    pub fn positions(&self) -> &[[f32;3]] { &self.positions }
+    //
+    /// the normals, empty if the mesh has none
+    pub fn normals(&self) -> &[[f32;3]] { &self.normals }
+    //
+    /// the texture coordinates, empty if the mesh has none
+    pub fn uvs(&self) -> &[[f32;2]] { &self.uvs }
+    //
+    /// the tangents, empty if the mesh has none
+    pub fn tangents(&self) -> &[[f32;4]] { &self.tangents }
+    //
+    /// the second texture coordinates, empty if the mesh has none
+    pub fn uvs1(&self) -> &[[f32;2]] { &self.uvs1 }
+    //
+    /// the vertex colors, empty if the mesh has none
+    pub fn colors(&self) -> &[[f32;4]] { &self.colors }
+    //
+    /// the joints of every vertex, empty if the mesh is not skinned
+    pub fn joints(&self) -> &[[u16;4]] { &self.joints }
+    //
+    /// the weights of the joints, empty if the mesh is not skinned
+    pub fn weights(&self) -> &[[f32;4]] { &self.weights }
+    //
+    pub fn indices(&self) -> &[u32] { &self.indices }
+    //
+    pub fn submeshes(&self) -> &[SubMesh] { &self.submeshes }
+    //
+    pub fn vertex_count(&self) -> usize { self.positions.len() }
+    //
+    pub fn triangle_count(&self) -> usize { self.indices.len() / 3 }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Binary format
+//
+// little endian, after the header come the positions, the optional attributes in the order of
+// their flag, the indices then the submeshes. A submesh is its start, count and the length of its material name (u32::MAX for none)
+// followed by the name
+//
+pub(crate) const MESH_MAGIC: &[u8] = b"CMSH";
+//
+const MESH_VERSION: u32 = 1;
+//
+const HAS_NORMALS: u32 = 0x1;
+const HAS_UVS: u32 = 0x2;
+const HAS_TANGENTS: u32 = 0x4;
+const HAS_UVS1: u32 = 0x8;
+const HAS_COLORS: u32 = 0x10;
+// joints and weights
+const HAS_SKIN: u32 = 0x20;
+//
+//
+impl Mesh {
+    //
+    /// the mesh in the engine binary format, fast to load compared to the source formats
+    pub fn encode(&self) -> Vec<u8> {
+
+        let mut bytes = MESH_MAGIC.to_vec();
+
+        let mut flags = 0;
+        if !self.normals.is_empty() { flags |= HAS_NORMALS; }
+        if !self.uvs.is_empty() { flags |= HAS_UVS; }
+        if !self.tangents.is_empty() { flags |= HAS_TANGENTS; }
+        if !self.uvs1.is_empty() { flags |= HAS_UVS1; }
+        if !self.colors.is_empty() { flags |= HAS_COLORS; }
+        if !self.joints.is_empty() { flags |= HAS_SKIN; }
+
+        let header = [
+            MESH_VERSION,
+            flags,
+            self.positions.len() as u32,
+            self.indices.len() as u32,
+            self.submeshes.len() as u32
+        ];
+
+        header.iter().for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+
+        let floats = self.positions
+            .iter()
+            .flatten()
+            .chain(self.normals.iter().flatten())
+            .chain(self.uvs.iter().flatten())
+            .chain(self.tangents.iter().flatten())
+            .chain(self.uvs1.iter().flatten())
+            .chain(self.colors.iter().flatten());
+
+        floats.for_each(|f| bytes.extend_from_slice(&f.to_le_bytes()));
+        self.joints.iter().flatten().for_each(|j| bytes.extend_from_slice(&j.to_le_bytes()));
+        self.weights.iter().flatten().for_each(|w| bytes.extend_from_slice(&w.to_le_bytes()));
+        self.indices.iter().for_each(|i| bytes.extend_from_slice(&i.to_le_bytes()));
+
+        for sub in self.submeshes.iter() {
+
+            bytes.extend_from_slice(&sub.start.to_le_bytes());
+            bytes.extend_from_slice(&sub.count.to_le_bytes());
+
+            match &sub.material {
+                Some(name) => {
+                    bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+                    bytes.extend_from_slice(name.as_bytes());
+                },
+                None => bytes.extend_from_slice(&u32::MAX.to_le_bytes())
+            }
+
+        }
+
+        bytes
+
+    }
+    //
+    /// Decode a mesh in the engine binary format
+    ///
+    /// # Parameters
+    ///
+    /// * 'bytes' - the encoded mesh
+    ///
+    pub fn decode(bytes:&[u8]) -> Result<Mesh,EMesh> {
+
+        if !bytes.starts_with(MESH_MAGIC) {
+
+            return Err(EMesh::Format.as_report().attach_printable("missing the signature of a cobia mesh"));
+
+        }
+
+        let mut reader = Reader { bytes, at: MESH_MAGIC.len() };
+
+        let version = reader.u32()?;
+
+        if version != MESH_VERSION {
+
+            return Err(EMesh::Format
+                .as_report()
+                .attach_printable(format!("cobia mesh version {} is not supported",version))
+            );
+
+        }
+
+        let flags = reader.u32()?;
+        let vertices = reader.u32()? as usize;
+        let index_count = reader.u32()? as usize;
+        let submesh_count = reader.u32()? as usize;
+
+        let positions = reader.vectors::<3>(vertices)?;
+
+        let normals = match flags & HAS_NORMALS != 0 {
+            true => reader.vectors::<3>(vertices)?,
+            false => Vec::new()
+        };
+
+        let uvs = match flags & HAS_UVS != 0 {
+            true => reader.vectors::<2>(vertices)?,
+            false => Vec::new()
+        };
+
+        let tangents = match flags & HAS_TANGENTS != 0 {
+            true => reader.vectors::<4>(vertices)?,
+            false => Vec::new()
+        };
+
+        let uvs1 = match flags & HAS_UVS1 != 0 {
+            true => reader.vectors::<2>(vertices)?,
+            false => Vec::new()
+        };
+
+        let colors = match flags & HAS_COLORS != 0 {
+            true => reader.vectors::<4>(vertices)?,
+            false => Vec::new()
+        };
+
+        let (joints, weights) = match flags & HAS_SKIN != 0 {
+            true => (reader.joints(vertices)?, reader.vectors::<4>(vertices)?),
+            false => (Vec::new(), Vec::new())
+        };
+
+        let indices = (0..index_count).map(|_| reader.u32()).collect::<Result<Vec<u32>,EMesh>>()?;
+
+        let mut submeshes = Vec::with_capacity(submesh_count.min(bytes.len()));
+
+        for _ in 0..submesh_count {
+
+            let start = reader.u32()?;
+            let count = reader.u32()?;
+
+            let material = match reader.u32()? {
+                u32::MAX => None,
+                len => {
+                    let name = reader.take(len as usize)?;
+                    Some(String::from_utf8(name.to_vec()).map_err(|e| EMesh::Format.attach_printable_default(e))?)
+                }
+            };
+
+            submeshes.push(SubMesh { start, count, material });
+
+        }
+
+        Mesh::new(positions, normals, uvs, indices, submeshes)?
+            .with_tangents(tangents)?
+            .with_uvs1(uvs1)?
+            .with_colors(colors)?
+            .with_skin(joints, weights)
+
+    }
+    //
+}
+//
+//
+/// Read the values of an encoded mesh one after the other
+struct Reader<'a> {
+
+    bytes: &'a [u8],
+    at: usize
+
+}
+//
+impl<'a> Reader<'a> {
+    //
+    fn take(&mut self, len:usize) -> Result<&'a [u8],EMesh> {
+
+        let slice = self.bytes.get(self.at..self.at.saturating_add(len)).ok_or_else(||
+            EMesh::Format.as_report().attach_printable("the cobia mesh is truncated")
+        )?;
+
+        self.at += len;
+
+        Ok(slice)
+
+    }
+    //
+    fn u32(&mut self) -> Result<u32,EMesh> {
+
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+
+    }
+    //
+    fn vectors<const N:usize>(&mut self, count:usize) -> Result<Vec<[f32;N]>,EMesh> {
+
+        let data = self.take(count.saturating_mul(N * 4))?;
+
+        Ok(data
+            .chunks_exact(N * 4)
+            .map(|v| std::array::from_fn(|i| f32::from_le_bytes([v[i * 4], v[i * 4 + 1], v[i * 4 + 2], v[i * 4 + 3]])))
+            .collect())
+
+    }
+    //
+    fn joints(&mut self, count:usize) -> Result<Vec<[u16;4]>,EMesh> {
+
+        let data = self.take(count.saturating_mul(8))?;
+
+        Ok(data
+            .chunks_exact(8)
+            .map(|v| std::array::from_fn(|i| u16::from_le_bytes([v[i * 2], v[i * 2 + 1]])))
+            .collect())
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load a mesh in the engine binary format from the disk
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the mesh
+///
+pub fn load_mesh(fp:&str) -> Result<Mesh,EMesh> {
+
+    let content = get_file_content(fp)
+        .change_context(EMesh::Load)
+        .attach_printable_lazy(|| format!("Can't load mesh {}",fp))?;
+
+    Mesh::decode(&content).attach_printable_lazy(|| format!("Can't load mesh {}",fp))
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Vectors
+//
+pub(crate) fn sub(a:[f32;3], b:[f32;3]) -> [f32;3] { [a[0] - b[0], a[1] - b[1], a[2] - b[2]] }
+//
+/// the vector with a length of 1, or the up vector for a null one
+pub(crate) fn normalize(v:[f32;3]) -> [f32;3] {
+
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+
+    match len > f32::EPSILON {
+        true => [v[0] / len, v[1] / len, v[2] / len],
+        false => [0.0, 1.0, 0.0]
+    }
+
+}
+//
+//
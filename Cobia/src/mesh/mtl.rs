@@ -0,0 +1,231 @@
+// Wavefront MTL material libraries, referenced by the OBJ files
+
+use super::obj::floats;
+use crate::core::error_handler::EMesh;
+use crate::core::file::get_file_content;
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn mtl_materials() {
+
+        let text = "\
+            # two materials\n\
+            newmtl wood\n\
+            Kd 0.5 0.25 0.1\n\
+            Ns 32\n\
+            map_Kd -s 2 2 1 textures/wood.png\n\
+            map_Bump wood_normal.png\n\
+            \n\
+            newmtl glass\n\
+            Ks 1 1 1\n\
+            Tr 0.75\n";
+
+        let library = decode_mtl(text.as_bytes()).unwrap();
+
+        assert_eq!(library.len(), 2);
+
+        let wood = library.get("wood").unwrap();
+        assert_eq!(wood.diffuse, [0.5, 0.25, 0.1]);
+        assert_eq!(wood.shininess, 32.0);
+        assert_eq!(wood.diffuse_map.as_deref(), Some("textures/wood.png"));
+        assert_eq!(wood.normal_map.as_deref(), Some("wood_normal.png"));
+        assert_eq!(wood.opacity, 1.0);
+
+        let glass = library.get("glass").unwrap();
+        assert_eq!(glass.diffuse, Material::new("default").diffuse);
+        assert_eq!(glass.opacity, 0.25);
+
+        assert!(library.get("stone").is_none());
+        assert!(decode_mtl(b"Kd 1 1 1\n").is_err());
+        assert!(decode_mtl(b"newmtl a\nKd 1 one 1\n").is_err());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Material
+//
+/// Surface of a submesh as described by a material library. The texture paths are the ones
+/// written in the library, relative to its directory
+#[derive(Debug,Clone,PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub ambient: [f32;3],
+    pub diffuse: [f32;3],
+    pub specular: [f32;3],
+    pub emissive: [f32;3],
+    // specular exponent
+    pub shininess: f32,
+    // 1 for opaque
+    pub opacity: f32,
+    pub diffuse_map: Option<String>,
+    pub specular_map: Option<String>,
+    pub normal_map: Option<String>
+}
+//
+impl Material {
+    //
+    /// a white material without texture, the default values of the MTL format
+    pub fn new(name:&str) -> Self {
+
+        Self {
+            name:           name.to_string(),
+            ambient:        [0.0; 3],
+            diffuse:        [0.8; 3],
+            specular:       [0.0; 3],
+            emissive:       [0.0; 3],
+            shininess:      0.0,
+            opacity:        1.0,
+            diffuse_map:    None,
+            specular_map:   None,
+            normal_map:     None
+        }
+
+    }
+    //
+}
+//
+//
+/// The materials of an MTL file, in their order in the file
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct MaterialLibrary {
+
+    materials: Vec<Material>
+
+}
+//
+impl MaterialLibrary {
+    //
+    pub fn new() -> Self { Self::default() }
+    //
+    /// Add a material, it replace the one with the same name
+    ///
+    /// # Parameters
+    ///
+    /// * 'material' - the new material
+    ///
+    pub fn add(&mut self, material:Material) {
+
+        match self.materials.iter_mut().find(|m| m.name == material.name) {
+            Some(existing) => *existing = material,
+            None => self.materials.push(material)
+        }
+
+    }
+    //
+    /// Add every material of another library
+    pub fn extend(&mut self, other:MaterialLibrary) { other.materials.into_iter().for_each(|m| self.add(m)); }
+    //
+    /// the material with this name
+    pub fn get(&self, name:&str) -> Option<&Material> { self.materials.iter().find(|m| m.name == name) }
+    //
+    pub fn iter(&self) -> impl Iterator<Item = &Material> { self.materials.iter() }
+    //
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Material> { self.materials.iter_mut() }
+    //
+    pub fn len(&self) -> usize { self.materials.len() }
+    //
+    pub fn is_empty(&self) -> bool { self.materials.is_empty() }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load a material library from the disk
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the mtl file
+///
+pub fn load_mtl(fp:&str) -> Result<MaterialLibrary,EMesh> {
+
+    let content = get_file_content(fp)
+        .change_context(EMesh::Load)
+        .attach_printable_lazy(|| format!("Can't load material library {}",fp))?;
+
+    decode_mtl(&content).attach_printable_lazy(|| format!("Can't load material library {}",fp))
+
+}
+//
+//
+/// Decode a material library already in memory, the statements not listed in Material are
+/// ignored
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the mtl file
+///
+pub fn decode_mtl(bytes:&[u8]) -> Result<MaterialLibrary,EMesh> {
+
+    let text = String::from_utf8_lossy(bytes);
+
+    let mut library = MaterialLibrary::new();
+    let mut current:Option<Material> = None;
+
+    for (i, line) in text.lines().enumerate() {
+
+        let context = || format!("mtl line {}: {}",i + 1,line.trim());
+        let mut parts = line.split_whitespace();
+
+        let keyword = match parts.next() {
+            Some(k) if !k.starts_with('#') => k,
+            _ => continue
+        };
+
+        if keyword == "newmtl" {
+
+            if let Some(material) = current.take() {
+                library.add(material);
+            }
+
+            current = Some(Material::new(&parts.collect::<Vec<&str>>().join(" ")));
+            continue;
+
+        }
+
+        let material = current.as_mut().ok_or_else(||
+            EMesh::Parse.as_report().attach_printable(format!("{}: the statement is before any 'newmtl'",context()))
+        )?;
+
+        // the options of the texture maps come before the file name
+        let map = || parts.clone().last().map(|p| p.to_string());
+
+        match keyword {
+            "Ka" => material.ambient = floats::<3>(parts.clone()).attach_printable_lazy(context)?,
+            "Kd" => material.diffuse = floats::<3>(parts.clone()).attach_printable_lazy(context)?,
+            "Ks" => material.specular = floats::<3>(parts.clone()).attach_printable_lazy(context)?,
+            "Ke" => material.emissive = floats::<3>(parts.clone()).attach_printable_lazy(context)?,
+            "Ns" => material.shininess = floats::<1>(parts.clone()).attach_printable_lazy(context)?[0],
+            "d" => material.opacity = floats::<1>(parts.clone()).attach_printable_lazy(context)?[0],
+            "Tr" => material.opacity = 1.0 - floats::<1>(parts.clone()).attach_printable_lazy(context)?[0],
+            "map_Kd" => material.diffuse_map = map(),
+            "map_Ks" => material.specular_map = map(),
+            "map_Bump" | "map_bump" | "bump" | "norm" => material.normal_map = map(),
+            _ => {}
+        }
+
+    }
+
+    if let Some(material) = current {
+        library.add(material);
+    }
+
+    Ok(library)
+
+}
+//
+//
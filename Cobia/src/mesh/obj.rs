@@ -0,0 +1,569 @@
+// Wavefront OBJ importer
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{Mesh, SubMesh, normalize, sub};
+use super::mtl::{MaterialLibrary, load_mtl};
+use crate::core::error_handler::EMesh;
+use crate::core::file::get_file_content;
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::get_relative_path;
+
+    #[test]
+    fn load_obj_fixture() {
+
+        let mesh = load_obj(&get_relative_path("tests/fixtures/mesh/quad.obj")).unwrap();
+
+        // the two triangles share two corners
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.indices(), &[0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.positions()[2], [1.0, 1.0, 0.0]);
+        assert_eq!(mesh.uvs()[1], [1.0, 0.0]);
+        assert_eq!(mesh.normals()[3], [0.0, 0.0, 1.0]);
+
+    }
+
+    #[test]
+    fn obj_faces() {
+
+        // a pentagon with relative indices and no uv or normal
+        let text = "v 0 0 0\nv 1 0 0\nv 2 1 0\nv 1 2 0\nv 0 1 0\nf -5 -4 -3 -2 -1\n";
+        let mesh = decode_obj(text.as_bytes()).unwrap();
+
+        assert_eq!(mesh.indices(), &[0, 1, 2, 0, 2, 3, 0, 3, 4]);
+        assert!(mesh.uvs().is_empty());
+        assert!(mesh.normals().iter().all(|n| *n == [0.0, 0.0, 1.0]));
+
+        // the same position with two uvs give two vertices
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 1\nf 1/1 2/1 3/1\nf 1/2 3/1 2/1\n";
+        assert_eq!(decode_obj(text.as_bytes()).unwrap().vertex_count(), 4);
+
+        // a concave polygon can't be a fan from its first corner
+        let text = "v 1 0.5 0\nv 0 2 0\nv 0 0 0\nv 2 0 0\nv 2 2 0\nf 2 3 4 5 1\n";
+        let mesh = decode_obj(text.as_bytes()).unwrap();
+
+        // every triangle start from the notch at the last corner
+        assert_eq!(mesh.triangle_count(), 3);
+        assert!(mesh.indices().chunks(3).all(|t| t.contains(&4)));
+        assert!(!mesh.indices().chunks(3).any(|t| t.contains(&0) && t.contains(&2)));
+
+        assert!(decode_obj(b"v 0 0 0\nf 1 2 3\n").is_err());
+        assert!(decode_obj(b"v 0 zero 0\n").is_err());
+        assert!(decode_obj(b"v 0 0 0\nv 1 0 0\nf 1 2\n").is_err());
+
+    }
+
+    #[test]
+    fn generated_normals() {
+
+        // two triangles of a tent sharing the edge 1-2, the normals are smoothed along it
+        let text = "v 0 0 0\nv 1 1 0\nv 1 1 1\nv 2 0 0\nf 1 3 2\nf 4 2 3\n";
+        let mesh = decode_obj(text.as_bytes()).unwrap();
+
+        let normals = mesh.normals();
+        let close = |a:[f32;3], b:[f32;3]| a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-5);
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+
+        assert_eq!(normals.len(), 4);
+        assert!(close(normals[0], [-h, h, 0.0]));
+        assert!(close(normals[3], [h, h, 0.0]));
+        assert!(close(normals[1], [0.0, 1.0, 0.0]));
+
+    }
+
+    #[test]
+    fn load_obj_with_materials() {
+
+        let model = load_obj_model(&get_relative_path("tests/fixtures/mesh/crate.obj")).unwrap();
+        let mesh = &model.mesh;
+
+        // a face per material
+        assert_eq!(mesh.submeshes(), &[
+            SubMesh { start: 0, count: 12, material: Some("wood".to_string()) },
+            SubMesh { start: 12, count: 6, material: Some("metal".to_string()) }
+        ]);
+
+        assert_eq!(mesh.vertex_count(), 12);
+        assert_eq!(mesh.normals().len(), 12);
+        assert_eq!(model.libraries, vec!["crate.mtl".to_string()]);
+        assert_eq!(model.materials.get("metal").unwrap().shininess, 96.0);
+        assert_eq!(model.materials.get("wood").unwrap().diffuse_map.as_deref(), Some("wood.png"));
+
+        // the faces before any material have none
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 1 3 2\n";
+        let mesh = decode_obj(text.as_bytes()).unwrap();
+
+        assert_eq!(mesh.submeshes()[0].material, None);
+        assert_eq!(mesh.submeshes()[1].material.as_deref(), Some("red"));
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load an obj file from the disk
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the obj file
+///
+pub fn load_obj(fp:&str) -> Result<Mesh,EMesh> {
+
+    let content = get_file_content(fp)
+        .change_context(EMesh::Load)
+        .attach_printable_lazy(|| format!("Can't load mesh {}",fp))?;
+
+    decode_obj(&content).attach_printable_lazy(|| format!("Can't load mesh {}",fp))
+
+}
+//
+//
+/// An obj file with the materials of its libraries
+#[derive(Debug,Clone)]
+pub struct ObjModel {
+    pub mesh: Mesh,
+    // the 'mtllib' files, as written in the obj file
+    pub libraries: Vec<String>,
+    pub materials: MaterialLibrary
+}
+//
+//
+/// load an obj file and its material libraries, found relative to the obj file
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the obj file
+///
+pub fn load_obj_model(fp:&str) -> Result<ObjModel,EMesh> {
+
+    let content = get_file_content(fp)
+        .change_context(EMesh::Load)
+        .attach_printable_lazy(|| format!("Can't load mesh {}",fp))?;
+
+    let (mesh, libraries) = parse(&content).attach_printable_lazy(|| format!("Can't load mesh {}",fp))?;
+
+    let dir = Path::new(fp).parent().unwrap_or(Path::new(""));
+    let mut materials = MaterialLibrary::new();
+
+    for library in libraries.iter() {
+
+        let path = dir.join(library);
+
+        let loaded = load_mtl(&path.to_string_lossy())
+            .attach_printable_lazy(|| format!("Can't load the materials of mesh {}",fp))?;
+
+        materials.extend(loaded);
+
+    }
+
+    Ok(ObjModel { mesh, libraries, materials })
+
+}
+//
+//
+/// Decode an obj file already in memory. The polygons are split in triangles, the corners with
+/// the same position, uv and normal become a single vertex and the faces are grouped in a
+/// submesh per material. The missing normals are generated from the faces
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the obj file
+///
+pub fn decode_obj(bytes:&[u8]) -> Result<Mesh,EMesh> {
+
+    parse(bytes).map(|(mesh, _)| mesh)
+
+}
+//
+//
+/// the mesh of an obj file and its material libraries
+fn parse(bytes:&[u8]) -> Result<(Mesh, Vec<String>),EMesh> {
+
+    let text = String::from_utf8_lossy(bytes);
+
+    let mut builder = ObjBuilder::default();
+
+    for (i, line) in text.lines().enumerate() {
+
+        builder
+            .line(line)
+            .attach_printable_lazy(|| format!("obj line {}: {}",i + 1,line.trim()))?;
+
+    }
+
+    let libraries = std::mem::take(&mut builder.libraries);
+
+    Ok((builder.build()?, libraries))
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Parsing
+//
+/// Index of the position, uv and normal of a face corner, starting at 0
+type Corner = (usize, Option<usize>, Option<usize>);
+//
+//
+#[derive(Default)]
+struct ObjBuilder {
+
+    positions:  Vec<[f32;3]>,
+    uvs:        Vec<[f32;2]>,
+    normals:    Vec<[f32;3]>,
+    // the vertex made for every distinct corner
+    vertices:   HashMap<Corner, u32>,
+    corners:    Vec<Corner>,
+    // the triangles of every material, in the order the materials are used
+    groups:     Vec<(Option<String>, Vec<u32>)>,
+    current:    usize,
+    libraries:  Vec<String>
+
+}
+//
+impl ObjBuilder {
+    //
+    fn line(&mut self, line:&str) -> Result<(),EMesh> {
+
+        let mut parts = line.split_whitespace();
+
+        match parts.next() {
+
+            Some("v") => {
+                let v = floats::<3>(parts)?;
+                self.positions.push(v);
+            },
+
+            Some("vt") => {
+                // the optional w coordinate is ignored
+                let vt = floats::<2>(parts)?;
+                self.uvs.push(vt);
+            },
+
+            Some("vn") => {
+                let vn = floats::<3>(parts)?;
+                self.normals.push(vn);
+            },
+
+            Some("f") => {
+
+                let corners = parts.map(|c| self.corner(c)).collect::<Result<Vec<Corner>,EMesh>>()?;
+
+                if corners.len() < 3 {
+                    return Err(EMesh::Parse.as_report().attach_printable("a face need at least 3 corners"));
+                }
+
+                let points:Vec<[f32;3]> = corners.iter().map(|c| self.positions[c.0]).collect();
+                let indices:Vec<u32> = corners.iter().map(|c| self.vertex(*c)).collect();
+
+                if self.groups.is_empty() {
+                    self.groups.push((None, Vec::new()));
+                }
+
+                let group = &mut self.groups[self.current].1;
+
+                for [a, b, c] in triangulate(&points) {
+                    group.extend_from_slice(&[indices[a], indices[b], indices[c]]);
+                }
+
+            },
+
+            Some("usemtl") => {
+
+                let name = parts.collect::<Vec<&str>>().join(" ");
+
+                self.current = match self.groups.iter().position(|g| g.0.as_deref() == Some(name.as_str())) {
+                    Some(i) => i,
+                    None => {
+                        self.groups.push((Some(name), Vec::new()));
+                        self.groups.len() - 1
+                    }
+                };
+
+            },
+
+            Some("mtllib") => {
+                // the names can't have spaces, several libraries can be given
+                self.libraries.extend(parts.map(|l| l.to_string()));
+            },
+
+            // comments, objects, groups and smoothing are not used, the generated normals are
+            // smooth everywhere
+            _ => {}
+
+        }
+
+        Ok(())
+
+    }
+    //
+    /// Parse a face corner, 'v', 'v/vt', 'v//vn' or 'v/vt/vn'
+    fn corner(&self, corner:&str) -> Result<Corner,EMesh> {
+
+        let mut parts = corner.split('/');
+
+        let position = resolve(parts.next(), self.positions.len())?
+            .ok_or_else(|| EMesh::Parse.as_report().attach_printable(format!("'{}' has no position",corner)))?;
+
+        let uv = resolve(parts.next(), self.uvs.len())?;
+        let normal = resolve(parts.next(), self.normals.len())?;
+
+        Ok((position, uv, normal))
+
+    }
+    //
+    /// index of the vertex of a corner, created the first time the corner is seen
+    fn vertex(&mut self, corner:Corner) -> u32 {
+
+        let next = self.corners.len() as u32;
+
+        *self.vertices.entry(corner).or_insert_with(|| {
+            self.corners.push(corner);
+            next
+        })
+
+    }
+    //
+    fn build(mut self) -> Result<Mesh,EMesh> {
+
+        let has_uvs = self.corners.iter().any(|c| c.1.is_some());
+
+        let positions = self.corners.iter().map(|c| self.positions[c.0]).collect();
+
+        let uvs = match has_uvs {
+            true => self.corners.iter().map(|c| c.1.map_or([0.0; 2], |i| self.uvs[i])).collect(),
+            false => Vec::new()
+        };
+
+        let mut indices = Vec::new();
+        let mut submeshes = Vec::new();
+
+        for (material, group) in std::mem::take(&mut self.groups) {
+
+            if group.is_empty() {
+                continue;
+            }
+
+            submeshes.push(SubMesh { start: indices.len() as u32, count: group.len() as u32, material });
+            indices.extend(group);
+
+        }
+
+        let generated = match self.corners.iter().any(|c| c.2.is_none()) {
+            true => self.smooth_normals(&indices),
+            false => Vec::new()
+        };
+
+        let normals = self.corners
+            .iter()
+            .map(|c| c.2.map_or_else(|| generated[c.0], |i| self.normals[i]))
+            .collect();
+
+        Mesh::new(positions, normals, uvs, indices, submeshes)
+
+    }
+    //
+    /// A normal per position, the average of the normals of the faces around it weighted by
+    /// their area
+    ///
+    /// # Parameters
+    ///
+    /// * 'indices' - the triangles, as indices of corners
+    ///
+    fn smooth_normals(&self, indices:&[u32]) -> Vec<[f32;3]> {
+
+        let mut normals = vec![[0.0f32; 3]; self.positions.len()];
+
+        for triangle in indices.chunks_exact(3) {
+
+            let ids = [0, 1, 2].map(|i| self.corners[triangle[i] as usize].0);
+            let [a, b, c] = ids.map(|i| self.positions[i]);
+
+            // the length of the cross product is twice the area of the triangle
+            let n = cross(sub(b, a), sub(c, a));
+
+            for id in ids {
+                (0..3).for_each(|k| normals[id][k] += n[k]);
+            }
+
+        }
+
+        normals.iter().map(|n| normalize(*n)).collect()
+
+    }
+    //
+}
+//
+//
+/// Parse the first N numbers of a line
+pub(super) fn floats<'a, const N:usize>(mut parts:impl Iterator<Item = &'a str>) -> Result<[f32;N],EMesh> {
+
+    let mut values = [0.0; N];
+
+    for v in values.iter_mut() {
+
+        let part = parts.next().unwrap_or_default();
+
+        *v = part.parse().map_err(|_|
+            EMesh::Parse.as_report().attach_printable(format!("'{}' is not a number",part))
+        )?;
+
+    }
+
+    Ok(values)
+
+}
+//
+//
+/// Turn an obj index into an index starting at 0, the negative ones are relative to the end
+///
+/// # Parameters
+///
+/// * 'index' - the index as written in the file, None or empty if there is none
+/// * 'count' - number of elements defined so far
+///
+fn resolve(index:Option<&str>, count:usize) -> Result<Option<usize>,EMesh> {
+
+    let index = match index {
+        None | Some("") => return Ok(None),
+        Some(i) => i
+    };
+
+    let value:i64 = index.parse().map_err(|_|
+        EMesh::Parse.as_report().attach_printable(format!("'{}' is not an index",index))
+    )?;
+
+    let resolved = match value {
+        v if v > 0 => v - 1,
+        v if v < 0 => count as i64 + v,
+        _ => -1
+    };
+
+    match resolved >= 0 && (resolved as usize) < count {
+        true => Ok(Some(resolved as usize)),
+        false => Err(EMesh::Parse
+            .as_report()
+            .attach_printable(format!("the index {} is out of the {} elements",value,count))
+        )
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Triangulation
+//
+/// Split a polygon in triangles by cutting its ears, so the concave polygons are supported. The
+/// triangles are indices in the polygon and keep its winding, a convex polygon give a fan from
+/// its first corner
+///
+/// # Parameters
+///
+/// * 'points' - the corners of the polygon, in order
+///
+fn triangulate(points:&[[f32;3]]) -> Vec<[usize;3]> {
+
+    let fan = || (1..points.len() - 1).map(|i| [0, i, i + 1]).collect();
+
+    if points.len() == 3 {
+        return fan();
+    }
+
+    // the polygon is projected on the plane of the axes where it is the biggest
+    let normal = newell_normal(points);
+    let axis = (0..3).max_by(|a, b| normal[*a].abs().total_cmp(&normal[*b].abs())).unwrap_or(2);
+
+    let (u, v) = match axis {
+        0 => (1, 2),
+        1 => (2, 0),
+        _ => (0, 1)
+    };
+
+    // counter clockwise when seen from the side of the normal
+    let flat:Vec<[f32;2]> = points.iter().map(|p| [p[u], p[v]]).collect();
+    let orientation = if normal[axis] < 0.0 { -1.0 } else { 1.0 };
+
+    let area = |a:[f32;2], b:[f32;2], c:[f32;2]| orientation * ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]));
+
+    let mut remaining:Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while remaining.len() > 3 {
+
+        let n = remaining.len();
+
+        let ear = (1..=n).map(|j| j % n).find(|&j| {
+
+            let (a, b, c) = (remaining[(j + n - 1) % n], remaining[j], remaining[(j + 1) % n]);
+            let (pa, pb, pc) = (flat[a], flat[b], flat[c]);
+
+            // a convex corner without any other corner inside its triangle
+            area(pa, pb, pc) > 0.0 && !remaining.iter().any(|&o| {
+                o != a && o != b && o != c
+                    && area(pa, pb, flat[o]) >= 0.0
+                    && area(pb, pc, flat[o]) >= 0.0
+                    && area(pc, pa, flat[o]) >= 0.0
+            })
+
+        });
+
+        // a degenerated or self intersecting polygon
+        let j = match ear {
+            Some(j) => j,
+            None => return fan()
+        };
+
+        triangles.push([remaining[(j + n - 1) % n], remaining[j], remaining[(j + 1) % n]]);
+        remaining.remove(j);
+
+    }
+
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+
+    triangles
+
+}
+//
+//
+/// Normal of a polygon that may not be planar, its length is twice the area of the polygon
+fn newell_normal(points:&[[f32;3]]) -> [f32;3] {
+
+    let mut normal = [0.0; 3];
+
+    for (i, p) in points.iter().enumerate() {
+
+        let q = points[(i + 1) % points.len()];
+
+        normal[0] += (p[1] - q[1]) * (p[2] + q[2]);
+        normal[1] += (p[2] - q[2]) * (p[0] + q[0]);
+        normal[2] += (p[0] - q[0]) * (p[1] + q[1]);
+
+    }
+
+    normal
+
+}
+//
+//
+fn cross(a:[f32;3], b:[f32;3]) -> [f32;3] {
+
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+
+}
+//
+//
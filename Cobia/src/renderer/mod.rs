@@ -31,7 +31,8 @@ use vulkano::{
 
 use error_stack::Result;
 
-use crate::core::error_handler::{ERendering, EVlkApi};
+use crate::core::error_handler::{EImage, ERendering, EVlkApi};
+use crate::image::TextureData;
 use crate::define::ENGINE_VERSION;
 
 //
@@ -118,6 +119,13 @@ impl RenderingSys {
 
         self.recreate_swapchain = false;
 
+    }
+    //
+    /// check that a texture can be uploaded to the device before doing it
+    pub(crate) fn check_texture_support(&self, texture:&TextureData) -> Result<(),EImage> {
+
+        texture.check_device_support(&self.vlk_sys.get_device())
+
     }
     //
     pub(crate) fn free_gpu_resource(&mut self) {
@@ -359,6 +359,16 @@ impl VlkBase {
             ..DeviceExtensions::default()
         };
 
+        let supported = pdevice.supported_features();
+
+        // compressed textures can only be used when their feature is enabled
+        let compression = Features {
+            texture_compression_bc:         supported.texture_compression_bc,
+            texture_compression_etc2:       supported.texture_compression_etc2,
+            texture_compression_astc_ldr:   supported.texture_compression_astc_ldr,
+            ..Features::default()
+        };
+
         // return also an iterator of created queue
         let (dev,mut queue) = Device::new(
 
@@ -369,7 +379,7 @@ impl VlkBase {
                 enabled_features:  Features {
                     // TODO: add other features needed with maybe conditions
                     dynamic_rendering: true,
-                    ..Features::default()
+                    ..compression
 
                 },
                 // TODO: check for other needed and possible queue
@@ -0,0 +1,719 @@
+// glTF 2.0 importer, for the .gltf files and their buffers and for the binary .glb files
+
+use std::path::Path;
+
+use ::gltf::{Gltf, buffer, image, mesh::Mode};
+use ::gltf::animation::{Interpolation as GltfInterpolation, Property, util::ReadOutputs};
+use ::gltf::material::AlphaMode as GltfAlphaMode;
+use ::gltf::texture::Info;
+
+use super::{
+    AlphaMode,
+    Animation,
+    AnimationChannel,
+    AnimationProperty,
+    IDENTITY,
+    ImageSource,
+    Interpolation,
+    Node,
+    PbrMaterial,
+    Scene,
+    SceneMesh,
+    Skin,
+    TextureRef,
+    Transform
+};
+use crate::core::error_handler::EScene;
+use crate::core::file::get_file_content;
+use crate::mesh::{Mesh, SubMesh, normalize, sub};
+
+use error_stack::{Result, ResultExt};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::get_relative_path;
+
+    fn close(a:&[f32], b:&[f32]) -> bool {
+
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-5)
+
+    }
+
+    // the content of the fixture, the same with every kind of buffer
+    fn check_scene(scene:&Scene) {
+
+        // nodes
+        assert_eq!(scene.nodes.len(), 3);
+        assert_eq!(scene.roots, vec![0]);
+
+        let body = scene.find_node("body").unwrap();
+        let bone = scene.find_node("bone").unwrap();
+
+        assert_eq!(scene.nodes[0].children, vec![body, bone]);
+        assert_eq!(scene.nodes[body].parent, Some(0));
+        assert_eq!(scene.nodes[body].transform.scale, [2.0; 3]);
+        assert_eq!((scene.nodes[body].mesh, scene.nodes[body].skin), (Some(0), Some(0)));
+
+        let world = scene.world_matrices();
+        assert!(close(&world[bone][3], &[1.0, 1.0, 0.0, 1.0]));
+        assert!(close(&world[body][0], &[0.0, 2.0, 0.0, 0.0]));
+
+        // mesh, the second primitive has no normals so it is split in flat triangles
+        let panel = &scene.meshes[0];
+        let mesh = &panel.mesh;
+
+        assert_eq!(panel.name.as_deref(), Some("panel"));
+        assert_eq!(panel.materials, vec![Some(0), None]);
+        assert_eq!(mesh.submeshes()[0], SubMesh { start: 0, count: 3, material: Some("painted".to_string()) });
+        assert_eq!(mesh.submeshes()[1], SubMesh { start: 3, count: 6, material: None });
+
+        assert_eq!(mesh.vertex_count(), 3 + 6);
+        assert_eq!(mesh.uvs()[1], [1.0, 0.0]);
+        assert_eq!(mesh.joints()[0], [0, 1, 0, 0]);
+        assert_eq!(mesh.weights()[0], [0.5, 0.5, 0.0, 0.0]);
+        assert_eq!(mesh.weights()[5], [1.0, 0.0, 0.0, 0.0]);
+        assert!(mesh.normals()[3..].iter().all(|n| close(n, &[0.0, 0.0, 1.0])));
+        assert!(mesh.tangents().is_empty() && mesh.colors().is_empty());
+
+        // material
+        let material = &scene.materials[0];
+
+        assert_eq!(material.name.as_deref(), Some("painted"));
+        assert_eq!(material.base_color, [1.0, 0.5, 0.25, 1.0]);
+        assert_eq!(material.base_color_texture, Some(TextureRef { image: 0, tex_coord: 0 }));
+        assert_eq!((material.metallic, material.roughness), (0.25, 0.75));
+        assert_eq!(material.normal_scale, 0.5);
+        assert_eq!(material.alpha_mode, AlphaMode::Mask(0.3));
+        assert!(material.double_sided && material.occlusion_texture.is_none());
+        assert_eq!(scene.images, vec![ImageSource::File("checker board.png".to_string())]);
+
+        // skin
+        let skin = &scene.skins[0];
+
+        assert_eq!(skin.joints, vec![0, bone]);
+        assert_eq!(skin.skeleton, Some(0));
+        assert_eq!(skin.inverse_bind_matrices[0], IDENTITY);
+        assert_eq!(skin.inverse_bind_matrices[1][3], [-1.0, 0.0, 0.0, 1.0]);
+
+        // animation
+        let animation = &scene.animations[0];
+        let rotation = &animation.channels[1];
+
+        assert_eq!(animation.name.as_deref(), Some("wave"));
+        assert_eq!(animation.duration(), 2.0);
+        assert_eq!(animation.channels[0].property, AnimationProperty::Translation);
+        assert_eq!(animation.channels[0].values, vec![0.0, 1.0, 0.0, 0.0, 3.0, 0.0]);
+        assert_eq!((rotation.node, rotation.property, rotation.interpolation), (bone, AnimationProperty::Rotation, Interpolation::Step));
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(close(&rotation.values[4..], &[0.0, 0.0, h, h]));
+
+    }
+
+    #[test]
+    fn load_gltf_external_buffer() {
+
+        check_scene(&load_gltf(&get_relative_path("tests/fixtures/scene/scene.gltf")).unwrap());
+
+    }
+
+    #[test]
+    fn load_gltf_embedded_buffer() {
+
+        let bytes = get_file_content(&get_relative_path("tests/fixtures/scene/embedded.gltf")).unwrap();
+        let scene = decode_gltf(&bytes, |uri| panic!("{} should not be read", uri)).unwrap();
+
+        check_scene(&scene);
+
+    }
+
+    #[test]
+    fn load_glb() {
+
+        // the fixture in a binary container, its buffer become the binary chunk
+        let json = get_file_content(&get_relative_path("tests/fixtures/scene/scene.gltf")).unwrap();
+        let json = String::from_utf8(json).unwrap().replace("\"uri\": \"scene.bin\",", "");
+        let bin = get_file_content(&get_relative_path("tests/fixtures/scene/scene.bin")).unwrap();
+
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        let mut glb = b"glTF".to_vec();
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&bin);
+
+        check_scene(&decode_gltf(&glb, |uri| panic!("{} should not be read", uri)).unwrap());
+
+        assert!(decode_gltf(&glb[..glb.len() - 8], |_| Ok(Vec::new())).is_err());
+        assert!(decode_gltf(b"{\"asset\":{}}", |_| Ok(Vec::new())).is_err());
+
+    }
+
+    #[test]
+    fn attribute_count_mismatch() {
+
+        // 3 positions and 2 uvs, without normals so the vertices would be unwelded
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 52, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAIA/AAAAAA=="}],
+            "bufferViews": [{"buffer": 0, "byteLength": 36}, {"buffer": 0, "byteOffset": 36, "byteLength": 16}],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+                {"bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC2"}
+            ],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0, "TEXCOORD_0": 1}}]}],
+            "nodes": [{"mesh": 0}],
+            "scenes": [{"nodes": [0]}]
+        }"#;
+
+        let report = decode_gltf(json.as_bytes(), |uri| panic!("{} should not be read", uri)).unwrap_err();
+        assert!(format!("{:?}",report).contains("2 uvs for 3 vertices"));
+
+    }
+
+    #[test]
+    fn base64_and_uris() {
+
+        assert_eq!(decode_base64("aGVsbG8gd29ybGQ=").unwrap(), b"hello world");
+        assert_eq!(decode_base64("YQ").unwrap(), b"a");
+        assert!(decode_base64("a$b=").is_err());
+
+        assert_eq!(decode_uri("textures/brick%20wall.png"), "textures/brick wall.png");
+        assert_eq!(decode_uri("100%"), "100%");
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Loading
+//
+/// load a glTF or GLB file from the disk, the external buffers are read relative to it
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the scene
+///
+pub fn load_gltf(fp:&str) -> Result<Scene,EScene> {
+
+    let content = get_file_content(fp)
+        .change_context(EScene::Load)
+        .attach_printable_lazy(|| format!("Can't load scene {}",fp))?;
+
+    let dir = Path::new(fp).parent().unwrap_or(Path::new(""));
+
+    let read = |uri:&str| {
+        let path = dir.join(uri);
+        get_file_content(&path.to_string_lossy()).change_context(EScene::Load)
+    };
+
+    decode_gltf(&content, read).attach_printable_lazy(|| format!("Can't load scene {}",fp))
+
+}
+//
+//
+/// Decode a glTF or GLB file already in memory. The buffers stored in the file or as data uris
+/// are decoded, the others are given by a function, like a read through the asset server so the
+/// scene is reloaded when they change. The images are not decoded, they are only referenced
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the scene file
+/// * 'read' - give the content of an external buffer from its uri, relative to the scene file
+///
+pub fn decode_gltf<F>(bytes:&[u8], mut read:F) -> Result<Scene,EScene>
+where F: FnMut(&str) -> Result<Vec<u8>,EScene> {
+
+    let gltf = Gltf::from_slice(bytes).map_err(|e| EScene::Parse.attach_printable_default(e))?;
+
+    let mut buffers = Vec::new();
+
+    for b in gltf.buffers() {
+
+        let data = match b.source() {
+            buffer::Source::Bin => gltf.blob.clone().ok_or_else(||
+                EScene::Format.as_report().attach_printable("the binary chunk of the glb file is missing")
+            )?,
+            buffer::Source::Uri(uri) => match data_uri(uri) {
+                Some((_, data)) => decode_base64(data)?,
+                None => read(&decode_uri(uri)).attach_printable_lazy(|| format!("Can't read the buffer {}",uri))?
+            }
+        };
+
+        if data.len() < b.length() {
+
+            return Err(EScene::Format
+                .as_report()
+                .attach_printable(format!("the buffer {} has {} bytes instead of {}",b.index(),data.len(),b.length()))
+            );
+
+        }
+
+        buffers.push(data);
+
+    }
+
+    let get_buffer = |b:buffer::Buffer| buffers.get(b.index()).map(|data| data.as_slice());
+
+    let mut scene = Scene::default();
+
+    // images
+    for img in gltf.images() {
+
+        let source = match img.source() {
+            image::Source::View { view, mime_type } => {
+                let data = get_buffer(view.buffer()).unwrap_or_default();
+                let range = view.offset()..view.offset() + view.length();
+                ImageSource::Embedded {
+                    mime_type: mime_type.to_string(),
+                    data: data.get(range).unwrap_or_default().to_vec()
+                }
+            },
+            image::Source::Uri { uri, .. } => match data_uri(uri) {
+                Some((mime_type, data)) => ImageSource::Embedded { mime_type: mime_type.to_string(), data: decode_base64(data)? },
+                None => ImageSource::File(decode_uri(uri))
+            }
+        };
+
+        scene.images.push(source);
+
+    }
+
+    // materials
+    let texture = |info:Option<Info>| info.map(|i| TextureRef { image: i.texture().source().index(), tex_coord: i.tex_coord() });
+
+    for m in gltf.materials() {
+
+        let pbr = m.pbr_metallic_roughness();
+        let normal = m.normal_texture();
+        let occlusion = m.occlusion_texture();
+
+        scene.materials.push(PbrMaterial {
+            name:                       m.name().map(|n| n.to_string()),
+            base_color:                 pbr.base_color_factor(),
+            base_color_texture:         texture(pbr.base_color_texture()),
+            metallic:                   pbr.metallic_factor(),
+            roughness:                  pbr.roughness_factor(),
+            metallic_roughness_texture: texture(pbr.metallic_roughness_texture()),
+            normal_scale:               normal.as_ref().map_or(1.0, |n| n.scale()),
+            normal_texture:             normal.map(|n| TextureRef { image: n.texture().source().index(), tex_coord: n.tex_coord() }),
+            occlusion_strength:         occlusion.as_ref().map_or(1.0, |o| o.strength()),
+            occlusion_texture:          occlusion.map(|o| TextureRef { image: o.texture().source().index(), tex_coord: o.tex_coord() }),
+            emissive:                   m.emissive_factor(),
+            emissive_texture:           texture(m.emissive_texture()),
+            alpha_mode:                 match m.alpha_mode() {
+                GltfAlphaMode::Opaque => AlphaMode::Opaque,
+                GltfAlphaMode::Mask => AlphaMode::Mask(m.alpha_cutoff().unwrap_or(0.5)),
+                GltfAlphaMode::Blend => AlphaMode::Blend
+            },
+            double_sided:               m.double_sided()
+        });
+
+    }
+
+    // meshes
+    for mesh in gltf.meshes() {
+
+        let mut primitives = Vec::new();
+
+        for p in mesh.primitives() {
+
+            let primitive = Primitive::read(&p, get_buffer)
+                .attach_printable_lazy(|| format!("mesh {} primitive {}",mesh.index(),p.index()))?;
+
+            primitives.push((primitive, p.material().index()));
+
+        }
+
+        let materials = primitives.iter().map(|(_, m)| *m).collect();
+
+        let names:Vec<Option<String>> = primitives
+            .iter()
+            .map(|(_, m)| m.and_then(|i| scene.materials.get(i)).and_then(|m| m.name.clone()))
+            .collect();
+
+        let primitives:Vec<Primitive> = primitives.into_iter().map(|(p, _)| p).collect();
+
+        let merged = merge(primitives, names).attach_printable_lazy(|| format!("mesh {}",mesh.index()))?;
+
+        scene.meshes.push(SceneMesh { name: mesh.name().map(|n| n.to_string()), mesh: merged, materials });
+
+    }
+
+    // hierarchy
+    for n in gltf.nodes() {
+
+        let (translation, rotation, scale) = n.transform().decomposed();
+
+        scene.nodes.push(Node {
+            name:       n.name().map(|n| n.to_string()),
+            parent:     None,
+            children:   n.children().map(|c| c.index()).collect(),
+            transform:  Transform { translation, rotation, scale },
+            mesh:       n.mesh().map(|m| m.index()),
+            skin:       n.skin().map(|s| s.index())
+        });
+
+    }
+
+    for i in 0..scene.nodes.len() {
+
+        for c in scene.nodes[i].children.clone() {
+            scene.nodes[c].parent = Some(i);
+        }
+
+    }
+
+    scene.roots = match gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        Some(s) => s.nodes().map(|n| n.index()).collect(),
+        None => (0..scene.nodes.len()).filter(|i| scene.nodes[*i].parent.is_none()).collect()
+    };
+
+    // skins
+    for s in gltf.skins() {
+
+        let joints:Vec<usize> = s.joints().map(|j| j.index()).collect();
+
+        let inverse_bind_matrices = match s.reader(get_buffer).read_inverse_bind_matrices() {
+            Some(matrices) => matrices.collect(),
+            None => vec![IDENTITY; joints.len()]
+        };
+
+        scene.skins.push(Skin {
+            name:       s.name().map(|n| n.to_string()),
+                        joints,
+                        inverse_bind_matrices,
+            skeleton:   s.skeleton().map(|n| n.index())
+        });
+
+    }
+
+    // animations
+    for a in gltf.animations() {
+
+        let mut channels = Vec::new();
+
+        for c in a.channels() {
+
+            let reader = c.reader(get_buffer);
+
+            let times:Vec<f32> = reader.read_inputs().map(|t| t.collect()).unwrap_or_default();
+
+            let values:Vec<f32> = match reader.read_outputs() {
+                Some(ReadOutputs::Translations(t)) => t.flatten().collect(),
+                Some(ReadOutputs::Rotations(r)) => r.into_f32().flatten().collect(),
+                Some(ReadOutputs::Scales(s)) => s.flatten().collect(),
+                Some(ReadOutputs::MorphTargetWeights(w)) => w.into_f32().collect(),
+                None => Vec::new()
+            };
+
+            channels.push(AnimationChannel {
+                node:           c.target().node().index(),
+                property:       match c.target().property() {
+                    Property::Translation => AnimationProperty::Translation,
+                    Property::Rotation => AnimationProperty::Rotation,
+                    Property::Scale => AnimationProperty::Scale,
+                    Property::MorphTargetWeights => AnimationProperty::Weights
+                },
+                interpolation:  match c.sampler().interpolation() {
+                    GltfInterpolation::Linear => Interpolation::Linear,
+                    GltfInterpolation::Step => Interpolation::Step,
+                    GltfInterpolation::CubicSpline => Interpolation::CubicSpline
+                },
+                                times,
+                                values
+            });
+
+        }
+
+        scene.animations.push(Animation { name: a.name().map(|n| n.to_string()), channels });
+
+    }
+
+    Ok(scene)
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Meshes
+//
+/// The attributes of a primitive, the optional ones are empty when missing
+#[derive(Default)]
+struct Primitive {
+
+    positions:  Vec<[f32;3]>,
+    normals:    Vec<[f32;3]>,
+    tangents:   Vec<[f32;4]>,
+    uvs:        Vec<[f32;2]>,
+    uvs1:       Vec<[f32;2]>,
+    colors:     Vec<[f32;4]>,
+    joints:     Vec<[u16;4]>,
+    weights:    Vec<[f32;4]>,
+    indices:    Vec<u32>
+
+}
+//
+impl Primitive {
+    //
+    /// Read the standard attributes of a primitive made of triangles
+    fn read<'a, 's, F>(p:&'a ::gltf::Primitive<'a>, get_buffer:F) -> Result<Primitive,EScene>
+    where F: Clone + Fn(buffer::Buffer<'a>) -> Option<&'s [u8]> {
+
+        let reader = p.reader(get_buffer);
+
+        let positions:Vec<[f32;3]> = reader.read_positions().map(|p| p.collect()).ok_or_else(||
+            EScene::Format.as_report().attach_printable("the primitive has no position")
+        )?;
+
+        let indices:Vec<u32> = match reader.read_indices() {
+            Some(i) => i.into_u32().collect(),
+            None => (0..positions.len() as u32).collect()
+        };
+
+        // the strips and fans are turned into a list of triangles
+        let indices = match p.mode() {
+            Mode::Triangles => indices,
+            Mode::TriangleStrip => (0..indices.len().saturating_sub(2))
+                .flat_map(|i| match i % 2 {
+                    0 => [indices[i], indices[i + 1], indices[i + 2]],
+                    _ => [indices[i + 1], indices[i], indices[i + 2]]
+                })
+                .collect(),
+            Mode::TriangleFan => (1..indices.len().saturating_sub(1))
+                .flat_map(|i| [indices[0], indices[i], indices[i + 1]])
+                .collect(),
+            mode => return Err(EScene::Format
+                .as_report()
+                .attach_printable(format!("the {:?} primitives are not supported, only the triangles",mode))
+            )
+        };
+
+        if let Some(i) = indices.iter().find(|i| **i as usize >= positions.len()) {
+
+            return Err(EScene::Format
+                .as_report()
+                .attach_printable(format!("the index {} is out of the {} vertices",i,positions.len()))
+            );
+
+        }
+
+        let mut primitive = Primitive {
+            normals:    reader.read_normals().map(|n| n.collect()).unwrap_or_default(),
+            tangents:   reader.read_tangents().map(|t| t.collect()).unwrap_or_default(),
+            uvs:        reader.read_tex_coords(0).map(|t| t.into_f32().collect()).unwrap_or_default(),
+            uvs1:       reader.read_tex_coords(1).map(|t| t.into_f32().collect()).unwrap_or_default(),
+            colors:     reader.read_colors(0).map(|c| c.into_rgba_f32().collect()).unwrap_or_default(),
+            joints:     reader.read_joints(0).map(|j| j.into_u16().collect()).unwrap_or_default(),
+            weights:    reader.read_weights(0).map(|w| w.into_f32().collect()).unwrap_or_default(),
+                        positions,
+                        indices
+        };
+
+        // the attributes are read by vertex index, they must all have a value per vertex
+        let counts = [
+            ("normals", primitive.normals.len()),
+            ("tangents", primitive.tangents.len()),
+            ("uvs", primitive.uvs.len()),
+            ("uvs1", primitive.uvs1.len()),
+            ("colors", primitive.colors.len()),
+            ("joints", primitive.joints.len()),
+            ("weights", primitive.weights.len())
+        ];
+
+        if let Some((name, count)) = counts.iter().find(|(_, c)| *c != 0 && *c != primitive.positions.len()) {
+
+            return Err(EScene::Format
+                .as_report()
+                .attach_printable(format!("the primitive has {} {} for {} vertices",count,name,primitive.positions.len()))
+            );
+
+        }
+
+        if primitive.normals.is_empty() {
+            primitive.flat_normals();
+        }
+
+        Ok(primitive)
+
+    }
+    //
+    /// Give every triangle its own vertices with the normal of the triangle, as required by
+    /// glTF for the primitives without normals
+    fn flat_normals(&mut self) {
+
+        fn unweld<T:Copy>(values:&mut Vec<T>, indices:&[u32]) {
+            if !values.is_empty() {
+                *values = indices.iter().map(|i| values[*i as usize]).collect();
+            }
+        }
+
+        let indices = std::mem::take(&mut self.indices);
+
+        unweld(&mut self.positions, &indices);
+        unweld(&mut self.tangents, &indices);
+        unweld(&mut self.uvs, &indices);
+        unweld(&mut self.uvs1, &indices);
+        unweld(&mut self.colors, &indices);
+        unweld(&mut self.joints, &indices);
+        unweld(&mut self.weights, &indices);
+
+        self.indices = (0..indices.len() as u32).collect();
+
+        self.normals = self.positions
+            .chunks_exact(3)
+            .flat_map(|t| {
+                let (u, v) = (sub(t[1], t[0]), sub(t[2], t[0]));
+                [normalize([u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]]); 3]
+            })
+            .collect();
+
+    }
+    //
+}
+//
+//
+/// Put the primitives of a mesh one after the other, each one become a submesh. An attribute
+/// that only some of the primitives have is given a default value in the others
+///
+/// # Parameters
+///
+/// * 'primitives' - the primitives of the mesh
+/// * 'materials' - the name of the material of every primitive
+///
+fn merge(primitives:Vec<Primitive>, materials:Vec<Option<String>>) -> Result<Mesh,EScene> {
+
+    fn append<T:Copy>(to:&mut Vec<T>, values:&[T], count:usize, used:bool, default:T) {
+        match (used, values.len() == count) {
+            (false, _) => {},
+            (true, true) => to.extend_from_slice(values),
+            (true, false) => to.extend(std::iter::repeat_n(default, count))
+        }
+    }
+
+    let has_tangents = primitives.iter().any(|p| !p.tangents.is_empty());
+    let has_uvs = primitives.iter().any(|p| !p.uvs.is_empty());
+    let has_uvs1 = primitives.iter().any(|p| !p.uvs1.is_empty());
+    let has_colors = primitives.iter().any(|p| !p.colors.is_empty());
+    let has_skin = primitives.iter().any(|p| !p.joints.is_empty() && !p.weights.is_empty());
+
+    let mut all = Primitive::default();
+    let mut submeshes = Vec::new();
+
+    for (p, material) in primitives.into_iter().zip(materials) {
+
+        let base = all.positions.len() as u32;
+        let count = p.positions.len();
+
+        submeshes.push(SubMesh { start: all.indices.len() as u32, count: p.indices.len() as u32, material });
+
+        all.indices.extend(p.indices.iter().map(|i| i + base));
+        all.positions.extend_from_slice(&p.positions);
+
+        append(&mut all.normals, &p.normals, count, true, [0.0, 0.0, 1.0]);
+        append(&mut all.tangents, &p.tangents, count, has_tangents, [1.0, 0.0, 0.0, 1.0]);
+        append(&mut all.uvs, &p.uvs, count, has_uvs, [0.0; 2]);
+        append(&mut all.uvs1, &p.uvs1, count, has_uvs1, [0.0; 2]);
+        append(&mut all.colors, &p.colors, count, has_colors, [1.0; 4]);
+        // the vertices without skin follow the first joint
+        append(&mut all.joints, &p.joints, count, has_skin, [0; 4]);
+        append(&mut all.weights, &p.weights, count, has_skin, [1.0, 0.0, 0.0, 0.0]);
+
+    }
+
+    Mesh::new(all.positions, all.normals, all.uvs, all.indices, submeshes)
+        .and_then(|m| m.with_tangents(all.tangents))
+        .and_then(|m| m.with_uvs1(all.uvs1))
+        .and_then(|m| m.with_colors(all.colors))
+        .and_then(|m| m.with_skin(all.joints, all.weights))
+        .change_context(EScene::Format)
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Uris
+//
+/// the mime type and the base64 content of a data uri, None for the other uris
+fn data_uri(uri:&str) -> Option<(&str, &str)> {
+
+    let (header, data) = uri.strip_prefix("data:")?.split_once(',')?;
+
+    Some((header.strip_suffix(";base64").unwrap_or(header), data))
+
+}
+//
+//
+/// Decode the %XX escapes of a relative uri, the invalid ones are kept as is
+fn decode_uri(uri:&str) -> String {
+
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+
+        let escaped = match bytes[i] {
+            b'%' => uri.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()),
+            _ => None
+        };
+
+        match escaped {
+            Some(b) => { decoded.push(b); i += 3; },
+            None => { decoded.push(bytes[i]); i += 1; }
+        }
+
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+
+}
+//
+//
+/// Decode standard base64, the padding is optional
+fn decode_base64(text:&str) -> Result<Vec<u8>,EScene> {
+
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+
+    for c in text.trim_end_matches('=').bytes() {
+
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(EScene::Format
+                .as_report()
+                .attach_printable(format!("'{}' is not a base64 character",c as char))
+            )
+        };
+
+        bits = ((bits << 6) | value as u32) & 0xff_ffff;
+        count += 6;
+
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+
+    }
+
+    Ok(bytes)
+
+}
+//
+//
@@ -0,0 +1,334 @@
+// Scenes imported from 3D content tools: meshes, materials, the node hierarchy, skins and
+// animations, with the glTF 2.0 importer
+
+mod gltf;
+
+pub use self::gltf::{decode_gltf, load_gltf};
+
+use crate::mesh::Mesh;
+//
+//
+/// A 4x4 matrix stored column by column, as in glTF
+pub type Mat4 = [[f32;4];4];
+//
+/// the matrix that does nothing
+pub const IDENTITY: Mat4 = [[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]];
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn transform_matrix() {
+
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+
+        // a quarter turn around z, then a scale of 2 and a translation
+        let transform = Transform { translation: [1.0, 2.0, 3.0], rotation: [0.0, 0.0, h, h], scale: [2.0; 3] };
+        let m = transform.matrix();
+
+        let close = |a:[f32;4], b:[f32;4]| a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-5);
+
+        assert!(close(m[0], [0.0, 2.0, 0.0, 0.0]));
+        assert!(close(m[1], [-2.0, 0.0, 0.0, 0.0]));
+        assert!(close(m[2], [0.0, 0.0, 2.0, 0.0]));
+        assert!(close(m[3], [1.0, 2.0, 3.0, 1.0]));
+
+        assert_eq!(Transform::default().matrix(), IDENTITY);
+        assert_eq!(multiply(&IDENTITY, &m), m);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Materials
+//
+/// Where the pixels of a texture are
+#[derive(Debug,Clone,PartialEq)]
+pub enum ImageSource {
+    // a file relative to the scene file
+    File(String),
+    // an image stored in the scene file
+    Embedded { mime_type:String, data:Vec<u8> }
+}
+//
+//
+/// A texture used by a material
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct TextureRef {
+    // index in the images of the scene
+    pub image: usize,
+    // the set of texture coordinates used, 0 for the uvs of the mesh and 1 for uvs1
+    pub tex_coord: u32
+}
+//
+//
+/// How the alpha of the base color is used
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    // fully opaque above the cutoff, fully transparent under it
+    Mask(f32),
+    Blend
+}
+//
+//
+/// A physically based material of the metallic-roughness workflow
+#[derive(Debug,Clone,PartialEq)]
+pub struct PbrMaterial {
+    pub name: Option<String>,
+    // linear rgba, multiplied with the base color texture
+    pub base_color: [f32;4],
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic: f32,
+    pub roughness: f32,
+    // the metalness in the blue channel and the roughness in the green one
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TextureRef>,
+    pub occlusion_strength: f32,
+    pub emissive: [f32;3],
+    pub emissive_texture: Option<TextureRef>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool
+}
+//
+impl Default for PbrMaterial {
+
+    /// the default material of glTF, a white rough metal
+    fn default() -> Self {
+
+        Self {
+            name:                       None,
+            base_color:                 [1.0; 4],
+            base_color_texture:         None,
+            metallic:                   1.0,
+            roughness:                  1.0,
+            metallic_roughness_texture: None,
+            normal_texture:             None,
+            normal_scale:               1.0,
+            occlusion_texture:          None,
+            occlusion_strength:         1.0,
+            emissive:                   [0.0; 3],
+            emissive_texture:           None,
+            alpha_mode:                 AlphaMode::Opaque,
+            double_sided:               false
+        }
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Hierarchy
+//
+/// Position, orientation and size of a node relative to its parent
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Transform {
+    pub translation: [f32;3],
+    // unit quaternion, xyzw
+    pub rotation: [f32;4],
+    pub scale: [f32;3]
+}
+//
+impl Default for Transform {
+
+    fn default() -> Self { Self { translation: [0.0; 3], rotation: [0.0, 0.0, 0.0, 1.0], scale: [1.0; 3] } }
+
+}
+//
+impl Transform {
+    //
+    /// the matrix that scale, rotate then translate
+    pub fn matrix(&self) -> Mat4 {
+
+        let [x, y, z, w] = self.rotation;
+        let [sx, sy, sz] = self.scale;
+        let [tx, ty, tz] = self.translation;
+
+        [
+            [(1.0 - 2.0 * (y * y + z * z)) * sx, 2.0 * (x * y + z * w) * sx, 2.0 * (x * z - y * w) * sx, 0.0],
+            [2.0 * (x * y - z * w) * sy, (1.0 - 2.0 * (x * x + z * z)) * sy, 2.0 * (y * z + x * w) * sy, 0.0],
+            [2.0 * (x * z + y * w) * sz, 2.0 * (y * z - x * w) * sz, (1.0 - 2.0 * (x * x + y * y)) * sz, 0.0],
+            [tx, ty, tz, 1.0]
+        ]
+
+    }
+    //
+}
+//
+//
+/// An element of the hierarchy of a scene
+#[derive(Debug,Clone,PartialEq)]
+pub struct Node {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub transform: Transform,
+    // index in the meshes of the scene
+    pub mesh: Option<usize>,
+    // index in the skins of the scene, for a skinned mesh
+    pub skin: Option<usize>
+}
+//
+//
+/// A mesh of a scene, its submeshes are the parts with a different material
+#[derive(Debug,Clone,PartialEq)]
+pub struct SceneMesh {
+    pub name: Option<String>,
+    pub mesh: Mesh,
+    // the index of the material of every submesh in the materials of the scene
+    pub materials: Vec<Option<usize>>
+}
+//
+//
+/// The joints that move a skinned mesh
+#[derive(Debug,Clone,PartialEq)]
+pub struct Skin {
+    pub name: Option<String>,
+    // the nodes used as joints, the joints of the vertices are indices in this list
+    pub joints: Vec<usize>,
+    // bring the mesh in the space of each joint
+    pub inverse_bind_matrices: Vec<Mat4>,
+    // the common root of the joints
+    pub skeleton: Option<usize>
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Animation
+//
+/// The property of a node that is animated
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AnimationProperty {
+    // 3 values per key
+    Translation,
+    // 4 values per key, a quaternion
+    Rotation,
+    // 3 values per key
+    Scale,
+    // a value per morph target per key
+    Weights
+}
+//
+//
+/// How the values are computed between two keys
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Interpolation {
+    Linear,
+    // the value of the previous key
+    Step,
+    // every key store an in tangent, the value then an out tangent
+    CubicSpline
+}
+//
+//
+/// The keys of a property of a node
+#[derive(Debug,Clone,PartialEq)]
+pub struct AnimationChannel {
+    pub node: usize,
+    pub property: AnimationProperty,
+    pub interpolation: Interpolation,
+    // the time of every key in seconds
+    pub times: Vec<f32>,
+    // the values of the keys one after the other
+    pub values: Vec<f32>
+}
+//
+//
+/// Keys that animate the nodes of a scene
+#[derive(Debug,Clone,PartialEq)]
+pub struct Animation {
+    pub name: Option<String>,
+    pub channels: Vec<AnimationChannel>
+}
+//
+impl Animation {
+    //
+    /// time of the last key, in seconds
+    pub fn duration(&self) -> f32 {
+
+        self.channels.iter().filter_map(|c| c.times.last()).fold(0.0, |a, b| a.max(*b))
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Scene
+//
+/// Everything imported from a scene file, the elements reference each other by index
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct Scene {
+    pub meshes: Vec<SceneMesh>,
+    pub materials: Vec<PbrMaterial>,
+    pub images: Vec<ImageSource>,
+    pub nodes: Vec<Node>,
+    // the nodes at the top of the hierarchy that are displayed
+    pub roots: Vec<usize>,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Animation>
+}
+//
+impl Scene {
+    //
+    /// index of the first node with this name
+    pub fn find_node(&self, name:&str) -> Option<usize> {
+
+        self.nodes.iter().position(|n| n.name.as_deref() == Some(name))
+
+    }
+    //
+    /// the matrix of every node in the space of the scene, its transform combined with the ones
+    /// of its parents
+    pub fn world_matrices(&self) -> Vec<Mat4> {
+
+        let mut matrices = vec![IDENTITY; self.nodes.len()];
+        let mut stack:Vec<(usize, Mat4)> = self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.parent.is_none())
+            .map(|(i, _)| (i, IDENTITY))
+            .collect();
+
+        let mut visited = vec![false; self.nodes.len()];
+
+        while let Some((i, parent)) = stack.pop() {
+
+            // a node can't be its own ancestor, but the file may be wrong
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+
+            let node = &self.nodes[i];
+            matrices[i] = multiply(&parent, &node.transform.matrix());
+
+            stack.extend(node.children.iter().map(|c| (*c, matrices[i])));
+
+        }
+
+        matrices
+
+    }
+    //
+}
+//
+//
+/// the product of two matrices, b is applied first
+pub fn multiply(a:&Mat4, b:&Mat4) -> Mat4 {
+
+    std::array::from_fn(|col| std::array::from_fn(|row| (0..4).map(|k| a[k][row] * b[col][k]).sum()))
+
+}
+//
+//
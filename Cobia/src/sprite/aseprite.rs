@@ -0,0 +1,353 @@
+// Import of the JSON written by the sprite sheet export of Aseprite, with the frames as a hash or
+// as an array
+
+use std::fmt;
+use std::time::Duration;
+
+use super::{ClipFrame, LoopMode, SpriteClip, SpriteFrame, SpriteSheet};
+use crate::core::error_handler::ESprite;
+use crate::core::file::get_file_content;
+use crate::image::AtlasRect;
+
+use error_stack::{Result, ResultExt};
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::get_relative_path;
+
+    #[test]
+    fn import_aseprite_hash() {
+
+        let sheet = load_aseprite(&get_relative_path("tests/fixtures/sprite/hero.json")).unwrap();
+
+        assert_eq!(sheet.image(), "hero.png");
+        assert_eq!(sheet.size(), (64, 32));
+
+        // the frames keep the order of the file, not the one of their names
+        let names:Vec<&str> = sheet.frames().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["hero 8.aseprite", "hero 9.aseprite", "hero 10.aseprite", "hero 11.aseprite"]);
+
+        // the pivot of the slice is moved in the trimmed frame
+        let trimmed = &sheet.frames()[3];
+        assert_eq!(trimmed.rect, AtlasRect { x: 48, y: 0, width: 16, height: 28 });
+        assert_eq!(trimmed.pivot, [0.5, 1.0]);
+        assert_eq!(sheet.frames()[0].pivot, [0.5, 1.0]);
+
+        let run = sheet.clip("run").unwrap();
+        assert_eq!(run.loop_mode, LoopMode::Loop);
+        assert_eq!(run.frames.iter().map(|f| f.frame).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(run.frames[2].duration, Duration::from_millis(120));
+
+        let turn = sheet.clip("turn").unwrap();
+        assert_eq!(turn.loop_mode, LoopMode::PingPong);
+        assert_eq!(turn.frames.iter().map(|f| f.frame).collect::<Vec<_>>(), vec![1, 2]);
+
+        // played backward a single time
+        let back = sheet.clip("back").unwrap();
+        assert_eq!(back.loop_mode, LoopMode::Once);
+        assert_eq!(back.frames.iter().map(|f| f.frame).collect::<Vec<_>>(), vec![2, 1, 0]);
+
+    }
+
+    #[test]
+    fn import_aseprite_array() {
+
+        let json = r#"{
+            "frames": [
+                { "filename": "a", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "rotated": false, "trimmed": false,
+                  "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 }, "sourceSize": { "w": 8, "h": 8 }, "duration": 50 },
+                { "filename": "b", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "rotated": false, "trimmed": false,
+                  "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 }, "sourceSize": { "w": 8, "h": 8 }, "duration": 50 }
+            ],
+            "meta": { "app": "https://www.aseprite.org/", "image": "a.png", "size": { "w": 16, "h": 8 } }
+        }"#;
+
+        let sheet = decode_aseprite(json.as_bytes()).unwrap();
+
+        // without tag every frame is in a single clip, the pivot is the center of the frame
+        assert_eq!(sheet.frames()[1].pivot, [0.5, 0.5]);
+        assert_eq!(sheet.clips().len(), 1);
+        assert_eq!(sheet.clip(DEFAULT_CLIP).unwrap().frames.len(), 2);
+
+        assert!(decode_aseprite(&json.replacen("\"rotated\": false", "\"rotated\": true", 1).into_bytes()).is_err());
+        assert!(decode_aseprite(&json.replace("\"w\": 16", "\"w\": 12").into_bytes()).is_err());
+        assert!(decode_aseprite(b"{ \"frames\": {} }").is_err());
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Import
+//
+/// name of the clip made of every frame when the export has no tag
+pub const DEFAULT_CLIP: &str = "default";
+//
+//
+/// load a sprite sheet exported by Aseprite
+///
+/// # Parameters
+///
+/// * 'fp' - A file path to the JSON file
+///
+pub fn load_aseprite(fp:&str) -> Result<SpriteSheet,ESprite> {
+
+    let content = get_file_content(fp)
+        .change_context(ESprite::Load)
+        .attach_printable_lazy(|| format!("Can't load Aseprite sheet {}",fp))?;
+
+    decode_aseprite(&content).attach_printable_lazy(|| format!("Can't load Aseprite sheet {}",fp))
+
+}
+//
+//
+/// Convert the JSON exported by Aseprite to a sprite sheet. Every tag become a clip, the
+/// reversed ones have their frames in the reverse order and the ones with a single repeat are
+/// played once. The pivots come from the first slice that has one, the center of the frames
+/// otherwise
+///
+/// # Parameters
+///
+/// * 'bytes' - the content of the JSON file
+///
+pub fn decode_aseprite(bytes:&[u8]) -> Result<SpriteSheet,ESprite> {
+
+    let file:AseFile = serde_json::from_slice(bytes).map_err(|e| ESprite::Parse.attach_printable_default(e))?;
+
+    let mut sheet = SpriteSheet::new(&file.meta.image, file.meta.size.w, file.meta.size.h);
+
+    let pivot_slice = file.meta.slices.iter().find(|s| s.keys.iter().any(|k| k.pivot.is_some()));
+
+    for (i, f) in file.frames.0.iter().enumerate() {
+
+        if f.rotated {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the frame '{}' is rotated, export the sheet without rotation",f.filename)));
+
+        }
+
+        // the key of the slice for this frame is the last one that start before it
+        let key = pivot_slice.and_then(|s| s.keys.iter().rev().find(|k| k.frame <= i));
+
+        // the pivot in the untrimmed frame, in pixels
+        let pivot = match key {
+            Some(AseSliceKey { bounds, pivot: Some(p), .. }) => [bounds.x as f32 + p.x, bounds.y as f32 + p.y],
+            _ => [f.source_size.w as f32 / 2.0, f.source_size.h as f32 / 2.0]
+        };
+
+        let (source, rect) = (&f.sprite_source_size, &f.frame);
+
+        sheet.add_frame(SpriteFrame {
+            name:   f.filename.clone(),
+            rect:   AtlasRect { x: rect.x, y: rect.y, width: rect.w, height: rect.h },
+            pivot:  [
+                (pivot[0] - source.x as f32) / rect.w.max(1) as f32,
+                (pivot[1] - source.y as f32) / rect.h.max(1) as f32
+            ]
+        })?;
+
+    }
+
+    let durations:Vec<Duration> = file.frames.0.iter().map(|f| Duration::from_millis(f.duration)).collect();
+
+    let clip_frame = |frame:usize| ClipFrame { frame, duration: durations[frame] };
+
+    if file.meta.frame_tags.is_empty() && !durations.is_empty() {
+
+        sheet.add_clip(SpriteClip {
+            name:       DEFAULT_CLIP.to_string(),
+            frames:     (0..durations.len()).map(clip_frame).collect(),
+            loop_mode:  LoopMode::Loop
+        })?;
+
+    }
+
+    for tag in file.meta.frame_tags.iter() {
+
+        if tag.from > tag.to || tag.to >= durations.len() {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the tag '{}' use frames that don't exist",tag.name)));
+
+        }
+
+        let mut frames:Vec<ClipFrame> = (tag.from..=tag.to).map(clip_frame).collect();
+
+        let loop_mode = match tag.direction.as_str() {
+            "forward" => LoopMode::Loop,
+            "reverse" => {
+                frames.reverse();
+                LoopMode::Loop
+            },
+            "pingpong" => LoopMode::PingPong,
+            "pingpong_reverse" => {
+                frames.reverse();
+                LoopMode::PingPong
+            },
+            other => return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the direction '{}' of the tag '{}' is unknown",other,tag.name)))
+        };
+
+        // the other repeat counts are played in loop
+        let loop_mode = match tag.repeat.as_deref() {
+            Some("1") => LoopMode::Once,
+            _ => loop_mode
+        };
+
+        sheet.add_clip(SpriteClip { name: tag.name.clone(), frames, loop_mode })?;
+
+    }
+
+    Ok(sheet)
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// File format
+//
+#[derive(Deserialize)]
+struct AseFile {
+    frames: AseFrames,
+    meta: AseMeta
+}
+//
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AseFrame {
+    // the key of the frame when they are exported as a hash
+    #[serde(default)]
+    filename: String,
+    // where the frame is in the image
+    frame: AseRect,
+    #[serde(default)]
+    rotated: bool,
+    // where the trimmed frame is in the untrimmed one
+    sprite_source_size: AseRect,
+    source_size: AseSize,
+    // milliseconds
+    duration: u64
+}
+//
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AseMeta {
+    image: String,
+    size: AseSize,
+    #[serde(default)]
+    frame_tags: Vec<AseTag>,
+    #[serde(default)]
+    slices: Vec<AseSlice>
+}
+//
+#[derive(Deserialize)]
+struct AseTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default = "forward")]
+    direction: String,
+    // the number of plays, as a string
+    #[serde(default)]
+    repeat: Option<String>
+}
+//
+#[derive(Deserialize)]
+struct AseSlice {
+    keys: Vec<AseSliceKey>
+}
+//
+#[derive(Deserialize)]
+struct AseSliceKey {
+    // the first frame where the key is used
+    frame: usize,
+    bounds: AseRect,
+    // relative to the bounds
+    #[serde(default)]
+    pivot: Option<AsePoint>
+}
+//
+#[derive(Deserialize)]
+struct AseRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32
+}
+//
+#[derive(Deserialize)]
+struct AseSize {
+    w: u32,
+    h: u32
+}
+//
+#[derive(Deserialize)]
+struct AsePoint {
+    x: f32,
+    y: f32
+}
+//
+fn forward() -> String { "forward".to_string() }
+//
+//
+/// The frames in the order of the file, a hash is read as a list of its entries
+struct AseFrames(Vec<AseFrame>);
+//
+impl<'de> Deserialize<'de> for AseFrames {
+
+    fn deserialize<D:Deserializer<'de>>(deserializer:D) -> std::result::Result<Self, D::Error> {
+
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+
+            type Value = AseFrames;
+
+            fn expecting(&self, f:&mut fmt::Formatter) -> fmt::Result { f.write_str("an array or a hash of frames") }
+
+            fn visit_seq<A:SeqAccess<'de>>(self, mut seq:A) -> std::result::Result<AseFrames, A::Error> {
+
+                let mut frames = Vec::new();
+
+                while let Some(frame) = seq.next_element()? {
+                    frames.push(frame);
+                }
+
+                Ok(AseFrames(frames))
+
+            }
+
+            fn visit_map<A:MapAccess<'de>>(self, mut map:A) -> std::result::Result<AseFrames, A::Error> {
+
+                let mut frames = Vec::new();
+
+                while let Some((filename, frame)) = map.next_entry::<String, AseFrame>()? {
+                    frames.push(AseFrame { filename, ..frame });
+                }
+
+                Ok(AseFrames(frames))
+
+            }
+
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+
+    }
+
+}
+//
+//
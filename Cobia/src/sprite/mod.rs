@@ -0,0 +1,556 @@
+// Sprite sheets: the frames of an image and the animation clips made of them, described in a
+// JSON file so the animations can change without touching the code
+//
+// The sheets are written by hand or imported from the JSON exported by Aseprite. A sheet only
+// reference its image, the image is loaded on its own.
+
+mod aseprite;
+
+pub use aseprite::{DEFAULT_CLIP, decode_aseprite, load_aseprite};
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::core::error_handler::ESprite;
+use crate::core::file::get_file_content;
+use crate::core::paths::write_atomic;
+use crate::image::{AtlasRect, UvRect};
+
+use error_stack::{Result, ResultExt};
+use serde::{Deserialize, Serialize};
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Test
+//
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::test_helper::get_relative_path;
+
+    fn clip(loop_mode:LoopMode, durations:&[u64]) -> SpriteClip {
+
+        SpriteClip {
+            name: "clip".to_string(),
+            frames: durations
+                .iter()
+                .enumerate()
+                .map(|(frame, ms)| ClipFrame { frame, duration: Duration::from_millis(*ms) })
+                .collect(),
+            loop_mode
+        }
+
+    }
+
+    #[test]
+    fn load_sprite_sheet() {
+
+        let sheet = SpriteSheet::load(&get_relative_path("tests/fixtures/sprite/hero.sprite")).unwrap();
+
+        assert_eq!(sheet.image(), "hero.png");
+        assert_eq!(sheet.size(), (64, 32));
+        assert_eq!(sheet.frames().len(), 4);
+
+        let jump = sheet.frame("jump").unwrap();
+        assert_eq!(jump.rect, AtlasRect { x: 32, y: 0, width: 16, height: 32 });
+        assert_eq!(jump.pivot, [0.5, 1.0]);
+
+        // the pivot is the center when the file doesn't give one
+        assert_eq!(sheet.frames()[0].pivot, [0.5, 0.5]);
+
+        let walk = sheet.clip("walk").unwrap();
+        assert_eq!(walk.loop_mode, LoopMode::PingPong);
+        assert_eq!(walk.frames.iter().map(|f| f.frame).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(walk.frames[1].duration, Duration::from_millis(150));
+
+        assert_eq!(sheet.clip("jump").unwrap().loop_mode, LoopMode::Once);
+        assert!(sheet.clip("run").is_none());
+
+        let uv = sheet.uv(3).unwrap();
+        assert_eq!((uv.min, uv.max), ([0.75, 0.0], [1.0, 0.5]));
+
+        // what is written is read back
+        assert_eq!(SpriteSheet::parse(&sheet.to_json()).unwrap(), sheet);
+
+    }
+
+    #[test]
+    fn invalid_sheets() {
+
+        let sheet = |frames:&str, clips:&str| format!(
+            r#"{{ "version": 1, "image": "a.png", "size": [16, 16], "frames": [{}], "clips": [{}] }}"#,
+            frames,
+            clips
+        );
+
+        let frame = r#"{ "name": "a", "rect": [0, 0, 8, 8] }"#;
+
+        assert!(SpriteSheet::parse(&sheet(frame, "")).is_ok());
+        assert!(SpriteSheet::parse(&sheet(frame, "").replace("\"version\": 1", "\"version\": 2")).is_err());
+        assert!(SpriteSheet::parse("{ not json").is_err());
+
+        // out of the image, or twice the same name
+        assert!(SpriteSheet::parse(&sheet(r#"{ "name": "a", "rect": [10, 0, 8, 8] }"#, "")).is_err());
+        assert!(SpriteSheet::parse(&sheet(&format!("{},{}",frame,frame), "")).is_err());
+
+        // the clips reference existing frames and have at least one
+        let clip = |frames:&str| format!(r#"{{ "name": "c", "frames": [{}] }}"#,frames);
+
+        assert!(SpriteSheet::parse(&sheet(frame, &clip(r#"{ "frame": "a", "duration": 10 }"#))).is_ok());
+        assert!(SpriteSheet::parse(&sheet(frame, &clip(r#"{ "frame": 1, "duration": 10 }"#))).is_err());
+        assert!(SpriteSheet::parse(&sheet(frame, &clip(r#"{ "frame": "b", "duration": 10 }"#))).is_err());
+        assert!(SpriteSheet::parse(&sheet(frame, &clip(""))).is_err());
+
+    }
+
+    #[test]
+    fn clip_playback() {
+
+        let ms = Duration::from_millis;
+
+        let looped = clip(LoopMode::Loop, &[100, 200, 100]);
+        assert_eq!(looped.duration(), ms(400));
+        assert_eq!(looped.frame_at(ms(0)), 0);
+        assert_eq!(looped.frame_at(ms(150)), 1);
+        assert_eq!(looped.frame_at(ms(350)), 2);
+        assert_eq!(looped.frame_at(ms(450)), 0);
+
+        let once = clip(LoopMode::Once, &[100, 200, 100]);
+        assert_eq!(once.frame_at(ms(350)), 2);
+        assert_eq!(once.frame_at(ms(5000)), 2);
+
+        // forward then backward, without showing the ends twice
+        let ping_pong = clip(LoopMode::PingPong, &[100, 100, 100]);
+        assert_eq!(ping_pong.duration(), ms(400));
+
+        let frames:Vec<usize> = (0..8).map(|i| ping_pong.frame_at(ms(i * 100 + 50))).collect();
+        assert_eq!(frames, vec![0, 1, 2, 1, 0, 1, 2, 1]);
+
+        assert_eq!(clip(LoopMode::PingPong, &[100]).frame_at(ms(250)), 0);
+
+    }
+
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// Sheet
+//
+/// A frame of a sprite sheet
+#[derive(Debug,Clone,PartialEq)]
+pub struct SpriteFrame {
+    pub name: String,
+    // where the frame is in the image
+    pub rect: AtlasRect,
+    // the point of the frame placed on the position of the sprite, from 0 to 1 in the rect
+    // from its top left corner. It can be out of the rect when the frame was trimmed
+    pub pivot: [f32;2]
+}
+//
+//
+/// What a clip do once its last frame is shown
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    // stay on the last frame
+    Once,
+    // start again from the first frame
+    Loop,
+    // play the frames backward, then forward again
+    PingPong
+}
+//
+//
+/// A frame of a clip and how long it is shown
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct ClipFrame {
+    // index in the frames of the sheet
+    pub frame: usize,
+    pub duration: Duration
+}
+//
+//
+/// A named animation, the same frame of the sheet can be used many times
+#[derive(Debug,Clone,PartialEq)]
+pub struct SpriteClip {
+    pub name: String,
+    pub frames: Vec<ClipFrame>,
+    pub loop_mode: LoopMode
+}
+//
+impl SpriteClip {
+    //
+    /// duration of one play of the clip, forward and backward for the ping pong
+    pub fn duration(&self) -> Duration { self.sequence().map(|i| self.frames[i].duration).sum() }
+    //
+    /// Find the frame of the sheet to show after some time, 0 for a clip without frame
+    ///
+    /// # Parameters
+    ///
+    /// * 'time' - the time since the clip started
+    ///
+    pub fn frame_at(&self, time:Duration) -> usize {
+
+        let total = self.duration().as_nanos();
+
+        let last = match self.frames.len() {
+            0 => return 0,
+            count => count - 1
+        };
+
+        if total == 0 {
+            return self.frames[0].frame;
+        }
+
+        let elapsed = time.as_nanos();
+
+        if self.loop_mode == LoopMode::Once && elapsed >= total {
+            return self.frames[last].frame;
+        }
+
+        let mut remaining = elapsed % total;
+
+        for i in self.sequence() {
+
+            let duration = self.frames[i].duration.as_nanos();
+
+            if remaining < duration {
+                return self.frames[i].frame;
+            }
+
+            remaining -= duration;
+
+        }
+
+        self.frames[last].frame
+
+    }
+    //
+    /// the index of the frames of the clip in the order they are shown during one play
+    fn sequence(&self) -> impl Iterator<Item = usize> {
+
+        let count = self.frames.len();
+
+        // the ends are not shown twice when the clip go back
+        let back = match self.loop_mode {
+            LoopMode::PingPong => (1..count.saturating_sub(1)).rev(),
+            _ => (0..0).rev()
+        };
+
+        (0..count).chain(back)
+
+    }
+    //
+}
+//
+//
+/// The frames of an image and the clips that animate them
+#[derive(Debug,Clone,PartialEq)]
+pub struct SpriteSheet {
+
+    // path of the image relative to the sheet, the asset path once loaded by the asset server
+    image:      String,
+    width:      u32,
+    height:     u32,
+    frames:     Vec<SpriteFrame>,
+    clips:      Vec<SpriteClip>
+
+}
+//
+impl SpriteSheet {
+    //
+    /// Create a sheet without frame
+    ///
+    /// # Parameters
+    ///
+    /// * 'image' - path of the image, relative to the sheet
+    /// * 'width' - width of the image
+    /// * 'height' - height of the image
+    ///
+    pub fn new(image:&str, width:u32, height:u32) -> Self {
+
+        Self { image: image.to_string(), width, height, frames: Vec::new(), clips: Vec::new() }
+
+    }
+    //
+    pub fn image(&self) -> &str { &self.image }
+    //
+    pub fn set_image(&mut self, image:&str) { self.image = image.to_string(); }
+    //
+    /// width and height of the image
+    pub fn size(&self) -> (u32, u32) { (self.width, self.height) }
+    //
+    pub fn frames(&self) -> &[SpriteFrame] { &self.frames }
+    //
+    pub fn clips(&self) -> &[SpriteClip] { &self.clips }
+    //
+    /// the frame with this name
+    pub fn frame(&self, name:&str) -> Option<&SpriteFrame> { self.frames.iter().find(|f| f.name == name) }
+    //
+    /// the clip with this name
+    pub fn clip(&self, name:&str) -> Option<&SpriteClip> { self.clips.iter().find(|c| c.name == name) }
+    //
+    /// texture coordinates of a frame
+    pub fn uv(&self, frame:usize) -> Option<UvRect> {
+
+        let rect = self.frames.get(frame)?.rect;
+        let (w, h) = (self.width as f32, self.height as f32);
+
+        Some(UvRect {
+            min: [rect.x as f32 / w, rect.y as f32 / h],
+            max: [(rect.x + rect.width) as f32 / w, (rect.y + rect.height) as f32 / h]
+        })
+
+    }
+    //
+    /// Add a frame, its name must be unique and it must be inside the image
+    ///
+    /// # Parameters
+    ///
+    /// * 'frame' - the new frame
+    ///
+    pub fn add_frame(&mut self, frame:SpriteFrame) -> Result<usize,ESprite> {
+
+        let r = frame.rect;
+
+        if r.x as u64 + r.width as u64 > self.width as u64 || r.y as u64 + r.height as u64 > self.height as u64 {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the frame '{}' goes out of the image",frame.name)));
+
+        }
+
+        if self.frame(&frame.name).is_some() {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the frame '{}' is defined twice",frame.name)));
+
+        }
+
+        self.frames.push(frame);
+
+        Ok(self.frames.len() - 1)
+
+    }
+    //
+    /// Add a clip, it must have frames and they must be in the sheet. It replace the clip with
+    /// the same name
+    ///
+    /// # Parameters
+    ///
+    /// * 'clip' - the new clip
+    ///
+    pub fn add_clip(&mut self, clip:SpriteClip) -> Result<(),ESprite> {
+
+        if clip.frames.is_empty() {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the clip '{}' has no frame",clip.name)));
+
+        }
+
+        if let Some(f) = clip.frames.iter().find(|f| f.frame >= self.frames.len()) {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("the clip '{}' use the frame {} that doesn't exist",clip.name,f.frame)));
+
+        }
+
+        match self.clips.iter_mut().find(|c| c.name == clip.name) {
+            Some(existing) => *existing = clip,
+            None => self.clips.push(clip)
+        }
+
+        Ok(())
+
+    }
+    //
+    /// Serialize the sheet as JSON:
+    ///
+    /// ```text
+    /// {
+    ///     "version": 1,
+    ///     "image": "hero.png",
+    ///     "size": [64, 32],
+    ///     "frames": [ { "name": "idle", "rect": [0, 0, 16, 32], "pivot": [0.5, 1.0] } ],
+    ///     "clips": [ { "name": "idle", "loop": "loop", "frames": [ { "frame": 0, "duration": 100 } ] } ]
+    /// }
+    /// ```
+    ///
+    /// The durations are in milliseconds and the clips can name their frames instead of giving
+    /// their index
+    pub fn to_json(&self) -> String {
+
+        let file = SheetFile {
+            version:    SHEET_VERSION,
+            image:      self.image.clone(),
+            size:       [self.width, self.height],
+            frames:     self.frames
+                .iter()
+                .map(|f| FrameFile { name: f.name.clone(), rect: [f.rect.x, f.rect.y, f.rect.width, f.rect.height], pivot: f.pivot })
+                .collect(),
+            clips:      self.clips
+                .iter()
+                .map(|c| ClipFile {
+                    name:       c.name.clone(),
+                    loop_mode:  c.loop_mode,
+                    frames:     c.frames
+                        .iter()
+                        .map(|f| ClipFrameFile { frame: FrameRef::Index(f.frame), duration: f.duration.as_millis() as u64 })
+                        .collect()
+                })
+                .collect()
+        };
+
+        serde_json::to_string_pretty(&file).unwrap()
+
+    }
+    //
+    /// Read a sheet serialized with to_json
+    ///
+    /// # Parameters
+    ///
+    /// * 'text' - the serialized sheet
+    ///
+    pub fn parse(text:&str) -> Result<SpriteSheet,ESprite> {
+
+        let file:SheetFile = serde_json::from_str(text).map_err(|e| ESprite::Parse.attach_printable_default(e))?;
+
+        if file.version != SHEET_VERSION {
+
+            return Err(ESprite::Parse
+                .as_report()
+                .attach_printable(format!("version {} of the sprite sheets is not supported",file.version)));
+
+        }
+
+        let mut sheet = SpriteSheet::new(&file.image, file.size[0], file.size[1]);
+
+        for f in file.frames {
+
+            let [x, y, width, height] = f.rect;
+            sheet.add_frame(SpriteFrame { name: f.name, rect: AtlasRect { x, y, width, height }, pivot: f.pivot })?;
+
+        }
+
+        for c in file.clips {
+
+            let mut frames = Vec::with_capacity(c.frames.len());
+
+            for f in c.frames {
+
+                let frame = match f.frame {
+                    FrameRef::Index(i) => i,
+                    FrameRef::Name(name) => sheet.frames.iter().position(|s| s.name == name).ok_or_else(||
+                        ESprite::Parse
+                            .as_report()
+                            .attach_printable(format!("the clip '{}' use the frame '{}' that doesn't exist",c.name,name))
+                    )?
+                };
+
+                frames.push(ClipFrame { frame, duration: Duration::from_millis(f.duration) });
+
+            }
+
+            sheet.add_clip(SpriteClip { name: c.name, frames, loop_mode: c.loop_mode })?;
+
+        }
+
+        Ok(sheet)
+
+    }
+    //
+    /// load a sheet saved with save
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the sheet
+    ///
+    pub fn load(fp:&str) -> Result<SpriteSheet,ESprite> {
+
+        let content = get_file_content(fp)
+            .change_context(ESprite::Load)
+            .attach_printable_lazy(|| format!("Can't load sprite sheet {}",fp))?;
+
+        let text = std::str::from_utf8(&content).map_err(|e| ESprite::Parse.attach_printable_default(e))?;
+
+        SpriteSheet::parse(text).attach_printable_lazy(|| format!("Can't load sprite sheet {}",fp))
+
+    }
+    //
+    /// save the sheet as JSON
+    ///
+    /// # Parameters
+    ///
+    /// * 'fp' - A file path to the new sheet
+    ///
+    pub fn save(&self, fp:&str) -> Result<(),ESprite> {
+
+        write_atomic(Path::new(fp), self.to_json().as_bytes())
+            .change_context(ESprite::Save)
+            .attach_printable_lazy(|| format!("Can't save sprite sheet {}",fp))
+
+    }
+    //
+}
+//
+//
+// ------------------------------------------------------------------------------------------------
+// File format
+//
+/// version of the sheets written by to_json
+const SHEET_VERSION: u32 = 1;
+//
+//
+#[derive(Serialize,Deserialize)]
+struct SheetFile {
+    version: u32,
+    image: String,
+    size: [u32;2],
+    frames: Vec<FrameFile>,
+    #[serde(default)]
+    clips: Vec<ClipFile>
+}
+//
+#[derive(Serialize,Deserialize)]
+struct FrameFile {
+    name: String,
+    // x, y, width and height
+    rect: [u32;4],
+    #[serde(default = "center")]
+    pivot: [f32;2]
+}
+//
+#[derive(Serialize,Deserialize)]
+struct ClipFile {
+    name: String,
+    #[serde(rename = "loop", default = "looped")]
+    loop_mode: LoopMode,
+    frames: Vec<ClipFrameFile>
+}
+//
+#[derive(Serialize,Deserialize)]
+struct ClipFrameFile {
+    frame: FrameRef,
+    // milliseconds
+    duration: u64
+}
+//
+/// a frame of the sheet by index or by name
+#[derive(Serialize,Deserialize)]
+#[serde(untagged)]
+enum FrameRef {
+    Index(usize),
+    Name(String)
+}
+//
+fn center() -> [f32;2] { [0.5, 0.5] }
+//
+fn looped() -> LoopMode { LoopMode::Loop }
+//
+//